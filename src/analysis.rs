@@ -0,0 +1,117 @@
+//!Analyses looking for structure across the hosts of a scan.
+use std::collections::BTreeSet;
+
+use crate::host::{Host, HostState};
+use crate::NmapResults;
+
+///Hosts sharing the same, or a similar, set of open ports.
+#[derive(Clone, Debug)]
+pub struct Cluster<'a> {
+    ///The open ports of the first host of the cluster, as `port/protocol`
+    ///followed by the service name when known, e.g. `22/tcp ssh`.
+    pub profile: BTreeSet<String>,
+
+    ///Hosts of the cluster, in scan order.
+    pub hosts: Vec<&'a Host>,
+}
+
+///Groups the hosts that are up by their open ports and services.
+///
+///A host joins the first cluster whose profile is at least
+///`min_similarity` similar to its own open ports, using the Jaccard index
+///(shared ports over all ports), or starts a new cluster. A
+///`min_similarity` of `1.0` only groups hosts with identical profiles.
+///Clusters are returned largest first, so fleets of identical appliances
+///come first and outliers last.
+pub fn cluster_by_ports(results: &NmapResults, min_similarity: f64) -> Vec<Cluster<'_>> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+
+    for host in results.hosts().filter(|h| h.status.state == HostState::Up) {
+        let profile = profile(host);
+        match clusters
+            .iter_mut()
+            .find(|c| similarity(&c.profile, &profile) >= min_similarity)
+        {
+            Some(cluster) => cluster.hosts.push(host),
+            None => clusters.push(Cluster {
+                profile,
+                hosts: vec![host],
+            }),
+        }
+    }
+
+    //Stable sort, so clusters of the same size keep scan order.
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.hosts.len()));
+    clusters
+}
+
+fn profile(host: &Host) -> BTreeSet<String> {
+    host.port_info
+        .open_ports()
+        .map(|p| match &p.service_info {
+            Some(service) => format!("{}/{} {}", p.port_number, p.protocol, service.name),
+            None => format!("{}/{}", p.port_number, p.protocol),
+        })
+        .collect()
+}
+
+fn similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn host(ip: &str, ports: &[u16]) -> String {
+        let ports = ports
+            .iter()
+            .map(|p| {
+                format!(
+                    r#"<port protocol="tcp" portid="{}"><state state="open" reason="syn-ack" reason_ttl="64"/></port>"#,
+                    p
+                )
+            })
+            .collect::<String>();
+        format!(
+            r#"<host><status state="up" reason="echo-reply" reason_ttl="64"/><address addr="{}" addrtype="ipv4"/><ports>{}</ports></host>"#,
+            ip, ports
+        )
+    }
+
+    #[test]
+    fn port_clusters() {
+        let xml = format!(
+            r#"<nmaprun start="1588318812">{}{}{}{}</nmaprun>"#,
+            host("10.0.0.1", &[22, 80, 443]),
+            host("10.0.0.2", &[3389]),
+            host("10.0.0.3", &[22, 80, 443]),
+            host("10.0.0.4", &[22, 80, 443, 8080]),
+        );
+        let results = NmapResults::parse(&xml).unwrap();
+        let ips = |cluster: &Cluster| {
+            cluster
+                .hosts
+                .iter()
+                .map(|h| h.primary_ip().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let exact = cluster_by_ports(&results, 1.0);
+        assert_eq!(exact.len(), 3);
+        assert_eq!(ips(&exact[0]), vec!["10.0.0.1", "10.0.0.3"]);
+        assert_eq!(
+            exact[0].profile.iter().collect::<Vec<_>>(),
+            vec!["22/tcp", "443/tcp", "80/tcp"]
+        );
+
+        let similar = cluster_by_ports(&results, 0.7);
+        assert_eq!(similar.len(), 2);
+        assert_eq!(ips(&similar[0]), vec!["10.0.0.1", "10.0.0.3", "10.0.0.4"]);
+        assert_eq!(ips(&similar[1]), vec!["10.0.0.2"]);
+    }
+}
@@ -0,0 +1,391 @@
+//!Pseudonymization of scan results for sharing.
+//!
+//!An [`Anonymizer`](struct.Anonymizer.html) replaces IP addresses, MAC
+//!addresses and hostnames with pseudonyms derived from a secret key with
+//!HMAC-SHA-1. The same value always gets the same pseudonym for a key, so
+//!hosts stay distinguishable and can still be matched across the scans
+//!anonymized with it, while the key is needed to test a guess.
+//!
+//!- IPv4 addresses map into `10.0.0.0/8`: their 24 host bits go through a
+//!  keyed permutation, a Feistel network over HMAC-SHA-1 tweaked with the
+//!  first octet, so addresses in the same `/8` never share a pseudonym.
+//!  IPv6 addresses map into `fd00::/8`.
+//!- MAC addresses map to locally administered addresses.
+//!- Each label of a hostname maps to a pseudonym, except the top-level
+//!  domain, so names sharing a domain still do.
+//!
+//!Addresses and known hostnames found in the command line and in script
+//!output are replaced too. As script output can hold anything, it can also
+//!be stripped altogether.
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+
+use crate::host::{Address, Host, Hostname, Script, ScriptElement};
+use crate::net::MacAddr;
+use crate::util::hmac_sha1;
+use crate::NmapResults;
+
+const FEISTEL_ROUNDS: u8 = 4;
+
+///Replaces identifying values with keyed pseudonyms.
+#[derive(Clone, Debug)]
+pub struct Anonymizer {
+    key: Vec<u8>,
+    strip_scripts: bool,
+    hostnames: HashMap<String, String>,
+}
+
+impl Anonymizer {
+    ///Returns an anonymizer deriving pseudonyms from `key`, which should be
+    ///kept secret and long enough not to be guessed.
+    pub fn new(key: &[u8]) -> Self {
+        Anonymizer {
+            key: key.to_vec(),
+            strip_scripts: false,
+            hostnames: HashMap::new(),
+        }
+    }
+
+    ///Empties the output of every script instead of rewriting it.
+    pub fn strip_scripts(mut self, strip: bool) -> Self {
+        self.strip_scripts = strip;
+        self
+    }
+
+    ///Returns the pseudonym of `ip`. It depends on the key and the address
+    ///alone, not on the addresses seen before.
+    pub fn ip(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(ip) => {
+                let [first, ..] = ip.octets();
+                let host = self.permute(first, u32::from(ip) & 0x00ff_ffff);
+                IpAddr::V4(Ipv4Addr::from(0x0a00_0000 | host))
+            }
+            IpAddr::V6(_) => {
+                let digest = hmac_sha1(&self.key, ip.to_string().as_bytes());
+                let mut octets = [0u8; 16];
+                octets[0] = 0xfd;
+                octets[1..].copy_from_slice(&digest[..15]);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+        }
+    }
+
+    //A keyed permutation of the 24 bit `host`: a balanced Feistel network
+    //over two 12 bit halves, each round keyed by HMAC-SHA-1 of the round,
+    //the `tweak` and the right half.
+    fn permute(&self, tweak: u8, host: u32) -> u32 {
+        let (mut left, mut right) = (host >> 12, host & 0xfff);
+        for round in 0..FEISTEL_ROUNDS {
+            let digest = hmac_sha1(
+                &self.key,
+                &[b'4', round, tweak, (right >> 8) as u8, right as u8],
+            );
+            let f = u32::from(u16::from_be_bytes([digest[0], digest[1]])) & 0xfff;
+            (left, right) = (right, left ^ f);
+        }
+        (left << 12) | right
+    }
+
+    ///Returns the pseudonym of `mac`.
+    pub fn mac(&self, mac: &MacAddr) -> MacAddr {
+        let digest = hmac_sha1(&self.key, mac.to_string().as_bytes());
+        let mut octets = [0; 6];
+        octets.copy_from_slice(&digest[..6]);
+        //Locally administered, unicast.
+        octets[0] = (octets[0] | 0x02) & 0xfe;
+        MacAddr::new(octets)
+    }
+
+    ///Returns the pseudonym of `name`. Names are compared
+    ///case-insensitively.
+    pub fn hostname(&mut self, name: &str) -> String {
+        let name = name.to_lowercase();
+        if let Some(pseudonym) = self.hostnames.get(&name) {
+            return pseudonym.clone();
+        }
+
+        let labels = name.split('.').collect::<Vec<_>>();
+        let pseudonym = labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                if i + 1 == labels.len() && labels.len() > 1 {
+                    label.to_string()
+                } else {
+                    let digest = hmac_sha1(&self.key, label.as_bytes());
+                    format!("h{}", hex(&digest[..4]))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        self.hostnames.insert(name, pseudonym.clone());
+        pseudonym
+    }
+
+    ///Pseudonymizes `results` in place.
+    pub fn anonymize(&mut self, results: &mut NmapResults) {
+        //Names are collected first so that script output mentioning a host
+        //scanned later is rewritten as well.
+        for host in &results.hosts {
+            for hostname in &host.host_names {
+                self.hostname(&hostname.name);
+            }
+            for hop in host.trace.iter().flat_map(|t| t.hops.iter()) {
+                if let Some(name) = &hop.host_name {
+                    self.hostname(name);
+                }
+            }
+        }
+
+        for host in &mut results.hosts {
+            self.anonymize_host(host);
+        }
+        results.args = results.args.take().map(|args| self.text(&args));
+        results.address_index = OnceLock::new();
+    }
+
+    fn anonymize_host(&mut self, host: &mut Host) {
+        for address in &mut host.addresses {
+            *address = match address {
+                Address::IpAddr(ip) => Address::IpAddr(self.ip(*ip)),
+                Address::MacAddr(mac) => Address::MacAddr(self.mac(mac)),
+            };
+        }
+        for hostname in &mut host.host_names {
+            *hostname = Hostname {
+                name: self.hostname(&hostname.name),
+                source: hostname.source.clone(),
+            };
+        }
+        if let Some(trace) = &mut host.trace {
+            for hop in &mut trace.hops {
+                hop.ip_addr = self.ip(hop.ip_addr);
+                hop.host_name = hop.host_name.take().map(|name| self.hostname(&name));
+            }
+        }
+
+        for script in &mut host.scripts {
+            self.script(script);
+        }
+        for port in &mut host.port_info.ports {
+            for script in &mut port.scripts {
+                self.script(script);
+            }
+        }
+    }
+
+    fn script(&mut self, script: &mut Script) {
+        if self.strip_scripts {
+            script.output.clear();
+            script.elements.clear();
+            return;
+        }
+        script.output = self.text(&script.output);
+        for element in &mut script.elements {
+            self.element(element);
+        }
+    }
+
+    fn element(&mut self, element: &mut ScriptElement) {
+        match element {
+            ScriptElement::Elem { value, .. } => *value = self.text(value),
+            ScriptElement::Table { elements, .. } => {
+                for element in elements {
+                    self.element(element);
+                }
+            }
+        }
+    }
+
+    //Replaces the addresses and known hostnames found in `text`.
+    fn text(&mut self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut token = String::new();
+        for c in text.chars().chain(std::iter::once('\0')) {
+            if c.is_ascii_hexdigit() || c == '.' || c == ':' {
+                token.push(c);
+                continue;
+            }
+            out.push_str(&self.token(&token));
+            token.clear();
+            if c != '\0' {
+                out.push(c);
+            }
+        }
+
+        let mut names = self.hostnames.iter().collect::<Vec<_>>();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+        names.into_iter().fold(out, |out, (name, pseudonym)| {
+            replace_ignore_case(&out, name, pseudonym)
+        })
+    }
+
+    fn token(&mut self, token: &str) -> String {
+        //Sentence punctuation is not part of an address.
+        let trimmed = token.trim_end_matches(['.', ':']);
+        let suffix = &token[trimmed.len()..];
+        if trimmed.len() == 17 && trimmed.matches(':').count() == 5 {
+            if let Ok(mac) = trimmed.parse::<MacAddr>() {
+                return format!("{}{}", self.mac(&mac), suffix);
+            }
+        }
+        match trimmed.parse::<IpAddr>() {
+            Ok(ip) if trimmed.contains(['.', ':']) => format!("{}{}", self.ip(ip), suffix),
+            _ => token.to_string(),
+        }
+    }
+}
+
+fn replace_ignore_case(text: &str, from: &str, to: &str) -> String {
+    let lower = text.to_lowercase();
+    //Lower-casing may change byte lengths outside ASCII.
+    if lower.len() != text.len() || from.is_empty() {
+        return text.replace(from, to);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in lower.match_indices(from) {
+        if start < last {
+            continue;
+        }
+        out.push_str(&text[last..start]);
+        out.push_str(to);
+        last = start + from.len();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812" args="nmap -sV 45.33.32.156 web.example.com">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<address addr="00:0C:29:71:23:2B" addrtype="mac" vendor="VMware"/>
+<hostnames><hostname name="web.example.com" type="user"/></hostnames>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/>
+<script id="http-title" output="Did not follow redirect to http://WEB.example.com/ from 45.33.32.156."><elem key="redirect_url">http://web.example.com/</elem></script>
+</port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.157" addrtype="ipv4"/>
+<hostnames><hostname name="db.example.com" type="PTR"/></hostnames>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn anonymize_results() {
+        let mut results = NmapResults::parse(SCAN).unwrap();
+        let mut anonymizer = Anonymizer::new(b"secret");
+        anonymizer.anonymize(&mut results);
+
+        let ip = anonymizer.ip("45.33.32.156".parse().unwrap());
+        let name = anonymizer.hostname("web.example.com");
+        assert!(ip.to_string().starts_with("10."));
+        assert!(name.ends_with(".com"));
+        assert_ne!(name, "web.example.com");
+        //Names sharing a domain keep sharing it.
+        let db = anonymizer.hostname("db.example.com");
+        assert_eq!(
+            db.split_once('.').unwrap().1,
+            name.split_once('.').unwrap().1
+        );
+
+        let host = results.host_by_addr(&ip).unwrap();
+        assert_eq!(host.host_names().next().unwrap().name, name);
+        let mac = anonymizer.mac(&"00:0c:29:71:23:2b".parse().unwrap());
+        assert!(results.host_by_mac(&mac.to_string()).is_some());
+        assert_eq!(
+            results.args.as_deref(),
+            Some(format!("nmap -sV {} {}", ip, name).as_str())
+        );
+        let script = &host.port_info.ports().next().unwrap().scripts[0];
+        assert_eq!(
+            script.output,
+            format!("Did not follow redirect to http://{}/ from {}.", name, ip)
+        );
+        assert_eq!(
+            script.elem("redirect_url"),
+            Some(format!("http://{}/", name).as_str())
+        );
+
+        //The same key gives the same pseudonyms.
+        let mut again = NmapResults::parse(SCAN).unwrap();
+        Anonymizer::new(b"secret").anonymize(&mut again);
+        assert!(again.host_by_addr(&ip).is_some());
+        let mut other = NmapResults::parse(SCAN).unwrap();
+        Anonymizer::new(b"other key").anonymize(&mut other);
+        assert!(other.host_by_addr(&ip).is_none());
+    }
+
+    #[test]
+    fn ip_pseudonyms_ignore_order() {
+        let scan = |addrs: &[&str]| {
+            let hosts = addrs
+                .iter()
+                .map(|addr| {
+                    format!(
+                        r#"<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="{}" addrtype="ipv4"/></host>"#,
+                        addr
+                    )
+                })
+                .collect::<String>();
+            let mut results = NmapResults::parse(&format!(
+                r#"<nmaprun start="1588318812">{}</nmaprun>"#,
+                hosts
+            ))
+            .unwrap();
+            Anonymizer::new(b"secret").anonymize(&mut results);
+            results
+                .hosts()
+                .map(|h| h.addresses().next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let addrs = ["45.33.32.156", "45.33.32.157", "192.168.0.1"];
+        let forward = scan(&addrs);
+        let mut backward = scan(&[addrs[2], addrs[1], addrs[0]]);
+        backward.reverse();
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn ip_pseudonyms_do_not_collide() {
+        let anonymizer = Anonymizer::new(b"secret");
+        let pseudonyms = (0..=u16::MAX)
+            .map(|i| anonymizer.ip(IpAddr::V4(Ipv4Addr::from(0x2d21_0000 | u32::from(i)))))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(pseudonyms.len(), 1 << 16);
+        assert!(pseudonyms
+            .iter()
+            .all(|ip| ip.to_string().starts_with("10.")));
+    }
+
+    #[test]
+    fn strip_scripts() {
+        let mut results = NmapResults::parse(SCAN).unwrap();
+        Anonymizer::new(b"secret")
+            .strip_scripts(true)
+            .anonymize(&mut results);
+        let script = &results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .next()
+            .unwrap()
+            .scripts[0];
+        assert_eq!(script.output, "");
+        assert_eq!(script.elements().count(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+//!Assertions for checking scans in tests and CI jobs.
+//!
+//!Each function panics with a message describing what the scan actually
+//!found, e.g.
+//!
+//!```text
+//!expected 10.0.0.1 to have only ports 22, 443 open, found 23/tcp (telnet), 8080/tcp (http-proxy)
+//!```
+//!
+//!Hosts are looked up by IP address and ports are checked for every
+//!protocol, so `443` matches both `443/tcp` and `443/udp`.
+use std::net::IpAddr;
+
+use crate::host::{Host, HostState};
+use crate::port::Port;
+use crate::NmapResults;
+
+///Asserts that port `port` of the host `ip` is open.
+#[track_caller]
+pub fn assert_port_open(results: &NmapResults, ip: &str, port: u16) {
+    let host = match lookup(results, ip) {
+        Some(host) => host,
+        None => panic!("expected port {} open on {}, host not in scan", port, ip),
+    };
+    if host.port_info.has_open(port) {
+        return;
+    }
+
+    let found = host
+        .port_info
+        .ports()
+        .filter(|p| p.port_number == port)
+        .map(|p| format!("{} ({})", p.status.state, p.status.reason))
+        .collect::<Vec<_>>();
+    let found = if found.is_empty() {
+        "it is not listed".to_string()
+    } else {
+        format!("found {}", found.join(", "))
+    };
+    panic!(
+        "expected port {} open on {}, {}; open ports: {}",
+        port,
+        ip,
+        found,
+        list(host.port_info.open_ports())
+    );
+}
+
+///Asserts that the host `ip` has no open ports other than `allowed`. A host
+///missing from the scan passes.
+#[track_caller]
+pub fn assert_no_ports_open_except(results: &NmapResults, ip: &str, allowed: &[u16]) {
+    let host = match lookup(results, ip) {
+        Some(host) => host,
+        None => return,
+    };
+    let unexpected = host
+        .port_info
+        .open_ports()
+        .filter(|p| !allowed.contains(&p.port_number))
+        .collect::<Vec<_>>();
+    if unexpected.is_empty() {
+        return;
+    }
+
+    let allowed = if allowed.is_empty() {
+        "no ports".to_string()
+    } else {
+        format!(
+            "only ports {}",
+            allowed
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    panic!(
+        "expected {} to have {} open, found {}",
+        ip,
+        allowed,
+        list(unexpected.into_iter())
+    );
+}
+
+///Asserts that the host `ip` is not up. A host missing from the scan
+///passes, as Nmap leaves out hosts that did not respond.
+#[track_caller]
+pub fn assert_host_down(results: &NmapResults, ip: &str) {
+    if let Some(host) = lookup(results, ip) {
+        if host.status.state == HostState::Up {
+            panic!(
+                "expected {} down, found it up ({}); open ports: {}",
+                ip,
+                host.status.reason,
+                list(host.port_info.open_ports())
+            );
+        }
+    }
+}
+
+#[track_caller]
+fn lookup<'a>(results: &'a NmapResults, ip: &str) -> Option<&'a Host> {
+    let addr = ip
+        .parse::<IpAddr>()
+        .unwrap_or_else(|_| panic!("invalid IP address `{}`", ip));
+    results.host_by_addr(&addr)
+}
+
+//`22/tcp (ssh), 80/tcp (http)`, or `none`.
+fn list<'a, I: Iterator<Item = &'a Port>>(ports: I) -> String {
+    let ports = ports
+        .map(|p| match &p.service_info {
+            Some(service) => format!("{}/{} ({})", p.port_number, p.protocol, service.name),
+            None => format!("{}/{}", p.port_number, p.protocol),
+        })
+        .collect::<Vec<_>>();
+    if ports.is_empty() {
+        "none".to_string()
+    } else {
+        ports.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+<port protocol="tcp" portid="443"><state state="filtered" reason="no-response" reason_ttl="0"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn passing_assertions() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        assert_port_open(&results, "10.0.0.1", 22);
+        assert_no_ports_open_except(&results, "10.0.0.1", &[22, 23, 80]);
+        assert_no_ports_open_except(&results, "10.0.0.3", &[]);
+        assert_host_down(&results, "10.0.0.2");
+        assert_host_down(&results, "10.0.0.3");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected port 443 open on 10.0.0.1, found filtered (no-response); open ports: 22/tcp (ssh), 23/tcp (telnet)"
+    )]
+    fn port_not_open() {
+        assert_port_open(&NmapResults::parse(SCAN).unwrap(), "10.0.0.1", 443);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected 10.0.0.1 to have only ports 22, 443 open, found 23/tcp (telnet)"
+    )]
+    fn unexpected_open_port() {
+        assert_no_ports_open_except(&NmapResults::parse(SCAN).unwrap(), "10.0.0.1", &[22, 443]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 10.0.0.1 down, found it up (echo-reply)")]
+    fn host_up() {
+        assert_host_down(&NmapResults::parse(SCAN).unwrap(), "10.0.0.1");
+    }
+}
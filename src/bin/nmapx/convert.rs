@@ -0,0 +1,159 @@
+//!`nmapx convert`: renders a scan with one of the crate's exporters.
+//!
+//!The input format is detected unless given with `--from`. The output
+//!format is given with `--to`, or guessed from the extension of the
+//!`--output` file, and defaults to JSON.
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitCode;
+
+use nmap_xml_parser::export::{csv, json};
+use nmap_xml_parser::import::{self, Format};
+use nmap_xml_parser::report::html;
+use nmap_xml_parser::NmapResults;
+
+use crate::{open_output, read_input, Args, CliError};
+
+///The formats `convert` writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    Json,
+    Ndjson,
+    Csv,
+    Html,
+}
+
+impl Output {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Output::Json),
+            "ndjson" | "jsonl" => Some(Output::Ndjson),
+            "csv" => Some(Output::Csv),
+            "html" | "htm" => Some(Output::Html),
+            _ => None,
+        }
+    }
+
+    fn write<W: Write>(self, results: &NmapResults, w: &mut W) -> std::io::Result<()> {
+        match self {
+            Output::Json => json::write(results, w),
+            Output::Ndjson => json::write_hosts(results, w),
+            Output::Csv => csv::write(results, w),
+            Output::Html => html::write(results, w),
+        }
+    }
+}
+
+pub fn run(args: &[String]) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["from", "to", "output"], &[])?;
+    let input = match args.positional() {
+        [] => None,
+        [input] => Some(input.as_str()),
+        _ => return Err(CliError::Usage("convert takes a single input".to_string())),
+    };
+    let from = args.value("from").map(input_format).transpose()?;
+    let to = match args.value("to") {
+        Some(name) => Output::from_name(name)
+            .ok_or_else(|| CliError::Usage(format!("unknown output format `{}`", name)))?,
+        None => args
+            .value("output")
+            .and_then(|path| Path::new(path).extension())
+            .and_then(|ext| Output::from_name(&ext.to_string_lossy().to_lowercase()))
+            .unwrap_or(Output::Json),
+    };
+
+    let results = parse(&read_input(input)?, from)?;
+    let mut output = open_output(args.value("output"))?;
+    to.write(&results, &mut output)?;
+    output.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+///Parses `input` as `format`, or as whatever format it looks like.
+pub fn parse(input: &[u8], format: Option<Format>) -> Result<NmapResults, CliError> {
+    match format {
+        Some(format) => {
+            let input = std::str::from_utf8(input)
+                .map_err(|_| CliError::Failed("input is not valid UTF-8".to_string()))?;
+            Ok(format.parse(input)?)
+        }
+        None => Ok(import::parse_any(input)?),
+    }
+}
+
+///Returns the import format named `name` on the command line.
+pub fn input_format(name: &str) -> Result<Format, CliError> {
+    match name {
+        "xml" | "nmap" => Ok(Format::NmapXml),
+        "grepable" | "gnmap" => Ok(Format::NmapGrepable),
+        "normal" => Ok(Format::NmapNormal),
+        "masscan-xml" => Ok(Format::MasscanXml),
+        "masscan-json" => Ok(Format::MasscanJson),
+        "masscan-list" => Ok(Format::MasscanList),
+        "zmap" => Ok(Format::ZmapCsv),
+        "naabu" => Ok(Format::NaabuJson),
+        #[cfg(feature = "nessus")]
+        "nessus" => Ok(Format::Nessus),
+        _ => Err(CliError::Usage(format!("unknown input format `{}`", name))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    fn convert(input: &str, from: Option<&str>, to: &str) -> String {
+        let from = from.map(|f| input_format(f).unwrap());
+        let results = parse(input.as_bytes(), from).unwrap();
+        let mut out = Vec::new();
+        Output::from_name(to)
+            .unwrap()
+            .write(&results, &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn convert_formats() {
+        assert_eq!(
+            convert(SCAN, None, "csv"),
+            "ip,hostname,host_state,protocol,port,state,service,product,version\n\
+             10.0.0.1,,up,tcp,22,open,ssh,,\n"
+        );
+        assert_eq!(convert(SCAN, Some("xml"), "ndjson").lines().count(), 1);
+        assert!(convert(SCAN, None, "html").starts_with("<!DOCTYPE html>"));
+        assert_eq!(
+            convert(
+                "Host: 10.0.0.1 ()\tPorts: 22/open/tcp//ssh///\n",
+                None,
+                "json"
+            ),
+            convert(
+                "Host: 10.0.0.1 ()\tPorts: 22/open/tcp//ssh///\n",
+                Some("gnmap"),
+                "json"
+            )
+        );
+    }
+
+    #[test]
+    fn convert_usage() {
+        let run = |args: &[&str]| {
+            run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(run(&["--to", "xlsx"]), "unknown output format `xlsx`");
+        assert_eq!(run(&["--from", "pcap"]), "unknown input format `pcap`");
+        assert_eq!(run(&["a.xml", "b.xml"]), "convert takes a single input");
+    }
+}
@@ -0,0 +1,190 @@
+//!`nmapx diff`: changes between two scans of the same targets.
+//!
+//!Hosts are matched as [`ScanDiff`] does, on their IP address, and only
+//!hosts that are up count. The default text format gives a line per change:
+//!
+//!```text
+//!+ 10.0.0.4: host up
+//!- 10.0.0.2: host gone
+//!+ 10.0.0.1: 443/tcp https
+//!- 10.0.0.1: 23/tcp telnet
+//!~ 10.0.0.1: 22/tcp ssh OpenSSH 7.4 -> OpenSSH 8.0
+//!```
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use nmap_xml_parser::diff::ScanDiff;
+use nmap_xml_parser::export::json;
+use nmap_xml_parser::port::Port;
+use nmap_xml_parser::report::ndiff;
+
+use crate::convert::parse;
+use crate::{address, describe, open_output, read_input, Args, CliError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Text,
+    Ndiff,
+    Json,
+}
+
+pub fn run(args: &[String]) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["format", "output"], &["exit-code"])?;
+    let (old, new) = match args.positional() {
+        [old, new] => (old.as_str(), new.as_str()),
+        _ => return Err(CliError::Usage("diff takes two scans".to_string())),
+    };
+    let format = match args.value("format").unwrap_or("text") {
+        "text" => Format::Text,
+        "ndiff" => Format::Ndiff,
+        "json" => Format::Json,
+        name => return Err(CliError::Usage(format!("unknown diff format `{}`", name))),
+    };
+
+    let old = parse(&read_input(Some(old))?, None)?;
+    let new = parse(&read_input(Some(new))?, None)?;
+    let diff = ScanDiff::between(&old, &new);
+
+    let mut output = open_output(args.value("output"))?;
+    write(&diff, format, &mut output)?;
+    output.flush()?;
+
+    if args.flag("exit-code") && !diff.is_empty() {
+        Ok(ExitCode::from(1))
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+fn write<W: Write>(diff: &ScanDiff, format: Format, w: &mut W) -> io::Result<()> {
+    match format {
+        Format::Text => write_text(diff, w),
+        Format::Ndiff => ndiff::write(diff, w),
+        Format::Json => json::write_diff(diff, w),
+    }
+}
+
+fn write_text<W: Write>(diff: &ScanDiff, w: &mut W) -> io::Result<()> {
+    for host in diff.new_hosts() {
+        writeln!(w, "+ {}: host up", address(host))?;
+    }
+    for host in diff.removed_hosts() {
+        writeln!(w, "- {}: host gone", address(host))?;
+    }
+    for host_diff in diff.changed_hosts() {
+        let address = address(host_diff.new);
+        for port in host_diff.opened() {
+            writeln!(w, "+ {}: {}", address, describe(port))?;
+        }
+        for port in host_diff.closed() {
+            writeln!(w, "- {}: {}", address, describe(port))?;
+        }
+        for change in host_diff.service_changes() {
+            writeln!(
+                w,
+                "~ {}: {} -> {}",
+                address,
+                describe(change.old),
+                version(change.new)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn version(port: &Port) -> String {
+    port.service_info
+        .as_ref()
+        .and_then(|s| s.version_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nmap_xml_parser::NmapResults;
+
+    const OLD: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    const NEW: &str = r#"<nmaprun start="1588405212">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.4" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn text_diff() {
+        let old = NmapResults::parse(OLD).unwrap();
+        let new = NmapResults::parse(NEW).unwrap();
+        let mut out = Vec::new();
+        write(&ScanDiff::between(&old, &new), Format::Text, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "+ 10.0.0.4: host up\n\
+             - 10.0.0.2: host gone\n\
+             + 10.0.0.1: 443/tcp https\n\
+             - 10.0.0.1: 23/tcp telnet\n\
+             ~ 10.0.0.1: 22/tcp ssh OpenSSH 7.4 -> OpenSSH 8.0\n"
+        );
+    }
+
+    #[test]
+    fn diff_usage() {
+        let run = |args: &[&str]| {
+            run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(run(&["old.xml"]), "diff takes two scans");
+        assert_eq!(
+            run(&["--format", "yaml", "old.xml", "new.xml"]),
+            "unknown diff format `yaml`"
+        );
+    }
+
+    #[test]
+    fn exit_code() {
+        let dir = std::env::temp_dir();
+        let path = |name: &str| {
+            dir.join(format!("nmapx-{}-{}", std::process::id(), name))
+                .to_string_lossy()
+                .into_owned()
+        };
+        let (old, new, out) = (path("old.xml"), path("new.xml"), path("diff.json"));
+        std::fs::write(&old, OLD).unwrap();
+        std::fs::write(&new, NEW).unwrap();
+
+        let run = |a: &str, b: &str| {
+            let args = ["--exit-code", "--format=json", "--output", &out, a, b];
+            run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>()).unwrap()
+        };
+        assert_eq!(run(&old, &new), ExitCode::from(1));
+        assert!(std::fs::read_to_string(&out)
+            .unwrap()
+            .starts_with(r#"{"new_hosts":[{"#));
+        assert_eq!(run(&old, &old), ExitCode::SUCCESS);
+
+        for file in [old, new, out] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+}
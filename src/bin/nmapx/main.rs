@@ -0,0 +1,249 @@
+//!`nmapx`, a command line front end to the crate, built with the `cli`
+//!feature.
+//!
+//!Input is read from the file given on the command line, or from standard
+//!input when there is none or it is `-`. Output goes to the file given with
+//!`--output`, or to standard output.
+//!
+//!Like `diff` and `grep`, `nmapx` exits with status 2 on errors, leaving 1
+//!for commands reporting a finding, such as `diff --exit-code`, or, for
+//!`query`, the lack of one.
+mod convert;
+mod diff;
+mod query;
+mod summarize;
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use nmap_xml_parser::host::Host;
+use nmap_xml_parser::port::Port;
+
+const USAGE: &str = "\
+usage: nmapx <command> [options]
+
+commands:
+  convert [--from FORMAT] [--to FORMAT] [--output FILE] [INPUT]
+      Converts Nmap XML, or any format nmapx can import, to json, ndjson,
+      csv or html.
+  diff [--format FORMAT] [--output FILE] [--exit-code] OLD NEW
+      Lists the hosts and ports that appeared or went away between two
+      scans, and the services that changed, as text, ndiff or json. With
+      --exit-code, exits with status 1 if the scans differ.
+  query [--format FORMAT] [--output FILE] [--hosts] QUERY [INPUT]
+      Lists the ports matching a query such as
+      'service.name == http && port.state == open', or with --hosts the
+      matching hosts, as text or json. Exits with status 1 if nothing
+      matches.
+  summarize [--top N] [--output FILE] [INPUT]
+      Prints host and port counts, scan durations and the N most common
+      open ports and services (10 by default).
+";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(String::as_str) {
+        Some("convert") => convert::run(&args[1..]),
+        Some("diff") => diff::run(&args[1..]),
+        Some("query") => query::run(&args[1..]),
+        Some("summarize") => summarize::run(&args[1..]),
+        Some("-h") | Some("--help") | Some("help") => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(CliError::Usage(format!("unknown command `{}`", command))),
+        None => Err(CliError::Usage("no command given".to_string())),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(CliError::Usage(message)) => {
+            eprint!("nmapx: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("nmapx: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+///Errors ending a command: wrong usage, reported with the usage text, or a
+///failure to do what was asked.
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) | CliError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<nmap_xml_parser::Error> for CliError {
+    fn from(e: nmap_xml_parser::Error) -> Self {
+        CliError::Failed(e.to_string())
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Failed(e.to_string())
+    }
+}
+
+///Command line arguments split into `--name value` options, `--name`
+///flags and positional arguments.
+#[derive(Debug, Default)]
+pub struct Args {
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    ///Splits `args`. Options listed in `options` take a value, given as the
+    ///next argument or after `=`; those in `flags` do not. Anything after
+    ///`--` is positional.
+    pub fn parse(args: &[String], options: &[&str], flags: &[&str]) -> Result<Self, CliError> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positional.extend(args.by_ref().cloned());
+                break;
+            }
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name,
+                None => {
+                    parsed.positional.push(arg.clone());
+                    continue;
+                }
+            };
+
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            if options.contains(&name) {
+                let value = match value {
+                    Some(value) => value,
+                    None => args
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| CliError::Usage(format!("`--{}` needs a value", name)))?,
+                };
+                parsed.options.push((name.to_string(), value));
+            } else if flags.contains(&name) && value.is_none() {
+                parsed.flags.push(name.to_string());
+            } else {
+                return Err(CliError::Usage(format!("unknown option `--{}`", name)));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    ///Returns the last value of the option `name`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    ///Returns `true` if the flag `name` was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+}
+
+///Returns the IP address of `host`, or its summary if it has none.
+pub fn address(host: &Host) -> String {
+    host.primary_ip()
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| host.to_string())
+}
+
+///Describes `port` with its service, e.g. `22/tcp ssh OpenSSH 7.4`.
+pub fn describe(port: &Port) -> String {
+    match service(port) {
+        Some(service) => format!("{}/{} {}", port.port_number, port.protocol, service),
+        None => format!("{}/{}", port.port_number, port.protocol),
+    }
+}
+
+///Describes the service of `port`, e.g. `ssh OpenSSH 7.4`.
+pub fn service(port: &Port) -> Option<String> {
+    let service = port.service_info.as_ref()?;
+    match service.version_string() {
+        Some(version) => Some(format!("{} {}", service.name, version)),
+        None => Some(service.name.clone()),
+    }
+}
+
+///Reads the file at `path`, or standard input for `None` and `-`.
+pub fn read_input(path: Option<&str>) -> Result<Vec<u8>, CliError> {
+    let mut input = Vec::new();
+    match path {
+        None | Some("-") => {
+            io::stdin().read_to_end(&mut input)?;
+        }
+        Some(path) => {
+            File::open(path)
+                .and_then(|mut f| f.read_to_end(&mut input))
+                .map_err(|e| CliError::Failed(format!("cannot read `{}`: {}", path, e)))?;
+        }
+    }
+    Ok(input)
+}
+
+///Opens the file at `path` for writing, or standard output for `None` and
+///`-`.
+pub fn open_output(path: Option<&str>) -> Result<Box<dyn Write>, CliError> {
+    match path {
+        None | Some("-") => Ok(Box::new(BufWriter::new(io::stdout()))),
+        Some(path) => File::create(path)
+            .map(|f| Box::new(BufWriter::new(f)) as Box<dyn Write>)
+            .map_err(|e| CliError::Failed(format!("cannot write `{}`: {}", path, e))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn argument_parsing() {
+        let parsed = Args::parse(
+            &args(&["--to", "csv", "scan.xml", "--output=out.csv", "--quiet"]),
+            &["to", "output"],
+            &["quiet"],
+        )
+        .unwrap();
+        assert_eq!(parsed.value("to"), Some("csv"));
+        assert_eq!(parsed.value("output"), Some("out.csv"));
+        assert!(parsed.flag("quiet"));
+        assert_eq!(parsed.positional(), ["scan.xml"]);
+
+        let error = |a: &[&str]| Args::parse(&args(a), &["to"], &[]).unwrap_err().to_string();
+        assert_eq!(error(&["--to"]), "`--to` needs a value");
+        assert_eq!(error(&["--from", "xml"]), "unknown option `--from`");
+    }
+}
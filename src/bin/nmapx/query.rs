@@ -0,0 +1,169 @@
+//!`nmapx query`: the ports, or hosts, matching a
+//![query](nmap_xml_parser::query).
+//!
+//!Text output has a line per port, `10.0.0.1 22/tcp open ssh OpenSSH 7.4`,
+//!or with `--hosts` a line per host address. JSON output is the array of
+//![`query_to_string()`](nmap_xml_parser::export::json::query_to_string),
+//!or with `--hosts` the scan document restricted to the matching hosts.
+//!Like `grep`, the command exits with status 1 when nothing matches.
+use std::io::Write;
+use std::process::ExitCode;
+
+use nmap_xml_parser::export::json;
+use nmap_xml_parser::query::Query;
+use nmap_xml_parser::NmapResults;
+
+use crate::convert::parse;
+use crate::{address, open_output, read_input, service, Args, CliError};
+
+pub fn run(args: &[String]) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["format", "output"], &["hosts"])?;
+    let (text, input) = match args.positional() {
+        [text] => (text.as_str(), None),
+        [text, input] => (text.as_str(), Some(input.as_str())),
+        _ => {
+            return Err(CliError::Usage(
+                "query takes a query and a scan".to_string(),
+            ))
+        }
+    };
+    let json = match args.value("format").unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+        name => return Err(CliError::Usage(format!("unknown query format `{}`", name))),
+    };
+    let query = Query::parse(text)?;
+
+    let mut results = parse(&read_input(input)?, None)?;
+    let mut output = open_output(args.value("output"))?;
+    let matched = if args.flag("hosts") {
+        results.retain_hosts(|host| query.matches_host(host));
+        if json {
+            json::write(&results, &mut output)?;
+        } else {
+            for host in results.hosts() {
+                writeln!(output, "{}", address(host))?;
+            }
+        }
+        !results.is_empty()
+    } else {
+        let ports = matching_ports(&results, &query);
+        if json {
+            writeln!(output, "{}", json::query_to_string(&results, text)?)?;
+        } else {
+            for line in &ports {
+                writeln!(output, "{}", line)?;
+            }
+        }
+        !ports.is_empty()
+    };
+    output.flush()?;
+
+    if matched {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(1))
+    }
+}
+
+//`10.0.0.1 22/tcp open ssh OpenSSH 7.4`
+fn matching_ports(results: &NmapResults, query: &Query) -> Vec<String> {
+    results
+        .iter_ports()
+        .filter(|(host, port)| query.matches_port(host, port))
+        .map(|(host, port)| {
+            let line = format!(
+                "{} {}/{} {}",
+                address(host),
+                port.port_number,
+                port.protocol,
+                port.status.state
+            );
+            match service(port) {
+                Some(service) => format!("{} {}", line, service),
+                None => line,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="closed" reason="reset" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+<port protocol="tcp" portid="8080"><state state="filtered" reason="no-response" reason_ttl="0"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn port_lines() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let lines = |query: &str| matching_ports(&results, &Query::parse(query).unwrap());
+
+        assert_eq!(
+            lines("service.name == http || port.number == 22"),
+            [
+                "10.0.0.1 22/tcp open ssh OpenSSH 7.4",
+                "10.0.0.1 80/tcp open http",
+                "10.0.0.2 80/tcp closed http"
+            ]
+        );
+        assert_eq!(
+            lines("port.state == filtered"),
+            ["10.0.0.2 8080/tcp filtered"]
+        );
+        assert!(lines("service.name == ftp").is_empty());
+    }
+
+    #[test]
+    fn query_exit_code() {
+        let path = std::env::temp_dir()
+            .join(format!("nmapx-{}-query.xml", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let out = format!("{}.out", path);
+        std::fs::write(&path, SCAN).unwrap();
+
+        let run = |args: &[&str]| {
+            let mut args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+            args.extend(vec!["--output".to_string(), out.clone(), path.clone()]);
+            run(&args).unwrap()
+        };
+        assert_eq!(
+            run(&["--hosts", "service.name == http && port.state == open"]),
+            ExitCode::SUCCESS
+        );
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "10.0.0.1\n");
+        assert_eq!(run(&["service.name == ftp"]), ExitCode::from(1));
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(out).unwrap();
+    }
+
+    #[test]
+    fn query_usage() {
+        let run = |args: &[&str]| {
+            run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(run(&[]), "query takes a query and a scan");
+        assert_eq!(
+            run(&["--format", "csv", "port.state == open"]),
+            "unknown query format `csv`"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//!`nmapx summarize`: an overview of a scan, built from
+//![`ScanStats`] and the top ports and services of
+//![`stats`].
+//!
+//!```text
+//!Nmap 7.80: nmap -sV 10.0.0.0/24
+//!Started 2020-05-01T07:40:12Z, took 72s
+//!Hosts: 3 (2 up, 1 down)
+//!Ports: 3 listed (2 open, 1 closed), 1997 unlisted
+//!Host scan times: min 5s, avg 7s, max 9s
+//!
+//!Top ports:
+//!  22 open on 2 hosts
+//!Top services:
+//!  ssh on 2 ports
+//!```
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use nmap_xml_parser::stats::{self, ScanStats};
+use nmap_xml_parser::util::format_rfc3339;
+use nmap_xml_parser::NmapResults;
+
+use crate::convert::parse;
+use crate::{open_output, read_input, Args, CliError};
+
+pub fn run(args: &[String]) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["top", "output"], &[])?;
+    let input = match args.positional() {
+        [] => None,
+        [input] => Some(input.as_str()),
+        _ => {
+            return Err(CliError::Usage(
+                "summarize takes a single input".to_string(),
+            ))
+        }
+    };
+    let top = match args.value("top") {
+        Some(top) => top
+            .parse()
+            .map_err(|_| CliError::Usage(format!("`--top` needs a number, not `{}`", top)))?,
+        None => 10,
+    };
+
+    let results = parse(&read_input(input)?, None)?;
+    let mut output = open_output(args.value("output"))?;
+    write(&results, top, &mut output)?;
+    output.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn write<W: Write>(results: &NmapResults, top: usize, w: &mut W) -> io::Result<()> {
+    let stats = ScanStats::from(results);
+
+    match (&results.nmap_version, &results.args) {
+        (Some(version), Some(args)) => writeln!(w, "Nmap {}: {}", version, args)?,
+        (Some(version), None) => writeln!(w, "Nmap {}", version)?,
+        (None, Some(args)) => writeln!(w, "{}", args)?,
+        (None, None) => {}
+    }
+    write!(w, "Started {}", format_rfc3339(results.scan_start_time))?;
+    match results.scan_duration() {
+        Some(duration) => writeln!(w, ", took {}", seconds(duration))?,
+        None => writeln!(w, ", not finished")?,
+    }
+
+    let hosts = &stats.hosts;
+    let host_counts = counts(&[
+        (hosts.up, "up"),
+        (hosts.down, "down"),
+        (hosts.unknown, "unknown"),
+        (hosts.skipped, "skipped"),
+    ]);
+    writeln!(w, "Hosts: {}{}", hosts.total, host_counts)?;
+
+    let ports = &stats.ports;
+    let port_counts = counts(&[
+        (ports.open, "open"),
+        (ports.closed, "closed"),
+        (ports.filtered, "filtered"),
+        (ports.unfiltered, "unfiltered"),
+        (ports.open_filtered, "open|filtered"),
+        (ports.closed_filtered, "closed|filtered"),
+    ]);
+    writeln!(
+        w,
+        "Ports: {} listed{}, {} unlisted",
+        ports.total, port_counts, ports.unlisted
+    )?;
+
+    let durations = results
+        .hosts()
+        .filter_map(|h| h.scan_duration())
+        .collect::<Vec<_>>();
+    if let (Some(min), Some(max)) = (durations.iter().min(), durations.iter().max()) {
+        let total = durations.iter().sum::<Duration>();
+        writeln!(
+            w,
+            "Host scan times: min {}, avg {}, max {}",
+            seconds(*min),
+            seconds(total / durations.len() as u32),
+            seconds(*max)
+        )?;
+    }
+
+    let top_ports = stats::top_ports(results, top);
+    if !top_ports.is_empty() {
+        writeln!(w, "\nTop ports:")?;
+        for (port, hosts) in top_ports {
+            writeln!(w, "  {} open on {} {}", port, hosts, plural(hosts, "host"))?;
+        }
+    }
+    let top_services = stats::top_services(results, top);
+    if !top_services.is_empty() {
+        writeln!(w, "Top services:")?;
+        for (service, ports) in top_services {
+            writeln!(w, "  {} on {} {}", service, ports, plural(ports, "port"))?;
+        }
+    }
+    Ok(())
+}
+
+//` (2 up, 1 down)`, leaving out the zero counts
+fn counts(counts: &[(usize, &str)]) -> String {
+    let counts = counts
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{} {}", count, name))
+        .collect::<Vec<_>>();
+    if counts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", counts.join(", "))
+    }
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{}s", duration.as_secs())
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let results = NmapResults::parse(
+            r#"<nmaprun scanner="nmap" args="nmap -sV 10.0.0.0/24" start="1588318812" version="7.80">
+<host starttime="1588318815" endtime="1588318820"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<extraports state="closed" count="998"><extrareasons reason="resets" count="998"/></extraports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="80"><state state="closed" reason="reset" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+</host>
+<host starttime="1588318815" endtime="1588318824"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<extraports state="closed" count="999"><extrareasons reason="resets" count="999"/></extraports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+<runstats><finished time="1588318884" timestr="Fri May  1 07:41:24 2020" elapsed="72.00" exit="success"/><hosts up="2" down="1" total="3"/></runstats>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        write(&results, 10, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Nmap 7.80: nmap -sV 10.0.0.0/24\n\
+             Started 2020-05-01T07:40:12Z, took 72s\n\
+             Hosts: 3 (2 up, 1 down)\n\
+             Ports: 3 listed (2 open, 1 closed), 1997 unlisted\n\
+             Host scan times: min 5s, avg 7s, max 9s\n\
+             \n\
+             Top ports:\n  \
+             22 open on 2 hosts\n\
+             Top services:\n  \
+             ssh on 2 ports\n"
+        );
+    }
+
+    #[test]
+    fn summarize_usage() {
+        let run = |args: &[&str]| {
+            run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(run(&["--top", "ten"]), "`--top` needs a number, not `ten`");
+        assert_eq!(run(&["a.xml", "b.xml"]), "summarize takes a single input");
+    }
+}
@@ -0,0 +1,488 @@
+//!Builders for constructing results without parsing a scan.
+//!
+//!Tests, importers and tools synthesizing scan data can build
+//![`NmapResults`](../struct.NmapResults.html), hosts and ports directly.
+//!Values are checked when `build()` is called:
+//!
+//!```
+//!use nmap_xml_parser::builder::{HostBuilder, NmapResultsBuilder, PortBuilder};
+//!use nmap_xml_parser::port::{PortProtocol, PortState};
+//!
+//!let results = NmapResultsBuilder::new()
+//!    .start_time(1588318812)
+//!    .host(
+//!        HostBuilder::new()
+//!            .ip("10.0.0.1".parse().unwrap())
+//!            .port(
+//!                PortBuilder::new(PortProtocol::Tcp, 22)
+//!                    .state(PortState::Open)
+//!                    .service("ssh")
+//!                    .product("OpenSSH")
+//!                    .build()
+//!                    .unwrap(),
+//!            )
+//!            .build()
+//!            .unwrap(),
+//!    )
+//!    .build()
+//!    .unwrap();
+//!assert_eq!(results.hosts().count(), 1);
+//!```
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::cpe::Cpe;
+use crate::host::{Address, Host, HostState, HostStatus, Hostname, HostnameType, Script};
+use crate::net::MacAddr;
+use crate::port::{
+    ExtraPorts, Port, PortProtocol, PortState, PortStatus, ServiceInfo, ServiceMethod,
+};
+use crate::{Error, NmapResults};
+
+///Builds [`NmapResults`](../struct.NmapResults.html).
+#[derive(Clone, Debug, Default)]
+pub struct NmapResultsBuilder {
+    hosts: Vec<Host>,
+    start_time: i64,
+    end_time: Option<i64>,
+    args: Option<String>,
+    nmap_version: Option<String>,
+}
+
+impl NmapResultsBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Sets the scan start time, in seconds since Unix epoch.
+    pub fn start_time(mut self, time: i64) -> Self {
+        self.start_time = time;
+        self
+    }
+
+    ///Sets the scan end time, in seconds since Unix epoch.
+    pub fn end_time(mut self, time: i64) -> Self {
+        self.end_time = Some(time);
+        self
+    }
+
+    pub fn args(mut self, args: &str) -> Self {
+        self.args = Some(args.to_string());
+        self
+    }
+
+    pub fn nmap_version(mut self, version: &str) -> Self {
+        self.nmap_version = Some(version.to_string());
+        self
+    }
+
+    pub fn host(mut self, host: Host) -> Self {
+        self.hosts.push(host);
+        self
+    }
+
+    ///Returns the results, failing if the scan ends before it starts.
+    pub fn build(self) -> Result<NmapResults, Error> {
+        if self.end_time.is_some_and(|end| end < self.start_time) {
+            return Err(invalid("scan end time is before its start time"));
+        }
+
+        let mut results = NmapResults::empty();
+        results.hosts = self.hosts;
+        results.scan_start_time = self.start_time;
+        results.scan_end_time = self.end_time;
+        results.args = self.args;
+        results.nmap_version = self.nmap_version;
+        Ok(results)
+    }
+}
+
+///Builds a [`Host`](../host/struct.Host.html). Hosts are up unless told
+///otherwise.
+#[derive(Clone, Debug)]
+pub struct HostBuilder {
+    addresses: Vec<Address>,
+    state: HostState,
+    reason: String,
+    reason_ttl: u8,
+    host_names: Vec<Hostname>,
+    ports: Vec<Port>,
+    extra_ports: Vec<ExtraPorts>,
+    scripts: Vec<Script>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    mac_vendor: Option<String>,
+}
+
+impl Default for HostBuilder {
+    fn default() -> Self {
+        HostBuilder {
+            addresses: Vec::new(),
+            state: HostState::Up,
+            reason: String::new(),
+            reason_ttl: 0,
+            host_names: Vec::new(),
+            ports: Vec::new(),
+            extra_ports: Vec::new(),
+            scripts: Vec::new(),
+            start_time: None,
+            end_time: None,
+            mac_vendor: None,
+        }
+    }
+}
+
+impl HostBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn ip(mut self, ip: IpAddr) -> Self {
+        self.addresses.push(Address::IpAddr(ip));
+        self
+    }
+
+    pub fn mac(mut self, mac: MacAddr) -> Self {
+        self.addresses.push(Address::MacAddr(mac));
+        self
+    }
+
+    pub fn mac_vendor(mut self, vendor: &str) -> Self {
+        self.mac_vendor = Some(vendor.to_string());
+        self
+    }
+
+    pub fn state(mut self, state: HostState) -> Self {
+        self.state = state;
+        self
+    }
+
+    ///Sets the reason for the host state, e.g. `echo-reply`, and the TTL
+    ///of the response.
+    pub fn reason(mut self, reason: &str, ttl: u8) -> Self {
+        self.reason = reason.to_string();
+        self.reason_ttl = ttl;
+        self
+    }
+
+    pub fn hostname(mut self, name: &str, source: HostnameType) -> Self {
+        self.host_names.push(Hostname {
+            name: name.to_string(),
+            source,
+        });
+        self
+    }
+
+    pub fn port(mut self, port: Port) -> Self {
+        self.ports.push(port);
+        self
+    }
+
+    ///Adds `count` ports in `state` that are not listed individually.
+    pub fn extra_ports(mut self, state: PortState, count: u32) -> Self {
+        self.extra_ports.push(ExtraPorts { state, count });
+        self
+    }
+
+    ///Adds a host script.
+    pub fn script(mut self, id: &str, output: &str) -> Self {
+        self.scripts
+            .push(Script::new(id.to_string(), output.to_string()));
+        self
+    }
+
+    ///Sets the start and end times of the scan of the host, in seconds
+    ///since Unix epoch.
+    pub fn times(mut self, start: i64, end: i64) -> Self {
+        self.start_time = Some(start);
+        self.end_time = Some(end);
+        self
+    }
+
+    ///Returns the host, failing if it has no address, a port is listed
+    ///twice or the scan of the host ends before it starts.
+    pub fn build(self) -> Result<Host, Error> {
+        if self.addresses.is_empty() {
+            return Err(invalid("host has no address"));
+        }
+        for (i, port) in self.ports.iter().enumerate() {
+            if self.ports[..i]
+                .iter()
+                .any(|p| p.protocol == port.protocol && p.port_number == port.port_number)
+            {
+                return Err(invalid(format!(
+                    "port {}/{} is listed twice",
+                    port.port_number, port.protocol
+                )));
+            }
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
+            if end < start {
+                return Err(invalid("host scan end time is before its start time"));
+            }
+        }
+
+        let mut host = Host::new(
+            self.addresses,
+            HostStatus {
+                state: self.state,
+                reason: self.reason,
+                reason_ttl: self.reason_ttl,
+            },
+        );
+        host.host_names = self.host_names;
+        host.port_info.ports = self.ports;
+        host.port_info.extra_ports = self.extra_ports;
+        host.scripts = self.scripts;
+        host.scan_start_time = self.start_time;
+        host.scan_end_time = self.end_time;
+        host.mac_vendor = self.mac_vendor;
+        Ok(host)
+    }
+}
+
+///Builds a [`Port`](../port/struct.Port.html). Ports are open unless told
+///otherwise, and have a service only if one is named.
+#[derive(Clone, Debug)]
+pub struct PortBuilder {
+    protocol: PortProtocol,
+    port_number: u16,
+    state: PortState,
+    reason: String,
+    reason_ttl: u8,
+    service: Option<String>,
+    method: ServiceMethod,
+    confidence_level: u8,
+    product: Option<String>,
+    version: Option<String>,
+    extra_info: Option<String>,
+    tunnel: Option<String>,
+    cpes: Vec<String>,
+    scripts: Vec<Script>,
+}
+
+impl PortBuilder {
+    pub fn new(protocol: PortProtocol, port_number: u16) -> Self {
+        PortBuilder {
+            protocol,
+            port_number,
+            state: PortState::Open,
+            reason: String::new(),
+            reason_ttl: 0,
+            service: None,
+            method: ServiceMethod::Table,
+            confidence_level: 3,
+            product: None,
+            version: None,
+            extra_info: None,
+            tunnel: None,
+            cpes: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    pub fn state(mut self, state: PortState) -> Self {
+        self.state = state;
+        self
+    }
+
+    ///Sets the reason for the port state, e.g. `syn-ack`, and the TTL of
+    ///the response.
+    pub fn reason(mut self, reason: &str, ttl: u8) -> Self {
+        self.reason = reason.to_string();
+        self.reason_ttl = ttl;
+        self
+    }
+
+    ///Names the service, as looked up in Nmap's table unless
+    ///[`probed()`](#method.probed) is called.
+    pub fn service(mut self, name: &str) -> Self {
+        self.service = Some(name.to_string());
+        self
+    }
+
+    ///Marks the service as detected by probing, with a confidence from 0
+    ///to 10.
+    pub fn probed(mut self, confidence_level: u8) -> Self {
+        self.method = ServiceMethod::Probe;
+        self.confidence_level = confidence_level;
+        self
+    }
+
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = Some(product.to_string());
+        self
+    }
+
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    pub fn extra_info(mut self, extra_info: &str) -> Self {
+        self.extra_info = Some(extra_info.to_string());
+        self
+    }
+
+    ///Sets the tunnel the service was found through, `ssl` for TLS.
+    pub fn tunnel(mut self, tunnel: &str) -> Self {
+        self.tunnel = Some(tunnel.to_string());
+        self
+    }
+
+    ///Adds a CPE name of the service, e.g. `cpe:/a:openbsd:openssh:7.4`.
+    pub fn cpe(mut self, cpe: &str) -> Self {
+        self.cpes.push(cpe.to_string());
+        self
+    }
+
+    pub fn script(mut self, id: &str, output: &str) -> Self {
+        self.scripts
+            .push(Script::new(id.to_string(), output.to_string()));
+        self
+    }
+
+    ///Returns the port, failing if service details are given without a
+    ///service name, the confidence is above 10 or a CPE name is malformed.
+    pub fn build(self) -> Result<Port, Error> {
+        let has_details = self.product.is_some()
+            || self.version.is_some()
+            || self.extra_info.is_some()
+            || self.tunnel.is_some()
+            || !self.cpes.is_empty();
+        if self.service.is_none() && has_details {
+            return Err(invalid("service details without a service name"));
+        }
+        if self.confidence_level > 10 {
+            return Err(invalid(format!(
+                "service confidence {} is above 10",
+                self.confidence_level
+            )));
+        }
+
+        let service_info = match self.service {
+            Some(name) => Some(ServiceInfo {
+                name,
+                confidence_level: self.confidence_level,
+                method: self.method,
+                product: self.product,
+                version: self.version,
+                extra_info: self.extra_info,
+                tunnel: self.tunnel,
+                cpes: self
+                    .cpes
+                    .iter()
+                    .map(|cpe| Cpe::from_str(cpe))
+                    .collect::<Result<_, _>>()?,
+            }),
+            None => None,
+        };
+
+        Ok(Port {
+            protocol: self.protocol,
+            port_number: self.port_number,
+            status: PortStatus {
+                state: self.state,
+                reason: self.reason,
+                reason_ttl: self.reason_ttl,
+            },
+            service_info,
+            scripts: self.scripts,
+        })
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidData(message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn build_results() {
+        let results = NmapResultsBuilder::new()
+            .start_time(1588318812)
+            .end_time(1588318814)
+            .nmap_version("7.80")
+            .host(
+                HostBuilder::new()
+                    .ip("10.0.0.1".parse().unwrap())
+                    .mac("00:0C:29:71:23:2B".parse().unwrap())
+                    .mac_vendor("VMware")
+                    .hostname("web.example.com", HostnameType::User)
+                    .port(
+                        PortBuilder::new(PortProtocol::Tcp, 443)
+                            .service("https")
+                            .probed(10)
+                            .product("nginx")
+                            .tunnel("ssl")
+                            .cpe("cpe:/a:igor_sysoev:nginx")
+                            .script("http-title", "Home")
+                            .build()
+                            .unwrap(),
+                    )
+                    .extra_ports(PortState::Closed, 999)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let host = results.hosts().next().unwrap();
+        assert_eq!(host.status.state, HostState::Up);
+        assert_eq!(host.mac_vendor(), Some("VMware"));
+        assert_eq!(
+            results
+                .host_by_addr(&"10.0.0.1".parse().unwrap())
+                .unwrap()
+                .host_names()
+                .next()
+                .unwrap()
+                .name,
+            "web.example.com"
+        );
+        let port = host.port_info.ports().next().unwrap();
+        let service = port.service_info.as_ref().unwrap();
+        assert_eq!(service.method, ServiceMethod::Probe);
+        assert_eq!(service.cpes[0].product, "nginx");
+        assert_eq!(port.scripts[0].id, "http-title");
+        assert_eq!(host.port_info.extra_ports().next().unwrap().count, 999);
+    }
+
+    #[test]
+    fn build_validation() {
+        let error = |e: Error| e.to_string();
+        assert_eq!(
+            error(HostBuilder::new().build().unwrap_err()),
+            "invalid scan data: host has no address"
+        );
+        let port = || PortBuilder::new(PortProtocol::Tcp, 22).build().unwrap();
+        assert_eq!(
+            error(
+                HostBuilder::new()
+                    .ip("10.0.0.1".parse().unwrap())
+                    .port(port())
+                    .port(port())
+                    .build()
+                    .unwrap_err()
+            ),
+            "invalid scan data: port 22/tcp is listed twice"
+        );
+        assert!(PortBuilder::new(PortProtocol::Tcp, 22)
+            .product("OpenSSH")
+            .build()
+            .is_err());
+        assert!(PortBuilder::new(PortProtocol::Tcp, 22)
+            .service("ssh")
+            .cpe("openssh")
+            .build()
+            .is_err());
+        assert!(NmapResultsBuilder::new()
+            .start_time(10)
+            .end_time(5)
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//!Common Platform Enumeration names, as reported by version and OS
+//!detection.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+
+///The kind of platform a CPE names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CpePart {
+    Application,
+    OperatingSystem,
+    Hardware,
+}
+
+///A CPE name such as `cpe:/a:openbsd:openssh:8.2p1`.
+///
+///Both the CPE 2.2 URI form used by Nmap and the CPE 2.3 formatted string
+///form (`cpe:2.3:a:openbsd:openssh:8.2p1:*:*:*:*:*:*:*`) are parsed.
+///Components that are empty or any (`*`, `-`) are `None`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cpe {
+    pub part: CpePart,
+    pub vendor: String,
+    pub product: String,
+    pub version: Option<String>,
+    pub update: Option<String>,
+    pub edition: Option<String>,
+    pub language: Option<String>,
+}
+
+impl FromStr for Cpe {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (components, formatted) = if let Some(rest) = s.strip_prefix("cpe:2.3:") {
+            (rest, true)
+        } else if let Some(rest) = s.strip_prefix("cpe:/") {
+            (rest, false)
+        } else {
+            return Err(Error::InvalidCpe(
+                "expected CPE name to start with `cpe:/` or `cpe:2.3:`".to_string(),
+            ));
+        };
+
+        let mut components = components.split(':').map(|c| {
+            if c.is_empty() || c == "*" || c == "-" {
+                None
+            } else if formatted {
+                Some(c.replace('\\', ""))
+            } else {
+                Some(percent_decode(c))
+            }
+        });
+
+        let part = match components.next().flatten().as_deref() {
+            Some("a") => CpePart::Application,
+            Some("o") => CpePart::OperatingSystem,
+            Some("h") => CpePart::Hardware,
+            _ => return Err(Error::InvalidCpe("failed to parse CPE part".to_string())),
+        };
+        let vendor = components
+            .next()
+            .flatten()
+            .ok_or_else(|| Error::InvalidCpe("expected vendor in CPE name".to_string()))?;
+        let product = components.next().flatten().unwrap_or_default();
+
+        Ok(Cpe {
+            part,
+            vendor,
+            product,
+            version: components.next().flatten(),
+            update: components.next().flatten(),
+            edition: components.next().flatten(),
+            language: components.next().flatten(),
+        })
+    }
+}
+
+///Formats the CPE as a CPE 2.2 URI, the form Nmap uses.
+impl fmt::Display for Cpe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let part = match self.part {
+            CpePart::Application => "a",
+            CpePart::OperatingSystem => "o",
+            CpePart::Hardware => "h",
+        };
+        let mut uri = format!("cpe:/{}:{}:{}", part, self.vendor, self.product);
+
+        let optional = [&self.version, &self.update, &self.edition, &self.language];
+        let last = optional.iter().rposition(|c| c.is_some());
+        if let Some(last) = last {
+            for component in &optional[..=last] {
+                uri.push(':');
+                uri.push_str(component.as_deref().unwrap_or(""));
+            }
+        }
+
+        write!(f, "{}", uri)
+    }
+}
+
+//The CPEs of the `cpe` children of `node`. Version detection reports the
+//CPEs of custom probe matches as written, so the ones that do not parse
+//are skipped rather than failing the scan.
+pub(crate) fn parse_cpe_nodes(node: roxmltree::Node) -> Vec<Cpe> {
+    node.children()
+        .filter(|child| child.tag_name().name() == "cpe")
+        .filter_map(|child| child.text().unwrap_or("").trim().parse().ok())
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let raw = s.as_bytes();
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == b'%' {
+            if let Some(Ok(byte)) = s.get(i + 1..i + 3).map(|h| u8::from_str_radix(h, 16)) {
+                bytes.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        bytes.push(raw[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cpe_uri() {
+        let cpe = "cpe:/a:openbsd:openssh:8.2p1".parse::<Cpe>().unwrap();
+        assert_eq!(cpe.part, CpePart::Application);
+        assert_eq!(cpe.vendor, "openbsd");
+        assert_eq!(cpe.product, "openssh");
+        assert_eq!(cpe.version.as_deref(), Some("8.2p1"));
+        assert_eq!(cpe.update, None);
+        assert_eq!(cpe.to_string(), "cpe:/a:openbsd:openssh:8.2p1");
+
+        let os = "cpe:/o:linux:linux_kernel".parse::<Cpe>().unwrap();
+        assert_eq!(os.part, CpePart::OperatingSystem);
+        assert_eq!(os.to_string(), "cpe:/o:linux:linux_kernel");
+
+        let encoded = "cpe:/a:microsoft:iis%2b:10.0".parse::<Cpe>().unwrap();
+        assert_eq!(encoded.product, "iis+");
+    }
+
+    #[test]
+    fn cpe_formatted_string() {
+        let cpe = "cpe:2.3:a:apache:http_server:2.4.41:*:*:*:*:*:*:*"
+            .parse::<Cpe>()
+            .unwrap();
+        assert_eq!(cpe.vendor, "apache");
+        assert_eq!(cpe.version.as_deref(), Some("2.4.41"));
+        assert_eq!(cpe.edition, None);
+        assert_eq!(cpe.to_string(), "cpe:/a:apache:http_server:2.4.41");
+
+        assert!("cpe:/x:vendor".parse::<Cpe>().is_err());
+        assert!("openssh".parse::<Cpe>().is_err());
+    }
+}
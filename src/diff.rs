@@ -0,0 +1,292 @@
+//!Differences between two scans of the same targets.
+//!
+//!Hosts are matched on their primary IP address; hosts without one are
+//!ignored. Only hosts that are up count as present, so a host that went
+//!down between the scans is reported as removed. Ports are matched on
+//!their protocol and number.
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::host::Host;
+use crate::port::{Port, PortState};
+use crate::NmapResults;
+
+///The differences between an old and a new scan.
+#[derive(Clone, Debug)]
+pub struct ScanDiff<'a> {
+    pub old: &'a NmapResults,
+    pub new: &'a NmapResults,
+    pub(crate) new_hosts: Vec<&'a Host>,
+    pub(crate) removed_hosts: Vec<&'a Host>,
+    pub(crate) changed_hosts: Vec<HostDiff<'a>>,
+}
+
+impl<'a> ScanDiff<'a> {
+    ///Compares `old` with `new`. Hosts are listed in the order of the scan
+    ///they were found in.
+    pub fn between(old: &'a NmapResults, new: &'a NmapResults) -> Self {
+        let old_hosts = up_hosts(old);
+        let new_hosts = up_hosts(new);
+
+        let mut diff = ScanDiff {
+            old,
+            new,
+            new_hosts: Vec::new(),
+            removed_hosts: Vec::new(),
+            changed_hosts: Vec::new(),
+        };
+        for (ip, host) in new_hosts.iter() {
+            match old_hosts.get(ip) {
+                Some(old_host) => {
+                    let host_diff = HostDiff::between(old_host, host);
+                    if !host_diff.is_empty() {
+                        diff.changed_hosts.push(host_diff);
+                    }
+                }
+                None => diff.new_hosts.push(host),
+            }
+        }
+        for (ip, host) in old_hosts.iter() {
+            if !new_hosts.contains_key(ip) {
+                diff.removed_hosts.push(host);
+            }
+        }
+        diff
+    }
+
+    ///Returns the hosts that are up in the new scan only.
+    pub fn new_hosts(&self) -> std::slice::Iter<'_, &'a Host> {
+        self.new_hosts.iter()
+    }
+
+    ///Returns the hosts that are up in the old scan only.
+    pub fn removed_hosts(&self) -> std::slice::Iter<'_, &'a Host> {
+        self.removed_hosts.iter()
+    }
+
+    ///Returns the hosts up in both scans whose ports or services changed.
+    pub fn changed_hosts(&self) -> std::slice::Iter<'_, HostDiff<'a>> {
+        self.changed_hosts.iter()
+    }
+
+    ///Returns `true` if the scans found the same hosts, ports and services.
+    pub fn is_empty(&self) -> bool {
+        self.new_hosts.is_empty() && self.removed_hosts.is_empty() && self.changed_hosts.is_empty()
+    }
+}
+
+///The differences between two scans of a host.
+#[derive(Clone, Debug)]
+pub struct HostDiff<'a> {
+    pub old: &'a Host,
+    pub new: &'a Host,
+    pub(crate) opened: Vec<&'a Port>,
+    pub(crate) closed: Vec<&'a Port>,
+    pub(crate) service_changes: Vec<ServiceChange<'a>>,
+}
+
+impl<'a> HostDiff<'a> {
+    fn between(old: &'a Host, new: &'a Host) -> Self {
+        let mut diff = HostDiff {
+            old,
+            new,
+            opened: Vec::new(),
+            closed: Vec::new(),
+            service_changes: Vec::new(),
+        };
+
+        for port in new.port_info.open_ports() {
+            match old
+                .port_info
+                .get_port(port.protocol.clone(), port.port_number)
+            {
+                Some(old_port) if old_port.status.state == PortState::Open => {
+                    if service_key(old_port) != service_key(port) {
+                        diff.service_changes.push(ServiceChange {
+                            old: old_port,
+                            new: port,
+                        });
+                    }
+                }
+                _ => diff.opened.push(port),
+            }
+        }
+        for port in old.port_info.open_ports() {
+            if !new
+                .port_info
+                .is_open(port.protocol.clone(), port.port_number)
+            {
+                diff.closed.push(port);
+            }
+        }
+
+        diff
+    }
+
+    ///Returns the ports open in the new scan only, as found in that scan.
+    pub fn opened(&self) -> std::slice::Iter<'_, &'a Port> {
+        self.opened.iter()
+    }
+
+    ///Returns the ports open in the old scan only, as found in that scan.
+    pub fn closed(&self) -> std::slice::Iter<'_, &'a Port> {
+        self.closed.iter()
+    }
+
+    ///Returns the ports open in both scans whose service name, product or
+    ///version changed.
+    pub fn service_changes(&self) -> std::slice::Iter<'_, ServiceChange<'a>> {
+        self.service_changes.iter()
+    }
+
+    ///Returns `true` if nothing changed on the host.
+    pub fn is_empty(&self) -> bool {
+        self.opened.is_empty() && self.closed.is_empty() && self.service_changes.is_empty()
+    }
+}
+
+///A port open in both scans with a different service.
+#[derive(Clone, Debug)]
+pub struct ServiceChange<'a> {
+    pub old: &'a Port,
+    pub new: &'a Port,
+}
+
+//The hosts that are up, keyed by IP address and kept in scan order. Only
+//the first host found for an address is kept.
+struct UpHosts<'a> {
+    hosts: Vec<(IpAddr, &'a Host)>,
+    index: HashMap<IpAddr, usize>,
+}
+
+impl<'a> UpHosts<'a> {
+    fn iter(&self) -> impl Iterator<Item = &(IpAddr, &'a Host)> {
+        self.hosts.iter()
+    }
+
+    fn get(&self, ip: &IpAddr) -> Option<&'a Host> {
+        self.index.get(ip).map(|&i| self.hosts[i].1)
+    }
+
+    fn contains_key(&self, ip: &IpAddr) -> bool {
+        self.index.contains_key(ip)
+    }
+}
+
+fn up_hosts(results: &NmapResults) -> UpHosts<'_> {
+    let mut up = UpHosts {
+        hosts: Vec::new(),
+        index: HashMap::new(),
+    };
+    for host in results.hosts_up() {
+        if let Some(ip) = host.primary_ip() {
+            if !up.index.contains_key(&ip) {
+                up.index.insert(ip, up.hosts.len());
+                up.hosts.push((ip, host));
+            }
+        }
+    }
+    up
+}
+
+fn service_key(port: &Port) -> Option<(&str, Option<&str>, Option<&str>)> {
+    port.service_info.as_ref().map(|service| {
+        (
+            service.name.as_str(),
+            service.product.as_deref(),
+            service.version.as_deref(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OLD: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    const NEW: &str = r#"<nmaprun start="1588405212">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="closed" reason="reset" reason_ttl="53"/></port>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.4" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    fn ip(host: &Host) -> String {
+        host.primary_ip().unwrap().to_string()
+    }
+
+    #[test]
+    fn scan_diff() {
+        let old = NmapResults::parse(OLD).unwrap();
+        let new = NmapResults::parse(NEW).unwrap();
+        let diff = ScanDiff::between(&old, &new);
+
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.new_hosts().map(|h| ip(h)).collect::<Vec<_>>(),
+            ["10.0.0.4"]
+        );
+        assert_eq!(
+            diff.removed_hosts().map(|h| ip(h)).collect::<Vec<_>>(),
+            ["10.0.0.2"]
+        );
+
+        let changed = diff.changed_hosts().collect::<Vec<_>>();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(ip(changed[0].new), "10.0.0.1");
+        assert_eq!(
+            changed[0]
+                .opened()
+                .map(|p| p.port_number)
+                .collect::<Vec<_>>(),
+            [443]
+        );
+        assert_eq!(
+            changed[0]
+                .closed()
+                .map(|p| p.port_number)
+                .collect::<Vec<_>>(),
+            [23]
+        );
+        let change = changed[0].service_changes().next().unwrap();
+        assert_eq!(change.old.port_number, 22);
+        assert_eq!(
+            change.new.service_info.as_ref().unwrap().version.as_deref(),
+            Some("8.0")
+        );
+    }
+
+    #[test]
+    fn identical_scans() {
+        let old = NmapResults::parse(OLD).unwrap();
+        assert!(ScanDiff::between(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//!Announcing autonomous system and prefix of IP addresses.
+//!
+//![`AsnDatabase`](struct.AsnDatabase.html) loads the offline
+//!prefix-to-AS datasets published by CAIDA (`routeviews-rv2-*.pfx2as`),
+//!and [`group_by_asn()`](fn.group_by_asn.html) groups the hosts of a scan
+//!by the network announcing them. Other sources can be plugged in by
+//!implementing [`AsnLookup`](trait.AsnLookup.html).
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+use crate::host::Host;
+use crate::net::IpNetwork;
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "pfx2as";
+
+///The autonomous system announcing an address, and the announced prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AsnInfo {
+    pub asn: u32,
+    pub prefix: IpNetwork,
+}
+
+///Source of [`AsnInfo`](struct.AsnInfo.html) for IP addresses.
+pub trait AsnLookup {
+    ///Returns the most specific announcement covering `addr`, if any.
+    fn lookup_asn(&self, addr: &IpAddr) -> Option<AsnInfo>;
+}
+
+///Announced prefixes and their origin autonomous system.
+#[derive(Clone, Debug, Default)]
+pub struct AsnDatabase {
+    prefixes: HashMap<IpNetwork, u32>,
+    //Prefix lengths present, to only try those on lookups.
+    prefix_lens: BTreeSet<(bool, u8)>,
+}
+
+impl AsnDatabase {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Parses a CAIDA `pfx2as` file, made of lines with the prefix address,
+    ///length and origin separated by tabs, e.g. `1.0.0.0 24 13335`. For prefixes announced by several
+    ///autonomous systems (`13335_4826` or `{13335,4826}`), the first one is
+    ///kept.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut database = AsnDatabase::new();
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::InvalidImport {
+                format: FORMAT,
+                message: format!("invalid prefix line {}", number + 1),
+            };
+            let mut fields = line.split_whitespace();
+            let (addr, len, asn) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(addr), Some(len), Some(asn)) => (addr, len, asn),
+                _ => return Err(invalid()),
+            };
+            let prefix = format!("{}/{}", addr, len)
+                .parse::<IpNetwork>()
+                .map_err(|_| invalid())?;
+            let asn = asn
+                .trim_start_matches('{')
+                .split(['_', ',', '}'])
+                .next()
+                .and_then(|asn| asn.parse::<u32>().ok())
+                .ok_or_else(invalid)?;
+
+            database.insert(prefix, asn);
+        }
+
+        Ok(database)
+    }
+
+    ///Adds or replaces the origin of `prefix`.
+    pub fn insert(&mut self, prefix: IpNetwork, asn: u32) {
+        self.prefix_lens
+            .insert((prefix.network().is_ipv4(), prefix.prefix_len()));
+        self.prefixes.insert(prefix, asn);
+    }
+
+    ///Returns the number of prefixes in the database.
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    ///Returns `true` if the database has no prefix.
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+}
+
+impl AsnLookup for AsnDatabase {
+    fn lookup_asn(&self, addr: &IpAddr) -> Option<AsnInfo> {
+        self.prefix_lens
+            .iter()
+            .rev()
+            .filter(|(ipv4, _)| *ipv4 == addr.is_ipv4())
+            .find_map(|&(_, len)| {
+                let prefix = IpNetwork::new(*addr, len).ok()?;
+                self.prefixes
+                    .get(&prefix)
+                    .map(|&asn| AsnInfo { asn, prefix })
+            })
+    }
+}
+
+///Groups the hosts of `results` by the autonomous system and prefix
+///announcing their IP address. Hosts without an IP address or without an
+///announcement are left out.
+pub fn group_by_asn<'a>(
+    results: &'a NmapResults,
+    lookup: &dyn AsnLookup,
+) -> BTreeMap<AsnInfo, Vec<&'a Host>> {
+    let mut groups: BTreeMap<AsnInfo, Vec<&Host>> = BTreeMap::new();
+    for host in results.hosts() {
+        if let Some(info) = host.primary_ip().and_then(|ip| lookup.lookup_asn(&ip)) {
+            groups.entry(info).or_default().push(host);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pfx2as_lookup() {
+        let database = AsnDatabase::parse(
+            "45.33.0.0\t17\t63949\n\
+             45.33.32.0\t24\t63949_3356\n\
+             8.8.8.0\t24\t{15169,396982}\n\
+             2600:3c01::\t32\t63949\n",
+        )
+        .unwrap();
+        assert_eq!(database.len(), 4);
+
+        let info = database
+            .lookup_asn(&"45.33.32.156".parse().unwrap())
+            .unwrap();
+        assert_eq!(info.asn, 63949);
+        assert_eq!(info.prefix.to_string(), "45.33.32.0/24");
+        let info = database.lookup_asn(&"45.33.1.1".parse().unwrap()).unwrap();
+        assert_eq!(info.prefix.to_string(), "45.33.0.0/17");
+        assert_eq!(
+            database
+                .lookup_asn(&"8.8.8.8".parse().unwrap())
+                .map(|i| i.asn),
+            Some(15169)
+        );
+        assert!(database
+            .lookup_asn(&"2600:3c01::1".parse().unwrap())
+            .is_some());
+        assert!(database.lookup_asn(&"10.0.0.1".parse().unwrap()).is_none());
+
+        assert!(AsnDatabase::parse("45.33.0.0\t17\n").is_err());
+    }
+
+    #[test]
+    fn hosts_by_asn() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.49.119" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let mut database = AsnDatabase::new();
+        database.insert("45.33.0.0/17".parse().unwrap(), 63949);
+
+        let groups = group_by_asn(&results, &database);
+        assert_eq!(groups.len(), 1);
+        let (info, hosts) = groups.iter().next().unwrap();
+        assert_eq!(info.asn, 63949);
+        assert_eq!(hosts.len(), 2);
+    }
+}
@@ -0,0 +1,220 @@
+//!Reverse DNS names for results scanned without name resolution (`-n`).
+//!
+//![`resolve_ptr()`](fn.resolve_ptr.html) asks a
+//![`PtrResolver`](trait.PtrResolver.html) for the PTR record of every host
+//!that has no name, and adds the names found with the
+//![`Resolved`](../../host/enum.HostnameType.html#variant.Resolved) source so
+//!they can be told apart from the names Nmap found. The resolver is
+//!asynchronous so it can be backed by any async DNS client, such as
+//!`hickory-resolver`, and any runtime.
+use std::future::{poll_fn, Future};
+use std::net::IpAddr;
+use std::task::Poll;
+
+use crate::host::{Hostname, HostnameType};
+use crate::NmapResults;
+
+///The number of lookups [`resolve_ptr()`](fn.resolve_ptr.html) keeps in
+///flight at once.
+pub const CONCURRENT_LOOKUPS: usize = 16;
+
+///Source of reverse DNS names.
+pub trait PtrResolver {
+    ///Returns the name of the PTR record of `addr`, if there is one.
+    fn resolve(&self, addr: IpAddr) -> impl Future<Output = Option<String>> + Send;
+}
+
+///Looks up the IP address of every host of `results` without any name,
+///[`CONCURRENT_LOOKUPS`](constant.CONCURRENT_LOOKUPS.html) at a time, and
+///adds the names found. Returns the number of hosts that were given a name.
+///A trailing dot is removed from the names.
+pub async fn resolve_ptr<R: PtrResolver>(results: &mut NmapResults, resolver: &R) -> usize {
+    resolve_ptr_concurrently(results, resolver, CONCURRENT_LOOKUPS).await
+}
+
+///Like [`resolve_ptr()`](fn.resolve_ptr.html), with at most `limit` lookups
+///in flight at once. A `limit` of 0 is taken as 1.
+pub async fn resolve_ptr_concurrently<R: PtrResolver>(
+    results: &mut NmapResults,
+    resolver: &R,
+    limit: usize,
+) -> usize {
+    let mut queue = results
+        .hosts
+        .iter()
+        .enumerate()
+        .filter(|(_, host)| host.host_names.is_empty())
+        .filter_map(|(i, host)| Some((i, host.primary_ip()?)))
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    //Every pending lookup is polled on each wake up, and a finished one is
+    //replaced by the next in the queue right away.
+    let mut pending = Vec::new();
+    let mut names = Vec::new();
+    poll_fn(|cx| loop {
+        while pending.len() < limit.max(1) {
+            match queue.next() {
+                Some((i, ip)) => pending.push((i, Box::pin(resolver.resolve(ip)))),
+                None => break,
+            }
+        }
+        let before = pending.len();
+        pending.retain_mut(|(i, lookup)| match lookup.as_mut().poll(cx) {
+            Poll::Ready(name) => {
+                names.extend(name.map(|name| (*i, name)));
+                false
+            }
+            Poll::Pending => true,
+        });
+        if pending.is_empty() && queue.as_slice().is_empty() {
+            return Poll::Ready(());
+        }
+        if pending.len() == before {
+            return Poll::Pending;
+        }
+    })
+    .await;
+
+    for (i, name) in &names {
+        results.hosts[*i].host_names.push(Hostname {
+            name: name.trim_end_matches('.').to_string(),
+            source: HostnameType::Resolved,
+        });
+    }
+    names.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::future::ready;
+    use std::pin::{pin, Pin};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Waker};
+
+    struct Table;
+
+    impl PtrResolver for Table {
+        fn resolve(&self, addr: IpAddr) -> impl Future<Output = Option<String>> + Send {
+            ready(match addr.to_string().as_str() {
+                "45.33.32.156" => Some("scanme.nmap.org.".to_string()),
+                "10.0.0.1" => panic!("hosts with a name are not resolved"),
+                _ => None,
+            })
+        }
+    }
+
+    #[test]
+    fn ptr_enrichment() {
+        let mut results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="router.lan" type="PTR"/></hostnames>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        let mut context = Context::from_waker(Waker::noop());
+        let resolved = pin!(resolve_ptr(&mut results, &Table)).poll(&mut context);
+        assert_eq!(resolved, Poll::Ready(1));
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(
+            hosts[0].host_names().next(),
+            Some(&Hostname {
+                name: "scanme.nmap.org".to_string(),
+                source: HostnameType::Resolved,
+            })
+        );
+        assert_eq!(hosts[1].host_names().count(), 1);
+        assert_eq!(hosts[2].host_names().count(), 0);
+    }
+
+    //Counts the lookups in flight, each of which takes two polls.
+    #[derive(Default)]
+    struct Counting {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    struct Lookup<'a> {
+        resolver: &'a Counting,
+        addr: IpAddr,
+        started: bool,
+    }
+
+    impl Future for Lookup<'_> {
+        type Output = Option<String>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            let resolver = self.resolver;
+            if !self.started {
+                self.started = true;
+                let in_flight = resolver.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                resolver
+                    .max_in_flight
+                    .fetch_max(in_flight, Ordering::SeqCst);
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            resolver.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Poll::Ready(Some(format!("host-{}.example.com", self.addr)))
+        }
+    }
+
+    impl PtrResolver for Counting {
+        fn resolve(&self, addr: IpAddr) -> impl Future<Output = Option<String>> + Send {
+            Lookup {
+                resolver: self,
+                addr,
+                started: false,
+            }
+        }
+    }
+
+    #[test]
+    fn concurrent_lookups() {
+        let hosts = (1..=5)
+            .map(|i| {
+                format!(
+                    r#"<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.{}" addrtype="ipv4"/></host>"#,
+                    i
+                )
+            })
+            .collect::<String>();
+        let mut results = NmapResults::parse(&format!(
+            r#"<nmaprun start="1588318812">{}</nmaprun>"#,
+            hosts
+        ))
+        .unwrap();
+
+        let resolver = Counting::default();
+        let mut context = Context::from_waker(Waker::noop());
+        let resolved = {
+            let mut resolving = pin!(resolve_ptr_concurrently(&mut results, &resolver, 2));
+            loop {
+                if let Poll::Ready(resolved) = resolving.as_mut().poll(&mut context) {
+                    break resolved;
+                }
+            }
+        };
+        assert_eq!(resolved, 5);
+        assert_eq!(resolver.max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(resolver.in_flight.load(Ordering::SeqCst), 0);
+        for (i, host) in results.hosts().enumerate() {
+            assert_eq!(
+                host.host_names().next().unwrap().name,
+                format!("host-10.0.0.{}.example.com", i + 1)
+            );
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//!Geolocation of public IP addresses, for external perimeter reports.
+//!
+//!Implement [`GeoLookup`](trait.GeoLookup.html) over a database such as
+//!MaxMind GeoLite2, or use a closure, and pass it to
+//![`enrich()`](fn.enrich.html). Private, loopback and other non-public
+//!addresses are never looked up.
+use std::net::IpAddr;
+
+use crate::enrich::Enriched;
+use crate::host::{Address, Host};
+use crate::net::is_public;
+use crate::NmapResults;
+
+///Location and network owner of an IP address.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GeoInfo {
+    ///ISO 3166-1 country code, e.g. `US`.
+    pub country: Option<String>,
+    pub city: Option<String>,
+    ///Autonomous system number of the network announcing the address.
+    pub asn: Option<u32>,
+    ///Organization owning the autonomous system.
+    pub as_org: Option<String>,
+}
+
+///Source of [`GeoInfo`](struct.GeoInfo.html) for IP addresses.
+pub trait GeoLookup {
+    ///Returns what is known about `addr`, or `None` if it is not in the
+    ///database.
+    fn lookup(&self, addr: &IpAddr) -> Option<GeoInfo>;
+}
+
+impl<F> GeoLookup for F
+where
+    F: Fn(&IpAddr) -> Option<GeoInfo>,
+{
+    fn lookup(&self, addr: &IpAddr) -> Option<GeoInfo> {
+        self(addr)
+    }
+}
+
+///Looks up the public IP addresses of `host`.
+pub fn enrich_host<'a>(host: &'a Host, lookup: &dyn GeoLookup) -> Enriched<'a, Host> {
+    let mut enriched = Enriched::new(host);
+    for address in host.addresses() {
+        if let Address::IpAddr(ip) = address {
+            if is_public(ip) {
+                if let Some(geo) = lookup.lookup(ip) {
+                    enriched.geo.push((*ip, geo));
+                }
+            }
+        }
+    }
+    enriched
+}
+
+///Looks up the public IP addresses of every host of `results`. Hosts are
+///returned in scan order, including the ones without any location.
+pub fn enrich<'a>(results: &'a NmapResults, lookup: &dyn GeoLookup) -> Vec<Enriched<'a, Host>> {
+    results
+        .hosts()
+        .map(|host| enrich_host(host, lookup))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn geo_enrichment() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="64"/>
+<address addr="192.168.1.1" addrtype="ipv4"/>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let lookup = |ip: &IpAddr| {
+            assert!(is_public(ip));
+            Some(GeoInfo {
+                country: Some("US".to_string()),
+                asn: Some(63949),
+                ..Default::default()
+            })
+        };
+
+        let hosts = enrich(&results, &lookup);
+        assert_eq!(hosts.len(), 2);
+        let ip = "45.33.32.156".parse().unwrap();
+        assert_eq!(
+            hosts[0].geo_for(&ip).unwrap().country.as_deref(),
+            Some("US")
+        );
+        assert_eq!(hosts[0].geo().count(), 1);
+        assert_eq!(hosts[0].status.reason, "echo-reply");
+        assert_eq!(hosts[1].geo().count(), 0);
+    }
+}
@@ -0,0 +1,52 @@
+//!Enrichment of scan results with data from outside the scan.
+//!
+//!Lookups go through provider traits, so results can be annotated from any
+//!database without this crate depending on it. Enriched hosts are returned
+//!as [`Enriched`](struct.Enriched.html) wrappers, which dereference to the
+//!host and leave the parsed results untouched.
+use std::net::IpAddr;
+use std::ops::Deref;
+
+pub mod asn;
+pub mod dns;
+pub mod geo;
+
+use self::geo::GeoInfo;
+
+///A value annotated with data looked up for its IP addresses.
+#[derive(Clone, Debug)]
+pub struct Enriched<'a, T> {
+    pub inner: &'a T,
+    pub(crate) geo: Vec<(IpAddr, GeoInfo)>,
+}
+
+impl<'a, T> Enriched<'a, T> {
+    pub(crate) fn new(inner: &'a T) -> Self {
+        Enriched {
+            inner,
+            geo: Vec::new(),
+        }
+    }
+
+    ///Returns an iterator over the addresses that were found in the
+    ///geolocation database, along with their location.
+    pub fn geo(&self) -> std::slice::Iter<'_, (IpAddr, GeoInfo)> {
+        self.geo.iter()
+    }
+
+    ///Returns the location of `addr`, if it was found.
+    pub fn geo_for(&self, addr: &IpAddr) -> Option<&GeoInfo> {
+        self.geo
+            .iter()
+            .find(|(ip, _)| ip == addr)
+            .map(|(_, geo)| geo)
+    }
+}
+
+impl<T> Deref for Enriched<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner
+    }
+}
@@ -0,0 +1,97 @@
+//!CSV export, one row per port.
+//!
+//!The columns are `ip,hostname,host_state,protocol,port,state,service,product,version`.
+//!Hosts without listed ports get a single row with the port columns left
+//!empty, so that every host of the scan appears. Fields are quoted as
+//!RFC 4180 describes when they contain a comma, a quote or a line break.
+use std::io::{self, Write};
+
+use crate::host::Host;
+use crate::port::Port;
+use crate::NmapResults;
+
+const HEADER: &str = "ip,hostname,host_state,protocol,port,state,service,product,version";
+
+///Writes `results` as CSV, with a header line, to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    writeln!(w, "{}", HEADER)?;
+    for host in results.hosts() {
+        if host.port_info.port_count() == 0 {
+            write_row(host, None, w)?;
+        }
+        for port in host.port_info.ports() {
+            write_row(host, Some(port), w)?;
+        }
+    }
+    Ok(())
+}
+
+///Renders `results` as CSV.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("CSV output is valid UTF-8")
+}
+
+fn write_row<W: Write>(host: &Host, port: Option<&Port>, w: &mut W) -> io::Result<()> {
+    let service = port.and_then(|p| p.service_info.as_ref());
+    let fields = [
+        host.primary_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_default(),
+        host.host_names()
+            .next()
+            .map(|h| h.name.clone())
+            .unwrap_or_default(),
+        host.status.state.to_string(),
+        port.map(|p| p.protocol.to_string()).unwrap_or_default(),
+        port.map(|p| p.port_number.to_string()).unwrap_or_default(),
+        port.map(|p| p.status.state.to_string()).unwrap_or_default(),
+        service.map(|s| s.name.clone()).unwrap_or_default(),
+        service.and_then(|s| s.product.clone()).unwrap_or_default(),
+        service.and_then(|s| s.version.clone()).unwrap_or_default(),
+    ];
+    let fields = fields.iter().map(|f| escape(f)).collect::<Vec<_>>();
+    writeln!(w, "{}", fields.join(","))
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn csv_rows() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="web.example.com" type="PTR"/></hostnames>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" extrainfo="protocol 2.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="Apache httpd, &quot;patched&quot;" method="probed" conf="10"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "ip,hostname,host_state,protocol,port,state,service,product,version\n\
+             10.0.0.1,web.example.com,up,tcp,22,open,ssh,OpenSSH,7.4\n\
+             10.0.0.1,web.example.com,up,tcp,80,open,http,\"Apache httpd, \"\"patched\"\"\",\n\
+             10.0.0.2,,down,,,,,,\n"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//!Graphviz DOT topology export.
+//!
+//!Produces a directed graph going from the scanner through every
+//!traceroute hop to the hosts that are up. Host nodes are labelled with
+//!their names, address and open ports, so the output of
+//!`dot -Tsvg` is a usable network map. Hosts without traceroute data are
+//!linked directly to the scanner.
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use crate::host::{Host, HostState};
+use crate::port::PortState;
+use crate::NmapResults;
+
+const SCANNER_NODE: &str = "scanner";
+
+///Writes a DOT graph of `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    let mut hops = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+
+    writeln!(w, "digraph nmap {{")?;
+    writeln!(w, "  rankdir=LR;")?;
+    writeln!(w, "  \"{}\" [shape=box];", SCANNER_NODE)?;
+
+    for host in results.hosts().filter(|h| h.status.state == HostState::Up) {
+        let id = match host.primary_ip() {
+            Some(ip) => ip.to_string(),
+            None => continue,
+        };
+
+        writeln!(w, "  {} [label={}];", quote(&id), quote(&host_label(host)))?;
+
+        let mut previous = SCANNER_NODE.to_string();
+        if let Some(trace) = host.trace() {
+            for hop in trace.hops() {
+                let hop_id = hop.ip_addr.to_string();
+                if hop_id == id {
+                    break;
+                }
+                let label = match &hop.host_name {
+                    Some(name) => format!("{}\n{}", name, hop_id),
+                    None => hop_id.clone(),
+                };
+                hops.insert((hop_id.clone(), label));
+                edges.insert((previous, hop_id.clone()));
+                previous = hop_id;
+            }
+        }
+        edges.insert((previous, id));
+    }
+
+    for (id, label) in &hops {
+        writeln!(
+            w,
+            "  {} [label={}, shape=ellipse, style=dashed];",
+            quote(id),
+            quote(label)
+        )?;
+    }
+
+    for (from, to) in &edges {
+        writeln!(w, "  {} -> {};", quote(from), quote(to))?;
+    }
+
+    writeln!(w, "}}")
+}
+
+///Renders a DOT graph of `results`.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("DOT output is valid UTF-8")
+}
+
+fn host_label(host: &Host) -> String {
+    let mut lines = Vec::new();
+
+    if let Some(name) = host.host_names().next() {
+        lines.push(name.name.clone());
+    }
+    if let Some(ip) = host.primary_ip() {
+        lines.push(ip.to_string());
+    }
+    for port in host
+        .port_info
+        .ports()
+        .filter(|p| p.status.state == PortState::Open)
+    {
+        match &port.service_info {
+            Some(service) => lines.push(format!(
+                "{}/{} {}",
+                port.port_number, port.protocol, service.name
+            )),
+            None => lines.push(format!("{}/{}", port.port_number, port.protocol)),
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dot_graph_through_hops() {
+        let xml = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="62"/>
+<address addr="10.0.1.5" addrtype="ipv4"/>
+<hostnames><hostname name="web.lan" type="PTR"/></hostnames>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="62"/><service name="http" method="table" conf="3"/></port>
+</ports>
+<trace port="80" proto="tcp">
+<hop ttl="1" ipaddr="192.168.1.1" rtt="0.52" host="gateway.lan"/>
+<hop ttl="2" ipaddr="10.0.1.5" rtt="1.10"/>
+</trace>
+</host>
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.1.20" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "digraph nmap {\n  \
+             rankdir=LR;\n  \
+             \"scanner\" [shape=box];\n  \
+             \"10.0.1.5\" [label=\"web.lan\\n10.0.1.5\\n80/tcp http\"];\n  \
+             \"192.168.1.20\" [label=\"192.168.1.20\"];\n  \
+             \"192.168.1.1\" [label=\"gateway.lan\\n192.168.1.1\", shape=ellipse, style=dashed];\n  \
+             \"192.168.1.1\" -> \"10.0.1.5\";\n  \
+             \"scanner\" -> \"192.168.1.1\";\n  \
+             \"scanner\" -> \"192.168.1.20\";\n\
+             }\n"
+        );
+    }
+}
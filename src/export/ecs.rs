@@ -0,0 +1,221 @@
+//!Elastic Common Schema (ECS) JSON export.
+//!
+//!Every host produces one document describing its status and every port
+//!produces one document describing the service found on it. Field names
+//!follow ECS (`host.ip`, `destination.port`, `service.name`, `event.*`), with
+//!Nmap specific values kept under the `nmap` namespace, so the documents
+//!can be bulk indexed into Elasticsearch or OpenSearch as they are.
+use std::io::{self, Write};
+
+use crate::host::{Address, Host};
+use crate::json::Value;
+use crate::port::Port;
+use crate::util::format_rfc3339;
+use crate::NmapResults;
+
+///Writes one ECS document per line (NDJSON) to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    for document in documents(results) {
+        writeln!(w, "{}", document)?;
+    }
+    Ok(())
+}
+
+///Writes the documents as an Elasticsearch `_bulk` request body targeting
+///`index`.
+pub fn write_bulk<W: Write>(results: &NmapResults, index: &str, w: &mut W) -> io::Result<()> {
+    let action = Value::object(vec![(
+        "index",
+        Value::object(vec![("_index", Value::from(index))]),
+    )]);
+
+    for document in documents(results) {
+        writeln!(w, "{}", action)?;
+        writeln!(w, "{}", document)?;
+    }
+    Ok(())
+}
+
+///Renders one ECS document per line (NDJSON).
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("JSON output is valid UTF-8")
+}
+
+fn documents(results: &NmapResults) -> Vec<Value> {
+    let mut documents = Vec::new();
+
+    for host in results.hosts() {
+        let timestamp = host
+            .scan_end_time
+            .or(results.scan_end_time)
+            .unwrap_or(results.scan_start_time);
+
+        documents.push(Value::object(vec![
+            ("@timestamp", Value::from(format_rfc3339(timestamp))),
+            ("event", event("nmap.host", host, results)),
+            ("host", host_fields(host)),
+            (
+                "nmap",
+                Value::object(vec![(
+                    "host",
+                    Value::object(vec![
+                        ("state", Value::from(host.status.state.to_string())),
+                        ("reason", Value::from(host.status.reason.as_str())),
+                    ]),
+                )]),
+            ),
+        ]));
+
+        for port in host.port_info.ports() {
+            documents.push(port_document(host, port, timestamp, results));
+        }
+    }
+
+    documents
+}
+
+fn port_document(host: &Host, port: &Port, timestamp: i64, results: &NmapResults) -> Value {
+    let service = port.service_info.as_ref();
+
+    Value::object(vec![
+        ("@timestamp", Value::from(format_rfc3339(timestamp))),
+        ("event", event("nmap.port", host, results)),
+        ("host", host_fields(host)),
+        (
+            "destination",
+            Value::sparse_object(vec![
+                (
+                    "ip",
+                    Value::from(host.primary_ip().map(|ip| ip.to_string())),
+                ),
+                ("port", Value::from(port.port_number)),
+                (
+                    "domain",
+                    Value::from(host.host_names().next().map(|h| h.name.as_str())),
+                ),
+            ]),
+        ),
+        (
+            "network",
+            Value::object(vec![("transport", Value::from(port.protocol.to_string()))]),
+        ),
+        (
+            "service",
+            Value::sparse_object(vec![
+                ("name", Value::from(service.map(|s| s.name.as_str()))),
+                ("state", Value::from(port.status.state.to_string())),
+            ]),
+        ),
+        (
+            "nmap",
+            Value::object(vec![(
+                "port",
+                Value::object(vec![
+                    ("reason", Value::from(port.status.reason.as_str())),
+                    ("reason_ttl", Value::from(port.status.reason_ttl)),
+                ]),
+            )]),
+        ),
+    ])
+}
+
+fn event(dataset: &str, host: &Host, results: &NmapResults) -> Value {
+    Value::sparse_object(vec![
+        ("kind", Value::from("event")),
+        ("category", Value::from(vec!["network"])),
+        ("type", Value::from(vec!["info"])),
+        ("module", Value::from("nmap")),
+        ("dataset", Value::from(dataset)),
+        (
+            "start",
+            Value::from(
+                host.scan_start_time
+                    .or(Some(results.scan_start_time))
+                    .map(format_rfc3339),
+            ),
+        ),
+        (
+            "end",
+            Value::from(
+                host.scan_end_time
+                    .or(results.scan_end_time)
+                    .map(format_rfc3339),
+            ),
+        ),
+    ])
+}
+
+fn host_fields(host: &Host) -> Value {
+    let mut ips = Vec::new();
+    let mut macs = Vec::new();
+    for address in host.addresses() {
+        match address {
+            Address::IpAddr(ip) => ips.push(ip.to_string()),
+            //ECS wants MAC addresses as upper case, dash separated octets.
+            Address::MacAddr(mac) => macs.push(mac.to_dashed_string()),
+        }
+    }
+
+    Value::sparse_object(vec![
+        ("ip", Value::from(ips)),
+        (
+            "mac",
+            if macs.is_empty() {
+                Value::Null
+            } else {
+                Value::from(macs)
+            },
+        ),
+        (
+            "hostname",
+            Value::from(host.host_names().next().map(|h| h.name.as_str())),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const XML: &str = r#"<nmaprun start="1588318812">
+<host starttime="1588318812" endtime="1588318814"><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.59.138" addrtype="ipv4"/>
+<address addr="00:0c:29:71:23:2b" addrtype="mac"/>
+<ports>
+<port protocol="tcp" portid="3389"><state state="open" reason="syn-ack" reason_ttl="128"/><service name="ms-wbt-server" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn ecs_documents() {
+        let results = NmapResults::parse(XML).unwrap();
+        let output = to_string(&results);
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"@timestamp":"2020-05-01T07:40:14Z","event":{"kind":"event","category":["network"],"type":["info"],"module":"nmap","dataset":"nmap.host","start":"2020-05-01T07:40:12Z","end":"2020-05-01T07:40:14Z"},"host":{"ip":["192.168.59.138"],"mac":["00-0C-29-71-23-2B"]},"nmap":{"host":{"state":"up","reason":"arp-response"}}}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"@timestamp":"2020-05-01T07:40:14Z","event":{"kind":"event","category":["network"],"type":["info"],"module":"nmap","dataset":"nmap.port","start":"2020-05-01T07:40:12Z","end":"2020-05-01T07:40:14Z"},"host":{"ip":["192.168.59.138"],"mac":["00-0C-29-71-23-2B"]},"destination":{"ip":"192.168.59.138","port":3389},"network":{"transport":"tcp"},"service":{"name":"ms-wbt-server","state":"open"},"nmap":{"port":{"reason":"syn-ack","reason_ttl":128}}}"#
+        );
+    }
+
+    #[test]
+    fn ecs_bulk_body() {
+        let results = NmapResults::parse(XML).unwrap();
+        let mut out = Vec::new();
+        write_bulk(&results, "nmap-scans", &mut out).unwrap();
+        let body = String::from_utf8(out).unwrap();
+        let lines = body.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], r#"{"index":{"_index":"nmap-scans"}}"#);
+        assert_eq!(lines[2], r#"{"index":{"_index":"nmap-scans"}}"#);
+    }
+}
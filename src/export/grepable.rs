@@ -0,0 +1,142 @@
+//!Nmap grepable (`-oG`) output.
+//!
+//!The output mirrors what `nmap -oG` prints so that existing `grep`/`awk`
+//!tooling keeps working: one tab-separated `Status` line per host, followed
+//!by a `Ports` line for hosts with port results.
+//!
+//!Times in the comment lines are printed in UTC.
+use std::io::{self, Write};
+
+use crate::host::{Host, HostState};
+use crate::port::Port;
+use crate::util::{done_comment, start_comment};
+use crate::NmapResults;
+
+///Writes `results` in grepable format to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    if let Some(line) = start_comment(results) {
+        writeln!(w, "{}", line)?;
+    }
+
+    for host in results.hosts() {
+        write_host(host, w)?;
+    }
+
+    if let Some(line) = done_comment(results) {
+        writeln!(w, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+///Renders `results` in grepable format.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("grepable output is valid UTF-8")
+}
+
+fn write_host<W: Write>(host: &Host, w: &mut W) -> io::Result<()> {
+    let ip = host
+        .primary_ip()
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    let name = host
+        .host_names()
+        .next()
+        .map(|h| h.name.as_str())
+        .unwrap_or("");
+    let prefix = format!("Host: {} ({})", ip, name);
+
+    writeln!(w, "{}\tStatus: {}", prefix, host_state(&host.status.state))?;
+
+    if host.port_info.ports().next().is_none() {
+        return Ok(());
+    }
+
+    let ports = host
+        .port_info
+        .ports()
+        .map(port_field)
+        .collect::<Vec<_>>()
+        .join(", ");
+    write!(w, "{}\tPorts: {}", prefix, ports)?;
+
+    if let Some(extra) = host.port_info.extra_ports().next() {
+        write!(w, "\tIgnored State: {} ({})", extra.state, extra.count)?;
+    }
+
+    writeln!(w)
+}
+
+fn host_state(state: &HostState) -> &'static str {
+    match state {
+        HostState::Up => "Up",
+        HostState::Down => "Down",
+        HostState::Unknown => "Unknown",
+        HostState::Skipped => "Skipped",
+    }
+}
+
+fn port_field(port: &Port) -> String {
+    let service = match &port.service_info {
+        Some(s) => match &s.tunnel {
+            Some(tunnel) => format!("{}/{}", tunnel, s.name),
+            None => s.name.clone(),
+        },
+        None => String::new(),
+    };
+    let version = port
+        .service_info
+        .as_ref()
+        .and_then(|s| s.version_string())
+        .unwrap_or_default();
+
+    format!(
+        "{}/{}/{}//{}//{}/",
+        port.port_number,
+        port.status.state,
+        port.protocol,
+        sanitize(&service),
+        sanitize(&version)
+    )
+}
+
+//Nmap replaces the field separator inside values so lines stay splittable.
+fn sanitize(field: &str) -> String {
+    field.replace('/', "|")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grepable_host_lines() {
+        let xml = r#"<nmaprun start="1588318812" args="nmap -sS scanme.nmap.org" version="7.60">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<hostnames><hostname name="scanme.nmap.org" type="user"/></hostnames>
+<ports><extraports state="closed" count="998"/>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="6.6.1p1" extrainfo="protocol 2.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" tunnel="ssl" method="probed" conf="10"/></port>
+<port protocol="udp" portid="53"><state state="open|filtered" reason="no-response" reason_ttl="0"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+</host>
+<runstats><finished time="1588318814"/></runstats>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "# Nmap 7.60 scan initiated Fri May  1 07:40:12 2020 as: nmap -sS scanme.nmap.org\n\
+             Host: 45.33.32.156 (scanme.nmap.org)\tStatus: Up\n\
+             Host: 45.33.32.156 (scanme.nmap.org)\tPorts: 22/open/tcp//ssh//OpenSSH 6.6.1p1 (protocol 2.0)/, 443/open/tcp//ssl|http///, 53/open|filtered/udp/////\tIgnored State: closed (998)\n\
+             Host: 10.0.0.1 ()\tStatus: Down\n\
+             # Nmap done at Fri May  1 07:40:14 2020 -- 2 IP addresses (1 host up) scanned in 2.00 seconds\n"
+        );
+    }
+}
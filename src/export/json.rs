@@ -0,0 +1,363 @@
+//!JSON export of the whole scan.
+//!
+//!The document mirrors the crate's model: the scan with its hosts, their
+//!addresses, host names, ports, services, scripts and OS matches. Values
+//!keep the spelling Nmap uses in its XML (`open|filtered`, `PTR`), and
+//!missing optional values are left out. It is meant for consumers that are
+//!not written in Rust, such as web dashboards.
+//!
+//![`write_hosts()`](fn.write_hosts.html) writes one host document per line
+//!(NDJSON) instead, for streaming large scans, and
+//![`query_to_string()`](fn.query_to_string.html) the ports matching a
+//![query](../../query/index.html), for bindings and tools that only pass
+//!strings around. [`write_diff()`](fn.write_diff.html) renders a
+//![`ScanDiff`](../../diff/struct.ScanDiff.html).
+use std::io::{self, Write};
+
+use crate::diff::ScanDiff;
+use crate::host::{Address, Host, HostnameType, Script, ScriptElement};
+use crate::json::Value;
+use crate::port::Port;
+use crate::{Error, NmapResults};
+
+///Writes `results` as a single JSON document to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    let document = Value::sparse_object(vec![
+        ("start", Value::from(results.scan_start_time)),
+        ("end", Value::from(results.scan_end_time)),
+        ("args", Value::from(results.args.as_deref())),
+        ("version", Value::from(results.nmap_version.as_deref())),
+        (
+            "hosts",
+            Value::Array(results.hosts().map(host_document).collect()),
+        ),
+    ]);
+    writeln!(w, "{}", document)
+}
+
+///Writes one JSON document per host and line (NDJSON) to `w`.
+pub fn write_hosts<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    for host in results.hosts() {
+        writeln!(w, "{}", host_document(host))?;
+    }
+    Ok(())
+}
+
+///Renders `results` as a single JSON document.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("JSON output is valid UTF-8")
+}
+
+///Runs `query` on `results` and renders the matching ports as a JSON
+///array of `{"host": ..., "port": ...}` objects. The host documents leave
+///out the ports.
+pub fn query_to_string(results: &NmapResults, query: &str) -> Result<String, Error> {
+    let matches = results
+        .query(query)?
+        .into_iter()
+        .map(|(host, port)| {
+            let mut host = host_document(host);
+            if let Value::Object(members) = &mut host {
+                members.retain(|(k, _)| k != "ports" && k != "extra_ports");
+            }
+            Value::object(vec![("host", host), ("port", port_document(port))])
+        })
+        .collect();
+    Ok(Value::Array(matches).to_string())
+}
+
+///Writes `diff` as a JSON document to `w`. New and removed hosts are
+///written in full; changed hosts list their address with the ports
+///opened, closed and whose service changed.
+pub fn write_diff<W: Write>(diff: &ScanDiff, w: &mut W) -> io::Result<()> {
+    let changed_hosts = diff
+        .changed_hosts()
+        .map(|host_diff| {
+            let service_changes = host_diff
+                .service_changes()
+                .map(|change| {
+                    Value::object(vec![
+                        ("old", port_document(change.old)),
+                        ("new", port_document(change.new)),
+                    ])
+                })
+                .collect();
+            Value::object(vec![
+                (
+                    "addr",
+                    Value::from(
+                        host_diff
+                            .new
+                            .primary_ip()
+                            .map(|ip| ip.to_string())
+                            .unwrap_or_default(),
+                    ),
+                ),
+                (
+                    "opened",
+                    Value::Array(host_diff.opened().map(|p| port_document(p)).collect()),
+                ),
+                (
+                    "closed",
+                    Value::Array(host_diff.closed().map(|p| port_document(p)).collect()),
+                ),
+                ("service_changes", Value::Array(service_changes)),
+            ])
+        })
+        .collect();
+
+    let document = Value::object(vec![
+        (
+            "new_hosts",
+            Value::Array(diff.new_hosts().map(|h| host_document(h)).collect()),
+        ),
+        (
+            "removed_hosts",
+            Value::Array(diff.removed_hosts().map(|h| host_document(h)).collect()),
+        ),
+        ("changed_hosts", Value::Array(changed_hosts)),
+    ]);
+    writeln!(w, "{}", document)
+}
+
+///Renders `diff` as a JSON document. See
+///[`write_diff()`](fn.write_diff.html).
+pub fn diff_to_string(diff: &ScanDiff) -> String {
+    let mut out = Vec::new();
+    write_diff(diff, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("JSON output is valid UTF-8")
+}
+
+fn host_document(host: &Host) -> Value {
+    let addresses = host
+        .addresses()
+        .map(|address| {
+            let (addr, addrtype) = match address {
+                Address::IpAddr(ip) if ip.is_ipv4() => (ip.to_string(), "ipv4"),
+                Address::IpAddr(ip) => (ip.to_string(), "ipv6"),
+                Address::MacAddr(mac) => (mac.to_string(), "mac"),
+            };
+            Value::object(vec![
+                ("addr", Value::from(addr)),
+                ("type", Value::from(addrtype)),
+            ])
+        })
+        .collect();
+    let host_names = host
+        .host_names()
+        .map(|name| {
+            let source = match name.source {
+                HostnameType::User => "user",
+                HostnameType::Dns => "PTR",
+                HostnameType::Resolved => "resolved",
+            };
+            Value::object(vec![
+                ("name", Value::from(name.name.as_str())),
+                ("type", Value::from(source)),
+            ])
+        })
+        .collect();
+    let extra_ports = host
+        .port_info
+        .extra_ports()
+        .map(|extra| {
+            Value::object(vec![
+                ("state", Value::from(extra.state.to_string())),
+                ("count", Value::from(extra.count)),
+            ])
+        })
+        .collect();
+    let os_matches = host
+        .os_matches()
+        .map(|os| {
+            Value::object(vec![
+                ("name", Value::from(os.name.as_str())),
+                ("accuracy", Value::from(os.accuracy)),
+            ])
+        })
+        .collect();
+
+    Value::sparse_object(vec![
+        (
+            "status",
+            Value::object(vec![
+                ("state", Value::from(host.status.state.to_string())),
+                ("reason", Value::from(host.status.reason.as_str())),
+                ("reason_ttl", Value::from(host.status.reason_ttl)),
+            ]),
+        ),
+        ("start", Value::from(host.scan_start_time)),
+        ("end", Value::from(host.scan_end_time)),
+        ("addresses", Value::Array(addresses)),
+        ("mac_vendor", Value::from(host.mac_vendor())),
+        ("hostnames", Value::Array(host_names)),
+        (
+            "ports",
+            Value::Array(host.port_info.ports().map(port_document).collect()),
+        ),
+        ("extra_ports", Value::Array(extra_ports)),
+        (
+            "scripts",
+            Value::Array(host.scripts().map(script_document).collect()),
+        ),
+        ("os_matches", Value::Array(os_matches)),
+    ])
+}
+
+fn port_document(port: &Port) -> Value {
+    let service = port.service_info.as_ref().map(|service| {
+        Value::sparse_object(vec![
+            ("name", Value::from(service.name.as_str())),
+            ("product", Value::from(service.product.as_deref())),
+            ("version", Value::from(service.version.as_deref())),
+            ("extra_info", Value::from(service.extra_info.as_deref())),
+            ("tunnel", Value::from(service.tunnel.as_deref())),
+            ("method", Value::from(service.method.to_string())),
+            ("conf", Value::from(service.confidence_level)),
+            (
+                "cpes",
+                Value::from(
+                    service
+                        .cpes
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+        ])
+    });
+
+    Value::sparse_object(vec![
+        ("protocol", Value::from(port.protocol.to_string())),
+        ("port", Value::from(port.port_number)),
+        ("state", Value::from(port.status.state.to_string())),
+        ("reason", Value::from(port.status.reason.as_str())),
+        ("reason_ttl", Value::from(port.status.reason_ttl)),
+        ("service", service.unwrap_or(Value::Null)),
+        (
+            "scripts",
+            Value::Array(port.scripts.iter().map(script_document).collect()),
+        ),
+    ])
+}
+
+fn script_document(script: &Script) -> Value {
+    Value::sparse_object(vec![
+        ("id", Value::from(script.id.as_str())),
+        ("output", Value::from(script.output.as_str())),
+        (
+            "elements",
+            Value::Array(script.elements().map(element_document).collect()),
+        ),
+    ])
+}
+
+fn element_document(element: &ScriptElement) -> Value {
+    match element {
+        ScriptElement::Elem { key, value } => Value::sparse_object(vec![
+            ("key", Value::from(key.as_deref())),
+            ("value", Value::from(value.as_str())),
+        ]),
+        ScriptElement::Table { key, elements } => Value::sparse_object(vec![
+            ("key", Value::from(key.as_deref())),
+            (
+                "elements",
+                Value::Array(elements.iter().map(element_document).collect()),
+            ),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const XML: &str = r#"<nmaprun start="1588318812" version="7.80">
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.59.138" addrtype="ipv4"/>
+<hostnames><hostname name="db.example.com" type="PTR"/></hostnames>
+<ports>
+<extraports state="closed" count="999"/>
+<port protocol="tcp" portid="3306"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="mysql" product="MySQL" method="probed" conf="10"><cpe>cpe:/a:mysql:mysql</cpe></service><script id="mysql-info" output="&#xa;  Protocol: 10"><elem key="Protocol">10</elem></script></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn json_document() {
+        let results = NmapResults::parse(XML).unwrap();
+        assert_eq!(
+            to_string(&results),
+            concat!(
+                r#"{"start":1588318812,"version":"7.80","hosts":[{"status":{"state":"up","reason":"arp-response","reason_ttl":0},"#,
+                r#""addresses":[{"addr":"192.168.59.138","type":"ipv4"}],"hostnames":[{"name":"db.example.com","type":"PTR"}],"#,
+                r#""ports":[{"protocol":"tcp","port":3306,"state":"open","reason":"syn-ack","reason_ttl":64,"#,
+                r#""service":{"name":"mysql","product":"MySQL","method":"probed","conf":10,"cpes":["cpe:/a:mysql:mysql"]},"#,
+                r#""scripts":[{"id":"mysql-info","output":"\n  Protocol: 10","elements":[{"key":"Protocol","value":"10"}]}]}],"#,
+                r#""extra_ports":[{"state":"closed","count":999}],"scripts":[],"os_matches":[]}]}"#,
+                "\n"
+            )
+        );
+
+        let parsed = crate::json::parse(to_string(&results).trim()).unwrap();
+        assert_eq!(parsed.get("hosts").unwrap().as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn json_query() {
+        let results = NmapResults::parse(XML).unwrap();
+        let output = query_to_string(&results, "service.name == mysql").unwrap();
+        let parsed = crate::json::parse(&output).unwrap();
+        let matches = parsed.as_array().unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].get("host").unwrap().get("ports").is_none());
+        assert_eq!(
+            matches[0]
+                .get("port")
+                .unwrap()
+                .get("port")
+                .unwrap()
+                .as_f64(),
+            Some(3306.0)
+        );
+
+        assert_eq!(
+            query_to_string(&results, "port.number == 22").unwrap(),
+            "[]"
+        );
+        assert!(query_to_string(&results, "port.number ==").is_err());
+    }
+
+    #[test]
+    fn json_diff() {
+        let old = NmapResults::parse(XML).unwrap();
+        let new =
+            NmapResults::parse(&XML.replace(r#"product="MySQL""#, r#"product="MariaDB""#)).unwrap();
+        let parsed =
+            crate::json::parse(diff_to_string(&ScanDiff::between(&old, &new)).trim()).unwrap();
+
+        assert_eq!(
+            parsed.get("new_hosts").unwrap().as_array().unwrap().len(),
+            0
+        );
+        let changed = &parsed.get("changed_hosts").unwrap().as_array().unwrap()[0];
+        assert_eq!(
+            changed.get("addr").unwrap().as_str(),
+            Some("192.168.59.138")
+        );
+        let change = &changed.get("service_changes").unwrap().as_array().unwrap()[0];
+        assert_eq!(
+            change
+                .get("new")
+                .unwrap()
+                .get("service")
+                .unwrap()
+                .get("product")
+                .unwrap()
+                .as_str(),
+            Some("MariaDB")
+        );
+    }
+}
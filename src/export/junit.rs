@@ -0,0 +1,182 @@
+//!JUnit XML report of port policy checks.
+//!
+//!Every host that is up becomes a test case that passes when it complies
+//!with a [`Policy`](../../policy/struct.Policy.html) and fails listing the
+//![violations](../../policy/fn.check.html) otherwise, one failure per kind
+//!of violation, so CI systems can gate on scan results without extra glue.
+use std::io::{self, Write};
+
+use crate::policy::{self, Policy, ViolationKind};
+use crate::util::{escape_xml, format_rfc3339};
+use crate::NmapResults;
+
+const SUITE_NAME: &str = "nmap port policy";
+
+//JUnit failure types and messages, per kind of violation.
+const FAILURES: [(&str, &str); 3] = [
+    ("UnexpectedOpenPort", "unexpected open port(s)"),
+    ("MissingExpectedPort", "missing expected port(s)"),
+    ("MissingExpectedService", "missing expected service(s)"),
+];
+
+///Writes the JUnit report of checking `results` against `policy` to `w`.
+pub fn write<W: Write>(results: &NmapResults, policy: &Policy, w: &mut W) -> io::Result<()> {
+    let mut violations = policy::check(results, policy).into_iter().peekable();
+    let mut cases = Vec::new();
+
+    //Violations are listed in the order of the hosts.
+    for host in results.hosts_up() {
+        let ip = match host.primary_ip() {
+            Some(ip) => ip,
+            None => continue,
+        };
+
+        let mut failures: [Vec<String>; 3] = Default::default();
+        while let Some(violation) = violations.next_if(|v| std::ptr::eq(v.host, host)) {
+            let (kind, line) = match violation.kind {
+                ViolationKind::UnexpectedPort(p) => match &p.service_info {
+                    Some(service) => (
+                        0,
+                        format!("{}/{} ({})", p.port_number, p.protocol, service.name),
+                    ),
+                    None => (0, format!("{}/{}", p.port_number, p.protocol)),
+                },
+                ViolationKind::MissingPort(protocol, number) => {
+                    (1, format!("{}/{}", number, protocol))
+                }
+                ViolationKind::MissingService(name) => (2, name),
+            };
+            failures[kind].push(line);
+        }
+
+        cases.push((ip.to_string(), failures));
+    }
+
+    let failures = cases
+        .iter()
+        .filter(|(_, f)| f.iter().any(|lines| !lines.is_empty()))
+        .count();
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<testsuites name="nmap" tests="{}" failures="{}">"#,
+        cases.len(),
+        failures
+    )?;
+    writeln!(
+        w,
+        r#"<testsuite name="{}" tests="{}" failures="{}" errors="0" timestamp="{}">"#,
+        SUITE_NAME,
+        cases.len(),
+        failures,
+        format_rfc3339(results.scan_start_time)
+    )?;
+
+    for (name, failures) in cases {
+        if failures.iter().all(|lines| lines.is_empty()) {
+            writeln!(
+                w,
+                r#"<testcase classname="nmap.port-policy" name="{}"/>"#,
+                escape_xml(&name)
+            )?;
+            continue;
+        }
+
+        writeln!(
+            w,
+            r#"<testcase classname="nmap.port-policy" name="{}">"#,
+            escape_xml(&name)
+        )?;
+        for ((kind, message), lines) in FAILURES.iter().zip(failures.iter()) {
+            if !lines.is_empty() {
+                writeln!(
+                    w,
+                    r#"<failure type="{}" message="{} {}">{}</failure>"#,
+                    kind,
+                    lines.len(),
+                    message,
+                    escape_xml(&lines.join("\n"))
+                )?;
+            }
+        }
+        writeln!(w, "</testcase>")?;
+    }
+
+    writeln!(w, "</testsuite>")?;
+    writeln!(w, "</testsuites>")
+}
+
+///Renders the JUnit report of checking `results` against `policy`.
+pub fn to_string(results: &NmapResults, policy: &Policy) -> String {
+    let mut out = Vec::new();
+    write(results, policy, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("JUnit output is valid UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::policy::Rule;
+    use crate::port::PortProtocol;
+
+    #[test]
+    fn junit_pass_and_failure() {
+        let xml = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="64"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="64"/>
+<address addr="10.0.0.6" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/></port>
+<port protocol="tcp" portid="25"><state state="closed" reason="reset" reason_ttl="64"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+        let policy = Policy::new()
+            .rule(Rule::new("10.0.0.0/24".parse().unwrap()).allow(PortProtocol::Tcp, 22));
+
+        assert_eq!(
+            to_string(&results, &policy),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="nmap" tests="2" failures="1">
+<testsuite name="nmap port policy" tests="2" failures="1" errors="0" timestamp="2020-05-01T07:40:12Z">
+<testcase classname="nmap.port-policy" name="10.0.0.5">
+<failure type="UnexpectedOpenPort" message="1 unexpected open port(s)">23/tcp (telnet)</failure>
+</testcase>
+<testcase classname="nmap.port-policy" name="10.0.0.6"/>
+</testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn junit_missing_requirements() {
+        let xml = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="64"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+        let policy = Policy::new().rule(
+            Rule::new("10.0.0.5".parse().unwrap())
+                .require(PortProtocol::Tcp, 22)
+                .require_service("https"),
+        );
+
+        let report = to_string(&results, &policy);
+        assert!(report.contains(
+            r#"<failure type="MissingExpectedPort" message="1 missing expected port(s)">22/tcp</failure>"#
+        ));
+        assert!(report.contains(
+            r#"<failure type="MissingExpectedService" message="1 missing expected service(s)">https</failure>"#
+        ));
+    }
+}
@@ -0,0 +1,16 @@
+//!Exporters that render parsed results into formats consumed by other tools.
+//!
+//!Every exporter exposes a `write()` function taking any
+//![`std::io::Write`](https://doc.rust-lang.org/std/io/trait.Write.html)
+//!implementation and a `to_string()` convenience wrapper.
+pub mod csv;
+pub mod dot;
+pub mod ecs;
+pub mod grepable;
+pub mod json;
+pub mod junit;
+pub mod prometheus;
+pub mod sarif;
+pub mod splunk;
+pub mod stix;
+pub mod targets;
@@ -0,0 +1,170 @@
+//!Prometheus text exposition format.
+//!
+//!Renders aggregate gauges for a scan so periodic scans can be scraped
+//!(e.g. through the node exporter textfile collector) into a monitoring
+//!stack:
+//!
+//!* `nmap_hosts{state}`: number of hosts per state,
+//!* `nmap_open_ports{service}`: number of open ports per service,
+//!* `nmap_scan_start_timestamp_seconds` and `nmap_scan_duration_seconds`,
+//!* `nmap_host_latency_seconds{ip}`: smoothed round trip time per host.
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::host::HostState;
+use crate::port::PortState;
+use crate::NmapResults;
+
+const HOST_STATES: [HostState; 4] = [
+    HostState::Up,
+    HostState::Down,
+    HostState::Unknown,
+    HostState::Skipped,
+];
+
+///Writes the metrics of `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    header(w, "nmap_hosts", "Number of scanned hosts by state.")?;
+    for state in HOST_STATES.iter() {
+        let count = results.hosts().filter(|h| h.status.state == *state).count();
+        writeln!(w, "nmap_hosts{{state=\"{}\"}} {}", state, count)?;
+    }
+
+    let mut services = BTreeMap::new();
+    for host in results.hosts() {
+        for port in host
+            .port_info
+            .ports()
+            .filter(|p| p.status.state == PortState::Open)
+        {
+            let name = port
+                .service_info
+                .as_ref()
+                .map(|s| s.name.as_str())
+                .unwrap_or("unknown");
+            *services.entry(name).or_insert(0) += 1;
+        }
+    }
+
+    header(w, "nmap_open_ports", "Number of open ports by service.")?;
+    for (service, count) in services {
+        writeln!(
+            w,
+            "nmap_open_ports{{service=\"{}\"}} {}",
+            escape_label(service),
+            count
+        )?;
+    }
+
+    header(
+        w,
+        "nmap_scan_start_timestamp_seconds",
+        "Start time of the scan since Unix epoch.",
+    )?;
+    writeln!(
+        w,
+        "nmap_scan_start_timestamp_seconds {}",
+        results.scan_start_time
+    )?;
+
+    if let Some(end) = results.scan_end_time {
+        header(w, "nmap_scan_duration_seconds", "Duration of the scan.")?;
+        writeln!(
+            w,
+            "nmap_scan_duration_seconds {}",
+            end.saturating_sub(results.scan_start_time)
+        )?;
+    }
+
+    let latencies = results
+        .hosts()
+        .filter_map(|h| Some((h.primary_ip()?, h.times()?.srtt)))
+        .collect::<Vec<_>>();
+    if !latencies.is_empty() {
+        header(
+            w,
+            "nmap_host_latency_seconds",
+            "Smoothed round trip time of a host.",
+        )?;
+        for (ip, srtt) in latencies {
+            writeln!(
+                w,
+                "nmap_host_latency_seconds{{ip=\"{}\"}} {}",
+                ip,
+                srtt as f64 / 1_000_000.0
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+///Renders the metrics of `results`.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("metrics output is valid UTF-8")
+}
+
+fn header<W: Write>(w: &mut W, name: &str, help: &str) -> io::Result<()> {
+    writeln!(w, "# HELP {} {}", name, help)?;
+    writeln!(w, "# TYPE {} gauge", name)
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prometheus_metrics() {
+        let xml = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="2222"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="80"><state state="closed" reason="reset" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+<times srtt="192516" rttvar="2233" to="201448"/>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="45.33.32.157" addrtype="ipv4"/>
+</host>
+<runstats><finished time="1588318814"/></runstats>
+</nmaprun>"#;
+        let mut results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "# HELP nmap_hosts Number of scanned hosts by state.\n\
+             # TYPE nmap_hosts gauge\n\
+             nmap_hosts{state=\"up\"} 1\n\
+             nmap_hosts{state=\"down\"} 1\n\
+             nmap_hosts{state=\"unknown\"} 0\n\
+             nmap_hosts{state=\"skipped\"} 0\n\
+             # HELP nmap_open_ports Number of open ports by service.\n\
+             # TYPE nmap_open_ports gauge\n\
+             nmap_open_ports{service=\"ssh\"} 2\n\
+             # HELP nmap_scan_start_timestamp_seconds Start time of the scan since Unix epoch.\n\
+             # TYPE nmap_scan_start_timestamp_seconds gauge\n\
+             nmap_scan_start_timestamp_seconds 1588318812\n\
+             # HELP nmap_scan_duration_seconds Duration of the scan.\n\
+             # TYPE nmap_scan_duration_seconds gauge\n\
+             nmap_scan_duration_seconds 2\n\
+             # HELP nmap_host_latency_seconds Smoothed round trip time of a host.\n\
+             # TYPE nmap_host_latency_seconds gauge\n\
+             nmap_host_latency_seconds{ip=\"45.33.32.156\"} 0.192516\n"
+        );
+
+        results.scan_start_time = i64::MIN;
+        results.scan_end_time = Some(i64::MAX);
+        assert!(to_string(&results).contains(&format!("nmap_scan_duration_seconds {}\n", i64::MAX)));
+    }
+}
@@ -0,0 +1,305 @@
+//!SARIF 2.1.0 export for security dashboards.
+//!
+//!Two kinds of findings are reported:
+//!
+//!* script results that flag the target as vulnerable, which is how the
+//!  scripts of Nmap's `vuln` category report their findings,
+//!* open ports running a service considered risky, such as `telnet`.
+//!
+//!Every result is located at `<protocol>://<ip>:<port>` (or the bare IP
+//!for host scripts) so GitHub and GitLab security views can group them per
+//!exposed service.
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::host::{Host, Script};
+use crate::json::Value;
+use crate::port::{Port, PortState};
+use crate::NmapResults;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+///Services reported as risky by default when found open.
+pub const DEFAULT_RISKY_SERVICES: &[&str] = &[
+    "telnet",
+    "ftp",
+    "tftp",
+    "rlogin",
+    "rsh",
+    "rexec",
+    "exec",
+    "login",
+    "shell",
+    "snmp",
+    "vnc",
+    "x11",
+    "netbios-ssn",
+    "microsoft-ds",
+    "ms-wbt-server",
+    "ms-sql-s",
+    "mysql",
+    "postgresql",
+    "redis",
+    "mongodb",
+    "memcached",
+];
+
+///Controls which findings end up in the SARIF log.
+#[derive(Clone, Debug)]
+pub struct Options {
+    ///Service names reported when found on an open port.
+    pub risky_services: Vec<String>,
+    ///Report the output of every script as a `note`, not only the ones
+    ///flagging a vulnerability.
+    pub include_all_scripts: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            risky_services: DEFAULT_RISKY_SERVICES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            include_all_scripts: false,
+        }
+    }
+}
+
+///Writes a SARIF log of `results` using the default [`Options`] to `w`.
+///
+///[`Options`]: struct.Options.html
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    write_with(results, &Options::default(), w)
+}
+
+///Writes a SARIF log of `results` to `w`.
+pub fn write_with<W: Write>(results: &NmapResults, options: &Options, w: &mut W) -> io::Result<()> {
+    writeln!(w, "{}", log(results, options))
+}
+
+///Renders a SARIF log of `results` using the default [`Options`].
+///
+///[`Options`]: struct.Options.html
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("SARIF output is valid UTF-8")
+}
+
+fn log(results: &NmapResults, options: &Options) -> Value {
+    let mut rules = BTreeMap::new();
+    let mut findings = Vec::new();
+
+    for host in results.hosts() {
+        for script in host.scripts() {
+            if let Some(finding) = script_finding(host, None, script, options, &mut rules) {
+                findings.push(finding);
+            }
+        }
+
+        for port in host
+            .port_info
+            .ports()
+            .filter(|p| p.status.state == PortState::Open)
+        {
+            for script in &port.scripts {
+                if let Some(finding) = script_finding(host, Some(port), script, options, &mut rules)
+                {
+                    findings.push(finding);
+                }
+            }
+
+            if let Some(service) = &port.service_info {
+                if options.risky_services.contains(&service.name) {
+                    let rule_id = format!("risky-service/{}", service.name);
+                    rules.insert(
+                        rule_id.clone(),
+                        format!("Potentially risky service `{}` exposed", service.name),
+                    );
+                    findings.push(finding(
+                        &rule_id,
+                        "warning",
+                        &format!(
+                            "{} is running {} on {}/{}",
+                            location_name(host, Some(port)),
+                            service.name,
+                            port.port_number,
+                            port.protocol
+                        ),
+                        host,
+                        Some(port),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut driver = vec![
+        ("name", Value::from("nmap")),
+        ("informationUri", Value::from("https://nmap.org")),
+    ];
+    if let Some(version) = &results.nmap_version {
+        driver.push(("version", Value::from(version.as_str())));
+    }
+    driver.push((
+        "rules",
+        Value::Array(
+            rules
+                .into_iter()
+                .map(|(id, description)| {
+                    Value::object(vec![
+                        ("id", Value::from(id)),
+                        (
+                            "shortDescription",
+                            Value::object(vec![("text", Value::from(description))]),
+                        ),
+                    ])
+                })
+                .collect(),
+        ),
+    ));
+
+    Value::object(vec![
+        ("$schema", Value::from(SCHEMA)),
+        ("version", Value::from("2.1.0")),
+        (
+            "runs",
+            Value::Array(vec![Value::object(vec![
+                (
+                    "tool",
+                    Value::object(vec![("driver", Value::object(driver))]),
+                ),
+                ("results", Value::Array(findings)),
+            ])]),
+        ),
+    ])
+}
+
+fn script_finding(
+    host: &Host,
+    port: Option<&Port>,
+    script: &Script,
+    options: &Options,
+    rules: &mut BTreeMap<String, String>,
+) -> Option<Value> {
+    let level = if is_vulnerable(script) {
+        "error"
+    } else if options.include_all_scripts {
+        "note"
+    } else {
+        return None;
+    };
+
+    let rule_id = format!("script/{}", script.id);
+    rules.insert(rule_id.clone(), format!("Nmap script `{}`", script.id));
+
+    Some(finding(&rule_id, level, script.output.trim(), host, port))
+}
+
+//Scripts from the `vuln` category report `State: VULNERABLE` (or
+//`LIKELY VULNERABLE`) in their output when the check succeeded.
+fn is_vulnerable(script: &Script) -> bool {
+    script.output.lines().any(|line| {
+        let line = line.trim();
+        line.starts_with("State: VULNERABLE") || line.starts_with("State: LIKELY VULNERABLE")
+    })
+}
+
+fn finding(rule_id: &str, level: &str, message: &str, host: &Host, port: Option<&Port>) -> Value {
+    let name = location_name(host, port);
+    let uri = match port {
+        Some(port) => format!("{}://{}", port.protocol, name),
+        None => name.clone(),
+    };
+
+    Value::object(vec![
+        ("ruleId", Value::from(rule_id)),
+        ("level", Value::from(level)),
+        (
+            "message",
+            Value::object(vec![("text", Value::from(message))]),
+        ),
+        (
+            "locations",
+            Value::Array(vec![Value::object(vec![
+                (
+                    "physicalLocation",
+                    Value::object(vec![(
+                        "artifactLocation",
+                        Value::object(vec![("uri", Value::from(uri))]),
+                    )]),
+                ),
+                (
+                    "logicalLocations",
+                    Value::Array(vec![Value::object(vec![
+                        ("fullyQualifiedName", Value::from(name)),
+                        (
+                            "kind",
+                            Value::from(if port.is_some() { "port" } else { "host" }),
+                        ),
+                    ])]),
+                ),
+            ])]),
+        ),
+    ])
+}
+
+fn location_name(host: &Host, port: Option<&Port>) -> String {
+    let ip = host
+        .primary_ip()
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    match port {
+        Some(port) => format!("{}:{}", ip, port.port_number),
+        None => ip,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const XML: &str = r#"<nmaprun start="1588318812" version="7.80">
+<host><status state="up" reason="echo-reply" reason_ttl="128"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="128"/><service name="telnet" method="table" conf="3"/></port>
+<port protocol="tcp" portid="445"><state state="open" reason="syn-ack" reason_ttl="128"/><service name="https" method="table" conf="3"/>
+<script id="smb-vuln-ms17-010" output="&#xa;  VULNERABLE:&#xa;  Remote Code Execution vulnerability in Microsoft SMBv1 servers (ms17-010)&#xa;    State: VULNERABLE&#xa;"/>
+<script id="http-title" output="Welcome"/>
+</port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn sarif_reports_vulnerable_scripts_and_risky_services() {
+        let results = NmapResults::parse(XML).unwrap();
+        let sarif = to_string(&results);
+
+        assert!(sarif.starts_with(
+            r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{"tool":{"driver":{"name":"nmap","informationUri":"https://nmap.org","version":"7.80","rules":[{"id":"risky-service/telnet","#
+        ));
+        assert!(sarif.contains(r#""ruleId":"risky-service/telnet","level":"warning""#));
+        assert!(sarif.contains(r#""uri":"tcp://10.0.0.5:23""#));
+        assert!(sarif.contains(r#""ruleId":"script/smb-vuln-ms17-010","level":"error""#));
+        assert!(sarif.contains(r#""uri":"tcp://10.0.0.5:445""#));
+        assert!(!sarif.contains("http-title"));
+    }
+
+    #[test]
+    fn sarif_all_scripts_as_notes() {
+        let results = NmapResults::parse(XML).unwrap();
+        let options = Options {
+            risky_services: Vec::new(),
+            include_all_scripts: true,
+        };
+        let mut out = Vec::new();
+        write_with(&results, &options, &mut out).unwrap();
+        let sarif = String::from_utf8(out).unwrap();
+
+        assert!(sarif.contains(r#""ruleId":"script/http-title","level":"note""#));
+        assert!(!sarif.contains("risky-service"));
+    }
+}
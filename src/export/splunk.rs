@@ -0,0 +1,104 @@
+//!Splunk friendly `key=value` events.
+//!
+//!One line is produced per open port, starting with the timestamp, which
+//!is the format most HTTP Event Collector and forwarder setups expect:
+//!
+//!```text
+//!2020-05-01T07:40:14Z ip=45.33.32.156 port=22 proto=tcp service="ssh" product="OpenSSH" version="6.6.1p1"
+//!```
+//!
+//!String values are always quoted; fields that are unknown are left out.
+use std::io::{self, Write};
+
+use crate::port::PortState;
+use crate::util::format_rfc3339;
+use crate::NmapResults;
+
+///Writes one event line per open port in `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    for host in results.hosts() {
+        let timestamp = host
+            .scan_end_time
+            .or(results.scan_end_time)
+            .unwrap_or(results.scan_start_time);
+        let ip = host
+            .primary_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_default();
+
+        for port in host
+            .port_info
+            .ports()
+            .filter(|p| p.status.state == PortState::Open)
+        {
+            write!(
+                w,
+                "{} ip={} port={} proto={}",
+                format_rfc3339(timestamp),
+                ip,
+                port.port_number,
+                port.protocol
+            )?;
+
+            if let Some(name) = host.host_names().next() {
+                write!(w, " hostname={}", quote(&name.name))?;
+            }
+            if let Some(service) = &port.service_info {
+                write!(w, " service={}", quote(&service.name))?;
+                if let Some(product) = &service.product {
+                    write!(w, " product={}", quote(product))?;
+                }
+                if let Some(version) = &service.version {
+                    write!(w, " version={}", quote(version))?;
+                }
+            }
+
+            writeln!(w)?;
+        }
+    }
+
+    Ok(())
+}
+
+///Renders one event line per open port in `results`.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("key=value output is valid UTF-8")
+}
+
+fn quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace(['\r', '\n'], " ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splunk_events_for_open_ports() {
+        let xml = r#"<nmaprun start="1588318812">
+<host endtime="1588318814"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="6.6.1p1 &quot;x&quot;" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="25"><state state="filtered" reason="no-response" reason_ttl="0"/><service name="smtp" method="table" conf="3"/></port>
+<port protocol="udp" portid="53"><state state="open" reason="udp-response" reason_ttl="53"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "2020-05-01T07:40:14Z ip=45.33.32.156 port=22 proto=tcp service=\"ssh\" product=\"OpenSSH\" version=\"6.6.1p1 \\\"x\\\"\"\n\
+             2020-05-01T07:40:14Z ip=45.33.32.156 port=53 proto=udp\n"
+        );
+    }
+}
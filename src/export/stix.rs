@@ -0,0 +1,269 @@
+//!STIX 2.1 bundle export.
+//!
+//!Hosts that are up are turned into `infrastructure` objects linked to
+//!their `ipv4-addr`/`ipv6-addr`, `mac-addr` and `domain-name` observables,
+//!and every open port into a `network-traffic` observable (plus a
+//!`software` observable when the product is known). All observables are
+//!referenced by a single `observed-data` object spanning the scan, so the
+//!bundle can be imported into threat intelligence platforms such as
+//!OpenCTI or MISP.
+//!
+//!Identifiers are deterministic: observables use the STIX namespace and
+//!their ID contributing properties as mandated by the specification, and
+//!the other objects are derived from the scan start time, so exporting the
+//!same scan twice yields the same bundle.
+use std::io::{self, Write};
+
+use crate::host::{Address, Host, HostState};
+use crate::json::Value;
+use crate::port::PortState;
+use crate::util::{format_rfc3339, uuid_v5};
+use crate::NmapResults;
+
+//00abedb4-aa42-466c-9c01-fed23315a9b7, the namespace of STIX Cyber
+//Observable identifiers.
+const STIX_NAMESPACE: [u8; 16] = [
+    0x00, 0xab, 0xed, 0xb4, 0xaa, 0x42, 0x46, 0x6c, 0x9c, 0x01, 0xfe, 0xd2, 0x33, 0x15, 0xa9, 0xb7,
+];
+
+///Writes a STIX 2.1 bundle of `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    writeln!(w, "{}", bundle(results))
+}
+
+///Renders a STIX 2.1 bundle of `results`.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("STIX output is valid UTF-8")
+}
+
+fn bundle(results: &NmapResults) -> Value {
+    let start = results.scan_start_time;
+    let end = results.scan_end_time.unwrap_or(start);
+    let created = timestamp(end);
+
+    let mut objects = Vec::new();
+    let mut observables = Vec::new();
+
+    for host in results.hosts().filter(|h| h.status.state == HostState::Up) {
+        let host_observables = host_objects(host, &mut objects);
+        if host_observables.is_empty() {
+            continue;
+        }
+
+        let name = host_name(host);
+        let infrastructure_id = sdo_id("infrastructure", &format!("{}:{}", name, start));
+        objects.push(Value::object(vec![
+            ("type", Value::from("infrastructure")),
+            ("spec_version", Value::from("2.1")),
+            ("id", Value::from(infrastructure_id.as_str())),
+            ("created", Value::from(created.as_str())),
+            ("modified", Value::from(created.as_str())),
+            ("name", Value::from(name)),
+            ("infrastructure_types", Value::from(vec!["unknown"])),
+        ]));
+
+        for target in &host_observables {
+            objects.push(Value::object(vec![
+                ("type", Value::from("relationship")),
+                ("spec_version", Value::from("2.1")),
+                (
+                    "id",
+                    Value::from(sdo_id(
+                        "relationship",
+                        &format!("{}:{}", infrastructure_id, target),
+                    )),
+                ),
+                ("created", Value::from(created.as_str())),
+                ("modified", Value::from(created.as_str())),
+                ("relationship_type", Value::from("consists-of")),
+                ("source_ref", Value::from(infrastructure_id.as_str())),
+                ("target_ref", Value::from(target.as_str())),
+            ]));
+        }
+
+        observables.extend(host_observables);
+    }
+
+    if !observables.is_empty() {
+        objects.push(Value::object(vec![
+            ("type", Value::from("observed-data")),
+            ("spec_version", Value::from("2.1")),
+            (
+                "id",
+                Value::from(sdo_id("observed-data", &start.to_string())),
+            ),
+            ("created", Value::from(created.as_str())),
+            ("modified", Value::from(created.as_str())),
+            ("first_observed", Value::from(timestamp(start))),
+            ("last_observed", Value::from(timestamp(end))),
+            ("number_observed", Value::from(1u8)),
+            ("object_refs", Value::from(observables)),
+        ]));
+    }
+
+    Value::object(vec![
+        ("type", Value::from("bundle")),
+        ("id", Value::from(sdo_id("bundle", &start.to_string()))),
+        ("objects", Value::Array(objects)),
+    ])
+}
+
+//Pushes the observables describing `host` to `objects` and returns their
+//identifiers.
+fn host_objects(host: &Host, objects: &mut Vec<Value>) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut ip_id = None;
+
+    for address in host.addresses() {
+        let (kind, value) = match address {
+            Address::IpAddr(ip) if ip.is_ipv4() => ("ipv4-addr", ip.to_string()),
+            Address::IpAddr(ip) => ("ipv6-addr", ip.to_string()),
+            Address::MacAddr(mac) => ("mac-addr", mac.to_string().to_lowercase()),
+        };
+        let id = push_observable(objects, kind, vec![("value", Value::from(value))]);
+        if kind != "mac-addr" && ip_id.is_none() {
+            ip_id = Some((id.clone(), kind));
+        }
+        ids.push(id);
+    }
+
+    for name in host.host_names() {
+        let id = push_observable(
+            objects,
+            "domain-name",
+            vec![("value", Value::from(name.name.as_str()))],
+        );
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    let (ip_id, ip_kind) = match ip_id {
+        Some(ip) => ip,
+        None => return ids,
+    };
+    let network = if ip_kind == "ipv4-addr" {
+        "ipv4"
+    } else {
+        "ipv6"
+    };
+
+    for port in host
+        .port_info
+        .ports()
+        .filter(|p| p.status.state == PortState::Open)
+    {
+        let mut protocols = vec![network.to_string(), port.protocol.to_string()];
+        if let Some(service) = &port.service_info {
+            protocols.push(service.name.to_lowercase());
+        }
+        ids.push(push_observable(
+            objects,
+            "network-traffic",
+            vec![
+                ("dst_port", Value::from(port.port_number)),
+                ("dst_ref", Value::from(ip_id.as_str())),
+                ("protocols", Value::from(protocols)),
+            ],
+        ));
+
+        if let Some(product) = port.service_info.as_ref().and_then(|s| s.product.as_ref()) {
+            let mut properties = vec![("name", Value::from(product.as_str()))];
+            if let Some(version) = port.service_info.as_ref().and_then(|s| s.version.as_ref()) {
+                properties.push(("version", Value::from(version.as_str())));
+            }
+            let id = push_observable(objects, "software", properties);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids
+}
+
+//Observables are identified by the UUIDv5 of their canonical (sorted,
+//compact JSON) ID contributing properties. `properties` must be sorted.
+fn push_observable(objects: &mut Vec<Value>, kind: &str, properties: Vec<(&str, Value)>) -> String {
+    let canonical = Value::object(properties.clone()).to_string();
+    let id = format!("{}--{}", kind, uuid_v5(&STIX_NAMESPACE, &canonical));
+
+    let exists = objects.iter().any(|o| match o {
+        Value::Object(members) => members
+            .iter()
+            .any(|(k, v)| k == "id" && *v == Value::from(id.as_str())),
+        _ => false,
+    });
+    if !exists {
+        let mut members = vec![
+            ("type", Value::from(kind)),
+            ("spec_version", Value::from("2.1")),
+            ("id", Value::from(id.as_str())),
+        ];
+        members.extend(properties);
+        objects.push(Value::object(members));
+    }
+
+    id
+}
+
+fn sdo_id(kind: &str, name: &str) -> String {
+    format!(
+        "{}--{}",
+        kind,
+        uuid_v5(&STIX_NAMESPACE, &format!("nmap:{}:{}", kind, name))
+    )
+}
+
+fn host_name(host: &Host) -> String {
+    host.host_names()
+        .next()
+        .map(|h| h.name.clone())
+        .or_else(|| host.primary_ip().map(|ip| ip.to_string()))
+        .unwrap_or_default()
+}
+
+//STIX requires millisecond precision for `created` and `modified`.
+fn timestamp(epoch: i64) -> String {
+    format_rfc3339(epoch).replace('Z', ".000Z")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stix_bundle() {
+        let xml = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<hostnames><hostname name="scanme.nmap.org" type="user"/><hostname name="scanme.nmap.org" type="PTR"/></hostnames>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="6.6.1p1" method="probed" conf="10"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="45.33.32.157" addrtype="ipv4"/>
+</host>
+<runstats><finished time="1588318814"/></runstats>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+        let stix = to_string(&results);
+
+        assert!(stix.starts_with(r#"{"type":"bundle","id":"bundle--"#));
+        //Same identifier as uuid5(STIX namespace, '{"value":"45.33.32.156"}').
+        assert!(stix.contains(
+            r#"{"type":"ipv4-addr","spec_version":"2.1","id":"ipv4-addr--7b645e01-c287-5ea5-9127-1ba855366718","value":"45.33.32.156"}"#
+        ));
+        assert_eq!(stix.matches(r#""type":"domain-name""#).count(), 1);
+        assert!(stix.contains(r#""dst_port":22"#));
+        assert!(stix.contains(r#""protocols":["ipv4","tcp","ssh"]"#));
+        assert!(stix.contains(r#""type":"software","spec_version":"2.1""#));
+        assert!(stix.contains(r#""name":"scanme.nmap.org","infrastructure_types":["unknown"]"#));
+        assert!(stix.contains(r#""first_observed":"2020-05-01T07:40:12.000Z","last_observed":"2020-05-01T07:40:14.000Z""#));
+        assert!(!stix.contains("45.33.32.157"));
+        assert_eq!(stix, to_string(&results));
+    }
+}
@@ -0,0 +1,125 @@
+//!Plain target lists for follow-up scans.
+//!
+//!Host lists contain one IP address per line and can be passed to Nmap with
+//!`-iL`. Port lists contain one `ip:port` per line, with IPv6 addresses in
+//!brackets (`[2001:db8::1]:443`), the form most other tools accept.
+//!Addresses and endpoints are written once, in scan order.
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::net::IpAddr;
+
+use crate::filter::Filter;
+use crate::host::{Host, HostState};
+use crate::port::Port;
+use crate::NmapResults;
+
+///Writes the addresses of the hosts that are up in `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    write_matching(results, &Filter::new().host_state(HostState::Up), w)
+}
+
+///Writes the addresses of the hosts of `results` matching `filter` to `w`.
+pub fn write_matching<W: Write>(
+    results: &NmapResults,
+    filter: &Filter,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut seen = HashSet::new();
+    for ip in results.filter_hosts(filter).filter_map(Host::primary_ip) {
+        if seen.insert(ip) {
+            writeln!(w, "{}", ip)?;
+        }
+    }
+    Ok(())
+}
+
+///Writes an `ip:port` line per open port in `results` to `w`.
+pub fn write_ports<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    write_ports_matching(results, &Filter::open(), w)
+}
+
+///Writes an `ip:port` line per port of `results` matching `filter` to `w`.
+pub fn write_ports_matching<W: Write>(
+    results: &NmapResults,
+    filter: &Filter,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut seen = HashSet::new();
+    for (host, port) in results.filter(filter) {
+        if let Some(ip) = host.primary_ip() {
+            let endpoint = endpoint(ip, port);
+            if seen.insert(endpoint.clone()) {
+                writeln!(w, "{}", endpoint)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+///Returns the host list of `results` as a string. See [`write()`](fn.write.html).
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("target lists are valid UTF-8")
+}
+
+fn endpoint(ip: IpAddr, port: &Port) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("{}:{}", ip, port.port_number),
+        IpAddr::V6(ip) => format!("[{}]:{}", ip, port.port_number),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+<port protocol="tcp" portid="443"><state state="closed" reason="reset" reason_ttl="53"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="2001:db8::1" addrtype="ipv6"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    fn written<F: Fn(&NmapResults, &mut Vec<u8>) -> io::Result<()>>(write: F) -> String {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let mut out = Vec::new();
+        write(&results, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn host_lists() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        assert_eq!(to_string(&results), "10.0.0.1\n2001:db8::1\n");
+        assert_eq!(
+            written(|r, w| write_matching(r, &Filter::open().service("http"), w)),
+            "10.0.0.1\n"
+        );
+    }
+
+    #[test]
+    fn port_lists() {
+        assert_eq!(
+            written(write_ports),
+            "10.0.0.1:22\n10.0.0.1:80\n[2001:db8::1]:22\n"
+        );
+        assert_eq!(
+            written(|r, w| write_ports_matching(r, &Filter::open().port(22), w)),
+            "10.0.0.1:22\n[2001:db8::1]:22\n"
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//!Typed, composable filters over scan results, the builder counterpart of
+//![`query`](../query/index.html).
+//!
+//!```
+//!# use std::path::PathBuf;
+//!# use std::fs;
+//!use nmap_xml_parser::{filter::Filter, NmapResults};
+//!# let mut nmap_xml_file = PathBuf::new();
+//!# nmap_xml_file.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+//!# nmap_xml_file.push("tests/test.xml");
+//!let content = fs::read_to_string(nmap_xml_file).unwrap();
+//!let results = NmapResults::parse(&content).unwrap();
+//!let filter = Filter::open()
+//!    .service("ssh")
+//!    .port_range(1..1024)
+//!    .and(Filter::in_cidr("45.33.32.0/24").unwrap());
+//!assert_eq!(results.filter(&filter).count(), 1);
+//!```
+use std::ops::{Bound, RangeBounds};
+
+use crate::host::{Address, Host, HostState};
+use crate::net::IpNetwork;
+use crate::port::{Port, PortProtocol, PortState};
+use crate::Error;
+
+///A predicate over ports and the host they belong to. All the conditions
+///of a filter must hold; [`or()`](#method.or) and [`not()`](#method.not)
+///build other combinations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    PortState(PortState),
+    Protocol(PortProtocol),
+    PortRange(u16, u16),
+    Service(String),
+    HostState(HostState),
+    Network(IpNetwork),
+    Any(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    ///Returns a filter matching everything.
+    pub fn new() -> Self {
+        Filter::default()
+    }
+
+    ///Returns a filter matching open ports.
+    pub fn open() -> Self {
+        Filter::new().state(PortState::Open)
+    }
+
+    ///Returns a filter matching hosts with an address in `network`.
+    pub fn in_network(network: IpNetwork) -> Self {
+        Filter::new().with(Condition::Network(network))
+    }
+
+    ///Like [`in_network()`](#method.in_network), with the network in CIDR
+    ///notation such as `10.0.0.0/8`.
+    pub fn in_cidr(cidr: &str) -> Result<Self, Error> {
+        Ok(Filter::in_network(cidr.parse()?))
+    }
+
+    ///Also requires the port to be in `state`.
+    pub fn state(self, state: PortState) -> Self {
+        self.with(Condition::PortState(state))
+    }
+
+    ///Also requires the port to use `protocol`.
+    pub fn protocol(self, protocol: PortProtocol) -> Self {
+        self.with(Condition::Protocol(protocol))
+    }
+
+    ///Also requires the port to be `port_number`.
+    pub fn port(self, port_number: u16) -> Self {
+        self.port_range(port_number..=port_number)
+    }
+
+    ///Also requires the port number to be in `range`.
+    pub fn port_range<R: RangeBounds<u16>>(self, range: R) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => i64::from(n),
+            Bound::Excluded(&n) => i64::from(n) + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => i64::from(n),
+            Bound::Excluded(&n) => i64::from(n) - 1,
+            Bound::Unbounded => i64::from(u16::MAX),
+        };
+        //An empty range matches no port.
+        let (start, end) = if start > end {
+            (1, 0)
+        } else {
+            (start as u16, end as u16)
+        };
+        self.with(Condition::PortRange(start, end))
+    }
+
+    ///Also requires the detected service name to be `name`.
+    pub fn service(self, name: &str) -> Self {
+        self.with(Condition::Service(name.to_string()))
+    }
+
+    ///Also requires the host to be in `state`.
+    pub fn host_state(self, state: HostState) -> Self {
+        self.with(Condition::HostState(state))
+    }
+
+    ///Also requires `other` to hold.
+    pub fn and(mut self, other: Filter) -> Self {
+        self.conditions.extend(other.conditions);
+        self
+    }
+
+    ///Returns a filter matching when this filter or `other` holds.
+    pub fn or(self, other: Filter) -> Self {
+        Filter::new().with(Condition::Any(vec![self, other]))
+    }
+
+    ///Returns a filter matching when this filter does not hold.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Filter::new().with(Condition::Not(Box::new(self)))
+    }
+
+    ///Returns whether the filter holds for `port` of `host`.
+    pub fn matches_port(&self, host: &Host, port: &Port) -> bool {
+        self.eval(host, Some(port))
+    }
+
+    ///Returns whether the filter holds for `host`. A filter with port
+    ///conditions has to hold for one of its ports, so that e.g.
+    ///`Filter::open().not()` only matches hosts with a port that is not
+    ///open. Other filters, and filters on hosts without any port, are
+    ///evaluated for the host alone, where port conditions are false.
+    pub fn matches_host(&self, host: &Host) -> bool {
+        if !self.has_port_conditions() || host.port_info.ports().next().is_none() {
+            return self.eval(host, None);
+        }
+        host.port_info.ports().any(|p| self.eval(host, Some(p)))
+    }
+
+    fn with(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    fn has_port_conditions(&self) -> bool {
+        self.conditions.iter().any(|condition| match condition {
+            Condition::PortState(_)
+            | Condition::Protocol(_)
+            | Condition::PortRange(..)
+            | Condition::Service(_) => true,
+            Condition::HostState(_) | Condition::Network(_) => false,
+            Condition::Any(filters) => filters.iter().any(Filter::has_port_conditions),
+            Condition::Not(filter) => filter.has_port_conditions(),
+        })
+    }
+
+    fn eval(&self, host: &Host, port: Option<&Port>) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            Condition::PortState(state) => port.is_some_and(|p| p.status.state == *state),
+            Condition::Protocol(protocol) => port.is_some_and(|p| p.protocol == *protocol),
+            Condition::PortRange(start, end) => {
+                port.is_some_and(|p| (*start..=*end).contains(&p.port_number))
+            }
+            Condition::Service(name) => port
+                .and_then(|p| p.service_info.as_ref())
+                .is_some_and(|s| s.name == *name),
+            Condition::HostState(state) => host.status.state == *state,
+            Condition::Network(network) => host.addresses().any(|a| match a {
+                Address::IpAddr(ip) => network.contains(ip),
+                Address::MacAddr(_) => false,
+            }),
+            Condition::Any(filters) => filters.iter().any(|f| f.eval(host, port)),
+            Condition::Not(filter) => !filter.eval(host, port),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="2222"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="80"><state state="closed" reason="reset" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="192.168.1.5" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.9" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    fn matches(results: &NmapResults, filter: &Filter) -> Vec<String> {
+        results
+            .filter(filter)
+            .map(|(h, p)| format!("{}:{}", h.primary_ip().unwrap(), p.port_number))
+            .collect()
+    }
+
+    #[test]
+    fn composed_filters() {
+        let results = NmapResults::parse(SCAN).unwrap();
+
+        let filter = Filter::open()
+            .service("ssh")
+            .port_range(1..1024)
+            .and(Filter::in_cidr("10.0.0.0/8").unwrap());
+        assert_eq!(matches(&results, &filter), vec!["10.0.0.1:22"]);
+
+        let filter = Filter::new().port(80).or(Filter::new().port_range(2000..));
+        assert_eq!(
+            matches(&results, &filter),
+            vec!["10.0.0.1:2222", "10.0.0.1:80"]
+        );
+
+        let filter = Filter::open().and(Filter::in_cidr("10.0.0.0/8").unwrap().not());
+        assert_eq!(matches(&results, &filter), vec!["192.168.1.5:22"]);
+
+        assert!(matches(&results, &Filter::new().port_range(22..22)).is_empty());
+        assert!(Filter::in_cidr("10.0.0.0/40").is_err());
+    }
+
+    #[test]
+    fn host_filters() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let hosts = |filter: &Filter| {
+            results
+                .filter_hosts(filter)
+                .map(|h| h.primary_ip().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            hosts(&Filter::new().host_state(HostState::Down)),
+            vec!["10.0.0.9"]
+        );
+        assert_eq!(hosts(&Filter::open().port(2222)), vec!["10.0.0.1"]);
+        //192.168.1.5 only has open ports.
+        assert_eq!(hosts(&Filter::open().not()), vec!["10.0.0.1", "10.0.0.9"]);
+        assert_eq!(
+            hosts(&Filter::new().host_state(HostState::Down).not()),
+            vec!["10.0.0.1", "192.168.1.5"]
+        );
+    }
+}
@@ -1,20 +1,43 @@
 //!Host related structs and enums.
-use const_format::formatcp;
 use roxmltree::Node;
-use std::net::IpAddr;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
-use strum_macros::{Display, EnumString};
+use std::time::{Duration, SystemTime};
 
-use crate::port::PortInfo;
-use crate::util::{from_node_attr, node_attr_as_string, parse_node_attr};
+use crate::cpe::{parse_cpe_nodes, Cpe};
+use crate::net::MacAddr;
+use crate::oui::VendorLookup;
+use crate::port::{PortInfo, PortProtocol};
+use crate::scripts::{ScriptParser, TypedScript};
+use crate::util::{
+    self, from_node_attr, node_attr_as_string, parse_node_attr, parse_optional_attr, string_enum,
+};
 use crate::Error;
 
-#[derive(Display, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Address {
     IpAddr(IpAddr),
-    MacAddr(String),
+    MacAddr(MacAddr),
 }
 
+///Formats the address as Nmap writes it.
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::IpAddr(ip) => write!(f, "{}", ip),
+            Address::MacAddr(mac) => write!(f, "{}", mac),
+        }
+    }
+}
+
+///A host found by the scan.
+///
+///Two hosts are equal when the scan found the same things about them:
+///their addresses, status, host names, ports, scripts and OS detection
+///results. The scan times, round trip times and traceroute change from one
+///scan to the next and are not compared.
 #[derive(Clone, Debug)]
 pub struct Host {
     pub(crate) addresses: Vec<Address>,
@@ -24,44 +47,74 @@ pub struct Host {
     pub port_info: PortInfo,
     pub scan_start_time: Option<i64>,
     pub scan_end_time: Option<i64>,
+    pub(crate) trace: Option<Trace>,
+    pub(crate) times: Option<Times>,
+    pub(crate) os_matches: Vec<OsMatch>,
+    pub(crate) os_fingerprint: Option<String>,
+    pub(crate) mac_vendor: Option<String>,
 }
 
 impl Host {
+    pub(crate) fn new(addresses: Vec<Address>, status: HostStatus) -> Self {
+        Host {
+            addresses,
+            scripts: Vec::new(),
+            status,
+            host_names: Vec::new(),
+            port_info: Default::default(),
+            scan_start_time: None,
+            scan_end_time: None,
+            trace: None,
+            times: None,
+            os_matches: Vec::new(),
+            os_fingerprint: None,
+            mac_vendor: None,
+        }
+    }
+
     pub(crate) fn parse(node: Node) -> Result<Self, Error> {
-        let scan_start_time = node
-            .attribute("starttime")
-            .map(|s| {
-                s.parse::<i64>()
-                    .map_err(|_| Error::from("failed to parse host start time"))
-            })
-            .transpose()?;
-
-        let scan_end_time = node
-            .attribute("endtime")
-            .map(|s| {
-                s.parse::<i64>()
-                    .map_err(|_| Error::from("failed to parse host end time"))
-            })
-            .transpose()?;
+        let scan_start_time = parse_optional_attr(node, "host", "starttime")?;
+
+        let scan_end_time = parse_optional_attr(node, "host", "endtime")?;
 
         let mut status = None;
         let mut host_names = Vec::new();
         let mut port_info = Default::default();
         let mut scripts = Vec::new();
         let mut addresses = Vec::new();
+        let mut trace = None;
+        let mut times = None;
+        let mut os_matches = Vec::new();
+        let mut os_fingerprint = None;
+        let mut mac_vendor = None;
 
         for child in node.children() {
-            match child.tag_name().name() {
-                "address" => addresses.push(parse_address_node(child)?),
-                "status" => status = Some(HostStatus::parse(child)?),
-                "hostnames" => host_names = parse_hostnames_node(child)?,
-                "hostscript" => scripts = parse_hostscript_node(child)?,
-                "ports" => port_info = PortInfo::parse(child)?,
+            let name = child.tag_name().name();
+            let within = |e: Error| e.within(name);
+            match name {
+                "address" => {
+                    let address = parse_address_node(child).map_err(within)?;
+                    if let Address::MacAddr(_) = address {
+                        mac_vendor =
+                            mac_vendor.or_else(|| child.attribute("vendor").map(String::from));
+                    }
+                    addresses.push(address);
+                }
+                "status" => status = Some(HostStatus::parse(child).map_err(within)?),
+                "hostnames" => host_names = parse_hostnames_node(child).map_err(within)?,
+                "hostscript" => scripts = parse_hostscript_node(child).map_err(within)?,
+                "ports" => port_info = PortInfo::parse(child).map_err(within)?,
+                "trace" => trace = Some(Trace::parse(child).map_err(within)?),
+                "times" => times = Some(Times::parse(child).map_err(within)?),
+                "os" => (os_matches, os_fingerprint) = parse_os_node(child).map_err(within)?,
                 _ => {}
             }
         }
 
-        let status = status.ok_or_else(|| Error::from("expected `status` node for host"))?;
+        let status = status.ok_or(Error::MissingElement {
+            element: "status",
+            parent: "host",
+        })?;
 
         Ok(Host {
             addresses,
@@ -71,42 +124,228 @@ impl Host {
             port_info,
             scan_start_time,
             scan_end_time,
+            trace,
+            times,
+            os_matches,
+            os_fingerprint,
+            mac_vendor,
+        })
+    }
+
+    //Like `parse()`, with the host and its address, as Nmap wrote it, in
+    //front of the path of errors.
+    pub(crate) fn parse_in_context(node: Node) -> Result<Self, Error> {
+        Host::parse(node).map_err(|e| {
+            let address = node
+                .children()
+                .find(|c| c.has_tag_name("address"))
+                .and_then(|c| c.attribute("addr"));
+            match address {
+                Some(address) => e.within(format!("host[{}]", address)),
+                None => e.within("host"),
+            }
         })
     }
 
     ///Returns an iterator over the addresses associated with this host.
-    pub fn addresses(&self) -> std::slice::Iter<Address> {
+    pub fn addresses(&self) -> std::slice::Iter<'_, Address> {
         self.addresses.iter()
     }
 
+    ///Returns the addresses of this host as a slice.
+    pub fn addresses_slice(&self) -> &[Address] {
+        &self.addresses
+    }
+
+    ///Consumes the host and returns its addresses.
+    pub fn into_addresses(self) -> Vec<Address> {
+        self.addresses
+    }
+
     ///Returns an iterator over the scripts associated with this host.
-    pub fn scripts(&self) -> std::slice::Iter<Script> {
+    pub fn scripts(&self) -> std::slice::Iter<'_, Script> {
         self.scripts.iter()
     }
 
+    ///Returns the scripts associated with this host as a slice.
+    pub fn scripts_slice(&self) -> &[Script] {
+        &self.scripts
+    }
+
+    ///Returns the number of scripts associated with this host, not counting
+    ///those run on its ports.
+    pub fn script_count(&self) -> usize {
+        self.scripts.len()
+    }
+
+    ///Consumes the host and returns its scripts.
+    pub fn into_scripts(self) -> Vec<Script> {
+        self.scripts
+    }
+
     ///Returns an iterator over the names associated with this host.
-    pub fn host_names(&self) -> std::slice::Iter<Hostname> {
+    pub fn host_names(&self) -> std::slice::Iter<'_, Hostname> {
         self.host_names.iter()
     }
+
+    ///Returns the names associated with this host as a slice.
+    pub fn host_names_slice(&self) -> &[Hostname] {
+        &self.host_names
+    }
+
+    ///Consumes the host and returns its names.
+    pub fn into_host_names(self) -> Vec<Hostname> {
+        self.host_names
+    }
+
+    ///Adds the name `name` to this host, unless it already has it.
+    pub fn add_hostname(&mut self, name: &str, source: HostnameType) {
+        if !self.host_names.iter().any(|h| h.name == name) {
+            self.host_names.push(Hostname {
+                name: name.to_string(),
+                source,
+            });
+        }
+    }
+
+    ///Removes the name `name` from this host. Returns `true` if it had it.
+    pub fn remove_hostname(&mut self, name: &str) -> bool {
+        let before = self.host_names.len();
+        self.host_names.retain(|h| h.name != name);
+        self.host_names.len() != before
+    }
+
+    ///Adds a host script, replacing the script with the same id if there
+    ///is one.
+    pub fn add_script(&mut self, script: Script) {
+        match self.scripts.iter_mut().find(|s| s.id == script.id) {
+            Some(existing) => *existing = script,
+            None => self.scripts.push(script),
+        }
+    }
+
+    ///Removes the host script `id` and returns it.
+    pub fn remove_script(&mut self, id: &str) -> Option<Script> {
+        let i = self.scripts.iter().position(|s| s.id == id)?;
+        Some(self.scripts.remove(i))
+    }
+
+    ///Returns the traceroute to this host, if Nmap ran one.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    ///Returns the time Nmap started scanning this host.
+    pub fn scan_start(&self) -> Option<SystemTime> {
+        self.scan_start_time.and_then(util::system_time)
+    }
+
+    ///Returns the time Nmap finished scanning this host.
+    pub fn scan_end(&self) -> Option<SystemTime> {
+        self.scan_end_time.and_then(util::system_time)
+    }
+
+    ///Returns how long the scan of this host took, to the second.
+    pub fn scan_duration(&self) -> Option<Duration> {
+        util::duration_between(self.scan_start_time?, self.scan_end_time?)
+    }
+
+    ///Returns the round trip timing information Nmap gathered for this host.
+    pub fn times(&self) -> Option<&Times> {
+        self.times.as_ref()
+    }
+
+    ///Returns the vendor of the network card of this host, as Nmap found it
+    ///from the MAC address.
+    pub fn mac_vendor(&self) -> Option<&str> {
+        self.mac_vendor.as_deref()
+    }
+
+    ///Like [`mac_vendor()`](#method.mac_vendor), but falls back to looking
+    ///up the MAC address with `lookup` when Nmap did not report a vendor.
+    pub fn mac_vendor_with<'a>(&'a self, lookup: &'a dyn VendorLookup) -> Option<&'a str> {
+        self.mac_vendor()
+            .or_else(|| self.mac().and_then(|mac| lookup.vendor(&mac.to_string())))
+    }
+
+    ///Returns an iterator over the operating systems Nmap guessed for this
+    ///host, most likely first.
+    pub fn os_matches(&self) -> std::slice::Iter<'_, OsMatch> {
+        self.os_matches.iter()
+    }
+
+    ///Returns the operating systems Nmap guessed for this host as a slice.
+    pub fn os_matches_slice(&self) -> &[OsMatch] {
+        &self.os_matches
+    }
+
+    ///Consumes the host and returns its operating system guesses.
+    pub fn into_os_matches(self) -> Vec<OsMatch> {
+        self.os_matches
+    }
+
+    ///Returns the TCP/IP fingerprint Nmap printed for this host, which it
+    ///does when no known operating system matched it closely. See
+    ///[`osdb`](../osdb/index.html) to compare it with known fingerprints.
+    pub fn os_fingerprint(&self) -> Option<&str> {
+        self.os_fingerprint.as_deref()
+    }
+
+    ///Parses the scripts run on this host and its ports with the parsers
+    ///of the global registry, the built-in ones and those added with
+    ///[`scripts::register()`](../scripts/fn.register.html). Scripts no
+    ///parser reads are left out.
+    pub fn typed_scripts(&self) -> Vec<TypedScript<'_>> {
+        crate::scripts::registry()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .host_scripts(self)
+    }
+
+    ///Returns the first IP address of this host, the one Nmap scanned.
+    pub fn primary_ip(&self) -> Option<IpAddr> {
+        self.addresses.iter().find_map(|a| match a {
+            Address::IpAddr(ip) => Some(*ip),
+            _ => None,
+        })
+    }
+
+    ///Returns the first IPv4 address of this host.
+    pub fn ipv4(&self) -> Option<Ipv4Addr> {
+        self.addresses.iter().find_map(|a| match a {
+            Address::IpAddr(IpAddr::V4(ip)) => Some(*ip),
+            _ => None,
+        })
+    }
+
+    ///Returns the first IPv6 address of this host.
+    pub fn ipv6(&self) -> Option<Ipv6Addr> {
+        self.addresses.iter().find_map(|a| match a {
+            Address::IpAddr(IpAddr::V6(ip)) => Some(*ip),
+            _ => None,
+        })
+    }
+
+    ///Returns the MAC address of this host, which Nmap only finds for hosts
+    ///on the local network.
+    pub fn mac(&self) -> Option<MacAddr> {
+        self.addresses.iter().find_map(|a| match a {
+            Address::MacAddr(mac) => Some(*mac),
+            _ => None,
+        })
+    }
 }
 
 fn parse_address_node(node: Node) -> Result<Address, Error> {
-    let addrtype = node
-        .attribute("addrtype")
-        .ok_or_else(|| Error::from("expected `addrtype` attribute in `address` node"))?;
-
-    let addr = node
-        .attribute("addr")
-        .ok_or_else(|| Error::from("expected `addr` attribute in `address` node"))?;
-
-    match addrtype {
-        "mac" => Ok(Address::MacAddr(addr.to_string())),
-        _ => {
-            let a = addr
-                .parse::<IpAddr>()
-                .map_err(|_| Error::from("failed to parse IP address"))?;
-            Ok(Address::IpAddr(a))
-        }
+    let addrtype = node_attr_as_string!(node, "address", "addrtype");
+
+    match addrtype.as_str() {
+        "mac" => Ok(Address::MacAddr(parse_node_attr!(
+            node, "address", "addr", MacAddr
+        ))),
+        _ => Ok(Address::IpAddr(parse_node_attr!(
+            node, "address", "addr", IpAddr
+        ))),
     }
 }
 
@@ -115,7 +354,7 @@ fn parse_hostscript_node(node: Node) -> Result<Vec<Script>, Error> {
 
     for child in node.children() {
         if child.tag_name().name() == "script" {
-            r.push(Script::parse(child)?);
+            r.push(Script::parse_in_context(child)?);
         }
     }
 
@@ -134,7 +373,77 @@ fn parse_hostnames_node(node: Node) -> Result<Vec<Hostname>, Error> {
     Ok(r)
 }
 
-#[derive(Clone, Debug)]
+///Summarizes the host on one line, e.g.
+///`10.0.0.1 (web.example.com) up, 2 open ports`.
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.primary_ip() {
+            Some(ip) => write!(f, "{}", ip)?,
+            None => match self.addresses.first() {
+                Some(address) => write!(f, "{}", address)?,
+                None => write!(f, "unknown address")?,
+            },
+        }
+        if let Some(name) = self.host_names.first() {
+            write!(f, " ({})", name.name)?;
+        }
+        write!(f, " {}", self.status.state)?;
+        let open = self.port_info.open_ports().count();
+        if open > 0 || self.status.state == HostState::Up {
+            write!(
+                f,
+                ", {} open port{}",
+                open,
+                if open == 1 { "" } else { "s" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Host {
+    //The fields compared for equality, see the type documentation.
+    #[allow(clippy::type_complexity)]
+    fn identity(
+        &self,
+    ) -> (
+        &[Address],
+        &HostStatus,
+        &[Hostname],
+        &PortInfo,
+        &[Script],
+        &[OsMatch],
+        &Option<String>,
+        &Option<String>,
+    ) {
+        (
+            &self.addresses,
+            &self.status,
+            &self.host_names,
+            &self.port_info,
+            &self.scripts,
+            &self.os_matches,
+            &self.os_fingerprint,
+            &self.mac_vendor,
+        )
+    }
+}
+
+impl PartialEq for Host {
+    fn eq(&self, other: &Self) -> bool {
+        self.identity() == other.identity()
+    }
+}
+
+impl Eq for Host {}
+
+impl Hash for Host {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identity().hash(state);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct HostStatus {
     pub state: HostState,
     pub reason: String,
@@ -143,11 +452,9 @@ pub struct HostStatus {
 
 impl HostStatus {
     fn parse(node: Node) -> Result<Self, Error> {
-        let state = from_node_attr!(node, "hoststatus", "state", HostState);
-
-        let reason = node_attr_as_string!(node, "hoststatus", "reason");
+        let state = from_node_attr!(node, "status", "state", HostState);
 
-        let reason_ttl = parse_node_attr!(node, "hoststatus", "reason_ttl", u8);
+        let (reason, reason_ttl) = util::parse_reason(node, "status")?;
 
         Ok(HostStatus {
             state,
@@ -157,27 +464,37 @@ impl HostStatus {
     }
 }
 
-#[derive(EnumString, Display, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HostState {
-    #[strum(serialize = "up")]
     Up,
-    #[strum(serialize = "down")]
     Down,
-    #[strum(serialize = "unknown")]
     Unknown,
-    #[strum(serialize = "skipped")]
     Skipped,
 }
 
-#[derive(EnumString, Display, Clone, Debug, PartialEq)]
+string_enum!(HostState, "host state", {
+    Up => "up",
+    Down => "down",
+    Unknown => "unknown",
+    Skipped => "skipped",
+});
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HostnameType {
-    #[strum(serialize = "user", to_string = "User")]
     User,
-    #[strum(serialize = "PTR", to_string = "Dns")]
     Dns,
+    ///Reverse DNS name looked up after the scan, see
+    ///[`enrich::dns`](../enrich/dns/index.html).
+    Resolved,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+string_enum!(HostnameType, "host name type", {
+    User => "User" | "user",
+    Dns => "Dns" | "PTR",
+    Resolved => "Resolved" | "resolved",
+});
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Hostname {
     pub name: String,
     pub source: HostnameType,
@@ -193,19 +510,370 @@ impl Hostname {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Script {
     pub id: String,
     pub output: String,
+    pub(crate) elements: Vec<ScriptElement>,
 }
 
 impl Script {
-    fn parse(node: Node) -> Result<Self, Error> {
+    pub(crate) fn new(id: String, output: String) -> Self {
+        Script {
+            id,
+            output,
+            elements: Vec::new(),
+        }
+    }
+
+    pub(crate) fn parse(node: Node) -> Result<Self, Error> {
         let id = node_attr_as_string!(node, "script", "id");
 
         let output = node_attr_as_string!(node, "script", "output");
 
-        Ok(Script { id, output })
+        let elements = parse_script_elements(node)?;
+
+        Ok(Script {
+            id,
+            output,
+            elements,
+        })
+    }
+
+    //Like `parse()`, with the script id in front of the path of errors.
+    pub(crate) fn parse_in_context(node: Node) -> Result<Self, Error> {
+        Script::parse(node).map_err(|e| match node.attribute("id") {
+            Some(id) => e.within(format!("script[{}]", id)),
+            None => e.within("script"),
+        })
+    }
+
+    ///Returns an iterator over the structured output of the script, the
+    ///`<elem>` and `<table>` nodes Nmap emits along with the text output.
+    pub fn elements(&self) -> std::slice::Iter<'_, ScriptElement> {
+        self.elements.iter()
+    }
+
+    ///Returns the structured output of the script as a slice.
+    pub fn elements_slice(&self) -> &[ScriptElement] {
+        &self.elements
+    }
+
+    ///Consumes the script and returns its structured output.
+    pub fn into_elements(self) -> Vec<ScriptElement> {
+        self.elements
+    }
+
+    ///Returns the top-level element with the key `key`.
+    pub fn get(&self, key: &str) -> Option<&ScriptElement> {
+        self.elements.iter().find(|e| e.key() == Some(key))
+    }
+
+    ///Returns the value of the top-level `<elem>` with the key `key`.
+    pub fn elem(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(ScriptElement::value)
+    }
+
+    ///Parses the script as a `T`, if `T` reads scripts with this id.
+    pub fn parse_as<T: ScriptParser>(&self) -> Option<T> {
+        if T::SCRIPT_IDS.is_empty() || T::SCRIPT_IDS.contains(&self.id.as_str()) {
+            T::parse(self)
+        } else {
+            None
+        }
+    }
+}
+
+///An element of the structured output of a script.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ScriptElement {
+    ///A value, keyed when it is part of an associative table.
+    Elem { key: Option<String>, value: String },
+    ///A table of elements, keyed when it is part of an associative table.
+    Table {
+        key: Option<String>,
+        elements: Vec<ScriptElement>,
+    },
+}
+
+impl ScriptElement {
+    fn parse(node: Node) -> Result<Option<Self>, Error> {
+        let key = node.attribute("key").map(String::from);
+        match node.tag_name().name() {
+            "elem" => Ok(Some(ScriptElement::Elem {
+                key,
+                value: node.text().unwrap_or("").to_string(),
+            })),
+            "table" => Ok(Some(ScriptElement::Table {
+                key,
+                elements: parse_script_elements(node)?,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            ScriptElement::Elem { key, .. } | ScriptElement::Table { key, .. } => key.as_deref(),
+        }
+    }
+
+    ///Returns the value of an `<elem>`, `None` for a table.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            ScriptElement::Elem { value, .. } => Some(value),
+            ScriptElement::Table { .. } => None,
+        }
+    }
+
+    ///Returns the elements of a table, nothing for an `<elem>`.
+    pub fn elements(&self) -> std::slice::Iter<'_, ScriptElement> {
+        match self {
+            ScriptElement::Elem { .. } => [].iter(),
+            ScriptElement::Table { elements, .. } => elements.iter(),
+        }
+    }
+
+    ///Consumes the element and returns the elements of a table, nothing for
+    ///an `<elem>`.
+    pub fn into_elements(self) -> Vec<ScriptElement> {
+        match self {
+            ScriptElement::Elem { .. } => Vec::new(),
+            ScriptElement::Table { elements, .. } => elements,
+        }
+    }
+
+    ///Returns the element of a table with the key `key`.
+    pub fn get(&self, key: &str) -> Option<&ScriptElement> {
+        self.elements().find(|e| e.key() == Some(key))
+    }
+
+    ///Returns the value of the `<elem>` of a table with the key `key`.
+    pub fn elem(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(ScriptElement::value)
+    }
+}
+
+fn parse_script_elements(node: Node) -> Result<Vec<ScriptElement>, Error> {
+    let mut elements = Vec::new();
+    for child in node.children() {
+        if let Some(element) = ScriptElement::parse(child)? {
+            elements.push(element);
+        }
+    }
+    Ok(elements)
+}
+
+///Round trip timing information of a host, in microseconds.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Times {
+    ///Smoothed round trip time.
+    pub srtt: i64,
+    ///Round trip time variance.
+    pub rttvar: i64,
+    ///Probe timeout.
+    pub timeout: i64,
+}
+
+impl Times {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let srtt = parse_node_attr!(node, "times", "srtt", i64);
+
+        let rttvar = parse_node_attr!(node, "times", "rttvar", i64);
+
+        let timeout = parse_node_attr!(node, "times", "to", i64);
+
+        Ok(Times {
+            srtt,
+            rttvar,
+            timeout,
+        })
+    }
+}
+
+///Traceroute results for a host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace {
+    ///Port used for the traceroute probes.
+    pub port: Option<u16>,
+    ///Protocol used for the traceroute probes.
+    pub protocol: Option<PortProtocol>,
+    pub(crate) hops: Vec<Hop>,
+}
+
+impl Trace {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let port = parse_optional_attr(node, "trace", "port")?;
+
+        let protocol = parse_optional_attr(node, "trace", "proto")?;
+
+        let mut hops = Vec::new();
+        for child in node.children() {
+            if child.tag_name().name() == "hop" {
+                hops.push(Hop::parse(child)?);
+            }
+        }
+
+        Ok(Trace {
+            port,
+            protocol,
+            hops,
+        })
+    }
+
+    ///Returns an iterator over the hops of the traceroute, ordered by TTL.
+    pub fn hops(&self) -> std::slice::Iter<'_, Hop> {
+        self.hops.iter()
+    }
+
+    ///Returns the hops of the traceroute as a slice.
+    pub fn hops_slice(&self) -> &[Hop] {
+        &self.hops
+    }
+
+    ///Consumes the traceroute and returns its hops.
+    pub fn into_hops(self) -> Vec<Hop> {
+        self.hops
+    }
+}
+
+///A single hop of a traceroute.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hop {
+    pub ttl: u8,
+    pub ip_addr: IpAddr,
+    ///Round trip time in milliseconds, if Nmap measured one. Nmap writes
+    ///`--` for hops it has none for.
+    pub rtt: Option<f64>,
+    pub host_name: Option<String>,
+}
+
+impl Hop {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let ttl = parse_node_attr!(node, "hop", "ttl", u8);
+
+        let ip_addr = parse_node_attr!(node, "hop", "ipaddr", IpAddr);
+
+        let rtt = node
+            .attribute("rtt")
+            .and_then(|rtt| rtt.parse::<f64>().ok())
+            .filter(|rtt| rtt.is_finite());
+
+        let host_name = node.attribute("host").map(String::from);
+
+        Ok(Hop {
+            ttl,
+            ip_addr,
+            rtt,
+            host_name,
+        })
+    }
+}
+
+fn parse_os_node(node: Node) -> Result<(Vec<OsMatch>, Option<String>), Error> {
+    let mut os_matches = Vec::new();
+    let mut os_fingerprint = None;
+    //Classes outside of any match, as written before output version 1.04
+    let mut classes = Vec::new();
+    for child in node.children() {
+        match child.tag_name().name() {
+            "osmatch" => os_matches.push(OsMatch::parse(child)?),
+            "osclass" => classes.push(OsClass::parse(child)?),
+            "osfingerprint" => {
+                os_fingerprint = Some(node_attr_as_string!(child, "osfingerprint", "fingerprint"))
+            }
+            _ => {}
+        }
+    }
+    if let Some(best) = os_matches.first_mut() {
+        best.classes.extend(classes);
+    }
+    Ok((os_matches, os_fingerprint))
+}
+
+///An operating system guessed by Nmap OS detection (`-O`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OsMatch {
+    ///Name of the guess, e.g. `Linux 3.2 - 4.9`.
+    pub name: String,
+    ///Likelihood of the guess, in percent.
+    pub accuracy: u8,
+    pub(crate) classes: Vec<OsClass>,
+}
+
+impl OsMatch {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let name = node_attr_as_string!(node, "osmatch", "name");
+
+        let accuracy = parse_node_attr!(node, "osmatch", "accuracy", u8);
+
+        let mut classes = Vec::new();
+        for child in node.children() {
+            if child.tag_name().name() == "osclass" {
+                classes.push(OsClass::parse(child)?);
+            }
+        }
+
+        Ok(OsMatch {
+            name,
+            accuracy,
+            classes,
+        })
+    }
+
+    ///Returns an iterator over the classifications of the guess.
+    pub fn classes(&self) -> std::slice::Iter<'_, OsClass> {
+        self.classes.iter()
+    }
+
+    ///Returns the classifications of the guess as a slice.
+    pub fn classes_slice(&self) -> &[OsClass] {
+        &self.classes
+    }
+
+    ///Consumes the guess and returns its classifications.
+    pub fn into_classes(self) -> Vec<OsClass> {
+        self.classes
+    }
+}
+
+///Classification of an [`OsMatch`](struct.OsMatch.html).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OsClass {
+    ///Device type, e.g. `general purpose` or `router`.
+    pub device_type: Option<String>,
+    pub vendor: String,
+    ///Operating system family, e.g. `Linux`.
+    pub family: String,
+    ///Operating system generation, e.g. `3.X`.
+    pub generation: Option<String>,
+    ///Likelihood of the classification, in percent.
+    pub accuracy: u8,
+    pub cpes: Vec<Cpe>,
+}
+
+impl OsClass {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let device_type = node.attribute("type").map(String::from);
+
+        let vendor = node_attr_as_string!(node, "osclass", "vendor");
+
+        let family = node_attr_as_string!(node, "osclass", "osfamily");
+
+        let generation = node.attribute("osgen").map(String::from);
+
+        let accuracy = parse_node_attr!(node, "osclass", "accuracy", u8);
+
+        let cpes = parse_cpe_nodes(node);
+
+        Ok(OsClass {
+            device_type,
+            vendor,
+            family,
+            generation,
+            accuracy,
+            cpes,
+        })
     }
 }
 
@@ -222,7 +890,7 @@ mod test {
     <address addr="192.168.59.234" addrtype="ipv4"/>
 </host>
         "#;
-        let doc = Document::parse(&xml).unwrap();
+        let doc = Document::parse(xml).unwrap();
         let ele = doc.root_element();
         let host = Host::parse(ele).unwrap();
 
@@ -230,6 +898,104 @@ mod test {
         assert_eq!(host.scan_end_time, Some(1589292535));
     }
 
+    #[test]
+    fn host_with_os_matches() {
+        let xml = r#"
+<host>
+    <status state="up" reason="echo-reply" reason_ttl="64"/>
+    <address addr="192.168.59.138" addrtype="ipv4"/>
+    <os>
+        <portused state="open" proto="tcp" portid="22"/>
+        <osmatch name="Linux 3.2 - 4.9" accuracy="100" line="61812">
+            <osclass type="general purpose" vendor="Linux" osfamily="Linux" osgen="3.X" accuracy="100"><cpe>cpe:/o:linux:linux_kernel:3</cpe></osclass>
+            <osclass type="general purpose" vendor="Linux" osfamily="Linux" osgen="4.X" accuracy="100"><cpe>linux 4</cpe><cpe>cpe:/o:linux:linux_kernel:4</cpe></osclass>
+        </osmatch>
+        <osmatch name="OpenWrt 21.02 (Linux 5.4)" accuracy="92" line="93124">
+            <osclass type="WAP" vendor="Linux" osfamily="Linux" osgen="5.X" accuracy="92"/>
+        </osmatch>
+    </os>
+</host>
+        "#;
+        let doc = Document::parse(xml).unwrap();
+        let host = Host::parse(doc.root_element()).unwrap();
+
+        let matches = host.os_matches().collect::<Vec<_>>();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].name, "Linux 3.2 - 4.9");
+        assert_eq!(matches[0].accuracy, 100);
+        let class = matches[0].classes().nth(1).unwrap();
+        assert_eq!(class.device_type.as_deref(), Some("general purpose"));
+        assert_eq!(class.generation.as_deref(), Some("4.X"));
+        //The malformed CPE is skipped.
+        assert_eq!(class.cpes.len(), 1);
+        assert_eq!(class.cpes[0].to_string(), "cpe:/o:linux:linux_kernel:4");
+        assert!(matches[1].classes().next().unwrap().cpes.is_empty());
+    }
+
+    #[test]
+    fn host_in_legacy_output_version() {
+        let xml = r#"<nmaprun scanner="nmap" start="1230000000" version="4.76" xmloutputversion="1.02">
+<host>
+    <status state="up"/>
+    <address addr="192.168.59.138" addrtype="ipv4"/>
+    <ports><port protocol="tcp" portid="22"><state state="open" reason="syn-ack"/></port></ports>
+    <os>
+        <osclass type="general purpose" vendor="Linux" osfamily="Linux" osgen="2.6.X" accuracy="100"/>
+        <osmatch name="Linux 2.6.17 - 2.6.24" accuracy="100" line="11424"/>
+        <osmatch name="Linux 2.6.9 - 2.6.15" accuracy="95" line="11200"/>
+    </os>
+</host>
+</nmaprun>"#;
+        let doc = Document::parse(xml).unwrap();
+        let host = Host::parse(doc.root_element().first_element_child().unwrap()).unwrap();
+
+        assert_eq!(host.status.reason, "");
+        assert_eq!(host.status.reason_ttl, 0);
+        let port = host.port_info.ports().next().unwrap();
+        assert_eq!(port.status.reason, "syn-ack");
+        assert_eq!(port.status.reason_ttl, 0);
+        let matches = host.os_matches_slice();
+        assert_eq!(
+            matches[0].classes().next().unwrap().generation.as_deref(),
+            Some("2.6.X")
+        );
+        assert_eq!(matches[1].classes().count(), 0);
+
+        let current = xml.replace("1.02", "1.04");
+        let doc = Document::parse(&current).unwrap();
+        let err = Host::parse(doc.root_element().first_element_child().unwrap()).unwrap_err();
+        assert!(matches!(
+            err.root_cause(),
+            Error::MissingAttribute {
+                element: "status",
+                attribute: "reason",
+            }
+        ));
+    }
+
+    #[test]
+    fn script_structured_output() {
+        let xml = r#"
+<script id="ssh-hostkey" output="&#xa;  2048 aa:bb (RSA)">
+    <table>
+        <elem key="bits">2048</elem>
+        <elem key="type">ssh-rsa</elem>
+    </table>
+    <elem key="note">keyless</elem>
+</script>
+        "#;
+        let doc = Document::parse(xml).unwrap();
+        let script = Script::parse(doc.root_element()).unwrap();
+
+        assert_eq!(script.elements().count(), 2);
+        let key = script.elements().next().unwrap();
+        assert_eq!(key.key(), None);
+        assert_eq!(key.elem("bits"), Some("2048"));
+        assert_eq!(key.value(), None);
+        assert_eq!(script.elem("note"), Some("keyless"));
+        assert_eq!(script.get("note").unwrap().elements().count(), 0);
+    }
+
     #[test]
     fn host_without_start_end_time() {
         let xml = r#"
@@ -238,7 +1004,7 @@ mod test {
     <address addr="192.168.59.234" addrtype="ipv4"/>
 </host>
         "#;
-        let doc = Document::parse(&xml).unwrap();
+        let doc = Document::parse(xml).unwrap();
         let ele = doc.root_element();
         let host = Host::parse(ele).unwrap();
 
@@ -254,13 +1020,21 @@ mod test {
     <address addr="192.168.59.234" addrtype="ipv4"/>
 </host>
         "#;
-        let doc = Document::parse(&xml).unwrap();
+        let doc = Document::parse(xml).unwrap();
         let ele = doc.root_element();
         let host_err = Host::parse(ele).unwrap_err();
 
+        assert!(matches!(
+            host_err,
+            Error::InvalidValue {
+                element: "host",
+                attribute: "starttime",
+                ..
+            }
+        ));
         assert_eq!(
             host_err.to_string(),
-            "error parsing Nmap XML output: failed to parse host start time"
+            "invalid value `NOT A NUMBER` for `starttime` attribute in `host` node"
         );
     }
 
@@ -274,15 +1048,145 @@ mod test {
 </script></hostscript><times srtt="5263" rttvar="4662" to="100000"/>
 </host>
         "#;
-        let doc = Document::parse(&xml).unwrap();
+        let doc = Document::parse(xml).unwrap();
         let ele = doc.root_element();
         let script_host = Host::parse(ele).unwrap();
         let script_output = script_host.scripts().collect::<Vec<_>>()[0];
+        let times = script_host.times().unwrap();
+
+        assert_eq!(times.srtt, 5263);
+        assert_eq!(times.rttvar, 4662);
+        assert_eq!(times.timeout, 100000);
 
         assert_eq!(script_output.id, "smb-print-text");
         assert_eq!(script_output.output, "false");
     }
 
+    #[test]
+    fn host_summary() {
+        let xml = r#"
+<host><status state="up" reason="echo-reply" reason_ttl="62"/>
+<address addr="10.0.1.5" addrtype="ipv4"/>
+<address addr="00:0C:29:AA:BB:CC" addrtype="mac"/>
+<hostnames><hostname name="web.example.com" type="PTR"/></hostnames>
+<ports>
+<extraports state="closed" count="997"/>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="62"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="62"/></port>
+<port protocol="udp" portid="53"><state state="open|filtered" reason="no-response" reason_ttl="0"/></port>
+</ports>
+</host>
+        "#;
+        let doc = Document::parse(xml).unwrap();
+        let host = Host::parse(doc.root_element()).unwrap();
+
+        assert_eq!(
+            host.to_string(),
+            "10.0.1.5 (web.example.com) up, 2 open ports"
+        );
+        assert_eq!(
+            host.port_info.to_string(),
+            "2 open, 1 open|filtered, 997 closed"
+        );
+        assert_eq!(
+            host.addresses().map(|a| a.to_string()).collect::<Vec<_>>(),
+            ["10.0.1.5", "00:0C:29:AA:BB:CC"]
+        );
+
+        let empty = Host::new(
+            Vec::new(),
+            HostStatus::parse(doc.root_element().first_element_child().unwrap()).unwrap(),
+        );
+        assert_eq!(empty.port_info.to_string(), "no ports");
+        assert_eq!(empty.to_string(), "unknown address up, 0 open ports");
+    }
+
+    #[test]
+    fn host_addresses() {
+        let xml = r#"
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="fe80::1" addrtype="ipv6"/>
+<address addr="192.168.1.1" addrtype="ipv4"/>
+<address addr="00:11:22:33:44:55" addrtype="mac" vendor="Cisco"/>
+</host>
+        "#;
+        let doc = Document::parse(xml).unwrap();
+        let host = Host::parse(doc.root_element()).unwrap();
+
+        assert_eq!(host.primary_ip(), Some("fe80::1".parse().unwrap()));
+        assert_eq!(host.ipv4(), Some(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(host.ipv6(), Some("fe80::1".parse().unwrap()));
+        assert_eq!(
+            host.mac(),
+            Some(MacAddr::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]))
+        );
+
+        let host = Host::new(Vec::new(), host.status.clone());
+        assert_eq!(host.primary_ip(), None);
+        assert_eq!(host.mac(), None);
+    }
+
+    #[test]
+    fn host_equality() {
+        use std::collections::HashSet;
+
+        let xml = r#"
+<host starttime="1588318812" endtime="1588318814"><status state="up" reason="echo-reply" reason_ttl="62"/>
+<address addr="10.0.1.5" addrtype="ipv4"/>
+<ports><port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="62"/></port></ports>
+<times srtt="5263" rttvar="4662" to="100000"/>
+</host>
+        "#;
+        let doc = Document::parse(xml).unwrap();
+        let host = Host::parse(doc.root_element()).unwrap();
+
+        let mut rescanned = host.clone();
+        rescanned.scan_start_time = Some(1588405212);
+        rescanned.times = None;
+        assert_eq!(host, rescanned);
+
+        let mut changed = host.clone();
+        changed
+            .port_info
+            .set_state(PortProtocol::Tcp, 22, crate::port::PortState::Closed);
+        assert_ne!(host, changed);
+
+        let set = vec![host, rescanned, changed]
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn host_with_trace() {
+        let xml = r#"
+<host>
+    <status state="up" reason="echo-reply" reason_ttl="62"/>
+    <address addr="10.0.1.5" addrtype="ipv4"/>
+    <trace port="80" proto="tcp">
+        <hop ttl="1" ipaddr="192.168.1.1" rtt="0.52" host="gateway.lan"/>
+        <hop ttl="2" ipaddr="10.0.0.1" rtt="--"/>
+        <hop ttl="3" ipaddr="10.0.1.5" rtt="1.10"/>
+    </trace>
+</host>
+        "#;
+        let doc = Document::parse(xml).unwrap();
+        let ele = doc.root_element();
+        let host = Host::parse(ele).unwrap();
+        let trace = host.trace().unwrap();
+
+        assert_eq!(trace.port, Some(80));
+        assert_eq!(trace.protocol, Some(PortProtocol::Tcp));
+        assert_eq!(trace.hops().count(), 3);
+
+        let hop = trace.hops().next().unwrap();
+        assert_eq!(hop.ttl, 1);
+        assert_eq!(hop.ip_addr, "192.168.1.1".parse::<IpAddr>().unwrap());
+        assert_eq!(hop.rtt, Some(0.52));
+        assert_eq!(hop.host_name.as_deref(), Some("gateway.lan"));
+        assert_eq!(trace.hops().nth(1).unwrap().rtt, None);
+    }
+
     #[test]
     fn host_with_invalid_end_time() {
         let xml = r#"
@@ -291,13 +1195,17 @@ mod test {
     <address addr="192.168.59.234" addrtype="ipv4"/>
 </host>
         "#;
-        let doc = Document::parse(&xml).unwrap();
+        let doc = Document::parse(xml).unwrap();
         let ele = doc.root_element();
         let host_err = Host::parse(ele).unwrap_err();
 
-        assert_eq!(
-            host_err.to_string(),
-            "error parsing Nmap XML output: failed to parse host end time"
-        );
+        assert!(matches!(
+            host_err,
+            Error::InvalidValue {
+                element: "host",
+                attribute: "endtime",
+                ..
+            }
+        ));
     }
 }
@@ -0,0 +1,265 @@
+//!Nmap grepable (`-oG`, `.gnmap`) output.
+//!
+//!The `Status` and `Ports` lines of a host are merged into a single
+//![`Host`](../../host/struct.Host.html). Grepable output only keeps the
+//!version of a service as one string, which is stored as the service
+//!`product`. Times in the comment lines are read as UTC.
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use super::{error, parse_comment, status};
+use crate::host::{Address, Host, HostState, Hostname, HostnameType};
+use crate::port::{
+    ExtraPorts, Port, PortProtocol, PortState, PortStatus, ServiceInfo, ServiceMethod,
+};
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "grepable";
+
+///Parses Nmap grepable output.
+pub fn parse(input: &str) -> Result<NmapResults, Error> {
+    let mut results = NmapResults::empty();
+    let mut index: HashMap<IpAddr, usize> = HashMap::new();
+
+    for line in input.lines() {
+        if let Some(comment) = line.strip_prefix("# ") {
+            parse_comment(comment, &mut results);
+            continue;
+        }
+
+        let mut fields = line.split('\t');
+        let host_field = match fields.next().and_then(|f| f.strip_prefix("Host: ")) {
+            Some(field) => field,
+            None => continue,
+        };
+
+        let (ip, name) = match host_field.split_once(' ') {
+            Some((ip, name)) => (ip, name.trim_start_matches('(').trim_end_matches(')')),
+            None => (host_field, ""),
+        };
+        let ip = ip
+            .parse::<IpAddr>()
+            .map_err(|_| error(FORMAT, format!("invalid host address `{}`", ip)))?;
+
+        let position = *index.entry(ip).or_insert_with(|| {
+            let mut host = Host::new(vec![Address::IpAddr(ip)], status(HostState::Up));
+            if !name.is_empty() {
+                host.host_names.push(Hostname {
+                    name: name.to_string(),
+                    source: HostnameType::Dns,
+                });
+            }
+            results.hosts.push(host);
+            results.hosts.len() - 1
+        });
+        let host = &mut results.hosts[position];
+
+        for field in fields {
+            if let Some(state) = field.strip_prefix("Status: ") {
+                host.status = status(parse_host_state(state)?);
+            } else if let Some(ports) = field.strip_prefix("Ports: ") {
+                host.port_info.ports = parse_ports(ports)?;
+            } else if let Some(ignored) = field.strip_prefix("Ignored State: ") {
+                host.port_info.extra_ports.push(parse_ignored(ignored)?);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+fn parse_host_state(state: &str) -> Result<HostState, Error> {
+    HostState::from_str(&state.to_lowercase())
+        .map_err(|_| error(FORMAT, format!("invalid host status `{}`", state)))
+}
+
+fn parse_ports(ports: &str) -> Result<Vec<Port>, Error> {
+    //Ports are separated by `, ` but the version field may contain that
+    //sequence as well, so only split before something looking like a port.
+    let mut entries: Vec<String> = Vec::new();
+    for part in ports.split(", ") {
+        let starts_port = part
+            .split('/')
+            .next()
+            .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        match entries.last_mut() {
+            Some(last) if !starts_port => {
+                last.push_str(", ");
+                last.push_str(part);
+            }
+            _ => entries.push(part.to_string()),
+        }
+    }
+
+    entries.iter().map(|e| parse_port(e)).collect()
+}
+
+//`number/state/protocol/owner/service/rpc info/version/`
+fn parse_port(entry: &str) -> Result<Port, Error> {
+    let fields = entry.split('/').collect::<Vec<_>>();
+    if fields.len() < 7 {
+        return Err(error(FORMAT, format!("invalid port entry `{}`", entry)));
+    }
+
+    let port_number = fields[0]
+        .parse::<u16>()
+        .map_err(|_| error(FORMAT, format!("invalid port number `{}`", fields[0])))?;
+    let state = PortState::from_str(fields[1])
+        .map_err(|_| error(FORMAT, format!("invalid port state `{}`", fields[1])))?;
+    let protocol = PortProtocol::from_str(fields[2])
+        .map_err(|_| error(FORMAT, format!("invalid port protocol `{}`", fields[2])))?;
+
+    let service_info = if fields[4].is_empty() {
+        None
+    } else {
+        let version = fields[6].replace('|', "/");
+        let (tunnel, name) = match fields[4].split_once('|') {
+            Some((tunnel, name)) => (Some(tunnel.to_string()), name),
+            None => (None, fields[4]),
+        };
+        Some(ServiceInfo {
+            name: name.to_string(),
+            confidence_level: if version.is_empty() { 3 } else { 10 },
+            method: if version.is_empty() {
+                ServiceMethod::Table
+            } else {
+                ServiceMethod::Probe
+            },
+            product: if version.is_empty() {
+                None
+            } else {
+                Some(version)
+            },
+            version: None,
+            extra_info: None,
+            tunnel,
+            cpes: Vec::new(),
+        })
+    };
+
+    Ok(Port {
+        protocol,
+        port_number,
+        status: PortStatus {
+            state,
+            reason: String::new(),
+            reason_ttl: 0,
+        },
+        service_info,
+        scripts: Vec::new(),
+    })
+}
+
+//`closed (996)`
+fn parse_ignored(ignored: &str) -> Result<ExtraPorts, Error> {
+    let invalid = || error(FORMAT, format!("invalid ignored state `{}`", ignored));
+
+    let (state, count) = ignored.split_once(" (").ok_or_else(invalid)?;
+    let state = PortState::from_str(state).map_err(|_| invalid())?;
+    let count = count
+        .trim_end_matches(')')
+        .parse::<u32>()
+        .map_err(|_| invalid())?;
+
+    Ok(ExtraPorts { state, count })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GNMAP: &str = "# Nmap 7.60 scan initiated Fri May  1 15:40:12 2020 as: nmap -sV -oA test scanme.nmap.org 10.0.0.1\n\
+Host: 45.33.32.156 (scanme.nmap.org)\tStatus: Up\n\
+Host: 45.33.32.156 (scanme.nmap.org)\tPorts: 22/open/tcp//ssh//OpenSSH 6.6.1p1 Ubuntu 2ubuntu2.13 (Ubuntu Linux, protocol 2.0)/, 80/open/tcp//http//Apache httpd 2.4.7 ((Ubuntu))/, 53/open|filtered/udp//domain///\tIgnored State: closed (996)\n\
+Host: 10.0.0.1 ()\tStatus: Down\n\
+# Nmap done at Fri May  1 15:40:14 2020 -- 2 IP addresses (1 host up) scanned in 2.59 seconds\n";
+
+    #[test]
+    fn gnmap_scan() {
+        let results = parse(GNMAP).unwrap();
+
+        assert_eq!(results.nmap_version.as_deref(), Some("7.60"));
+        assert_eq!(
+            results.args.as_deref(),
+            Some("nmap -sV -oA test scanme.nmap.org 10.0.0.1")
+        );
+        assert_eq!(results.scan_start_time, 1588347612);
+        assert_eq!(results.scan_end_time, Some(1588347614));
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].status.state, HostState::Up);
+        assert_eq!(
+            hosts[0].host_names().next().unwrap().name,
+            "scanme.nmap.org"
+        );
+        assert_eq!(hosts[1].status.state, HostState::Down);
+        assert_eq!(hosts[1].host_names().count(), 0);
+
+        let ports = hosts[0].port_info.ports().collect::<Vec<_>>();
+        assert_eq!(ports.len(), 3);
+        assert_eq!(ports[0].port_number, 22);
+        assert_eq!(
+            ports[0].service_info.as_ref().unwrap().product.as_deref(),
+            Some("OpenSSH 6.6.1p1 Ubuntu 2ubuntu2.13 (Ubuntu Linux, protocol 2.0)")
+        );
+        assert_eq!(ports[2].status.state, PortState::OpenFiltered);
+        assert_eq!(ports[2].protocol, PortProtocol::Udp);
+        assert!(ports[2].service_info.as_ref().unwrap().product.is_none());
+
+        let extra = hosts[0].port_info.extra_ports().next().unwrap();
+        assert_eq!(extra.state, PortState::Closed);
+        assert_eq!(extra.count, 996);
+    }
+
+    #[test]
+    fn gnmap_invalid_port() {
+        let err = parse("Host: 10.0.0.1 ()\tPorts: 22/open/tcp\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error parsing grepable input: invalid port entry `22/open/tcp`"
+        );
+    }
+
+    #[test]
+    fn gnmap_tunnel() {
+        let results = parse("Host: 10.0.0.1 ()\tPorts: 443/open/tcp//ssl|http///\n").unwrap();
+        let port = results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .next()
+            .unwrap();
+        let service = port.service_info.as_ref().unwrap();
+        assert_eq!(service.name, "http");
+        assert_eq!(service.tunnel.as_deref(), Some("ssl"));
+    }
+
+    #[test]
+    fn gnmap_round_trip() {
+        let results = parse(GNMAP).unwrap();
+        let reparsed = parse(&crate::export::grepable::to_string(&results)).unwrap();
+
+        assert_eq!(reparsed.hosts().count(), 2);
+        assert_eq!(
+            reparsed
+                .hosts()
+                .next()
+                .unwrap()
+                .port_info
+                .ports()
+                .collect::<Vec<_>>(),
+            results
+                .hosts()
+                .next()
+                .unwrap()
+                .port_info
+                .ports()
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//!Masscan output: XML (`-oX`), JSON (`-oJ`/`--ndjson`) and list (`-oL`).
+//!
+//!Masscan writes one record per discovered port or banner, without any
+//!host status. Records are merged per address, hosts are marked up and
+//!banners are mapped onto the matching port: the banner type becomes the
+//!service name and the banner text is stored as a `banner` script (or
+//!`http-title` for page titles), mirroring what the equivalent Nmap
+//!scripts would report.
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use roxmltree::{Document, Node};
+
+use super::{error, port_record, scan_times_from_hosts};
+use crate::host::Script;
+use crate::json::{self, Value};
+use crate::port::{Port, PortProtocol, PortState, ServiceInfo, ServiceMethod};
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "masscan XML";
+
+//Banner types that describe content rather than the service itself.
+const NON_SERVICE_BANNERS: &[&str] = &["title", "X509", "X509CA"];
+
+///Parses masscan XML output.
+pub fn parse(xml: &str) -> Result<NmapResults, Error> {
+    let doc = Document::parse(xml)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "nmaprun" {
+        return Err(error(FORMAT, "expected `nmaprun` root tag"));
+    }
+
+    let mut results = NmapResults::empty();
+    results.nmap_version = root.attribute("version").map(String::from);
+    results.scan_start_time = match root.attribute("start") {
+        Some(start) => start
+            .parse()
+            .map_err(|_| error(FORMAT, "failed to parse start time"))?,
+        None => 0,
+    };
+
+    let mut index: HashMap<IpAddr, usize> = HashMap::new();
+
+    for child in root.children() {
+        match child.tag_name().name() {
+            "host" => parse_host(child, &mut results, &mut index)?,
+            "runstats" => {
+                results.scan_end_time = child
+                    .children()
+                    .find(|c| c.tag_name().name() == "finished")
+                    .and_then(|f| f.attribute("time"))
+                    .and_then(|t| t.parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(results)
+}
+
+fn parse_host(
+    node: Node,
+    results: &mut NmapResults,
+    index: &mut HashMap<IpAddr, usize>,
+) -> Result<(), Error> {
+    let ip = node
+        .children()
+        .find(|c| c.tag_name().name() == "address")
+        .and_then(|a| a.attribute("addr"))
+        .ok_or_else(|| error(FORMAT, "expected `address` node in `host`"))?
+        .parse::<IpAddr>()
+        .map_err(|_| error(FORMAT, "failed to parse IP address"))?;
+    let time = node
+        .attribute("endtime")
+        .and_then(|t| t.parse::<i64>().ok());
+
+    let ports = node
+        .children()
+        .filter(|c| c.tag_name().name() == "ports")
+        .flat_map(|p| p.children())
+        .filter(|c| c.tag_name().name() == "port");
+
+    for port_node in ports {
+        let protocol = parse_protocol(port_node.attribute("protocol"), FORMAT)?;
+        let port_number = port_node
+            .attribute("portid")
+            .and_then(|p| p.parse::<u16>().ok())
+            .ok_or_else(|| error(FORMAT, "failed to parse port number"))?;
+        let port = port_record(results, index, ip, time, protocol, port_number);
+
+        for child in port_node.children() {
+            match child.tag_name().name() {
+                "state" => {
+                    apply_state(
+                        port,
+                        child.attribute("state"),
+                        child.attribute("reason"),
+                        child.attribute("reason_ttl").and_then(|t| t.parse().ok()),
+                        FORMAT,
+                    )?;
+                }
+                "service" => apply_banner(
+                    port,
+                    child.attribute("name").unwrap_or(""),
+                    child.attribute("banner").unwrap_or(""),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+///Parses masscan JSON output, either a JSON array (`-oJ`) or one object
+///per line (`--ndjson`).
+pub fn parse_json(input: &str) -> Result<NmapResults, Error> {
+    const FORMAT: &str = "masscan JSON";
+
+    let records = if input.trim_start().starts_with('[') {
+        //Older masscan versions close the array with a `{finished: 1}`
+        //pseudo record that is not valid JSON.
+        let input = input
+            .lines()
+            .filter(|l| !l.trim_start().starts_with("{finished"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        match json::parse(&input).map_err(|e| error(FORMAT, e))? {
+            Value::Array(records) => records,
+            _ => return Err(error(FORMAT, "expected an array of records")),
+        }
+    } else {
+        input
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| json::parse(l).map_err(|e| error(FORMAT, e)))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut results = NmapResults::empty();
+    let mut index = HashMap::new();
+
+    for record_value in &records {
+        let ip = record_value
+            .get("ip")
+            .and_then(Value::as_str)
+            .and_then(|ip| ip.parse::<IpAddr>().ok())
+            .ok_or_else(|| error(FORMAT, "expected `ip` in record"))?;
+        let time = record_value.get("timestamp").and_then(|t| match t {
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => Some(*n as i64),
+            _ => None,
+        });
+
+        let ports = record_value
+            .get("ports")
+            .and_then(Value::as_array)
+            .unwrap_or(&[]);
+        for port_value in ports {
+            let protocol = parse_protocol(port_value.get("proto").and_then(Value::as_str), FORMAT)?;
+            let port_number = port_value
+                .get("port")
+                .and_then(Value::as_f64)
+                .filter(|p| *p >= 0.0 && *p <= u16::MAX as f64)
+                .ok_or_else(|| error(FORMAT, "expected `port` in record"))?
+                as u16;
+            let port = port_record(&mut results, &mut index, ip, time, protocol, port_number);
+
+            if port_value.get("status").is_some() {
+                apply_state(
+                    port,
+                    port_value.get("status").and_then(Value::as_str),
+                    port_value.get("reason").and_then(Value::as_str),
+                    port_value
+                        .get("ttl")
+                        .and_then(Value::as_f64)
+                        .map(|t| t as u8),
+                    FORMAT,
+                )?;
+            }
+            if let Some(service) = port_value.get("service") {
+                apply_banner(
+                    port,
+                    service.get("name").and_then(Value::as_str).unwrap_or(""),
+                    service.get("banner").and_then(Value::as_str).unwrap_or(""),
+                );
+            }
+        }
+    }
+
+    scan_times_from_hosts(&mut results);
+
+    Ok(results)
+}
+
+///Parses masscan list output (`-oL`).
+pub fn parse_list(input: &str) -> Result<NmapResults, Error> {
+    const FORMAT: &str = "masscan list";
+
+    let mut results = NmapResults::empty();
+    let mut index = HashMap::new();
+
+    for line in input.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        //`<state> <proto> <port> <ip> <timestamp>` optionally followed by
+        //`<banner type> <banner>` for banner records.
+        let mut fields = line.splitn(7, ' ');
+        let invalid = || error(FORMAT, format!("invalid record `{}`", line));
+        let kind = fields.next().ok_or_else(invalid)?;
+        let protocol = parse_protocol(fields.next(), FORMAT)?;
+        let port_number = fields
+            .next()
+            .and_then(|p| p.parse::<u16>().ok())
+            .ok_or_else(invalid)?;
+        let ip = fields
+            .next()
+            .and_then(|ip| ip.parse::<IpAddr>().ok())
+            .ok_or_else(invalid)?;
+        let time = fields.next().and_then(|t| t.parse::<i64>().ok());
+        let port = port_record(&mut results, &mut index, ip, time, protocol, port_number);
+
+        if kind == "banner" {
+            let banner_type = fields.next().ok_or_else(invalid)?;
+            let banner = unescape(fields.next().unwrap_or(""));
+            apply_banner(port, banner_type, &banner);
+        } else {
+            apply_state(port, Some(kind), None, None, FORMAT)?;
+        }
+    }
+
+    scan_times_from_hosts(&mut results);
+
+    Ok(results)
+}
+
+fn parse_protocol(protocol: Option<&str>, format: &'static str) -> Result<PortProtocol, Error> {
+    protocol
+        .and_then(|p| PortProtocol::from_str(p).ok())
+        .ok_or_else(|| error(format, "failed to parse port protocol"))
+}
+
+fn apply_state(
+    port: &mut Port,
+    state: Option<&str>,
+    reason: Option<&str>,
+    reason_ttl: Option<u8>,
+    format: &'static str,
+) -> Result<(), Error> {
+    if let Some(state) = state {
+        port.status.state =
+            PortState::from_str(state).map_err(|_| error(format, "failed to parse port state"))?;
+    }
+    if let Some(reason) = reason {
+        port.status.reason = reason.to_string();
+    }
+    if let Some(reason_ttl) = reason_ttl {
+        port.status.reason_ttl = reason_ttl;
+    }
+    Ok(())
+}
+
+fn apply_banner(port: &mut Port, kind: &str, banner: &str) {
+    if !kind.is_empty() && !NON_SERVICE_BANNERS.contains(&kind) && port.service_info.is_none() {
+        port.service_info = Some(ServiceInfo {
+            name: kind.to_string(),
+            confidence_level: 10,
+            method: ServiceMethod::Probe,
+            product: None,
+            version: None,
+            extra_info: None,
+            tunnel: None,
+            cpes: Vec::new(),
+        });
+    }
+
+    if !banner.is_empty() {
+        let id = if kind == "title" {
+            "http-title"
+        } else {
+            "banner"
+        };
+        port.scripts
+            .push(Script::new(id.to_string(), banner.to_string()));
+    }
+}
+
+//List output escapes non printable banner bytes as `\xNN`.
+fn unescape(banner: &str) -> String {
+    let mut bytes = Vec::with_capacity(banner.len());
+    let raw = banner.as_bytes();
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == b'\\' && raw.get(i + 1) == Some(&b'x') {
+            if let Some(b) = raw
+                .get(i + 2..i + 4)
+                .and_then(|h| std::str::from_utf8(h).ok())
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                bytes.push(b);
+                i += 4;
+                continue;
+            }
+        }
+        bytes.push(raw[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::host::HostState;
+
+    #[test]
+    fn masscan_json() {
+        let input = r#"[
+{   "ip": "10.0.0.1",   "timestamp": "1490242775", "ports": [ {"port": 443, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.1",   "timestamp": "1490242779", "ports": [ {"port": 443, "proto": "tcp", "service": {"name": "ssl", "banner": "TLS/1.1 cipher:0xc014"} } ] }
+,
+{finished: 1}
+]"#;
+        let results = parse_json(input).unwrap();
+
+        assert_eq!(results.scan_start_time, 1490242775);
+        assert_eq!(results.scan_end_time, Some(1490242779));
+        let host = results.hosts().next().unwrap();
+        let port = host.port_info.ports().next().unwrap();
+        assert_eq!(port.port_number, 443);
+        assert_eq!(port.status.reason, "syn-ack");
+        assert_eq!(port.status.reason_ttl, 64);
+        assert_eq!(port.service_info.as_ref().unwrap().name, "ssl");
+        assert_eq!(port.scripts[0].output, "TLS/1.1 cipher:0xc014");
+
+        let ndjson = "{\"ip\":\"10.0.0.2\",\"timestamp\":\"1\",\"ports\":[{\"port\":22,\"proto\":\"tcp\",\"status\":\"open\"}]}\n";
+        assert_eq!(parse_json(ndjson).unwrap().hosts().count(), 1);
+    }
+
+    #[test]
+    fn masscan_list() {
+        let input = "#masscan\n\
+open tcp 80 10.0.0.1 1490242775\n\
+open tcp 22 10.0.0.2 1490242776\n\
+banner tcp 80 10.0.0.1 1490242777 http HTTP/1.0 200 OK\\x0d\\x0aServer: nginx\n\
+# end\n";
+        let results = parse_list(input).unwrap();
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 2);
+        let port = hosts[0].port_info.ports().next().unwrap();
+        assert_eq!(port.service_info.as_ref().unwrap().name, "http");
+        assert_eq!(port.scripts[0].output, "HTTP/1.0 200 OK\r\nServer: nginx");
+        assert_eq!(results.scan_end_time, Some(1490242777));
+
+        assert!(parse_list("open tcp x 10.0.0.1 1\n").is_err());
+    }
+
+    #[test]
+    fn masscan_records_are_merged() {
+        let xml = r#"<?xml version="1.0"?>
+<!-- masscan v1.0 scan -->
+<nmaprun scanner="masscan" start="1490242774" version="1.0-BETA" xmloutputversion="1.03">
+<scaninfo type="syn" protocol="tcp" />
+<host endtime="1490242775"><address addr="10.0.0.1" addrtype="ipv4"/><ports><port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="64"/></port></ports></host>
+<host endtime="1490242776"><address addr="10.0.0.2" addrtype="ipv4"/><ports><port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="63"/></port></ports></host>
+<host endtime="1490242777"><address addr="10.0.0.1" addrtype="ipv4"/><ports><port protocol="tcp" portid="80"><state state="open" reason="response" reason_ttl="64"/><service name="http" banner="HTTP/1.0 200 OK&#x0d;&#x0a;Server: nginx"></service></port></ports></host>
+<host endtime="1490242778"><address addr="10.0.0.1" addrtype="ipv4"/><ports><port protocol="tcp" portid="80"><state state="open" reason="response" reason_ttl="64"/><service name="title" banner="Welcome to nginx!"></service></port></ports></host>
+<runstats>
+<finished time="1490242785" timestr="2017-03-23 00:19:45" elapsed="13" />
+<hosts up="2" down="0" total="2" />
+</runstats>
+</nmaprun>"#;
+        let results = parse(xml).unwrap();
+
+        assert_eq!(results.scan_start_time, 1490242774);
+        assert_eq!(results.scan_end_time, Some(1490242785));
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].status.state, HostState::Up);
+        assert_eq!(hosts[0].scan_start_time, Some(1490242775));
+        assert_eq!(hosts[0].scan_end_time, Some(1490242778));
+
+        let ports = hosts[0].port_info.ports().collect::<Vec<_>>();
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].service_info.as_ref().unwrap().name, "http");
+        assert_eq!(ports[0].scripts.len(), 2);
+        assert_eq!(ports[0].scripts[0].id, "banner");
+        assert_eq!(
+            ports[0].scripts[0].output,
+            "HTTP/1.0 200 OK\r\nServer: nginx"
+        );
+        assert_eq!(ports[0].scripts[1].id, "http-title");
+        assert_eq!(ports[0].scripts[1].output, "Welcome to nginx!");
+
+        assert_eq!(hosts[1].port_info.ports().next().unwrap().port_number, 22);
+    }
+}
@@ -0,0 +1,318 @@
+//!Importers reading the output of other scanners, or of Nmap's non-XML
+//!output formats, into [`NmapResults`](../struct.NmapResults.html).
+//!
+//!Every importer exposes a `parse()` function. Information missing from
+//!the source format is left empty: for example ports imported from
+//!grepable output have an empty `reason`.
+//!
+//!The [`nessus`](nessus/index.html) importer is only available with the
+//!`nessus` feature.
+//!
+//![`parse_any()`](fn.parse_any.html) detects the format of its input and
+//!calls the matching importer, and [`ScanSource`](trait.ScanSource.html)
+//!lets code consume hosts without caring which scanner found them.
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::host::{Address, Host, HostState, HostStatus};
+use crate::port::{Port, PortProtocol, PortState, PortStatus};
+use crate::util::parse_ctime;
+use crate::{Error, NmapResults};
+
+pub mod gnmap;
+pub mod masscan;
+pub mod naabu;
+#[cfg(feature = "nessus")]
+pub mod nessus;
+pub mod normal;
+pub mod zmap;
+
+///Anything hosts can be read from. The trait is object safe, so sources
+///picked at runtime can be used as `dyn ScanSource`.
+pub trait ScanSource {
+    ///Returns the hosts of the scan, in the crate's model.
+    fn scan_hosts(&self) -> Box<dyn Iterator<Item = Host> + '_>;
+}
+
+impl ScanSource for NmapResults {
+    fn scan_hosts(&self) -> Box<dyn Iterator<Item = Host> + '_> {
+        Box::new(self.hosts.iter().cloned())
+    }
+}
+
+impl ScanSource for [Host] {
+    fn scan_hosts(&self) -> Box<dyn Iterator<Item = Host> + '_> {
+        Box::new(self.iter().cloned())
+    }
+}
+
+impl ScanSource for Vec<Host> {
+    fn scan_hosts(&self) -> Box<dyn Iterator<Item = Host> + '_> {
+        self.as_slice().scan_hosts()
+    }
+}
+
+///The input formats known to [`parse_any()`](fn.parse_any.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    NmapXml,
+    NmapGrepable,
+    NmapNormal,
+    MasscanXml,
+    MasscanJson,
+    MasscanList,
+    ZmapCsv,
+    NaabuJson,
+    #[cfg(feature = "nessus")]
+    Nessus,
+}
+
+impl Format {
+    ///Guesses the format of `input` from its first lines.
+    pub fn detect(input: &str) -> Option<Format> {
+        let input = input.trim_start_matches('\u{feff}').trim_start();
+        let first_line = input.lines().next()?.trim();
+
+        if input.starts_with('<') {
+            let root = root_tag(input)?;
+            let name = root.split_whitespace().next().unwrap_or("");
+            #[cfg(feature = "nessus")]
+            {
+                if name == "NessusClientData_v2" {
+                    return Some(Format::Nessus);
+                }
+            }
+            return match name {
+                "nmaprun" if root.contains("scanner=\"masscan\"") => Some(Format::MasscanXml),
+                "nmaprun" => Some(Format::NmapXml),
+                _ => None,
+            };
+        }
+        if first_line.starts_with('[') || first_line.starts_with('{') {
+            let record = input.lines().map(str::trim).find(|l| l.starts_with('{'))?;
+            return if record.contains("\"ports\"") {
+                Some(Format::MasscanJson)
+            } else {
+                Some(Format::NaabuJson)
+            };
+        }
+        if first_line.starts_with("#masscan") {
+            return Some(Format::MasscanList);
+        }
+        if first_line.split(',').any(|f| f.trim() == "saddr") {
+            return Some(Format::ZmapCsv);
+        }
+        if input.lines().take(5).any(|l| {
+            l.starts_with("Host: ") && (l.contains("\tStatus: ") || l.contains("\tPorts: "))
+        }) {
+            return Some(Format::NmapGrepable);
+        }
+        if input.lines().take(5).any(|l| {
+            l.starts_with("# Nmap ")
+                || l.starts_with("Starting Nmap ")
+                || l.starts_with("Nmap scan report for ")
+        }) {
+            return Some(Format::NmapNormal);
+        }
+
+        None
+    }
+
+    ///Parses `input` with the importer of this format. Warnings of the
+    ///normal output importer are dropped.
+    pub fn parse(self, input: &str) -> Result<NmapResults, Error> {
+        match self {
+            Format::NmapXml => NmapResults::parse(input),
+            Format::NmapGrepable => gnmap::parse(input),
+            Format::NmapNormal => normal::parse(input).map(|(results, _)| results),
+            Format::MasscanXml => masscan::parse(input),
+            Format::MasscanJson => masscan::parse_json(input),
+            Format::MasscanList => masscan::parse_list(input),
+            Format::ZmapCsv => zmap::parse(input),
+            Format::NaabuJson => naabu::parse(input),
+            #[cfg(feature = "nessus")]
+            Format::Nessus => nessus::parse(input),
+        }
+    }
+}
+
+///Parses scan output of any format known to [`Format`](enum.Format.html),
+///detecting which one it is.
+pub fn parse_any(bytes: &[u8]) -> Result<NmapResults, Error> {
+    let input =
+        std::str::from_utf8(bytes).map_err(|_| error("scan output", "input is not valid UTF-8"))?;
+    let format =
+        Format::detect(input).ok_or_else(|| error("scan output", "unrecognized input format"))?;
+    format.parse(input)
+}
+
+//Returns the content of the root start tag of an XML document, e.g.
+//`nmaprun scanner="nmap"`, skipping the prolog, comments and doctype.
+fn root_tag(xml: &str) -> Option<&str> {
+    let mut rest = xml;
+    loop {
+        rest = &rest[rest.find('<')? + 1..];
+        if rest.starts_with('?') || rest.starts_with('!') {
+            let end = if rest.starts_with("!--") {
+                rest.find("-->")? + 3
+            } else {
+                rest.find('>')? + 1
+            };
+            rest = &rest[end..];
+            continue;
+        }
+        let end = rest.find('>')?;
+        return Some(rest[..end].trim_end_matches('/'));
+    }
+}
+
+fn error(format: &'static str, message: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format,
+        message: message.into(),
+    }
+}
+
+//Reads the `# Nmap ... scan initiated` and `# Nmap done at` comments
+//shared by Nmap's text output formats.
+fn parse_comment(comment: &str, results: &mut NmapResults) {
+    if let Some(rest) = comment.strip_prefix("Nmap ") {
+        if let Some((version, rest)) = rest.split_once(" scan initiated ") {
+            if let Some((time, args)) = rest.split_once(" as: ") {
+                results.nmap_version = Some(version.to_string());
+                results.args = Some(args.to_string());
+                results.scan_start_time = parse_ctime(time).unwrap_or(0);
+            }
+        } else if let Some(rest) = rest.strip_prefix("done at ") {
+            let time = rest.split(" -- ").next().unwrap_or(rest);
+            results.scan_end_time = parse_ctime(time);
+        }
+    }
+}
+
+//Text outputs do not record why a host is in a given state.
+fn status(state: HostState) -> HostStatus {
+    HostStatus {
+        state,
+        reason: String::new(),
+        reason_ttl: 0,
+    }
+}
+
+//Returns the host of `ip` a scanner record is about, creating it (up) on
+//first sight and widening its time window with `time`. `index` maps
+//addresses to their position in `results`.
+fn host_record<'a>(
+    results: &'a mut NmapResults,
+    index: &mut HashMap<IpAddr, usize>,
+    ip: IpAddr,
+    time: Option<i64>,
+) -> &'a mut Host {
+    let position = *index.entry(ip).or_insert_with(|| {
+        results
+            .hosts
+            .push(Host::new(vec![Address::IpAddr(ip)], status(HostState::Up)));
+        results.hosts.len() - 1
+    });
+    let host = &mut results.hosts[position];
+    host.scan_start_time = match (host.scan_start_time, time) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    host.scan_end_time = host.scan_end_time.max(time);
+    host
+}
+
+//Like `host_record`, but returns the port the record is about, creating
+//it (open) on first sight.
+fn port_record<'a>(
+    results: &'a mut NmapResults,
+    index: &mut HashMap<IpAddr, usize>,
+    ip: IpAddr,
+    time: Option<i64>,
+    protocol: PortProtocol,
+    port_number: u16,
+) -> &'a mut Port {
+    let host = host_record(results, index, ip, time);
+    let ports = &mut host.port_info.ports;
+    match ports
+        .iter()
+        .position(|p| p.protocol == protocol && p.port_number == port_number)
+    {
+        Some(i) => &mut ports[i],
+        None => {
+            ports.push(Port {
+                protocol,
+                port_number,
+                status: PortStatus {
+                    state: PortState::Open,
+                    reason: String::new(),
+                    reason_ttl: 0,
+                },
+                service_info: None,
+                scripts: Vec::new(),
+            });
+            ports.last_mut().expect("port pushed above")
+        }
+    }
+}
+
+//Derives the scan time window from the hosts of record based formats.
+fn scan_times_from_hosts(results: &mut NmapResults) {
+    results.scan_start_time = results
+        .hosts()
+        .filter_map(|h| h.scan_start_time)
+        .min()
+        .unwrap_or(0);
+    results.scan_end_time = results.hosts().filter_map(|h| h.scan_end_time).max();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_detection() {
+        let detect = Format::detect;
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?>\n<!DOCTYPE nmaprun>\n<nmaprun scanner=\"nmap\">"),
+            Some(Format::NmapXml)
+        );
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?>\n<!-- masscan -->\n<nmaprun scanner=\"masscan\">"),
+            Some(Format::MasscanXml)
+        );
+        assert_eq!(
+            detect("# Nmap 7.60 scan initiated\nHost: 10.0.0.1 ()\tStatus: Up\n"),
+            Some(Format::NmapGrepable)
+        );
+        assert_eq!(
+            detect("Starting Nmap 7.60\nNmap scan report for 10.0.0.1\n"),
+            Some(Format::NmapNormal)
+        );
+        assert_eq!(
+            detect("[\n{\"ip\": \"10.0.0.1\", \"ports\": []},\n"),
+            Some(Format::MasscanJson)
+        );
+        assert_eq!(
+            detect("{\"ip\":\"10.0.0.1\",\"port\":80}\n"),
+            Some(Format::NaabuJson)
+        );
+        assert_eq!(detect("#masscan\n"), Some(Format::MasscanList));
+        assert_eq!(detect("saddr,sport\n"), Some(Format::ZmapCsv));
+        assert_eq!(detect("hello"), None);
+    }
+
+    #[test]
+    fn parse_any_source() {
+        let results = parse_any(b"#masscan\nopen tcp 80 10.0.0.1 1490242775\n").unwrap();
+        let hosts = results.scan_hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[..].scan_hosts().count(), 1);
+        let sources: [&dyn ScanSource; 2] = [&results, &hosts];
+        assert_eq!(sources.iter().flat_map(|s| s.scan_hosts()).count(), 2);
+
+        assert!(parse_any(b"hello").is_err());
+        assert!(parse_any(&[0xff, 0xfe]).is_err());
+    }
+}
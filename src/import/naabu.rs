@@ -0,0 +1,112 @@
+//!naabu JSON lines output.
+//!
+//!naabu only reports open ports, so every record becomes an open port of
+//!an up host. Records of the same address are merged into one host, and the
+//!`host` field, when it differs from the address, is kept as a user
+//!provided hostname.
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use super::{error, port_record, scan_times_from_hosts};
+use crate::host::{Hostname, HostnameType};
+use crate::json::{self, Value};
+use crate::port::PortProtocol;
+use crate::util::parse_rfc3339;
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "naabu JSON";
+
+///Parses the output of `naabu -json`, one JSON object per line such as
+///`{"host":"example.com","ip":"93.184.216.34","port":443,"protocol":"tcp"}`.
+///The port may also be given as an object with a `Port` member, as older
+///naabu versions do.
+pub fn parse(input: &str) -> Result<NmapResults, Error> {
+    let mut results = NmapResults::empty();
+    let mut index = HashMap::new();
+
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let record =
+            json::parse(line).map_err(|e| error(FORMAT, format!("{} in `{}`", e, line)))?;
+
+        let ip = record
+            .get("ip")
+            .and_then(Value::as_str)
+            .ok_or_else(|| error(FORMAT, format!("missing `ip` in `{}`", line)))?
+            .parse::<IpAddr>()
+            .map_err(|_| error(FORMAT, format!("invalid address in `{}`", line)))?;
+
+        let port = record.get("port");
+        let port_number = port
+            .and_then(|p| p.get("Port").or(Some(p)))
+            .and_then(Value::as_f64)
+            .filter(|p| p.fract() == 0.0 && *p >= 0.0 && *p <= f64::from(u16::MAX))
+            .ok_or_else(|| error(FORMAT, format!("missing or invalid `port` in `{}`", line)))?
+            as u16;
+
+        let protocol = match record.get("protocol").and_then(Value::as_str) {
+            Some("udp") => PortProtocol::Udp,
+            _ => PortProtocol::Tcp,
+        };
+        let time = record
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(parse_rfc3339);
+
+        port_record(&mut results, &mut index, ip, time, protocol, port_number);
+
+        if let Some(name) = record.get("host").and_then(Value::as_str) {
+            let host = &mut results.hosts[index[&ip]];
+            if name != ip.to_string() && !host.host_names.iter().any(|h| h.name == name) {
+                host.host_names.push(Hostname {
+                    name: name.to_string(),
+                    source: HostnameType::User,
+                });
+            }
+        }
+    }
+
+    scan_times_from_hosts(&mut results);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::port::PortState;
+
+    #[test]
+    fn naabu_json() {
+        let input = r#"
+{"host":"example.com","ip":"93.184.216.34","timestamp":"2023-06-01T12:00:00.5Z","port":443,"protocol":"tcp","tls":true}
+{"host":"example.com","ip":"93.184.216.34","timestamp":"2023-06-01T12:00:02Z","port":80,"protocol":"tcp"}
+{"ip":"10.0.0.1","port":{"Port":22,"Protocol":0,"TLS":false}}
+"#;
+        let results = parse(input).unwrap();
+
+        assert_eq!(results.scan_start_time, 1685620800);
+        assert_eq!(results.scan_end_time, Some(1685620802));
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 2);
+        let names = hosts[0].host_names().collect::<Vec<_>>();
+        assert_eq!(names.len(), 1);
+        assert_eq!(names[0].name, "example.com");
+        assert_eq!(names[0].source, HostnameType::User);
+        let ports = hosts[0]
+            .port_info
+            .ports()
+            .map(|p| p.port_number)
+            .collect::<Vec<_>>();
+        assert_eq!(ports, vec![443, 80]);
+        let port = hosts[1].port_info.ports().next().unwrap();
+        assert_eq!(port.port_number, 22);
+        assert_eq!(port.status.state, PortState::Open);
+    }
+
+    #[test]
+    fn naabu_errors() {
+        assert!(parse(r#"{"port":80}"#).is_err());
+        assert!(parse(r#"{"ip":"10.0.0.1","port":70000}"#).is_err());
+        assert!(parse(r#"{"ip":"10.0.0.1""#).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//!Nessus v2 XML (`.nessus`) reports.
+//!
+//!Every `ReportHost` becomes an up host. `ReportItem`s on a non-zero port
+//!mark that port open, with the Nessus service name as the port's service.
+//!Each plugin finding is stored as a script whose id is `nessus-<pluginID>`
+//!and whose output starts with the plugin name, followed by the plugin
+//!output if any. Findings on port 0 are host scripts.
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use roxmltree::{Document, Node};
+
+use super::{error, host_record, port_record, scan_times_from_hosts};
+use crate::host::{Address, Hostname, HostnameType, Script};
+use crate::net::MacAddr;
+use crate::port::{PortProtocol, ServiceInfo, ServiceMethod};
+use crate::util::parse_ctime;
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "Nessus";
+
+///Parses a Nessus v2 XML report.
+pub fn parse(xml: &str) -> Result<NmapResults, Error> {
+    let doc = Document::parse(xml)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "NessusClientData_v2" {
+        return Err(error(FORMAT, "expected `NessusClientData_v2` root tag"));
+    }
+
+    let mut results = NmapResults::empty();
+    let mut index: HashMap<IpAddr, usize> = HashMap::new();
+
+    let report_hosts = root
+        .children()
+        .filter(|c| c.tag_name().name() == "Report")
+        .flat_map(|r| r.children())
+        .filter(|c| c.tag_name().name() == "ReportHost");
+    for node in report_hosts {
+        parse_report_host(node, &mut results, &mut index)?;
+    }
+
+    scan_times_from_hosts(&mut results);
+    Ok(results)
+}
+
+fn parse_report_host(
+    node: Node,
+    results: &mut NmapResults,
+    index: &mut HashMap<IpAddr, usize>,
+) -> Result<(), Error> {
+    let properties = node
+        .children()
+        .filter(|c| c.tag_name().name() == "HostProperties")
+        .flat_map(|p| p.children())
+        .filter(|c| c.tag_name().name() == "tag")
+        .filter_map(|t| Some((t.attribute("name")?, t.text().unwrap_or("").trim())))
+        .collect::<HashMap<_, _>>();
+
+    let ip = properties
+        .get("host-ip")
+        .copied()
+        .or_else(|| node.attribute("name"))
+        .ok_or_else(|| error(FORMAT, "expected `host-ip` tag or `name` in `ReportHost`"))?
+        .parse::<IpAddr>()
+        .map_err(|_| error(FORMAT, "failed to parse IP address"))?;
+
+    let time = |epoch: &str, ctime: &str| {
+        properties
+            .get(epoch)
+            .and_then(|t| t.parse::<i64>().ok())
+            .or_else(|| properties.get(ctime).and_then(|t| parse_ctime(t)))
+    };
+    let start = time("HOST_START_TIMESTAMP", "HOST_START");
+    let end = time("HOST_END_TIMESTAMP", "HOST_END");
+
+    let host = host_record(results, index, ip, start);
+    host.scan_end_time = host.scan_end_time.max(end);
+    if let Some(mac) = properties
+        .get("mac-address")
+        .and_then(|m| m.split_whitespace().next())
+    {
+        let mac = mac
+            .parse::<MacAddr>()
+            .map_err(|_| error(FORMAT, format!("invalid MAC address `{}`", mac)))?;
+        host.addresses.push(Address::MacAddr(mac));
+    }
+    if let Some(fqdn) = properties.get("host-fqdn") {
+        host.host_names.push(Hostname {
+            name: fqdn.to_string(),
+            source: HostnameType::Dns,
+        });
+    }
+
+    for item in node
+        .children()
+        .filter(|c| c.tag_name().name() == "ReportItem")
+    {
+        let port_number = item
+            .attribute("port")
+            .and_then(|p| p.parse::<u16>().ok())
+            .ok_or_else(|| error(FORMAT, "failed to parse port number"))?;
+        let script = finding(item)?;
+
+        if port_number == 0 {
+            host_record(results, index, ip, None).scripts.push(script);
+            continue;
+        }
+
+        let protocol = match item.attribute("protocol") {
+            Some("tcp") => PortProtocol::Tcp,
+            Some("udp") => PortProtocol::Udp,
+            Some("sctp") => PortProtocol::Sctp,
+            _ => return Err(error(FORMAT, "failed to parse port protocol")),
+        };
+        let port = port_record(results, index, ip, None, protocol, port_number);
+        if port.service_info.is_none() {
+            port.service_info = service(item.attribute("svc_name").unwrap_or(""));
+        }
+        port.scripts.push(script);
+    }
+
+    Ok(())
+}
+
+//Nessus marks service names it is unsure about with a trailing `?`.
+fn service(svc_name: &str) -> Option<ServiceInfo> {
+    let (name, confidence_level) = match svc_name.strip_suffix('?') {
+        Some(name) => (name, 3),
+        None => (svc_name, 10),
+    };
+    if name.is_empty() || name == "unknown" || name == "general" {
+        return None;
+    }
+
+    Some(ServiceInfo {
+        name: name.to_string(),
+        confidence_level,
+        method: ServiceMethod::Probe,
+        product: None,
+        version: None,
+        extra_info: None,
+        tunnel: None,
+        cpes: Vec::new(),
+    })
+}
+
+fn finding(item: Node) -> Result<Script, Error> {
+    let plugin_id = item
+        .attribute("pluginID")
+        .ok_or_else(|| error(FORMAT, "expected `pluginID` in `ReportItem`"))?;
+    let mut output = item.attribute("pluginName").unwrap_or("").to_string();
+    if let Some(plugin_output) = item
+        .children()
+        .find(|c| c.tag_name().name() == "plugin_output")
+        .and_then(|o| o.text())
+    {
+        output.push('\n');
+        output.push_str(plugin_output.trim());
+    }
+
+    Ok(Script::new(format!("nessus-{}", plugin_id), output))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::host::HostState;
+    use crate::port::PortState;
+
+    const REPORT: &str = r#"<?xml version="1.0" ?>
+<NessusClientData_v2>
+<Policy><policyName>Basic</policyName></Policy>
+<Report name="lab">
+<ReportHost name="192.168.1.10">
+<HostProperties>
+<tag name="HOST_END">Fri May  1 07:45:00 2020</tag>
+<tag name="host-ip">192.168.1.10</tag>
+<tag name="host-fqdn">db.example.com</tag>
+<tag name="mac-address">00:0c:29:aa:bb:cc</tag>
+<tag name="HOST_START">Fri May  1 07:40:12 2020</tag>
+</HostProperties>
+<ReportItem port="0" svc_name="general" protocol="tcp" severity="0" pluginID="19506" pluginName="Nessus Scan Information" pluginFamily="Settings">
+<plugin_output>Nessus version : 10.5.0</plugin_output>
+</ReportItem>
+<ReportItem port="22" svc_name="ssh" protocol="tcp" severity="0" pluginID="22964" pluginName="Service Detection" pluginFamily="Service detection">
+<plugin_output>An SSH server is running on this port.</plugin_output>
+</ReportItem>
+<ReportItem port="22" svc_name="ssh" protocol="tcp" severity="2" pluginID="90317" pluginName="SSH Weak Algorithms Supported" pluginFamily="Misc.">
+</ReportItem>
+<ReportItem port="8080" svc_name="www?" protocol="tcp" severity="0" pluginID="11219" pluginName="Nessus SYN scanner" pluginFamily="Port scanners">
+<plugin_output>Port 8080/tcp was found to be open</plugin_output>
+</ReportItem>
+</ReportHost>
+</Report>
+</NessusClientData_v2>"#;
+
+    #[test]
+    fn nessus_report() {
+        let results = parse(REPORT).unwrap();
+        assert_eq!(results.scan_start_time, 1588318812);
+        assert_eq!(results.scan_end_time, Some(1588319100));
+
+        let host = results.hosts().next().unwrap();
+        assert_eq!(host.status.state, HostState::Up);
+        let addresses = host.addresses().collect::<Vec<_>>();
+        assert_eq!(
+            addresses[1],
+            &Address::MacAddr("00:0C:29:AA:BB:CC".parse().unwrap())
+        );
+        assert_eq!(host.host_names().next().unwrap().name, "db.example.com");
+
+        let script = host.scripts().next().unwrap();
+        assert_eq!(script.id, "nessus-19506");
+        assert_eq!(
+            script.output,
+            "Nessus Scan Information\nNessus version : 10.5.0"
+        );
+
+        let ports = host.port_info.ports().collect::<Vec<_>>();
+        assert_eq!(ports.len(), 2);
+        assert_eq!(ports[0].port_number, 22);
+        assert_eq!(ports[0].status.state, PortState::Open);
+        assert_eq!(ports[0].service_info.as_ref().unwrap().name, "ssh");
+        assert_eq!(ports[0].scripts.len(), 2);
+        assert_eq!(ports[0].scripts[1].output, "SSH Weak Algorithms Supported");
+        let service = ports[1].service_info.as_ref().unwrap();
+        assert_eq!(service.name, "www");
+        assert_eq!(service.confidence_level, 3);
+    }
+
+    #[test]
+    fn nessus_errors() {
+        assert!(parse("<nmaprun/>").is_err());
+        assert!(parse(
+            r#"<NessusClientData_v2><Report><ReportHost name="not-an-ip"/></Report></NessusClientData_v2>"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,364 @@
+//!Nmap normal (`-oN`) output, best effort.
+//!
+//!Normal output is meant for humans, so only the host headers, port tables,
+//!script blocks and MAC addresses are read back. Lines that are not
+//!understood are skipped and reported as [`Warning`](struct.Warning.html)s
+//!so callers know which information was lost. As with grepable output, the
+//!version column of a port is stored as the service `product` and times in
+//!the comment lines are read as UTC.
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use super::{error, parse_comment, status};
+use crate::host::{Address, Host, HostState, Hostname, HostnameType, Script};
+use crate::net::MacAddr;
+use crate::port::{
+    ExtraPorts, Port, PortProtocol, PortState, PortStatus, ServiceInfo, ServiceMethod,
+};
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "normal";
+
+//Lines printed by Nmap that carry no information about the results.
+const IGNORED_PREFIXES: &[&str] = &[
+    "Starting Nmap",
+    "Host is up",
+    "Other addresses for",
+    "Read data files from",
+    "Service detection performed",
+    "OS detection performed",
+    "Nmap done:",
+];
+
+///A line of the input that could not be mapped onto the data model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    ///1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+///Parses Nmap normal output. Returns the results along with the lines
+///that were skipped.
+pub fn parse(input: &str) -> Result<(NmapResults, Vec<Warning>), Error> {
+    let mut results = NmapResults::empty();
+    let mut warnings = Vec::new();
+    let mut in_port_table = false;
+    let mut script: Option<(String, Vec<String>, bool)> = None;
+
+    for (number, line) in input.lines().enumerate() {
+        let number = number + 1;
+
+        if let Some(content) = line.strip_prefix('|') {
+            let (content, last) = match content.strip_prefix('_') {
+                Some(content) => (content, true),
+                None => (content.strip_prefix(' ').unwrap_or(content), false),
+            };
+
+            match script.as_mut() {
+                Some((_, lines, _)) => lines.push(content.to_string()),
+                None => {
+                    let (id, first) = content
+                        .split_once(':')
+                        .ok_or_else(|| error(FORMAT, format!("invalid script line {}", number)))?;
+                    script = Some((
+                        id.to_string(),
+                        vec![first.trim_start().to_string()],
+                        in_port_table,
+                    ));
+                }
+            }
+
+            if last {
+                let (id, lines, on_port) = script.take().expect("script started above");
+                attach_script(&mut results, script_from_lines(id, lines), on_port);
+            }
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix("# ") {
+            parse_comment(comment, &mut results);
+            continue;
+        }
+
+        if let Some(target) = line.strip_prefix("Nmap scan report for ") {
+            results.hosts.push(parse_report_header(target, number)?);
+            in_port_table = false;
+            continue;
+        }
+
+        let host = match results.hosts.last_mut() {
+            Some(host) => host,
+            None => {
+                if !line.trim().is_empty() && !is_ignored(line) {
+                    warnings.push(warning(number, "line outside of a host report"));
+                }
+                continue;
+            }
+        };
+
+        if line.trim().is_empty() {
+            in_port_table = false;
+        } else if line.starts_with("PORT ") {
+            in_port_table = true;
+        } else if in_port_table && line.starts_with(|c: char| c.is_ascii_digit()) {
+            host.port_info.ports.push(parse_port_line(line, number)?);
+        } else if let Some(not_shown) = line.strip_prefix("Not shown: ") {
+            match parse_not_shown(not_shown) {
+                Some(extra) => host.port_info.extra_ports.extend(extra),
+                None => warnings.push(warning(number, "unrecognized `Not shown` line")),
+            }
+        } else if let Some(mac) = line.strip_prefix("MAC Address: ") {
+            let (mac, vendor) = match mac.split_once(" (") {
+                Some((mac, vendor)) => (mac, Some(vendor.trim_end_matches(')'))),
+                None => (mac, None),
+            };
+            match mac.parse::<MacAddr>() {
+                Ok(mac) => host.addresses.push(Address::MacAddr(mac)),
+                Err(_) => warnings.push(warning(number, format!("invalid MAC address `{}`", mac))),
+            }
+            if host.mac_vendor.is_none() {
+                host.mac_vendor = vendor.filter(|v| *v != "Unknown").map(String::from);
+            }
+        } else if let Some(record) = line.strip_prefix("rDNS record for ") {
+            if let Some((_, name)) = record.split_once(": ") {
+                host.host_names.push(Hostname {
+                    name: name.to_string(),
+                    source: HostnameType::Dns,
+                });
+            }
+        } else if line == "Host script results:" {
+            in_port_table = false;
+        } else if !is_ignored(line) {
+            in_port_table = false;
+            warnings.push(warning(number, format!("skipped `{}`", line.trim())));
+        }
+    }
+
+    if let Some((id, lines, on_port)) = script.take() {
+        attach_script(&mut results, script_from_lines(id, lines), on_port);
+    }
+
+    Ok((results, warnings))
+}
+
+fn warning(line: usize, message: impl Into<String>) -> Warning {
+    Warning {
+        line,
+        message: message.into(),
+    }
+}
+
+fn is_ignored(line: &str) -> bool {
+    IGNORED_PREFIXES.iter().any(|p| line.starts_with(p))
+}
+
+//`scanme.nmap.org (45.33.32.156)`, `10.0.0.1` or `10.0.0.1 [host down]`
+fn parse_report_header(target: &str, number: usize) -> Result<Host, Error> {
+    let (target, state) = match target.strip_suffix(" [host down]") {
+        Some(target) => (target, HostState::Down),
+        None => (target, HostState::Up),
+    };
+
+    let (name, ip) = match target.split_once(" (") {
+        Some((name, ip)) => (Some(name), ip.trim_end_matches(')')),
+        None => (None, target),
+    };
+    let ip = ip.parse::<IpAddr>().map_err(|_| {
+        error(
+            FORMAT,
+            format!("invalid host address `{}` on line {}", ip, number),
+        )
+    })?;
+
+    let mut host = Host::new(vec![Address::IpAddr(ip)], status(state));
+    if let Some(name) = name {
+        host.host_names.push(Hostname {
+            name: name.to_string(),
+            source: HostnameType::User,
+        });
+    }
+
+    Ok(host)
+}
+
+//`22/tcp   open  ssh     OpenSSH 6.6.1p1 Ubuntu 2ubuntu2.13`
+fn parse_port_line(line: &str, number: usize) -> Result<Port, Error> {
+    let invalid = || error(FORMAT, format!("invalid port line {}", number));
+
+    let mut tokens = line.split_whitespace();
+    let (port_number, protocol) = tokens
+        .next()
+        .and_then(|t| t.split_once('/'))
+        .ok_or_else(invalid)?;
+    let port_number = port_number.parse::<u16>().map_err(|_| invalid())?;
+    let protocol = PortProtocol::from_str(protocol).map_err(|_| invalid())?;
+    let state = tokens
+        .next()
+        .and_then(|t| PortState::from_str(t).ok())
+        .ok_or_else(invalid)?;
+    let service = tokens.next();
+    let version = service
+        .and_then(|s| line.find(&format!(" {}", s)).map(|i| i + s.len() + 1))
+        .map(|i| line[i..].trim())
+        .filter(|v| !v.is_empty());
+
+    let service_info = match service {
+        None | Some("unknown") => None,
+        Some(name) => Some(ServiceInfo {
+            name: name.rsplit('/').next().unwrap_or(name).to_string(),
+            confidence_level: if version.is_some() { 10 } else { 3 },
+            method: if version.is_some() {
+                ServiceMethod::Probe
+            } else {
+                ServiceMethod::Table
+            },
+            product: version.map(String::from),
+            version: None,
+            extra_info: None,
+            tunnel: name.split_once('/').map(|(tunnel, _)| tunnel.to_string()),
+            cpes: Vec::new(),
+        }),
+    };
+
+    Ok(Port {
+        protocol,
+        port_number,
+        status: PortStatus {
+            state,
+            reason: String::new(),
+            reason_ttl: 0,
+        },
+        service_info,
+        scripts: Vec::new(),
+    })
+}
+
+//`996 closed ports`, `996 closed tcp ports (reset)` or
+//`998 filtered ports, 1 closed port`
+fn parse_not_shown(not_shown: &str) -> Option<Vec<ExtraPorts>> {
+    not_shown
+        .split(", ")
+        .map(|group| {
+            let mut tokens = group.split_whitespace();
+            let count = tokens.next()?.parse::<u32>().ok()?;
+            let state = PortState::from_str(tokens.next()?).ok()?;
+            Some(ExtraPorts { state, count })
+        })
+        .collect()
+}
+
+//Undoes the `|`/`|_` prefixing done by Nmap. Multi-line outputs start on
+//the line after the script id, as they do in XML output.
+fn script_from_lines(id: String, lines: Vec<String>) -> Script {
+    let output = if lines.len() > 1 && lines[0].is_empty() {
+        format!("\n{}", lines[1..].join("\n"))
+    } else {
+        lines.join("\n")
+    };
+
+    Script::new(id, output)
+}
+
+fn attach_script(results: &mut NmapResults, script: Script, on_port: bool) {
+    if let Some(host) = results.hosts.last_mut() {
+        match host.port_info.ports.last_mut() {
+            Some(port) if on_port => port.scripts.push(script),
+            _ => host.scripts.push(script),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const NORMAL: &str =
+        "# Nmap 7.80 scan initiated Tue May 12 15:08:55 2020 as: nmap -sV -sC 192.168.59.138
+Nmap scan report for fileserver.lan (192.168.59.138)
+Host is up (0.00050s latency).
+Not shown: 998 closed ports
+PORT     STATE SERVICE       VERSION
+22/tcp   open  ssh           OpenSSH 7.4 (protocol 2.0)
+| ssh-hostkey: 
+|   2048 aa:bb (RSA)
+|_  256 cc:dd (ECDSA)
+3389/tcp open  ms-wbt-server
+|_rdp-ntlm-info: ERROR
+MAC Address: 00:0C:29:71:23:2B (VMware)
+Service Info: OS: Linux; CPE: cpe:/o:linux:linux_kernel
+
+Host script results:
+|_smb2-time: Protocol negotiation failed (SMB2)
+
+Nmap scan report for 192.168.59.139 [host down]
+# Nmap done at Tue May 12 15:08:56 2020 -- 2 IP addresses (1 host up) scanned in 0.39 seconds
+";
+
+    #[test]
+    fn normal_scan() {
+        let (results, warnings) = parse(NORMAL).unwrap();
+
+        assert_eq!(results.nmap_version.as_deref(), Some("7.80"));
+        assert_eq!(results.scan_end_time, Some(1589296136));
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].host_names().next().unwrap().name, "fileserver.lan");
+        assert_eq!(hosts[0].addresses().count(), 2);
+        assert_eq!(hosts[0].mac_vendor(), Some("VMware"));
+        assert_eq!(hosts[1].status.state, HostState::Down);
+
+        let ports = hosts[0].port_info.ports().collect::<Vec<_>>();
+        assert_eq!(ports.len(), 2);
+        let ssh = ports[0].service_info.as_ref().unwrap();
+        assert_eq!(ssh.name, "ssh");
+        assert_eq!(ssh.product.as_deref(), Some("OpenSSH 7.4 (protocol 2.0)"));
+        assert_eq!(ports[0].scripts[0].id, "ssh-hostkey");
+        assert_eq!(
+            ports[0].scripts[0].output,
+            "\n  2048 aa:bb (RSA)\n  256 cc:dd (ECDSA)"
+        );
+        assert_eq!(ports[1].scripts[0].output, "ERROR");
+        assert!(ports[1].service_info.as_ref().unwrap().product.is_none());
+
+        let host_script = hosts[0].scripts().next().unwrap();
+        assert_eq!(host_script.id, "smb2-time");
+        assert_eq!(host_script.output, "Protocol negotiation failed (SMB2)");
+
+        assert_eq!(hosts[0].port_info.extra_ports().next().unwrap().count, 998);
+
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                line: 13,
+                message: "skipped `Service Info: OS: Linux; CPE: cpe:/o:linux:linux_kernel`"
+                    .to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn normal_round_trip() {
+        let (results, _) = parse(NORMAL).unwrap();
+        let (reparsed, warnings) = parse(&crate::report::normal::to_string(&results)).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(
+            reparsed
+                .hosts()
+                .next()
+                .unwrap()
+                .port_info
+                .ports()
+                .collect::<Vec<_>>(),
+            results
+                .hosts()
+                .next()
+                .unwrap()
+                .port_info
+                .ports()
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//!ZMap CSV output.
+//!
+//!ZMap is typically used for fast discovery before a detailed Nmap scan.
+//!Each response becomes a port of the responding host: successful
+//!responses are open ports and failed ones (e.g. `rst`, only present when
+//!ZMap's output filter lets them through) are closed ports. Hosts are
+//!marked up since they answered.
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use super::{error, port_record, scan_times_from_hosts};
+use crate::port::{PortProtocol, PortState};
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "ZMap CSV";
+
+///Parses ZMap CSV output with a header line, such as the one produced by
+///`zmap -O csv -f saddr,sport,success`. The `saddr` and `sport` fields are
+///required, `success`, `classification` and `timestamp_ts` are used when
+///present.
+pub fn parse(input: &str) -> Result<NmapResults, Error> {
+    let mut lines = input.lines().filter(|l| !l.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or_else(|| error(FORMAT, "missing header line"))?
+        .split(',')
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let column = |name: &str| header.iter().position(|h| *h == name);
+
+    let saddr = column("saddr").ok_or_else(|| error(FORMAT, "missing `saddr` column"))?;
+    let sport = column("sport").ok_or_else(|| error(FORMAT, "missing `sport` column"))?;
+    let success = column("success");
+    let classification = column("classification");
+    let timestamp = column("timestamp_ts");
+
+    let mut results = NmapResults::empty();
+    let mut index = HashMap::new();
+
+    for line in lines {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .copied()
+                .ok_or_else(|| error(FORMAT, format!("missing field in `{}`", line)))
+        };
+
+        let ip = field(saddr)?
+            .parse::<IpAddr>()
+            .map_err(|_| error(FORMAT, format!("invalid address in `{}`", line)))?;
+        let port_number = field(sport)?
+            .parse::<u16>()
+            .map_err(|_| error(FORMAT, format!("invalid port in `{}`", line)))?;
+        let open = !matches!(success.map(field).transpose()?, Some("0") | Some("false"));
+        let class = classification.map(field).transpose()?.unwrap_or("");
+        let time = timestamp
+            .map(field)
+            .transpose()?
+            .and_then(|t| t.parse::<i64>().ok());
+
+        add_response(&mut results, &mut index, ip, port_number, open, class, time);
+    }
+
+    scan_times_from_hosts(&mut results);
+    Ok(results)
+}
+
+///Parses ZMap's default output, one responding address per line, for a
+///scan of `port`.
+pub fn parse_addresses(input: &str, port: u16) -> Result<NmapResults, Error> {
+    let mut results = NmapResults::empty();
+    let mut index = HashMap::new();
+
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let ip = line
+            .parse::<IpAddr>()
+            .map_err(|_| error(FORMAT, format!("invalid address `{}`", line)))?;
+        add_response(&mut results, &mut index, ip, port, true, "", None);
+    }
+
+    Ok(results)
+}
+
+fn add_response(
+    results: &mut NmapResults,
+    index: &mut HashMap<IpAddr, usize>,
+    ip: IpAddr,
+    port_number: u16,
+    open: bool,
+    classification: &str,
+    time: Option<i64>,
+) {
+    let protocol = if classification.starts_with("udp") {
+        PortProtocol::Udp
+    } else {
+        PortProtocol::Tcp
+    };
+
+    let port = port_record(results, index, ip, time, protocol, port_number);
+    if !open {
+        port.status.state = PortState::Closed;
+    }
+    port.status.reason = classification.to_string();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zmap_csv() {
+        let input = "saddr,sport,classification,success,timestamp_ts\n\
+                     10.0.0.1,443,synack,1,1490242775\n\
+                     10.0.0.2,443,rst,0,1490242776\n\
+                     10.0.0.1,443,synack,1,1490242777\n";
+        let results = parse(input).unwrap();
+
+        assert_eq!(results.scan_start_time, 1490242775);
+        assert_eq!(results.scan_end_time, Some(1490242777));
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].port_info.ports().count(), 1);
+        let port = hosts[0].port_info.ports().next().unwrap();
+        assert_eq!(port.port_number, 443);
+        assert_eq!(port.status.state, PortState::Open);
+        assert_eq!(port.status.reason, "synack");
+        assert_eq!(
+            hosts[1].port_info.ports().next().unwrap().status.state,
+            PortState::Closed
+        );
+    }
+
+    #[test]
+    fn zmap_errors_and_plain_addresses() {
+        assert!(parse("daddr,dport\n10.0.0.1,80\n").is_err());
+        assert!(parse("saddr,sport\n10.0.0.1\n").is_err());
+
+        let results = parse_addresses("10.0.0.1\n10.0.0.2\n", 80).unwrap();
+        assert_eq!(results.hosts().count(), 2);
+        assert_eq!(
+            results
+                .hosts()
+                .next()
+                .unwrap()
+                .port_info
+                .ports()
+                .next()
+                .unwrap()
+                .port_number,
+            80
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//!Inventories of what a scan found running, for patch management and
+//!exposure tracking, and of the assets found across a series of scans.
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+
+use crate::cpe::Cpe;
+use crate::host::{Host, HostnameType, OsMatch};
+use crate::net::MacAddr;
+use crate::port::{Port, PortProtocol};
+use crate::series::{self, ScanSeries};
+use crate::NmapResults;
+
+///A distinct service, product and version combination found on open ports.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceEntry {
+    ///Service name, e.g. `http`.
+    pub service: String,
+
+    ///Product name, e.g. `Apache httpd`, when version detection found it.
+    pub product: Option<String>,
+
+    ///Product version, when version detection found it.
+    pub version: Option<String>,
+
+    pub(crate) hosts: Vec<IpAddr>,
+}
+
+impl ServiceEntry {
+    ///Returns an iterator over the addresses of the hosts running this
+    ///service, in scan order.
+    pub fn hosts(&self) -> std::slice::Iter<'_, IpAddr> {
+        self.hosts.iter()
+    }
+
+    ///Returns the number of hosts running this service.
+    pub fn host_count(&self) -> usize {
+        self.hosts.len()
+    }
+}
+
+///Returns the deduplicated list of services found on open ports, as
+///identified by `-sV`, sorted by service, product and version. A host
+///running the same service on several ports is listed once.
+pub fn services(results: &NmapResults) -> Vec<ServiceEntry> {
+    let mut entries = BTreeMap::new();
+
+    for (host, port) in results.open_ports() {
+        let (service, ip) = match (&port.service_info, host.primary_ip()) {
+            (Some(service), Some(ip)) => (service, ip),
+            _ => continue,
+        };
+        let key = (
+            service.name.clone(),
+            service.product.clone(),
+            service.version.clone(),
+        );
+        let hosts = entries.entry(key).or_insert_with(Vec::new);
+        if !hosts.contains(&ip) {
+            hosts.push(ip);
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|((service, product, version), hosts)| ServiceEntry {
+            service,
+            product,
+            version,
+            hosts,
+        })
+        .collect()
+}
+
+///A distinct CPE and the places it was observed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpeEntry {
+    pub cpe: Cpe,
+    pub(crate) locations: Vec<CpeLocation>,
+}
+
+impl CpeEntry {
+    ///Returns an iterator over the places the CPE was observed, in scan
+    ///order.
+    pub fn locations(&self) -> std::slice::Iter<'_, CpeLocation> {
+        self.locations.iter()
+    }
+}
+
+///Where a CPE was observed: a host, and the port of the service it was
+///reported for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpeLocation {
+    pub ip: IpAddr,
+    pub protocol: PortProtocol,
+    pub port_number: u16,
+}
+
+///Returns the deduplicated list of CPEs that version detection reported
+///for open ports, sorted by CPE. This is the usual input of vulnerability
+///correlation tools.
+pub fn cpes(results: &NmapResults) -> Vec<CpeEntry> {
+    let mut entries: BTreeMap<&Cpe, Vec<CpeLocation>> = BTreeMap::new();
+
+    for (host, port) in results.open_ports() {
+        let (service, ip) = match (&port.service_info, host.primary_ip()) {
+            (Some(service), Some(ip)) => (service, ip),
+            _ => continue,
+        };
+        for cpe in &service.cpes {
+            entries.entry(cpe).or_default().push(CpeLocation {
+                ip,
+                protocol: port.protocol.clone(),
+                port_number: port.port_number,
+            });
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|(cpe, locations)| CpeEntry {
+            cpe: cpe.clone(),
+            locations,
+        })
+        .collect()
+}
+
+///Returns the URLs of the web services on open ports, e.g.
+///`https://www.example.com:8443/`, in scan order and without duplicates.
+///
+///The scheme is `https` when the service was found through TLS or is
+///named `https`, and `http` for other services named `http*`. The host is
+///the first user supplied hostname, then the first reverse DNS name, then
+///the IP address. Default ports are left out.
+pub fn http_urls(results: &NmapResults) -> Vec<String> {
+    let mut urls = Vec::new();
+
+    for (host, port) in results.open_ports() {
+        let scheme = match web_scheme(port) {
+            Some(scheme) => scheme,
+            None => continue,
+        };
+        let name = match url_host(host) {
+            Some(name) => name,
+            None => continue,
+        };
+        let url = match (scheme, port.port_number) {
+            ("http", 80) | ("https", 443) => format!("{}://{}/", scheme, name),
+            _ => format!("{}://{}:{}/", scheme, name, port.port_number),
+        };
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+///Stable identity of an [`Asset`](struct.Asset.html) across scans.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AssetId {
+    ///MAC address, for hosts on the local network.
+    Mac(MacAddr),
+    ///IP address and first host name in lower case, for other hosts.
+    Ip(IpAddr, Option<String>),
+}
+
+impl AssetId {
+    fn of(host: &Host) -> Option<Self> {
+        match host.mac() {
+            Some(mac) => Some(AssetId::Mac(mac)),
+            None => {
+                let name = host
+                    .host_names()
+                    .next()
+                    .map(|h| h.name.trim_end_matches('.').to_lowercase());
+                host.primary_ip().map(|ip| AssetId::Ip(ip, name))
+            }
+        }
+    }
+}
+
+///What a scan found about an asset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    ///Seconds since Unix epoch, from the host when Nmap recorded it and from
+    ///the scan otherwise.
+    pub time: i64,
+    pub ip: Option<IpAddr>,
+    pub(crate) host_names: Vec<String>,
+    pub(crate) open_ports: Vec<Port>,
+    ///Most likely operating system, when OS detection ran.
+    pub os: Option<OsMatch>,
+}
+
+impl Observation {
+    ///Returns an iterator over the names of the asset.
+    pub fn host_names(&self) -> std::slice::Iter<'_, String> {
+        self.host_names.iter()
+    }
+
+    ///Returns an iterator over the open ports of the asset, along with
+    ///their services.
+    pub fn open_ports(&self) -> std::slice::Iter<'_, Port> {
+        self.open_ports.iter()
+    }
+}
+
+///A device followed across scans.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Asset {
+    pub id: AssetId,
+    pub(crate) observations: Vec<Observation>,
+}
+
+impl Asset {
+    ///Returns an iterator over the observations of the asset, oldest first.
+    pub fn observations(&self) -> std::slice::Iter<'_, Observation> {
+        self.observations.iter()
+    }
+
+    ///Returns the most recent observation of the asset.
+    pub fn latest(&self) -> &Observation {
+        self.observations
+            .last()
+            .expect("assets have at least one observation")
+    }
+
+    ///Returns when the asset was first seen up.
+    pub fn first_seen(&self) -> i64 {
+        self.observations[0].time
+    }
+
+    ///Returns when the asset was last seen up.
+    pub fn last_seen(&self) -> i64 {
+        self.latest().time
+    }
+
+    ///Returns the IP addresses the asset had, in the order they were first
+    ///seen.
+    pub fn ips(&self) -> Vec<IpAddr> {
+        let mut ips = Vec::new();
+        for ip in self.observations.iter().filter_map(|o| o.ip) {
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+        ips
+    }
+}
+
+///Returns the assets found up by the scans of `series`, in the order they
+///were first seen, each one with an observation per scan that found it.
+///Hosts are identified by their MAC address when the scan found one, and
+///by their IP address and host name otherwise, so a host scanned both from
+///its local network and from outside of it yields two assets.
+pub fn assets(series: &ScanSeries) -> Vec<Asset> {
+    let mut assets: Vec<Asset> = Vec::new();
+    let mut index: HashMap<AssetId, usize> = HashMap::new();
+
+    for scan in series.scans() {
+        for host in scan.hosts_up() {
+            let id = match AssetId::of(host) {
+                Some(id) => id,
+                None => continue,
+            };
+            let observation = Observation {
+                time: series::seen_at(scan, host),
+                ip: host.primary_ip(),
+                host_names: host.host_names().map(|h| h.name.clone()).collect(),
+                open_ports: host.port_info.open_ports().cloned().collect(),
+                os: host.os_matches().next().cloned(),
+            };
+            match index.get(&id) {
+                Some(&i) => assets[i].observations.push(observation),
+                None => {
+                    index.insert(id.clone(), assets.len());
+                    assets.push(Asset {
+                        id,
+                        observations: vec![observation],
+                    });
+                }
+            }
+        }
+    }
+
+    assets
+}
+
+fn web_scheme(port: &Port) -> Option<&'static str> {
+    let service = port.service_info.as_ref()?;
+    if port.protocol != PortProtocol::Tcp
+        || !service.name.starts_with("http")
+        || service.name == "http-rpc-epmap"
+    {
+        return None;
+    }
+    if service.tunnel.as_deref() == Some("ssl") || service.name.starts_with("https") {
+        Some("https")
+    } else {
+        Some("http")
+    }
+}
+
+fn url_host(host: &Host) -> Option<String> {
+    let name = host
+        .host_names()
+        .find(|h| h.source == HostnameType::User)
+        .or_else(|| host.host_names().next());
+    match (name, host.primary_ip()) {
+        (Some(name), _) => Some(name.name.clone()),
+        (None, Some(IpAddr::V6(ip))) => Some(format!("[{}]", ip)),
+        (None, Some(ip)) => Some(ip.to_string()),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn service_inventory() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="nginx" version="1.18.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="8080"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="nginx" version="1.18.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.2p1" method="probed" conf="10"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="nginx" version="1.18.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="443"><state state="closed" reason="reset" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let inventory = services(&results);
+
+        assert_eq!(inventory.len(), 2);
+        assert_eq!(inventory[0].service, "http");
+        assert_eq!(inventory[0].product.as_deref(), Some("nginx"));
+        assert_eq!(inventory[0].version.as_deref(), Some("1.18.0"));
+        assert_eq!(inventory[0].host_count(), 2);
+        assert_eq!(
+            inventory[0]
+                .hosts()
+                .map(|ip| ip.to_string())
+                .collect::<Vec<_>>(),
+            vec!["10.0.0.1", "10.0.0.2"]
+        );
+        assert_eq!(inventory[1].service, "ssh");
+        assert_eq!(inventory[1].host_count(), 1);
+    }
+
+    #[test]
+    fn cpe_inventory() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.2p1" method="probed" conf="10"><cpe>cpe:/a:openbsd:openssh:8.2p1</cpe><cpe>cpe:/o:linux:linux_kernel</cpe></service></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="2222"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.2p1" method="probed" conf="10"><cpe>cpe:/a:openbsd:openssh:8.2p1</cpe><cpe>openssh 8.2</cpe></service></port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let cpes = results.cpes();
+
+        assert_eq!(cpes.len(), 2);
+        assert_eq!(cpes[0].cpe.to_string(), "cpe:/a:openbsd:openssh:8.2p1");
+        let locations = cpes[0].locations().collect::<Vec<_>>();
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[1].ip.to_string(), "10.0.0.2");
+        assert_eq!(locations[1].port_number, 2222);
+        assert_eq!(cpes[1].cpe.to_string(), "cpe:/o:linux:linux_kernel");
+        //The malformed CPE of 10.0.0.2 is skipped.
+        assert!("openssh 8.2".parse::<Cpe>().is_err());
+        let service = results
+            .hosts()
+            .nth(1)
+            .unwrap()
+            .port_info
+            .ports()
+            .next()
+            .unwrap();
+        assert_eq!(service.service_info.as_ref().unwrap().cpes.len(), 1);
+    }
+
+    #[test]
+    fn web_urls() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="web.example.com" type="PTR"/><hostname name="www.example.com" type="user"/></hostnames>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" tunnel="ssl" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="8443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https-alt" method="table" conf="3"/></port>
+<port protocol="tcp" portid="593"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http-rpc-epmap" method="table" conf="3"/></port>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="2001:db8::1" addrtype="ipv6"/>
+<ports>
+<port protocol="tcp" portid="8080"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http-proxy" method="table" conf="3"/></port>
+<port protocol="tcp" portid="8081"><state state="closed" reason="reset" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            results.http_urls(),
+            vec![
+                "http://www.example.com/",
+                "https://www.example.com/",
+                "https://www.example.com:8443/",
+                "http://[2001:db8::1]:8080/",
+            ]
+        );
+    }
+
+    #[test]
+    fn asset_history() {
+        let scan = |start: i64, ip: &str, ports: &str| {
+            NmapResults::parse(&format!(
+                r#"<nmaprun start="{}">
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="{}" addrtype="ipv4"/>
+<address addr="00:0c:29:71:23:2b" addrtype="mac"/>
+<ports>{}</ports>
+<os><osmatch name="Linux 4.15 - 5.6" accuracy="100" line="1"/></os>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<hostnames><hostname name="Scanme.nmap.org" type="PTR"/></hostnames>
+</host>
+</nmaprun>"#,
+                start, ip, ports
+            ))
+            .unwrap()
+        };
+        let ssh = r#"<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="ssh" method="table" conf="3"/></port>"#;
+        let series = ScanSeries::from(vec![scan(100, "10.0.0.5", ""), scan(200, "10.0.0.7", ssh)]);
+
+        let assets = assets(&series);
+        assert_eq!(assets.len(), 2);
+        assert_eq!(
+            assets[0].id,
+            AssetId::Mac("00:0C:29:71:23:2B".parse().unwrap())
+        );
+        assert_eq!(assets[0].first_seen(), 100);
+        assert_eq!(assets[0].last_seen(), 200);
+        assert_eq!(
+            assets[0].ips(),
+            vec![
+                "10.0.0.5".parse::<IpAddr>().unwrap(),
+                "10.0.0.7".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            assets[0]
+                .observations()
+                .next()
+                .unwrap()
+                .open_ports()
+                .count(),
+            0
+        );
+        assert_eq!(
+            assets[0].latest().open_ports().next().unwrap().port_number,
+            22
+        );
+        assert_eq!(
+            assets[0].latest().os.as_ref().unwrap().name,
+            "Linux 4.15 - 5.6"
+        );
+        assert_eq!(
+            assets[1].id,
+            AssetId::Ip(
+                "45.33.32.156".parse().unwrap(),
+                Some("scanme.nmap.org".to_string())
+            )
+        );
+        assert_eq!(assets[1].observations().count(), 2);
+    }
+}
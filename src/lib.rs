@@ -23,24 +23,176 @@
 //!crate reaches 1.0. Use with care.
 use roxmltree::{Document, Node};
 
+pub mod analysis;
+pub mod anonymize;
+pub mod assertions;
+pub mod builder;
+pub mod cpe;
+pub mod diff;
+pub mod enrich;
+pub mod export;
+pub mod filter;
 pub mod host;
+pub mod import;
+pub mod inventory;
+mod json;
+pub mod merge;
+pub mod net;
+pub mod osdb;
+pub mod oui;
+pub mod policy;
 pub mod port;
+pub mod query;
+pub mod report;
+pub mod resume;
+#[cfg(feature = "scanner")]
+pub mod scanner;
+//Browsers cannot start processes.
+#[cfg(all(feature = "scanner", target_arch = "wasm32"))]
+compile_error!("the `scanner` feature is not available on wasm32");
+pub mod scripts;
+pub mod search;
+pub mod series;
+pub mod service_probes;
+pub mod services;
+pub mod snapshot;
+pub mod stats;
+#[cfg(feature = "testgen")]
+pub mod testgen;
 pub mod util;
+pub mod validate;
+pub mod version;
+#[cfg(feature = "nvd")]
+pub mod vuln;
 
-use crate::host::Host;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::filter::Filter;
+use crate::host::{Address, Host, HostState};
+use crate::merge::{DedupStrategy, MergeStrategy};
+use crate::net::{IpNetwork, MacAddr};
 use crate::port::Port;
+use crate::query::Query;
+use crate::util::parse_node_attr;
+use crate::version::{OutputVersion, SUPPORTED_OUTPUT_VERSION};
 
+///Errors returned by the crate. Errors parsing Nmap XML are one of
+///`XmlSyntax`, `MissingElement`, `MissingAttribute` or `InvalidValue`,
+///naming the node at fault. Errors inside a host are wrapped in `Context`
+///with the path to that node, e.g.
+///`host[192.168.1.70] > ports > port[tcp/445] > service`.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("error parsing file as XML document")]
-    XmlError(#[from] roxmltree::Error),
-    #[error("error parsing Nmap XML output: {0}")]
-    InvalidNmapOutput(String),
+    #[error("error parsing file as XML document: {0}")]
+    XmlSyntax(#[from] roxmltree::Error),
+    #[error("expected `{element}` node in `{parent}`")]
+    MissingElement {
+        element: &'static str,
+        parent: &'static str,
+    },
+    #[error("expected `{attribute}` attribute in `{element}` node")]
+    MissingAttribute {
+        element: &'static str,
+        attribute: &'static str,
+    },
+    #[error("invalid value `{value}` for `{attribute}` attribute in `{element}` node")]
+    InvalidValue {
+        element: &'static str,
+        attribute: &'static str,
+        value: String,
+    },
+    #[error("invalid CPE name: {0}")]
+    InvalidCpe(String),
+    #[error("invalid network: {0}")]
+    InvalidNetwork(String),
+    #[error("invalid MAC address: {0}")]
+    InvalidMac(String),
+    #[error("unknown {kind} `{value}`")]
+    UnknownValue { kind: &'static str, value: String },
+    #[error("error parsing {format} input: {message}")]
+    InvalidImport {
+        format: &'static str,
+        message: String,
+    },
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("invalid search pattern: {0}")]
+    InvalidPattern(String),
+    #[error("invalid scan data: {0}")]
+    InvalidData(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{path}: {source}")]
+    Context { path: String, source: Box<Error> },
+    #[cfg(feature = "scanner")]
+    #[error("nmap failed with {status}: {stderr}")]
+    ScanFailed {
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+}
+
+impl Error {
+    ///Returns the path to the node the error occurred in, if known.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::Context { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    ///Returns the error without its context.
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+
+    //Adds `segment` in front of the path of the error.
+    pub(crate) fn within(self, segment: impl Into<String>) -> Error {
+        let segment = segment.into();
+        match self {
+            Error::Context { path, source } => Error::Context {
+                path: format!("{} > {}", segment, path),
+                source,
+            },
+            e => Error::Context {
+                path: segment,
+                source: Box::new(e),
+            },
+        }
+    }
 }
 
-impl From<&str> for Error {
-    fn from(s: &str) -> Self {
-        Self::InvalidNmapOutput(s.to_string())
+///Something in a scan that this crate may have misread, reported by
+///[`NmapResults::parse_with_warnings()`](struct.NmapResults.html#method.parse_with_warnings).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    ///The scan declares an output version newer than
+    ///[`SUPPORTED_OUTPUT_VERSION`](version/constant.SUPPORTED_OUTPUT_VERSION.html).
+    NewerOutputVersion(OutputVersion),
+    ///The `xmloutputversion` of the scan is not a version number.
+    UnknownOutputVersion(String),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::NewerOutputVersion(version) => write!(
+                f,
+                "output version {} is newer than the supported {}",
+                version, SUPPORTED_OUTPUT_VERSION
+            ),
+            Warning::UnknownOutputVersion(version) => {
+                write!(f, "unknown output version `{}`", version)
+            }
+        }
     }
 }
 
@@ -55,23 +207,101 @@ pub struct NmapResults {
 
     ///End time of the Nmap scan as seconds since Unix epoch.
     pub scan_end_time: Option<i64>,
+
+    ///Command line the Nmap scan was launched with.
+    pub args: Option<String>,
+
+    ///Version of Nmap that produced the scan.
+    pub nmap_version: Option<String>,
+
+    ///Version of the XML output format declared by the scan, if it is a
+    ///version number.
+    pub output_version: Option<OutputVersion>,
+
+    ///Lookup tables from addresses to positions in `hosts`, built on the
+    ///first lookup.
+    address_index: OnceLock<AddressIndex>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct AddressIndex {
+    ip: HashMap<IpAddr, usize>,
+    mac: HashMap<MacAddr, usize>,
+}
+
+impl AddressIndex {
+    fn build(hosts: &[Host]) -> Self {
+        let mut index = AddressIndex::default();
+        for (i, host) in hosts.iter().enumerate() {
+            for address in host.addresses() {
+                match address {
+                    Address::IpAddr(ip) => {
+                        index.ip.entry(*ip).or_insert(i);
+                    }
+                    Address::MacAddr(mac) => {
+                        index.mac.entry(*mac).or_insert(i);
+                    }
+                }
+            }
+        }
+        index
+    }
 }
 
 impl NmapResults {
+    pub(crate) fn empty() -> Self {
+        NmapResults {
+            hosts: Vec::new(),
+            scan_start_time: 0,
+            scan_end_time: None,
+            args: None,
+            nmap_version: None,
+            output_version: None,
+            address_index: OnceLock::new(),
+        }
+    }
+
     pub fn parse(xml: &str) -> Result<Self, Error> {
+        Self::parse_document(&Document::parse(xml)?)
+    }
+
+    ///Parses `xml` as [`parse()`](#method.parse) does, and also returns
+    ///[`Warning`](enum.Warning.html)s about what this crate may have
+    ///misread, such as an output version newer than it supports.
+    pub fn parse_with_warnings(xml: &str) -> Result<(Self, Vec<Warning>), Error> {
         let doc = Document::parse(xml)?;
+        let results = Self::parse_document(&doc)?;
+
+        let mut warnings = Vec::new();
+        let declared = doc.root_element().attribute("xmloutputversion");
+        match (results.output_version, declared) {
+            (Some(version), _) if !version.is_supported() => {
+                warnings.push(Warning::NewerOutputVersion(version))
+            }
+            (None, Some(declared)) => {
+                warnings.push(Warning::UnknownOutputVersion(declared.to_string()))
+            }
+            _ => {}
+        }
+        Ok((results, warnings))
+    }
+
+    fn parse_document(doc: &Document) -> Result<Self, Error> {
         let root_element = doc.root_element();
         if root_element.tag_name().name() != "nmaprun" {
-            return Err(Error::from("expected `nmaprun` root tag"));
+            return Err(Error::MissingElement {
+                element: "nmaprun",
+                parent: "document",
+            });
         }
 
-        let scan_start_time = root_element
-            .attribute("start")
-            .ok_or_else(|| Error::from("expected start time attribute"))
-            .and_then(|s| {
-                s.parse::<i64>()
-                    .map_err(|_| Error::from("failed to parse start time"))
-            })?;
+        let scan_start_time = parse_node_attr!(root_element, "nmaprun", "start", i64);
+
+        let args = root_element.attribute("args").map(String::from);
+        let nmap_version = root_element.attribute("version").map(String::from);
+        let output_version = root_element
+            .attribute("xmloutputversion")
+            .and_then(|v| v.parse().ok());
 
         let mut hosts: Vec<Host> = Vec::new();
         let mut scan_end_time = None;
@@ -79,7 +309,7 @@ impl NmapResults {
         for child in root_element.children() {
             match child.tag_name().name() {
                 "host" => {
-                    hosts.push(Host::parse(child)?);
+                    hosts.push(Host::parse_in_context(child)?);
                 }
                 "runstats" => scan_end_time = Some(parse_runstats(child)?),
                 _ => {}
@@ -90,14 +320,326 @@ impl NmapResults {
             hosts,
             scan_start_time,
             scan_end_time,
+            args,
+            nmap_version,
+            output_version,
+            address_index: OnceLock::new(),
         })
     }
 
+    ///Parses the output of a scan that did not finish, e.g. because Nmap
+    ///was interrupted. Hosts written completely are kept and the rest of
+    ///the document is dropped. Complete documents are parsed as by
+    ///[`parse()`](#method.parse).
+    pub fn parse_partial(xml: &str) -> Result<Self, Error> {
+        if xml.trim_end().ends_with("</nmaprun>") {
+            return Self::parse(xml);
+        }
+
+        let end = match xml.rfind("</host>") {
+            Some(end) => end + "</host>".len(),
+            None => match xml
+                .find("<nmaprun")
+                .and_then(|start| xml[start..].find('>').map(|end| start + end + 1))
+            {
+                Some(end) => end,
+                None => return Self::parse(xml),
+            },
+        };
+        Self::parse(&format!("{}\n</nmaprun>", &xml[..end]))
+    }
+
+    ///Returns the time the scan started, or `None` if it is out of the
+    ///range of `SystemTime` on this platform.
+    pub fn scan_start(&self) -> Option<SystemTime> {
+        util::system_time(self.scan_start_time)
+    }
+
+    ///Returns the time the scan ended, unless the results are incomplete
+    ///or the time is out of the range of `SystemTime` on this platform.
+    pub fn scan_end(&self) -> Option<SystemTime> {
+        self.scan_end_time.and_then(util::system_time)
+    }
+
+    ///Returns how long the scan took, to the second.
+    pub fn scan_duration(&self) -> Option<Duration> {
+        util::duration_between(self.scan_start_time, self.scan_end_time?)
+    }
+
     ///Returns an iterator over the hosts in the scan.
-    pub fn hosts(&self) -> std::slice::Iter<Host> {
+    pub fn hosts(&self) -> std::slice::Iter<'_, Host> {
         self.hosts.iter()
     }
 
+    ///Returns the hosts in the scan as a slice.
+    pub fn hosts_slice(&self) -> &[Host] {
+        &self.hosts
+    }
+
+    ///Returns the number of hosts in the scan, whatever their state.
+    pub fn host_count(&self) -> usize {
+        self.hosts.len()
+    }
+
+    ///Returns `true` if the scan has no hosts.
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    ///Consumes the results and returns the hosts in the scan.
+    pub fn into_hosts(self) -> Vec<Host> {
+        self.hosts
+    }
+
+    ///Sorts the hosts by IP address, IPv4 before IPv6 and in numeric order.
+    ///Hosts without an IP address go last. The sort is stable.
+    pub fn sort_hosts(&mut self) {
+        self.hosts.sort_by_key(host_sort_key);
+        self.address_index = OnceLock::new();
+    }
+
+    ///Returns the hosts ordered like [`sort_hosts()`](#method.sort_hosts)
+    ///would, without modifying the results.
+    pub fn sorted_hosts(&self) -> Vec<&Host> {
+        let mut hosts = self.hosts.iter().collect::<Vec<_>>();
+        hosts.sort_by_key(|h| host_sort_key(h));
+        hosts
+    }
+
+    ///Groups the hosts by the network of `prefix_len` bits containing their
+    ///IP address, e.g. by `/24` network. The prefix length is capped to the
+    ///length of the address, so IPv4 hosts are grouped by `/32` at most.
+    ///Hosts without an IP address are left out.
+    pub fn group_by_subnet(&self, prefix_len: u8) -> BTreeMap<IpNetwork, Vec<&Host>> {
+        let mut groups: BTreeMap<IpNetwork, Vec<&Host>> = BTreeMap::new();
+        for host in &self.hosts {
+            if let Some(ip) = host.primary_ip() {
+                let max = if ip.is_ipv4() { 32 } else { 128 };
+                let network = IpNetwork::new(ip, prefix_len.min(max))
+                    .expect("prefix length is capped to the address length");
+                groups.entry(network).or_default().push(host);
+            }
+        }
+        groups
+    }
+
+    ///Collapses hosts sharing an IP address, such as hosts of several
+    ///scans combined into one result, according to `strategy`. The
+    ///collapsed host takes the position of the first one.
+    pub fn dedup_hosts(&mut self, strategy: DedupStrategy) {
+        let hosts = std::mem::take(&mut self.hosts);
+        self.hosts = merge::dedup_hosts(hosts, strategy);
+        self.address_index = OnceLock::new();
+    }
+
+    ///Combines `other` into these results, e.g. a UDP scan into a TCP scan
+    ///of the same network. Hosts sharing an IP address are merged, keeping
+    ///the union of their addresses, names, scripts and ports; `strategy`
+    ///decides which port is kept when both have it. The scan start and end
+    ///times widen to cover both scans.
+    pub fn merge(&mut self, other: NmapResults, strategy: MergeStrategy) {
+        //A start time of 0 means the time is unknown, as for some imports.
+        self.scan_start_time = match (self.scan_start_time, other.scan_start_time) {
+            (0, time) | (time, 0) => time,
+            (a, b) => a.min(b),
+        };
+        self.scan_end_time = self.scan_end_time.max(other.scan_end_time);
+        if self.args.is_none() {
+            self.args = other.args;
+        }
+        if self.nmap_version.is_none() {
+            self.nmap_version = other.nmap_version;
+        }
+        if self.output_version.is_none() {
+            self.output_version = other.output_version;
+        }
+
+        let hosts = std::mem::take(&mut self.hosts);
+        self.hosts = merge::merge_hosts_into(hosts, other.hosts, strategy);
+        self.address_index = OnceLock::new();
+    }
+
+    ///Returns the host with the IP address `addr`, if any. The first lookup
+    ///builds an index of all addresses, later lookups take constant time.
+    pub fn host_by_addr(&self, addr: &IpAddr) -> Option<&Host> {
+        let index = self.address_index();
+        index.ip.get(addr).map(|&i| &self.hosts[i])
+    }
+
+    ///Like [`host_by_addr()`](#method.host_by_addr), returning the host
+    ///for editing. The address index is rebuilt on the next lookup, in
+    ///case the addresses of the host change.
+    pub fn host_by_addr_mut(&mut self, addr: &IpAddr) -> Option<&mut Host> {
+        let i = *self.address_index().ip.get(addr)?;
+        self.address_index = OnceLock::new();
+        Some(&mut self.hosts[i])
+    }
+
+    ///Returns an iterator over the hosts for editing. The address index is
+    ///rebuilt on the next lookup.
+    pub fn hosts_mut(&mut self) -> std::slice::IterMut<'_, Host> {
+        self.address_index = OnceLock::new();
+        self.hosts.iter_mut()
+    }
+
+    ///Appends `host` to the hosts of the scan.
+    pub fn add_host(&mut self, host: Host) {
+        self.hosts.push(host);
+        self.address_index = OnceLock::new();
+    }
+
+    ///Removes the first host with the IP address `addr` and returns it.
+    pub fn remove_host(&mut self, addr: &IpAddr) -> Option<Host> {
+        let i = *self.address_index().ip.get(addr)?;
+        self.address_index = OnceLock::new();
+        Some(self.hosts.remove(i))
+    }
+
+    ///Keeps only the hosts for which `keep` returns `true`, e.g. to drop
+    ///hosts out of scope before exporting the results.
+    pub fn retain_hosts<F: FnMut(&Host) -> bool>(&mut self, keep: F) {
+        self.hosts.retain(keep);
+        self.address_index = OnceLock::new();
+    }
+
+    ///Returns the host with the MAC address `mac`, in any of the notations
+    ///[`MacAddr`](net/struct.MacAddr.html) parses. Like
+    ///[`host_by_addr()`](#method.host_by_addr), lookups use an index built
+    ///on first use.
+    pub fn host_by_mac(&self, mac: &str) -> Option<&Host> {
+        let mac = mac.parse::<MacAddr>().ok()?;
+        let index = self.address_index();
+        index.mac.get(&mac).map(|&i| &self.hosts[i])
+    }
+
+    ///Returns an iterator over the hosts with a hostname, user supplied or
+    ///from reverse DNS, equal to `name`. Names are compared
+    ///case-insensitively and a trailing dot is ignored.
+    pub fn hosts_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Host> {
+        let name = name.trim_end_matches('.');
+        self.hosts.iter().filter(move |host| {
+            host.host_names()
+                .any(|h| h.name.trim_end_matches('.').eq_ignore_ascii_case(name))
+        })
+    }
+
+    ///Returns an iterator over the hosts with an IP address in `network`.
+    pub fn hosts_in(&self, network: IpNetwork) -> impl Iterator<Item = &Host> {
+        self.hosts.iter().filter(move |host| {
+            host.addresses().any(|a| match a {
+                Address::IpAddr(ip) => network.contains(ip),
+                Address::MacAddr(_) => false,
+            })
+        })
+    }
+
+    ///Like [`hosts_in()`](#method.hosts_in), with the network given in CIDR
+    ///notation such as `10.0.0.0/24`.
+    pub fn hosts_in_cidr(&self, cidr: &str) -> Result<impl Iterator<Item = &Host>, Error> {
+        Ok(self.hosts_in(cidr.parse()?))
+    }
+
+    ///Returns the ports matching `query`, together with the host they belong
+    ///to. See the [`query`](query/index.html) module for the syntax.
+    pub fn query(&self, query: &str) -> Result<Vec<(&Host, &Port)>, Error> {
+        let query = Query::parse(query)?;
+        Ok(self
+            .iter_ports()
+            .filter(|(host, port)| query.matches_port(host, port))
+            .collect())
+    }
+
+    ///Returns the hosts matching `query`, for the host alone or for one of
+    ///its ports. See the [`query`](query/index.html) module for the syntax.
+    pub fn query_hosts(&self, query: &str) -> Result<Vec<&Host>, Error> {
+        let query = Query::parse(query)?;
+        Ok(self
+            .hosts
+            .iter()
+            .filter(|h| query.matches_host(h))
+            .collect())
+    }
+
+    ///Returns the ports matching `filter`, together with the host they
+    ///belong to.
+    pub fn filter<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = (&'a Host, &'a Port)> {
+        self.hosts.iter().flat_map(move |host| {
+            host.port_info
+                .ports()
+                .filter(move |port| filter.matches_port(host, port))
+                .map(move |port| (host, port))
+        })
+    }
+
+    ///Returns the hosts matching `filter`, for the host alone or for one of
+    ///its ports.
+    pub fn filter_hosts<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a Host> {
+        self.hosts.iter().filter(move |h| filter.matches_host(h))
+    }
+
+    fn address_index(&self) -> &AddressIndex {
+        self.address_index
+            .get_or_init(|| AddressIndex::build(&self.hosts))
+    }
+
+    ///Returns the lines of host and port script outputs containing `text`.
+    ///See [`search::search_scripts()`](search/fn.search_scripts.html).
+    pub fn search_scripts(&self, text: &str) -> Vec<search::ScriptMatch<'_>> {
+        search::search_scripts(self, text)
+    }
+
+    ///Returns the matches of `pattern` in hostnames, service information
+    ///and script outputs. See [`search::Pattern`](search/struct.Pattern.html)
+    ///for the syntax and [`search::search_fields()`](search/fn.search_fields.html)
+    ///for what is searched.
+    pub fn search_pattern(&self, pattern: &str) -> Result<Vec<search::FieldMatch<'_>>, Error> {
+        let pattern = search::Pattern::new(pattern)?;
+        Ok(search::search_fields(self, &pattern))
+    }
+
+    ///Returns the URLs of the web services found by the scan. See
+    ///[`inventory::http_urls()`](inventory/fn.http_urls.html).
+    pub fn http_urls(&self) -> Vec<String> {
+        inventory::http_urls(self)
+    }
+
+    ///Returns the deduplicated CPEs reported by version detection, with
+    ///where they were observed. See
+    ///[`inventory::cpes()`](inventory/fn.cpes.html).
+    pub fn cpes(&self) -> Vec<inventory::CpeEntry> {
+        inventory::cpes(self)
+    }
+
+    ///Takes a snapshot of the exposure found by the scan. See
+    ///[`snapshot`](snapshot/index.html).
+    pub fn snapshot(&self) -> snapshot::Snapshot {
+        snapshot::Snapshot::from_results(self)
+    }
+
+    ///Compares the exposure found by the scan with the snapshot stored at
+    ///`path`.
+    pub fn matches_snapshot<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<snapshot::SnapshotReport, Error> {
+        let expected = snapshot::Snapshot::parse(&std::fs::read_to_string(path)?)?;
+        Ok(expected.compare(&self.snapshot()))
+    }
+
+    ///Returns an iterator over the hosts that are up.
+    pub fn hosts_up(&self) -> impl Iterator<Item = &Host> {
+        self.hosts_in_state(HostState::Up)
+    }
+
+    ///Returns an iterator over the hosts that are down.
+    pub fn hosts_down(&self) -> impl Iterator<Item = &Host> {
+        self.hosts_in_state(HostState::Down)
+    }
+
+    fn hosts_in_state(&self, state: HostState) -> impl Iterator<Item = &Host> {
+        self.hosts.iter().filter(move |h| h.status.state == state)
+    }
+
     ///Returns an iterator over the ports in the scan.
     pub fn iter_ports(&self) -> std::vec::IntoIter<(&Host, &Port)> {
         let mut results = Vec::new();
@@ -109,21 +651,49 @@ impl NmapResults {
 
         results.into_iter()
     }
+
+    ///Returns an iterator over every open port in the scan, together with
+    ///the host it belongs to.
+    pub fn open_ports(&self) -> impl Iterator<Item = (&Host, &Port)> {
+        self.hosts
+            .iter()
+            .flat_map(|host| host.port_info.open_ports().map(move |p| (host, p)))
+    }
+}
+
+impl IntoIterator for NmapResults {
+    type Item = Host;
+    type IntoIter = std::vec::IntoIter<Host>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hosts.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NmapResults {
+    type Item = &'a Host;
+    type IntoIter = std::slice::Iter<'a, Host>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hosts.iter()
+    }
+}
+
+//`None` sorts before `Some`, so hosts with an address get `false` first.
+fn host_sort_key(host: &Host) -> (bool, Option<IpAddr>) {
+    let ip = host.primary_ip();
+    (ip.is_none(), ip)
 }
 
 fn parse_runstats(node: Node) -> Result<i64, Error> {
     for child in node.children() {
         if child.tag_name().name() == "finished" {
-            let finished = child
-                .attribute("time")
-                .ok_or_else(|| Error::from("expected `time` `runstats`.`finished`"))
-                .and_then(|s| {
-                    s.parse::<i64>()
-                        .map_err(|_| Error::from("failed to parse end time"))
-                })?;
-            return Ok(finished);
+            return Ok(parse_node_attr!(child, "finished", "time", i64));
         }
     }
 
-    Err(Error::from("expected `finished` tag in `runstats`"))
+    Err(Error::MissingElement {
+        element: "finished",
+        parent: "runstats",
+    })
 }
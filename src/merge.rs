@@ -0,0 +1,321 @@
+//!Combining hosts that were scanned more than once.
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::host::Host;
+use crate::port::{Port, ServiceInfo};
+
+///How [`dedup_hosts()`](../struct.NmapResults.html#method.dedup_hosts)
+///collapses hosts sharing an IP address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupStrategy {
+    ///Keep only the most recently scanned host, as given by its end time
+    ///(or start time). On a tie the host found last wins.
+    Latest,
+    ///Keep the union of the ports of all the hosts. A port found several
+    ///times is taken from the most recent scan.
+    UnionPorts,
+    ///Like `UnionPorts`, but a port found several times is taken from the
+    ///scan with the most detailed service information.
+    RicherService,
+}
+
+///How [`merge()`](../struct.NmapResults.html#method.merge) resolves a host
+///or port found in both results. Hosts and ports found in only one of them
+///are always kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    ///Take the port from the most recently scanned host, as given by its
+    ///end time (or start time). On a tie the merged-in results win.
+    Latest,
+    ///Take the port with the most detailed service information, falling
+    ///back to the most recent one.
+    RicherService,
+    ///Keep the port of the results merged into.
+    KeepExisting,
+}
+
+//Collapses the hosts sharing a primary IP address into the position of
+//the first one. Hosts without an IP address are kept as they are.
+pub(crate) fn dedup_hosts(hosts: Vec<Host>, strategy: DedupStrategy) -> Vec<Host> {
+    let mut groups: Vec<Vec<Host>> = Vec::with_capacity(hosts.len());
+    let mut index: HashMap<IpAddr, usize> = HashMap::new();
+
+    for host in hosts {
+        match host.primary_ip() {
+            Some(ip) => match index.get(&ip) {
+                Some(&i) => groups[i].push(host),
+                None => {
+                    index.insert(ip, groups.len());
+                    groups.push(vec![host]);
+                }
+            },
+            None => groups.push(vec![host]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| group.into_iter().reduce(|a, b| merge_hosts(a, b, strategy)))
+        .collect()
+}
+
+//Merges `other` into `hosts`. Hosts of `other` sharing a primary IP address
+//with a host of `hosts`, or with an earlier host of `other`, are merged into
+//it; the others are appended.
+pub(crate) fn merge_hosts_into(
+    hosts: Vec<Host>,
+    other: Vec<Host>,
+    strategy: MergeStrategy,
+) -> Vec<Host> {
+    let mut groups: Vec<(Host, Vec<Host>)> = Vec::with_capacity(hosts.len() + other.len());
+    let mut index: HashMap<IpAddr, usize> = HashMap::new();
+
+    for host in hosts.into_iter().chain(other) {
+        match host.primary_ip() {
+            Some(ip) => match index.get(&ip) {
+                Some(&i) => groups[i].1.push(host),
+                None => {
+                    index.insert(ip, groups.len());
+                    groups.push((host, Vec::new()));
+                }
+            },
+            None => groups.push((host, Vec::new())),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(host, incoming)| {
+            incoming
+                .into_iter()
+                .fold(host, |existing, host| match strategy {
+                    MergeStrategy::Latest => merge_hosts(existing, host, DedupStrategy::UnionPorts),
+                    MergeStrategy::RicherService => {
+                        merge_hosts(existing, host, DedupStrategy::RicherService)
+                    }
+                    MergeStrategy::KeepExisting => union_hosts(host, existing, false),
+                })
+        })
+        .collect()
+}
+
+fn scan_time(host: &Host) -> Option<i64> {
+    host.scan_end_time.or(host.scan_start_time)
+}
+
+//Merges two scans of the same host, `b` being found after `a`.
+fn merge_hosts(a: Host, b: Host, strategy: DedupStrategy) -> Host {
+    let (older, newer) = if scan_time(&b) >= scan_time(&a) {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    if strategy == DedupStrategy::Latest {
+        return newer;
+    }
+
+    union_hosts(older, newer, strategy == DedupStrategy::RicherService)
+}
+
+//Adds the addresses, names, scripts and ports of `older` missing from
+//`newer`. A port found in both is taken from `newer`, unless `richer` is set
+//and `older` has more detailed service information.
+fn union_hosts(older: Host, newer: Host, richer: bool) -> Host {
+    let mut host = newer;
+    host.scan_start_time = match (host.scan_start_time, older.scan_start_time) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    host.scan_end_time = host.scan_end_time.max(older.scan_end_time);
+
+    for address in older.addresses {
+        if !host.addresses.contains(&address) {
+            host.addresses.push(address);
+        }
+    }
+    for name in older.host_names {
+        if !host.host_names.contains(&name) {
+            host.host_names.push(name);
+        }
+    }
+    for script in older.scripts {
+        if !host.scripts.iter().any(|s| s.id == script.id) {
+            host.scripts.push(script);
+        }
+    }
+    if host.trace.is_none() {
+        host.trace = older.trace;
+    }
+    if host.times.is_none() {
+        host.times = older.times;
+    }
+    if host.os_matches.is_empty() {
+        host.os_matches = older.os_matches;
+    }
+    if host.os_fingerprint.is_none() {
+        host.os_fingerprint = older.os_fingerprint;
+    }
+    if host.mac_vendor.is_none() {
+        host.mac_vendor = older.mac_vendor;
+    }
+
+    for port in older.port_info.ports {
+        let ports = &mut host.port_info.ports;
+        match ports
+            .iter_mut()
+            .find(|p| p.protocol == port.protocol && p.port_number == port.port_number)
+        {
+            Some(existing) => {
+                if richer && service_detail(&port) > service_detail(existing) {
+                    *existing = port;
+                }
+            }
+            None => ports.push(port),
+        }
+    }
+    host.port_info
+        .ports
+        .sort_by_key(|p| (p.port_number, p.protocol.to_string()));
+
+    host
+}
+
+//Ranks how much version detection found about the service of a port.
+fn service_detail(port: &Port) -> (usize, u8) {
+    match &port.service_info {
+        Some(ServiceInfo {
+            product,
+            version,
+            extra_info,
+            cpes,
+            confidence_level,
+            ..
+        }) => (
+            1 + product.iter().count()
+                + version.iter().count()
+                + extra_info.iter().count()
+                + cpes.len(),
+            *confidence_level,
+        ),
+        None => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    const SCANS: &str = r#"<nmaprun start="1588318812">
+<host starttime="100" endtime="200"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.2p1" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+<host starttime="300" endtime="400"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    fn deduped(strategy: DedupStrategy) -> NmapResults {
+        let mut results = NmapResults::parse(SCANS).unwrap();
+        results.dedup_hosts(strategy);
+        results
+    }
+
+    fn ports(results: &NmapResults) -> Vec<(u16, Option<String>)> {
+        results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .map(|p| {
+                (
+                    p.port_number,
+                    p.service_info.as_ref().and_then(|s| s.version_string()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dedup_latest() {
+        let results = deduped(DedupStrategy::Latest);
+        assert_eq!(results.hosts().count(), 2);
+        assert_eq!(ports(&results), vec![(22, None), (443, None)]);
+    }
+
+    #[test]
+    fn dedup_union() {
+        let results = deduped(DedupStrategy::UnionPorts);
+        assert_eq!(results.hosts().count(), 2);
+        assert_eq!(ports(&results), vec![(22, None), (80, None), (443, None)]);
+        let host = results.hosts().next().unwrap();
+        assert_eq!(host.scan_start_time, Some(100));
+        assert_eq!(host.scan_end_time, Some(400));
+
+        let results = deduped(DedupStrategy::RicherService);
+        assert_eq!(
+            ports(&results),
+            vec![
+                (22, Some("OpenSSH 8.2p1".to_string())),
+                (80, None),
+                (443, None)
+            ]
+        );
+    }
+
+    const UDP_SCAN: &str = r#"<nmaprun start="1588318000" args="nmap -sU 10.0.0.0/30">
+<host starttime="500" endtime="600"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="udp" portid="53"><state state="open" reason="udp-response" reason_ttl="53"/><service name="domain" method="table" conf="3"/></port>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    fn merged(strategy: MergeStrategy) -> NmapResults {
+        let mut results = deduped(DedupStrategy::RicherService);
+        results.merge(NmapResults::parse(UDP_SCAN).unwrap(), strategy);
+        results
+    }
+
+    #[test]
+    fn merge_results() {
+        let results = merged(MergeStrategy::Latest);
+        assert_eq!(results.scan_start_time, 1588318000);
+        assert_eq!(results.args.as_deref(), Some("nmap -sU 10.0.0.0/30"));
+        assert_eq!(results.hosts().count(), 3);
+        assert!(results.host_by_addr(&"10.0.0.3".parse().unwrap()).is_some());
+
+        let host = results.hosts().next().unwrap();
+        assert_eq!(host.scan_start_time, Some(100));
+        assert_eq!(host.scan_end_time, Some(600));
+        assert_eq!(
+            ports(&results),
+            vec![(22, None), (53, None), (80, None), (443, None)]
+        );
+
+        for strategy in &[MergeStrategy::RicherService, MergeStrategy::KeepExisting] {
+            assert_eq!(
+                ports(&merged(*strategy))[0],
+                (22, Some("OpenSSH 8.2p1".to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//!IP network and MAC address helpers.
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::Error;
+
+///An IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8`.
+///
+///A bare address parses as a network containing only that address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    ///Creates the network of `prefix_len` bits containing `addr`. Host bits
+    ///of `addr` are cleared.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, Error> {
+        if prefix_len > max_prefix_len(&addr) {
+            return Err(Error::InvalidNetwork(
+                "network prefix length out of range".to_string(),
+            ));
+        }
+
+        Ok(IpNetwork {
+            addr: mask(addr, prefix_len),
+            prefix_len,
+        })
+    }
+
+    ///Returns the first address of the network.
+    pub fn network(&self) -> IpAddr {
+        self.addr
+    }
+
+    ///Returns the number of bits of the network prefix.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    ///Returns whether `addr` is part of this network.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                mask(*addr, self.prefix_len) == self.addr
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, len)) => (
+                addr,
+                Some(len.parse::<u8>().map_err(|_| {
+                    Error::InvalidNetwork("failed to parse network prefix length".to_string())
+                })?),
+            ),
+            None => (s, None),
+        };
+
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| Error::InvalidNetwork("failed to parse network address".to_string()))?;
+        let prefix_len = prefix_len.unwrap_or_else(|| max_prefix_len(&addr));
+
+        IpNetwork::new(addr, prefix_len)
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(addr: IpAddr) -> Self {
+        IpNetwork {
+            addr,
+            prefix_len: max_prefix_len(&addr),
+        }
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+///A MAC address.
+///
+///Parses from colon (`00:0C:29:71:23:2B`), dash (`00-0c-29-71-23-2b`) and
+///Cisco dot (`000c.2971.232b`) notations in any case. Displays in upper case
+///colon notation, as Nmap writes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddr([u8; 6]);
+
+impl MacAddr {
+    pub fn new(octets: [u8; 6]) -> Self {
+        MacAddr(octets)
+    }
+
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    ///Returns the address in upper case dash notation, `00-0C-29-71-23-2B`.
+    pub fn to_dashed_string(&self) -> String {
+        self.to_string().replace(':', "-")
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidMac(s.to_string());
+
+        let digits = match s.len() {
+            17 => {
+                let separator = s.as_bytes()[2];
+                if !matches!(separator, b':' | b'-')
+                    || !s.bytes().skip(2).step_by(3).all(|b| b == separator)
+                {
+                    return Err(invalid());
+                }
+                s.split(separator as char).collect::<String>()
+            }
+            14 if s.bytes().skip(4).step_by(5).all(|b| b == b'.') => s.replace('.', ""),
+            _ => return Err(invalid()),
+        };
+        if digits.len() != 12 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut octets = [0; 6];
+        for (i, octet) in octets.iter_mut().enumerate() {
+            *octet = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(MacAddr(octets))
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> Self {
+        MacAddr(octets)
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+//Networks that are not routed on the Internet.
+const NON_PUBLIC: &[&str] = &[
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.0.0.0/24",
+    "192.0.2.0/24",
+    "192.168.0.0/16",
+    "198.18.0.0/15",
+    "198.51.100.0/24",
+    "203.0.113.0/24",
+    "224.0.0.0/3",
+    "::/127",
+    "::ffff:0:0/96",
+    "64:ff9b:1::/48",
+    "100::/64",
+    "2001:db8::/32",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+///Returns whether `addr` is routed on the Internet, as opposed to private,
+///loopback, link-local, multicast or documentation addresses.
+pub fn is_public(addr: &IpAddr) -> bool {
+    static NETWORKS: OnceLock<Vec<IpNetwork>> = OnceLock::new();
+    let networks = NETWORKS.get_or_init(|| {
+        NON_PUBLIC
+            .iter()
+            .map(|net| net.parse().expect("non-public networks are valid"))
+            .collect()
+    });
+    !networks.iter().any(|net| net.contains(addr))
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(a) => {
+            let bits = u32::from(a);
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            IpAddr::V4((bits & mask).into())
+        }
+        IpAddr::V6(a) => {
+            let bits = u128::from(a);
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            IpAddr::V6((bits & mask).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_contains() {
+        let net = "192.168.1.77/24".parse::<IpNetwork>().unwrap();
+        assert_eq!(net.to_string(), "192.168.1.0/24");
+        assert!(net.contains(&"192.168.1.254".parse().unwrap()));
+        assert!(!net.contains(&"192.168.2.1".parse().unwrap()));
+        assert!(!net.contains(&"::1".parse().unwrap()));
+
+        let any = "0.0.0.0/0".parse::<IpNetwork>().unwrap();
+        assert!(any.contains(&"8.8.8.8".parse().unwrap()));
+
+        let host = "2001:db8::1".parse::<IpNetwork>().unwrap();
+        assert_eq!(host.prefix_len(), 128);
+        assert!(host.contains(&"2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn public_addresses() {
+        for public in &["45.33.32.156", "8.8.8.8", "2600:3c01::f03c:91ff:fe18:bb2f"] {
+            assert!(is_public(&public.parse().unwrap()), "{}", public);
+        }
+        for private in &[
+            "10.1.2.3",
+            "172.31.0.1",
+            "127.0.0.1",
+            "100.64.0.1",
+            "fe80::1",
+            "::1",
+        ] {
+            assert!(!is_public(&private.parse().unwrap()), "{}", private);
+        }
+    }
+
+    #[test]
+    fn invalid_networks() {
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("10.0.0/8".parse::<IpNetwork>().is_err());
+        assert!("10.0.0.0/x".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn mac_addresses() {
+        let mac = "00:0c:29:71:23:2b".parse::<MacAddr>().unwrap();
+        assert_eq!(mac.octets(), [0x00, 0x0c, 0x29, 0x71, 0x23, 0x2b]);
+        assert_eq!(mac.to_string(), "00:0C:29:71:23:2B");
+        assert_eq!(mac.to_dashed_string(), "00-0C-29-71-23-2B");
+        assert_eq!("00-0C-29-71-23-2B".parse::<MacAddr>().unwrap(), mac);
+        assert_eq!("000c.2971.232b".parse::<MacAddr>().unwrap(), mac);
+
+        for invalid in ["00:0C:29", "00:0C:29-71:23:2B", "00:0C:29:71:23:2G", ""] {
+            assert_eq!(
+                invalid.parse::<MacAddr>().unwrap_err().to_string(),
+                format!("invalid MAC address: {}", invalid)
+            );
+        }
+    }
+}
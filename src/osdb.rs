@@ -0,0 +1,365 @@
+//!Operating system fingerprints from Nmap's `nmap-os-db` file.
+//!
+//!When no known operating system matches a host closely, Nmap prints the
+//!fingerprint it measured instead, available as
+//![`Host::os_fingerprint()`](../host/struct.Host.html#method.os_fingerprint).
+//![`OsDb::compare()`](struct.OsDb.html#method.compare) scores such a
+//!fingerprint against the reference fingerprints of the database the way
+//!Nmap does: each attribute of a test present in both earns the points
+//!given to it by the `MatchPoints` section when the measured value matches
+//!the reference expression.
+use std::collections::HashMap;
+
+use crate::Error;
+
+const FORMAT: &str = "nmap-os-db";
+
+///The tests of a fingerprint, e.g. `SEQ` or `T1`, each with its attributes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fingerprint {
+    pub(crate) tests: Vec<FingerprintTest>,
+}
+
+///A test of a fingerprint and its `name=value` attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FingerprintTest {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl FingerprintTest {
+    ///Returns the value of the attribute `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl Fingerprint {
+    ///Parses a fingerprint as Nmap prints it, with or without the `OS:`
+    ///prefix Nmap puts on each line when wrapping it.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let joined = text
+            .lines()
+            .map(|line| line.trim())
+            .map(|line| line.strip_prefix("OS:").unwrap_or(line))
+            .collect::<String>();
+
+        let mut fingerprint = Fingerprint::default();
+        let mut rest = joined.as_str();
+        while !rest.is_empty() {
+            let test = rest
+                .split_once(')')
+                .and_then(|(test, after)| {
+                    rest = after;
+                    parse_test(test)
+                })
+                .ok_or_else(|| invalid(format!("invalid fingerprint test in `{}`", joined)))?;
+            fingerprint.tests.push(test);
+        }
+        Ok(fingerprint)
+    }
+
+    ///Returns an iterator over the tests, in the order they were written.
+    pub fn tests(&self) -> std::slice::Iter<'_, FingerprintTest> {
+        self.tests.iter()
+    }
+
+    ///Returns the test `name`.
+    pub fn test(&self, name: &str) -> Option<&FingerprintTest> {
+        self.tests.iter().find(|t| t.name == name)
+    }
+}
+
+///A reference fingerprint of the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OsFingerprint {
+    ///Name of the operating system, e.g. `Linux 3.2 - 4.9`.
+    pub name: String,
+    pub(crate) classes: Vec<FingerprintClass>,
+    ///The CPEs of the operating system, without the `auto` flag.
+    pub cpes: Vec<String>,
+    ///The tests, whose attribute values are expressions such as
+    ///`FA-104|108` rather than measured values.
+    pub fingerprint: Fingerprint,
+}
+
+impl OsFingerprint {
+    ///Returns an iterator over the classifications of the fingerprint.
+    pub fn classes(&self) -> std::slice::Iter<'_, FingerprintClass> {
+        self.classes.iter()
+    }
+}
+
+///A `Class` line of a reference fingerprint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FingerprintClass {
+    pub vendor: String,
+    pub family: String,
+    pub generation: Option<String>,
+    pub device_type: String,
+}
+
+///A reference fingerprint scored against a measured one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OsDbMatch<'a> {
+    pub fingerprint: &'a OsFingerprint,
+    ///The share of the points available that were earned, between 0 and 1.
+    pub accuracy: f64,
+}
+
+///The reference fingerprints of an `nmap-os-db` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OsDb {
+    ///The points of each attribute of each test.
+    pub(crate) match_points: HashMap<(String, String), u32>,
+    pub(crate) fingerprints: Vec<OsFingerprint>,
+}
+
+impl OsDb {
+    ///Parses the content of an `nmap-os-db` file.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut db = OsDb::default();
+        let mut in_match_points = false;
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = || invalid(format!("invalid line {}", number + 1));
+
+            if line == "MatchPoints" {
+                in_match_points = true;
+            } else if let Some(name) = line.strip_prefix("Fingerprint ") {
+                in_match_points = false;
+                db.fingerprints.push(OsFingerprint {
+                    name: name.trim().to_string(),
+                    classes: Vec::new(),
+                    cpes: Vec::new(),
+                    fingerprint: Fingerprint::default(),
+                });
+            } else if in_match_points {
+                let test = line
+                    .strip_suffix(')')
+                    .and_then(parse_test)
+                    .ok_or_else(invalid_line)?;
+                for (attribute, points) in test.attributes {
+                    let points = points.parse::<u32>().map_err(|_| invalid_line())?;
+                    db.match_points
+                        .insert((test.name.clone(), attribute), points);
+                }
+            } else {
+                let fingerprint = db.fingerprints.last_mut().ok_or_else(invalid_line)?;
+                if let Some(class) = line.strip_prefix("Class ") {
+                    let fields = class.split('|').map(str::trim).collect::<Vec<_>>();
+                    if fields.len() != 4 {
+                        return Err(invalid_line());
+                    }
+                    fingerprint.classes.push(FingerprintClass {
+                        vendor: fields[0].to_string(),
+                        family: fields[1].to_string(),
+                        generation: Some(fields[2]).filter(|g| !g.is_empty()).map(String::from),
+                        device_type: fields[3].to_string(),
+                    });
+                } else if let Some(cpe) = line.strip_prefix("CPE ") {
+                    let cpe = cpe.trim();
+                    let cpe = cpe.strip_suffix(" auto").unwrap_or(cpe);
+                    fingerprint.cpes.push(cpe.to_string());
+                } else {
+                    let test = line
+                        .strip_suffix(')')
+                        .and_then(parse_test)
+                        .ok_or_else(invalid_line)?;
+                    fingerprint.fingerprint.tests.push(test);
+                }
+            }
+        }
+
+        Ok(db)
+    }
+
+    ///Returns an iterator over the reference fingerprints, in file order.
+    pub fn fingerprints(&self) -> std::slice::Iter<'_, OsFingerprint> {
+        self.fingerprints.iter()
+    }
+
+    ///Returns the points of the attribute `attribute` of the test `test`.
+    pub fn match_points(&self, test: &str, attribute: &str) -> Option<u32> {
+        self.match_points
+            .get(&(test.to_string(), attribute.to_string()))
+            .copied()
+    }
+
+    ///Scores `subject` against `reference`, returning the share of the
+    ///available points earned. Attributes without points are ignored.
+    pub fn score(&self, subject: &Fingerprint, reference: &OsFingerprint) -> f64 {
+        let mut earned = 0;
+        let mut possible = 0;
+        for test in reference.fingerprint.tests() {
+            let measured = match subject.test(&test.name) {
+                Some(measured) => measured,
+                None => continue,
+            };
+            for (attribute, expression) in &test.attributes {
+                let value = match measured.get(attribute) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let points = match self.match_points(&test.name, attribute) {
+                    Some(points) => points,
+                    None => continue,
+                };
+                possible += points;
+                if expression_matches(expression, value) {
+                    earned += points;
+                }
+            }
+        }
+
+        if possible == 0 {
+            0.0
+        } else {
+            f64::from(earned) / f64::from(possible)
+        }
+    }
+
+    ///Scores `subject` against every reference fingerprint and returns the
+    ///`limit` best matches, most accurate first.
+    pub fn compare(&self, subject: &Fingerprint, limit: usize) -> Vec<OsDbMatch<'_>> {
+        let mut matches = self
+            .fingerprints
+            .iter()
+            .map(|fingerprint| OsDbMatch {
+                fingerprint,
+                accuracy: self.score(subject, fingerprint),
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.accuracy.total_cmp(&a.accuracy));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+//`SEQ(SP=104%GCD=1` without the closing parenthesis.
+fn parse_test(test: &str) -> Option<FingerprintTest> {
+    let (name, attributes) = test.split_once('(')?;
+    if name.is_empty() {
+        return None;
+    }
+    let attributes = attributes
+        .split('%')
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            a.split_once('=')
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(FingerprintTest {
+        name: name.to_string(),
+        attributes,
+    })
+}
+
+//Reference values are alternatives separated by `|`, each a literal, a hex
+//range `A-B` or a bound `>A` or `<A`.
+fn expression_matches(expression: &str, value: &str) -> bool {
+    let number = u64::from_str_radix(value, 16).ok();
+    let hex = |s: &str| u64::from_str_radix(s, 16).ok();
+    expression.split('|').any(|alternative| {
+        if alternative == value {
+            return true;
+        }
+        let number = match number {
+            Some(number) => number,
+            None => return false,
+        };
+        if let Some(bound) = alternative.strip_prefix('>') {
+            hex(bound).is_some_and(|b| number > b)
+        } else if let Some(bound) = alternative.strip_prefix('<') {
+            hex(bound).is_some_and(|b| number < b)
+        } else if let Some((low, high)) = alternative.split_once('-') {
+            matches!((hex(low), hex(high)), (Some(low), Some(high)) if (low..=high).contains(&number))
+        } else {
+            false
+        }
+    })
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidImport {
+        format: FORMAT,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    const OS_DB: &str = "# Nmap OS fingerprinting database\n\
+MatchPoints\n\
+SEQ(SP=25%GCD=75%TI=100)\n\
+T1(R=100%DF=20%W=25)\n\
+\n\
+Fingerprint Linux 3.2 - 4.9\n\
+Class Linux | Linux | 3.X | general purpose\n\
+CPE cpe:/o:linux:linux_kernel:3 auto\n\
+SEQ(SP=FA-104%GCD=1-6%TI=Z)\n\
+T1(R=Y%DF=Y%W=7210|FFFF)\n\
+\n\
+Fingerprint Microsoft Windows 10\n\
+Class Microsoft | Windows | 10 | general purpose\n\
+SEQ(SP=>100%GCD=1-6%TI=I)\n\
+T1(R=Y%DF=Y%W=2000)\n";
+
+    #[test]
+    fn os_database() {
+        let db = OsDb::parse(OS_DB).unwrap();
+        assert_eq!(db.fingerprints().count(), 2);
+        assert_eq!(db.match_points("SEQ", "GCD"), Some(75));
+
+        let linux = db.fingerprints().next().unwrap();
+        assert_eq!(linux.name, "Linux 3.2 - 4.9");
+        let class = linux.classes().next().unwrap();
+        assert_eq!(class.family, "Linux");
+        assert_eq!(class.generation.as_deref(), Some("3.X"));
+        assert_eq!(linux.cpes, ["cpe:/o:linux:linux_kernel:3"]);
+        assert_eq!(
+            linux.fingerprint.test("T1").unwrap().get("W"),
+            Some("7210|FFFF")
+        );
+
+        let err = OsDb::parse("SEQ(SP=25)\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error parsing nmap-os-db input: invalid line 1"
+        );
+    }
+
+    #[test]
+    fn compare_host_fingerprint() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<os><osfingerprint fingerprint="OS:SCAN(V=7.60%E=4%D=5/1%OT=22%CT=1%P=x86_64-pc-linux-gnu)SEQ(SP=10&#xa;OS:4%GCD=1%TI=Z)T1(R=Y%DF=Y%W=FFFF)&#xa;"/></os>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let text = results.hosts().next().unwrap().os_fingerprint().unwrap();
+        let subject = Fingerprint::parse(text).unwrap();
+        assert_eq!(subject.test("SEQ").unwrap().get("SP"), Some("104"));
+
+        let db = OsDb::parse(OS_DB).unwrap();
+        let matches = db.compare(&subject, 5);
+        assert_eq!(matches[0].fingerprint.name, "Linux 3.2 - 4.9");
+        assert_eq!(matches[0].accuracy, 1.0);
+        //All but TI and W match.
+        assert_eq!(matches[1].accuracy, 220.0 / 345.0);
+    }
+}
@@ -0,0 +1,178 @@
+//!MAC address vendor lookup.
+//!
+//!Nmap reports the vendor of the MAC addresses it finds, but results
+//!imported from other tools or stored without it only have the address.
+//![`VendorLookup`](trait.VendorLookup.html) resolves those, and
+//![`OuiDatabase`](struct.OuiDatabase.html) implements it over the prefix
+//!lists Nmap and the IEEE publish, loaded at runtime so the crate does not
+//!ship a copy that goes stale.
+use std::collections::HashMap;
+
+use crate::Error;
+
+const FORMAT: &str = "OUI";
+
+///Resolves MAC addresses to the vendor of the network card.
+pub trait VendorLookup {
+    ///Returns the vendor of `mac`, in any of the usual notations
+    ///(`00:0C:29:71:23:2B`, `00-0c-29-71-23-2b` or `000c.2971.232b`).
+    fn vendor(&self, mac: &str) -> Option<&str>;
+}
+
+///Vendors by MAC address prefix.
+#[derive(Clone, Debug, Default)]
+pub struct OuiDatabase {
+    //Upper case hex prefixes of 6, 7 or 9 digits (MA-L, MA-M and MA-S).
+    prefixes: HashMap<String, String>,
+}
+
+impl OuiDatabase {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Parses Nmap's `nmap-mac-prefixes` file, made of `000C29 VMware`
+    ///lines, or the IEEE `oui.txt` file, of which only the lines such as
+    ///`00-0C-29   (hex)    VMware, Inc.` are read. Comments and blank lines
+    ///are skipped.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut database = OuiDatabase::new();
+        let ieee = input.contains("(hex)");
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (prefix, vendor) = if ieee {
+                match line.split_once("(hex)") {
+                    Some((prefix, vendor)) => (prefix.trim().replace('-', ""), vendor.trim()),
+                    None => continue,
+                }
+            } else {
+                match line.split_once(char::is_whitespace) {
+                    Some((prefix, vendor)) => (prefix.to_string(), vendor.trim()),
+                    None => (line.to_string(), ""),
+                }
+            };
+            if !matches!(prefix.len(), 6 | 7 | 9)
+                || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+                || vendor.is_empty()
+            {
+                return Err(Error::InvalidImport {
+                    format: FORMAT,
+                    message: format!("invalid prefix line {}", number + 1),
+                });
+            }
+
+            database.insert(&prefix, vendor);
+        }
+
+        Ok(database)
+    }
+
+    ///Adds or replaces the vendor of the MAC addresses starting with the
+    ///hex digits of `prefix`.
+    pub fn insert(&mut self, prefix: &str, vendor: &str) {
+        self.prefixes
+            .insert(prefix.to_uppercase(), vendor.to_string());
+    }
+
+    ///Returns the number of prefixes in the database.
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    ///Returns `true` if the database has no prefix.
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+}
+
+impl VendorLookup for OuiDatabase {
+    fn vendor(&self, mac: &str) -> Option<&str> {
+        let digits = mac
+            .chars()
+            .filter(|c| c.is_ascii_hexdigit())
+            .map(|c| c.to_ascii_uppercase())
+            .collect::<String>();
+        if digits.len() != 12 {
+            return None;
+        }
+
+        //The most specific prefix wins.
+        [9, 7, 6]
+            .iter()
+            .find_map(|&len| self.prefixes.get(&digits[..len]))
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    #[test]
+    fn prefix_files() {
+        let mut database = OuiDatabase::parse(
+            "# nmap-mac-prefixes\n\
+             000C29 VMware\n\
+             70B3D5 IEEE Registration Authority\n\
+             70B3D5F2E Klein Electronics\n",
+        )
+        .unwrap();
+        let ieee = OuiDatabase::parse(
+            "OUI/MA-L\t\t\tOrganization\n\
+             \n\
+             08-00-27   (hex)\t\tPCS Systemtechnik GmbH\n\
+             080027     (base 16)\t\tPCS Systemtechnik GmbH\n\
+             \t\t\t\tIm Spitzhau 4\n",
+        )
+        .unwrap();
+        assert_eq!(ieee.len(), 1);
+        database.prefixes.extend(ieee.prefixes);
+        assert_eq!(database.vendor("00:0c:29:71:23:2b"), Some("VMware"));
+        assert_eq!(
+            database.vendor("08-00-27-AA-BB-CC"),
+            Some("PCS Systemtechnik GmbH")
+        );
+        assert_eq!(database.vendor("70b3.d5f2.e001"), Some("Klein Electronics"));
+        assert_eq!(
+            database.vendor("70:B3:D5:00:00:01"),
+            Some("IEEE Registration Authority")
+        );
+        assert_eq!(database.vendor("00:0C:29"), None);
+
+        assert!(OuiDatabase::parse("000C29").is_err());
+    }
+
+    #[test]
+    fn host_vendor() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1589292535">
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.59.138" addrtype="ipv4"/>
+<address addr="00:0C:29:71:23:2B" addrtype="mac" vendor="VMware"/>
+</host>
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.59.139" addrtype="ipv4"/>
+<address addr="08:00:27:00:00:01" addrtype="mac"/>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let mut database = OuiDatabase::new();
+        database.insert("080027", "PCS Systemtechnik GmbH");
+
+        let hosts = results.hosts().collect::<Vec<_>>();
+        assert_eq!(hosts[0].mac_vendor(), Some("VMware"));
+        assert_eq!(hosts[0].mac_vendor_with(&database), Some("VMware"));
+        assert_eq!(hosts[1].mac_vendor(), None);
+        assert_eq!(
+            hosts[1].mac_vendor_with(&database),
+            Some("PCS Systemtechnik GmbH")
+        );
+    }
+}
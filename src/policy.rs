@@ -0,0 +1,275 @@
+//!Expected network exposure.
+//!
+//!A [`Policy`](struct.Policy.html) is a list of rules, each one allowing a
+//!set of ports or services on a host or network, and possibly requiring
+//!some of them. A port found open on a host is expected if any rule
+//!covering that host allows it. [`check()`](fn.check.html) reports the
+//!hosts that are up and break the policy.
+use std::net::IpAddr;
+
+use crate::host::Host;
+use crate::net::IpNetwork;
+use crate::port::{Port, PortProtocol};
+use crate::NmapResults;
+
+///Ports allowed on a host or network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub target: IpNetwork,
+    pub(crate) allowed_ports: Vec<(PortProtocol, u16)>,
+    pub(crate) allowed_services: Vec<String>,
+    pub(crate) required_ports: Vec<(PortProtocol, u16)>,
+    pub(crate) required_services: Vec<String>,
+}
+
+impl Rule {
+    ///Creates a rule for `target` that does not allow any port yet.
+    pub fn new(target: IpNetwork) -> Self {
+        Rule {
+            target,
+            allowed_ports: Vec::new(),
+            allowed_services: Vec::new(),
+            required_ports: Vec::new(),
+            required_services: Vec::new(),
+        }
+    }
+
+    ///Allows `port` over `protocol`.
+    pub fn allow(mut self, protocol: PortProtocol, port: u16) -> Self {
+        self.allowed_ports.push((protocol, port));
+        self
+    }
+
+    ///Allows any port running the service detected as `name`.
+    pub fn allow_service(mut self, name: &str) -> Self {
+        self.allowed_services.push(name.to_string());
+        self
+    }
+
+    ///Requires `port` over `protocol` to be open, and allows it.
+    pub fn require(mut self, protocol: PortProtocol, port: u16) -> Self {
+        self.allowed_ports.push((protocol.clone(), port));
+        self.required_ports.push((protocol, port));
+        self
+    }
+
+    ///Requires a port running the service detected as `name` to be open,
+    ///and allows it.
+    pub fn require_service(mut self, name: &str) -> Self {
+        self.allowed_services.push(name.to_string());
+        self.required_services.push(name.to_string());
+        self
+    }
+
+    ///Returns an iterator over the allowed `(protocol, port)` pairs.
+    pub fn allowed_ports(&self) -> std::slice::Iter<'_, (PortProtocol, u16)> {
+        self.allowed_ports.iter()
+    }
+
+    ///Returns an iterator over the allowed service names.
+    pub fn allowed_services(&self) -> std::slice::Iter<'_, String> {
+        self.allowed_services.iter()
+    }
+
+    ///Returns an iterator over the required `(protocol, port)` pairs.
+    pub fn required_ports(&self) -> std::slice::Iter<'_, (PortProtocol, u16)> {
+        self.required_ports.iter()
+    }
+
+    ///Returns an iterator over the required service names.
+    pub fn required_services(&self) -> std::slice::Iter<'_, String> {
+        self.required_services.iter()
+    }
+
+    fn allows_port(&self, port: &Port) -> bool {
+        self.allowed_ports
+            .iter()
+            .any(|(p, n)| *p == port.protocol && *n == port.port_number)
+            || port
+                .service_info
+                .as_ref()
+                .is_some_and(|s| self.allowed_services.contains(&s.name))
+    }
+}
+
+///A set of rules describing the expected exposure of a network.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Policy {
+    pub(crate) rules: Vec<Rule>,
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Adds a rule to the policy.
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    ///Returns an iterator over the rules of the policy.
+    pub fn rules(&self) -> std::slice::Iter<'_, Rule> {
+        self.rules.iter()
+    }
+
+    ///Returns whether `port` over `protocol` may be open on `addr`.
+    pub fn allows(&self, addr: &IpAddr, protocol: &PortProtocol, port: u16) -> bool {
+        self.rules
+            .iter()
+            .filter(|r| r.target.contains(addr))
+            .any(|r| {
+                r.allowed_ports
+                    .iter()
+                    .any(|(p, n)| p == protocol && *n == port)
+            })
+    }
+
+    ///Returns whether the open `port` is allowed on `addr`, either by its
+    ///number or by its detected service.
+    pub fn allows_port(&self, addr: &IpAddr, port: &Port) -> bool {
+        self.rules
+            .iter()
+            .filter(|r| r.target.contains(addr))
+            .any(|r| r.allows_port(port))
+    }
+}
+
+///A host breaking a [`Policy`](struct.Policy.html).
+#[derive(Clone, Debug)]
+pub struct Violation<'a> {
+    pub host: &'a Host,
+    pub kind: ViolationKind<'a>,
+}
+
+///How a host breaks a [`Policy`](struct.Policy.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind<'a> {
+    ///The port is open but no rule allows it.
+    UnexpectedPort(&'a Port),
+    ///The port is required by a rule but is not open.
+    MissingPort(PortProtocol, u16),
+    ///The service is required by a rule but no open port runs it.
+    MissingService(String),
+}
+
+///Checks the hosts of `results` that are up against `policy`. Violations
+///are listed per host in scan order, unexpected ports first. Hosts without
+///an IP address are not checked.
+pub fn check<'a>(results: &'a NmapResults, policy: &Policy) -> Vec<Violation<'a>> {
+    let mut violations = Vec::new();
+
+    for host in results.hosts_up() {
+        let ip = match host.primary_ip() {
+            Some(ip) => ip,
+            None => continue,
+        };
+        let mut add = |kind| violations.push(Violation { host, kind });
+
+        for port in host.port_info.open_ports() {
+            if !policy.allows_port(&ip, port) {
+                add(ViolationKind::UnexpectedPort(port));
+            }
+        }
+
+        let rules = policy.rules.iter().filter(|r| r.target.contains(&ip));
+        let mut missing_ports: Vec<&(PortProtocol, u16)> = Vec::new();
+        let mut missing_services: Vec<&String> = Vec::new();
+        for rule in rules {
+            for required in rule.required_ports.iter() {
+                let (protocol, number) = required;
+                if !host.port_info.is_open(protocol.clone(), *number)
+                    && !missing_ports.contains(&required)
+                {
+                    missing_ports.push(required);
+                }
+            }
+            for name in rule.required_services.iter() {
+                let running = host
+                    .port_info
+                    .open_ports()
+                    .filter_map(|p| p.service_info.as_ref())
+                    .any(|s| s.name == *name);
+                if !running && !missing_services.contains(&name) {
+                    missing_services.push(name);
+                }
+            }
+        }
+        for (protocol, number) in missing_ports {
+            add(ViolationKind::MissingPort(protocol.clone(), *number));
+        }
+        for name in missing_services {
+            add(ViolationKind::MissingService(name.clone()));
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn union_of_matching_rules() {
+        let policy = Policy::new()
+            .rule(Rule::new("10.0.0.0/24".parse().unwrap()).allow(PortProtocol::Tcp, 22))
+            .rule(Rule::new("10.0.0.5".parse().unwrap()).allow(PortProtocol::Tcp, 443));
+        let web = "10.0.0.5".parse().unwrap();
+        let other = "10.0.0.6".parse().unwrap();
+
+        assert!(policy.allows(&web, &PortProtocol::Tcp, 22));
+        assert!(policy.allows(&web, &PortProtocol::Tcp, 443));
+        assert!(!policy.allows(&web, &PortProtocol::Udp, 443));
+        assert!(!policy.allows(&other, &PortProtocol::Tcp, 443));
+    }
+
+    #[test]
+    fn policy_violations() {
+        let xml = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="64"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="telnet" method="table" conf="3"/></port>
+<port protocol="tcp" portid="8443"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="https" method="probed" conf="10"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.6" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+        let policy = Policy::new()
+            .rule(
+                Rule::new("10.0.0.0/24".parse().unwrap())
+                    .require(PortProtocol::Tcp, 22)
+                    .allow_service("https"),
+            )
+            .rule(
+                Rule::new("10.0.0.5".parse().unwrap())
+                    .require(PortProtocol::Tcp, 443)
+                    .require_service("http"),
+            );
+
+        let violations = check(&results, &policy);
+        let kinds = violations.iter().map(|v| &v.kind).collect::<Vec<_>>();
+        let telnet = results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .nth(1)
+            .unwrap();
+        assert_eq!(
+            kinds,
+            vec![
+                &ViolationKind::UnexpectedPort(telnet),
+                &ViolationKind::MissingPort(PortProtocol::Tcp, 443),
+                &ViolationKind::MissingService("http".to_string()),
+            ]
+        );
+    }
+}
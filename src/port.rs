@@ -1,44 +1,239 @@
 //!Port related structs and enums.
-use const_format::formatcp;
 use roxmltree::Node;
+use std::fmt;
 use std::str::FromStr;
-use strum_macros::{Display, EnumString};
 
-use crate::util::{from_node_attr, node_attr_as_string, parse_node_attr};
+use crate::cpe::{parse_cpe_nodes, Cpe};
+use crate::host::Script;
+use crate::util::{self, from_node_attr, node_attr_as_string, parse_node_attr, string_enum};
 use crate::Error;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct PortInfo {
     pub(crate) ports: Vec<Port>,
+    pub(crate) extra_ports: Vec<ExtraPorts>,
 }
 
 impl PortInfo {
     pub(crate) fn parse(node: Node) -> Result<Self, Error> {
         let mut ports = Vec::new();
+        let mut extra_ports = Vec::new();
 
         for child in node.children() {
-            #[allow(clippy::single_match)]
             match child.tag_name().name() {
-                "port" => ports.push(Port::parse(child)?),
+                "port" => ports.push(Port::parse(child).map_err(|e| {
+                    match (child.attribute("protocol"), child.attribute("portid")) {
+                        (Some(protocol), Some(port)) => {
+                            e.within(format!("port[{}/{}]", protocol, port))
+                        }
+                        _ => e.within("port"),
+                    }
+                })?),
+                "extraports" => {
+                    extra_ports.push(ExtraPorts::parse(child).map_err(|e| e.within("extraports"))?)
+                }
                 _ => {}
             }
         }
 
-        Ok(PortInfo { ports })
+        Ok(PortInfo { ports, extra_ports })
     }
 
     ///Returns an iterator over the ports associated with this host.
-    pub fn ports(&self) -> std::slice::Iter<Port> {
+    pub fn ports(&self) -> std::slice::Iter<'_, Port> {
         self.ports.iter()
     }
+
+    ///Returns the ports associated with this host as a slice.
+    pub fn ports_slice(&self) -> &[Port] {
+        &self.ports
+    }
+
+    ///Returns the number of ports Nmap listed individually.
+    pub fn port_count(&self) -> usize {
+        self.ports.len()
+    }
+
+    ///Returns `true` if Nmap reported no ports for this host, listed or not.
+    pub fn is_empty(&self) -> bool {
+        self.ports.is_empty() && self.extra_ports.is_empty()
+    }
+
+    ///Consumes the port information and returns the listed ports.
+    pub fn into_ports(self) -> Vec<Port> {
+        self.ports
+    }
+
+    ///Returns an iterator over the ports in `state`.
+    pub fn ports_in_state(&self, state: PortState) -> impl Iterator<Item = &Port> {
+        self.ports.iter().filter(move |p| p.status.state == state)
+    }
+
+    ///Returns an iterator over the open ports.
+    pub fn open_ports(&self) -> impl Iterator<Item = &Port> {
+        self.ports_in_state(PortState::Open)
+    }
+
+    ///Returns an iterator over the closed ports.
+    pub fn closed_ports(&self) -> impl Iterator<Item = &Port> {
+        self.ports_in_state(PortState::Closed)
+    }
+
+    ///Returns an iterator over the filtered ports. Ports Nmap could not
+    ///tell apart, such as `open|filtered` ones, are not included.
+    pub fn filtered_ports(&self) -> impl Iterator<Item = &Port> {
+        self.ports_in_state(PortState::Filtered)
+    }
+
+    ///Returns the port `port_number` of `protocol`, if Nmap listed it.
+    pub fn get_port(&self, protocol: PortProtocol, port_number: u16) -> Option<&Port> {
+        self.ports
+            .iter()
+            .find(|p| p.protocol == protocol && p.port_number == port_number)
+    }
+
+    ///Returns an iterator over the ports, for editing.
+    pub fn ports_mut(&mut self) -> std::slice::IterMut<'_, Port> {
+        self.ports.iter_mut()
+    }
+
+    ///Returns the port `port_number` of `protocol` for editing, if Nmap
+    ///listed it.
+    pub fn get_port_mut(&mut self, protocol: PortProtocol, port_number: u16) -> Option<&mut Port> {
+        self.ports
+            .iter_mut()
+            .find(|p| p.protocol == protocol && p.port_number == port_number)
+    }
+
+    ///Adds `port`, replacing the port with the same protocol and number if
+    ///there is one.
+    pub fn add_port(&mut self, port: Port) {
+        match self.get_port_mut(port.protocol.clone(), port.port_number) {
+            Some(existing) => *existing = port,
+            None => self.ports.push(port),
+        }
+    }
+
+    ///Removes the port `port_number` of `protocol` and returns it.
+    pub fn remove_port(&mut self, protocol: PortProtocol, port_number: u16) -> Option<Port> {
+        let i = self
+            .ports
+            .iter()
+            .position(|p| p.protocol == protocol && p.port_number == port_number)?;
+        Some(self.ports.remove(i))
+    }
+
+    ///Keeps only the ports for which `keep` returns `true`.
+    pub fn retain_ports<F: FnMut(&Port) -> bool>(&mut self, keep: F) {
+        self.ports.retain(keep);
+    }
+
+    ///Changes the state of the port `port_number` of `protocol`. Returns
+    ///`false` if Nmap did not list the port.
+    pub fn set_state(
+        &mut self,
+        protocol: PortProtocol,
+        port_number: u16,
+        state: PortState,
+    ) -> bool {
+        match self.get_port_mut(protocol, port_number) {
+            Some(port) => {
+                port.status.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///Returns whether port `port_number` is open, for any protocol.
+    pub fn has_open(&self, port_number: u16) -> bool {
+        self.open_ports().any(|p| p.port_number == port_number)
+    }
+
+    ///Returns whether port `port_number` of `protocol` is open.
+    pub fn is_open(&self, protocol: PortProtocol, port_number: u16) -> bool {
+        self.get_port(protocol, port_number)
+            .is_some_and(|p| p.status.state == PortState::Open)
+    }
+
+    ///Returns an iterator over the groups of ports that Nmap did not list
+    ///individually, e.g. the "996 closed ports" of a default scan.
+    pub fn extra_ports(&self) -> std::slice::Iter<'_, ExtraPorts> {
+        self.extra_ports.iter()
+    }
+
+    ///Returns the groups of ports Nmap did not list individually as a slice.
+    pub fn extra_ports_slice(&self) -> &[ExtraPorts] {
+        &self.extra_ports
+    }
+
+    ///Consumes the port information and returns the groups of ports Nmap
+    ///did not list individually.
+    pub fn into_extra_ports(self) -> Vec<ExtraPorts> {
+        self.extra_ports
+    }
+}
+
+///Counts the ports per state, listed or not, e.g.
+///`2 open, 1 filtered, 997 closed`, or `no ports`.
+impl fmt::Display for PortInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let states = [
+            PortState::Open,
+            PortState::OpenFiltered,
+            PortState::Filtered,
+            PortState::Unfiltered,
+            PortState::CloseFiltered,
+            PortState::Closed,
+        ];
+        let mut first = true;
+        for state in states.iter() {
+            let count = self.ports_in_state(state.clone()).count()
+                + self
+                    .extra_ports
+                    .iter()
+                    .filter(|e| &e.state == state)
+                    .map(|e| e.count as usize)
+                    .sum::<usize>();
+            if count > 0 {
+                if !first {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{} {}", count, state)?;
+                first = false;
+            }
+        }
+        if first {
+            write!(f, "no ports")?;
+        }
+        Ok(())
+    }
+}
+
+///Ports summarised by Nmap as a count instead of being listed one by one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExtraPorts {
+    pub state: PortState,
+    pub count: u32,
+}
+
+impl ExtraPorts {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let state = from_node_attr!(node, "extraports", "state", PortState);
+
+        let count = parse_node_attr!(node, "extraports", "count", u32);
+
+        Ok(ExtraPorts { state, count })
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Port {
     pub protocol: PortProtocol,
     pub port_number: u16,
     pub status: PortStatus,
     pub service_info: Option<ServiceInfo>,
+    pub scripts: Vec<Script>,
 }
 
 impl Port {
@@ -49,39 +244,56 @@ impl Port {
 
         let mut status = None;
         let mut service_info = None;
+        let mut scripts = Vec::new();
 
         for child in node.children() {
             match child.tag_name().name() {
-                "state" => status = Some(PortStatus::parse(child)?),
-                "service" => service_info = Some(ServiceInfo::parse(child)?),
+                "state" => status = Some(PortStatus::parse(child).map_err(|e| e.within("state"))?),
+                "service" => {
+                    service_info = Some(ServiceInfo::parse(child).map_err(|e| e.within("service"))?)
+                }
+                "script" => scripts.push(Script::parse_in_context(child)?),
                 _ => {}
             }
         }
 
-        let status = status.ok_or_else(|| Error::from("expected `state` attribute for port"))?;
+        let status = status.ok_or(Error::MissingElement {
+            element: "state",
+            parent: "port",
+        })?;
 
         Ok(Port {
             protocol,
             port_number,
             status,
             service_info,
+            scripts,
         })
     }
+
+    ///Returns the banner the `banner` script grabbed from this port,
+    ///normalized as described in [`scripts::banner`](../scripts/banner/index.html).
+    pub fn banner(&self) -> Option<String> {
+        self.scripts.iter().find_map(crate::scripts::banner::parse)
+    }
 }
 
-#[derive(EnumString, Display, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PortProtocol {
-    #[strum(serialize = "ip")]
     Ip,
-    #[strum(serialize = "tcp")]
     Tcp,
-    #[strum(serialize = "udp")]
     Udp,
-    #[strum(serialize = "sctp")]
     Sctp,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+string_enum!(PortProtocol, "port protocol", {
+    Ip => "ip",
+    Tcp => "tcp",
+    Udp => "udp",
+    Sctp => "sctp",
+});
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PortStatus {
     pub state: PortState,
     pub reason: String,
@@ -90,11 +302,9 @@ pub struct PortStatus {
 
 impl PortStatus {
     fn parse(node: Node) -> Result<Self, Error> {
-        let state = from_node_attr!(node, "port", "state", PortState);
-
-        let reason = node_attr_as_string!(node, "port", "reason");
+        let state = from_node_attr!(node, "state", "state", PortState);
 
-        let reason_ttl = parse_node_attr!(node, "port", "reason_ttl", u8);
+        let (reason, reason_ttl) = util::parse_reason(node, "state")?;
 
         Ok(PortStatus {
             state,
@@ -104,27 +314,36 @@ impl PortStatus {
     }
 }
 
-#[derive(EnumString, Display, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PortState {
-    #[strum(serialize = "open")]
     Open,
-    #[strum(serialize = "closed")]
     Closed,
-    #[strum(serialize = "filtered")]
     Filtered,
-    #[strum(serialize = "unfiltered")]
     Unfiltered,
-    #[strum(serialize = "open|filtered")]
     OpenFiltered,
-    #[strum(serialize = "close|filtered")]
     CloseFiltered,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+string_enum!(PortState, "port state", {
+    Open => "open",
+    Closed => "closed",
+    Filtered => "filtered",
+    Unfiltered => "unfiltered",
+    OpenFiltered => "open|filtered",
+    CloseFiltered => "closed|filtered" | "close|filtered",
+});
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ServiceInfo {
     pub name: String,
     pub confidence_level: u8,
     pub method: ServiceMethod,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub extra_info: Option<String>,
+    ///Tunnel the service was found through, `ssl` for TLS.
+    pub tunnel: Option<String>,
+    pub cpes: Vec<Cpe>,
 }
 
 impl ServiceInfo {
@@ -135,18 +354,54 @@ impl ServiceInfo {
 
         let method = from_node_attr!(node, "service", "method", ServiceMethod);
 
+        let product = node.attribute("product").map(String::from);
+        let version = node.attribute("version").map(String::from);
+        let extra_info = node.attribute("extrainfo").map(String::from);
+        let tunnel = node.attribute("tunnel").map(String::from);
+
+        let cpes = parse_cpe_nodes(node);
+
         Ok(ServiceInfo {
             name,
             confidence_level,
             method,
+            product,
+            version,
+            extra_info,
+            tunnel,
+            cpes,
         })
     }
+
+    ///Returns the version string Nmap prints for the service, e.g.
+    ///`OpenSSH 6.6.1p1 (protocol 2.0)`, or `None` if no version was detected.
+    pub fn version_string(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(product) = &self.product {
+            parts.push(product.clone());
+        }
+        if let Some(version) = &self.version {
+            parts.push(version.clone());
+        }
+        if let Some(extra_info) = &self.extra_info {
+            parts.push(format!("({})", extra_info));
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" "))
+        }
+    }
 }
 
-#[derive(EnumString, Display, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ServiceMethod {
-    #[strum(serialize = "table")]
     Table,
-    #[strum(serialize = "probed")]
     Probe,
 }
+
+string_enum!(ServiceMethod, "service detection method", {
+    Table => "table",
+    Probe => "probed",
+});
@@ -0,0 +1,590 @@
+//!A small expression language for filtering scan results.
+//!
+//!A query is a boolean expression over host, port, service and script
+//!fields:
+//!
+//!```text
+//!port.state == open && service.name == 'http' && host.state == up
+//!```
+//!
+//!Comparisons are written `field op value`, where `op` is one of `==`,
+//!`!=`, `<`, `<=`, `>`, `>=`, `~=` (contains, case-insensitive) or `in`
+//!(address in a CIDR network). Values are bare words, numbers or quoted
+//!strings. Comparisons combine with `&&`, `||`, `!` and parentheses.
+//!
+//!The fields are:
+//!
+//!* `host.state`, `host.addr`, `host.mac`, `host.name`
+//!* `port.number`, `port.protocol`, `port.state`, `port.reason`
+//!* `service.name`, `service.product`, `service.version`,
+//!  `service.extrainfo`
+//!* `script.id`, `script.output`, over the scripts of the port and of the
+//!  host
+//!
+//!Fields with several values, such as `host.name`, match if any value
+//!does, and `!=` holds when no value is equal. A comparison on a field
+//!without a value, e.g. on a port that has no service, is false.
+//!
+//!```
+//!# use std::path::PathBuf;
+//!# use std::fs;
+//!use nmap_xml_parser::NmapResults;
+//!# let mut nmap_xml_file = PathBuf::new();
+//!# nmap_xml_file.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+//!# nmap_xml_file.push("tests/test.xml");
+//!let content = fs::read_to_string(nmap_xml_file).unwrap();
+//!let results = NmapResults::parse(&content).unwrap();
+//!let web = results.query("port.state == open && port.number < 1024").unwrap();
+//!assert_eq!(web.len(), 2);
+//!```
+use std::net::IpAddr;
+
+use crate::host::{Address, Host};
+use crate::net::IpNetwork;
+use crate::port::Port;
+use crate::Error;
+
+///A compiled query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    //Chains are kept flat, so that only `!` and parentheses nest.
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    HostState,
+    HostAddr,
+    HostMac,
+    HostName,
+    PortNumber,
+    PortProtocol,
+    PortState,
+    PortReason,
+    ServiceName,
+    ServiceProduct,
+    ServiceVersion,
+    ServiceExtraInfo,
+    ScriptId,
+    ScriptOutput,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    In,
+}
+
+impl Query {
+    ///Compiles `query`.
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Query { expr }),
+            Some(token) => Err(invalid(format!("unexpected `{}`", token.text()))),
+        }
+    }
+
+    ///Returns whether the query holds for `port` of `host`.
+    pub fn matches_port(&self, host: &Host, port: &Port) -> bool {
+        self.expr.eval(host, Some(port))
+    }
+
+    ///Returns whether the query holds for `host`. A query on port, service
+    ///or script fields has to hold for one of its ports, so that e.g.
+    ///`!(port.state == open)` only matches hosts with a port that is not
+    ///open. Other queries, and queries on hosts without any port, are
+    ///evaluated for the host alone, where port fields have no value.
+    pub fn matches_host(&self, host: &Host) -> bool {
+        if !self.expr.has_port_fields() || host.port_info.ports().next().is_none() {
+            return self.expr.eval(host, None);
+        }
+        host.port_info
+            .ports()
+            .any(|p| self.expr.eval(host, Some(p)))
+    }
+}
+
+impl Expr {
+    fn has_port_fields(&self) -> bool {
+        match self {
+            Expr::And(exprs) | Expr::Or(exprs) => exprs.iter().any(Expr::has_port_fields),
+            Expr::Not(a) => a.has_port_fields(),
+            Expr::Compare(field, _, _) => field.is_port_field(),
+        }
+    }
+
+    fn eval(&self, host: &Host, port: Option<&Port>) -> bool {
+        match self {
+            Expr::And(exprs) => exprs.iter().all(|e| e.eval(host, port)),
+            Expr::Or(exprs) => exprs.iter().any(|e| e.eval(host, port)),
+            Expr::Not(a) => !a.eval(host, port),
+            Expr::Compare(field, op, value) => {
+                let values = field.values(host, port);
+                if values.is_empty() {
+                    return false;
+                }
+                match op {
+                    Op::Ne => !values.iter().any(|v| compare(v, Op::Eq, value)),
+                    _ => values.iter().any(|v| compare(v, *op, value)),
+                }
+            }
+        }
+    }
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        Some(match name {
+            "host.state" => Field::HostState,
+            "host.addr" => Field::HostAddr,
+            "host.mac" => Field::HostMac,
+            "host.name" => Field::HostName,
+            "port.number" => Field::PortNumber,
+            "port.protocol" => Field::PortProtocol,
+            "port.state" => Field::PortState,
+            "port.reason" => Field::PortReason,
+            "service.name" => Field::ServiceName,
+            "service.product" => Field::ServiceProduct,
+            "service.version" => Field::ServiceVersion,
+            "service.extrainfo" => Field::ServiceExtraInfo,
+            "script.id" => Field::ScriptId,
+            "script.output" => Field::ScriptOutput,
+            _ => return None,
+        })
+    }
+
+    //Script fields count as port fields, as the scripts of a port include
+    //those of its host.
+    fn is_port_field(self) -> bool {
+        !matches!(
+            self,
+            Field::HostState | Field::HostAddr | Field::HostMac | Field::HostName
+        )
+    }
+
+    fn values(self, host: &Host, port: Option<&Port>) -> Vec<String> {
+        let service = port.and_then(|p| p.service_info.as_ref());
+        let scripts = || {
+            host.scripts()
+                .chain(port.into_iter().flat_map(|p| p.scripts.iter()))
+        };
+
+        match self {
+            Field::HostState => vec![host.status.state.to_string()],
+            Field::HostAddr => host
+                .addresses()
+                .filter_map(|a| match a {
+                    Address::IpAddr(ip) => Some(ip.to_string()),
+                    Address::MacAddr(_) => None,
+                })
+                .collect(),
+            Field::HostMac => host
+                .addresses()
+                .filter_map(|a| match a {
+                    Address::MacAddr(mac) => Some(mac.to_string()),
+                    Address::IpAddr(_) => None,
+                })
+                .collect(),
+            Field::HostName => host.host_names().map(|h| h.name.clone()).collect(),
+            Field::PortNumber => port
+                .map(|p| p.port_number.to_string())
+                .into_iter()
+                .collect(),
+            Field::PortProtocol => port.map(|p| p.protocol.to_string()).into_iter().collect(),
+            Field::PortState => port
+                .map(|p| p.status.state.to_string())
+                .into_iter()
+                .collect(),
+            Field::PortReason => port.map(|p| p.status.reason.clone()).into_iter().collect(),
+            Field::ServiceName => service.map(|s| s.name.clone()).into_iter().collect(),
+            Field::ServiceProduct => service
+                .and_then(|s| s.product.clone())
+                .into_iter()
+                .collect(),
+            Field::ServiceVersion => service
+                .and_then(|s| s.version.clone())
+                .into_iter()
+                .collect(),
+            Field::ServiceExtraInfo => service
+                .and_then(|s| s.extra_info.clone())
+                .into_iter()
+                .collect(),
+            Field::ScriptId => scripts().map(|s| s.id.clone()).collect(),
+            Field::ScriptOutput => scripts().map(|s| s.output.clone()).collect(),
+        }
+    }
+}
+
+fn compare(actual: &str, op: Op, expected: &str) -> bool {
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Contains => actual.to_lowercase().contains(&expected.to_lowercase()),
+        Op::In => match (actual.parse::<IpAddr>(), expected.parse::<IpNetwork>()) {
+            (Ok(ip), Ok(network)) => network.contains(&ip),
+            _ => false,
+        },
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+            let ordering = match (actual.parse::<f64>(), expected.parse::<f64>()) {
+                (Ok(a), Ok(b)) => a.partial_cmp(&b),
+                _ => Some(actual.cmp(expected)),
+            };
+            match ordering {
+                Some(ordering) => match op {
+                    Op::Lt => ordering.is_lt(),
+                    Op::Le => ordering.is_le(),
+                    Op::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+                None => false,
+            }
+        }
+    }
+}
+
+impl Op {
+    fn text(self) -> &'static str {
+        match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "~=",
+            Op::In => "in",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Word(w) => w.clone(),
+            Token::Quoted(q) => format!("'{}'", q),
+            Token::Op(op) => op.text().to_string(),
+            Token::And => "&&".to_string(),
+            Token::Or => "||".to_string(),
+            Token::Not => "!".to_string(),
+            Token::Open => "(".to_string(),
+            Token::Close => ")".to_string(),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, Error> {
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('~', Some('=')) => (Token::Op(Op::Contains), 2),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('\'', _) | ('"', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or_else(|| invalid("unterminated string"))?;
+                let text = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Quoted(text), end + 2)
+            }
+            _ => {
+                let mut end = i;
+                while end < chars.len() && is_word_char(chars[end], chars.get(end + 1)) {
+                    end += 1;
+                }
+                if end == i {
+                    return Err(invalid(format!("unexpected `{}`", c)));
+                }
+                let word = chars[i..end].iter().collect::<String>();
+                let token = if word == "in" {
+                    Token::Op(Op::In)
+                } else {
+                    Token::Word(word)
+                };
+                (token, end - i)
+            }
+        };
+        tokens.push(token);
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+//Bare words may contain a single `|`, as in `open|filtered`.
+fn is_word_char(c: char, next: Option<&char>) -> bool {
+    c.is_alphanumeric() || "._-/:*@".contains(c) || (c == '|' && next != Some(&'|'))
+}
+
+//The deepest nesting of `!` and parentheses accepted, so that a hostile
+//query cannot overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut exprs = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut exprs = vec![self.unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            exprs.push(self.unary()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::And(exprs)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some(Token::Not) => {
+                self.enter()?;
+                let expr = self.unary()?;
+                self.depth -= 1;
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            Some(Token::Open) => {
+                self.enter()?;
+                let expr = self.or()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(invalid("expected `)`")),
+                }
+            }
+            Some(Token::Word(name)) => {
+                let field = Field::parse(&name)
+                    .ok_or_else(|| invalid(format!("unknown field `{}`", name)))?;
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => return Err(invalid(format!("expected operator after `{}`", name))),
+                };
+                let value = match self.next() {
+                    Some(Token::Word(value)) | Some(Token::Quoted(value)) => value,
+                    _ => return Err(invalid(format!("expected value after `{}`", name))),
+                };
+                Ok(Expr::Compare(field, op, value))
+            }
+            Some(token) => Err(invalid(format!("unexpected `{}`", token.text()))),
+            None => Err(invalid("unexpected end of query")),
+        }
+    }
+}
+
+impl Parser {
+    fn enter(&mut self) -> Result<(), Error> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(invalid(format!("nested deeper than {} levels", MAX_DEPTH)));
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidQuery(message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="web.example.com" type="user"/></hostnames>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.2p1" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="nginx" method="probed" conf="10"/><script id="http-title" output="Welcome to nginx!"/></port>
+<port protocol="udp" portid="161"><state state="open|filtered" reason="no-response" reason_ttl="0"/><service name="snmp" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="192.168.1.5" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="8080"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+<port protocol="tcp" portid="443"><state state="closed" reason="reset" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.9" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    fn ports(results: &NmapResults, query: &str) -> Vec<u16> {
+        results
+            .query(query)
+            .unwrap()
+            .into_iter()
+            .map(|(_, p)| p.port_number)
+            .collect()
+    }
+
+    #[test]
+    fn port_queries() {
+        let results = NmapResults::parse(SCAN).unwrap();
+
+        assert_eq!(
+            ports(
+                &results,
+                "port.state == open && service.name == 'http' && host.state == up"
+            ),
+            vec![80, 8080]
+        );
+        assert_eq!(
+            ports(&results, "host.addr in 10.0.0.0/8 && port.number <= 100"),
+            vec![22, 80]
+        );
+        assert_eq!(ports(&results, "port.state == open|filtered"), vec![161]);
+        assert_eq!(
+            ports(
+                &results,
+                "script.output ~= \"NGINX\" || !(port.protocol == tcp)"
+            ),
+            vec![80, 161]
+        );
+        assert_eq!(ports(&results, "service.product != nginx"), vec![22]);
+    }
+
+    #[test]
+    fn host_queries() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let hosts = |query| {
+            results
+                .query_hosts(query)
+                .unwrap()
+                .into_iter()
+                .map(|h| h.primary_ip().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(hosts("host.state == down"), vec!["10.0.0.9"]);
+        assert_eq!(hosts("port.number == 443"), vec!["192.168.1.5"]);
+        assert_eq!(hosts("host.name == web.example.com"), vec!["10.0.0.1"]);
+        //Negated port conditions need a port they hold for: every port of
+        //10.0.0.1 is open or open|filtered.
+        assert_eq!(
+            hosts("!(port.state ~= open)"),
+            vec!["192.168.1.5", "10.0.0.9"]
+        );
+        assert_eq!(hosts("!(host.state == up)"), vec!["10.0.0.9"]);
+        assert_eq!(
+            hosts("host.state == up && !(service.name == http)"),
+            vec!["10.0.0.1", "192.168.1.5"]
+        );
+    }
+
+    #[test]
+    fn invalid_queries() {
+        for query in &[
+            "",
+            "port.state ==",
+            "port.colour == red",
+            "(port.state == open",
+            "port.state == open )",
+            "host.name == 'web",
+            "port.state open",
+        ] {
+            assert!(Query::parse(query).is_err(), "{}", query);
+        }
+        assert_eq!(
+            Query::parse("<= 1024").unwrap_err().to_string(),
+            "invalid query: unexpected `<=`"
+        );
+
+        let chain = vec!["port.state == open"; 200_000].join(" && ");
+        assert!(Query::parse(&chain).is_ok());
+        let nested = format!("{}port.state == open{}", "(".repeat(64), ")".repeat(64));
+        assert!(Query::parse(&nested).is_ok());
+        for query in &[
+            "(".repeat(200_000),
+            "!".repeat(200_000),
+            format!("{}port.state == open{}", "(".repeat(65), ")".repeat(65)),
+        ] {
+            assert!(matches!(
+                Query::parse(query),
+                Err(Error::InvalidQuery(message)) if message.contains("nested deeper")
+            ));
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//!Exposure drift between a baseline scan and a current scan.
+//!
+//!A [`DriftReport`](struct.DriftReport.html) lists the services that were
+//!exposed, closed or changed version since the baseline, one entry per
+//!port. It is built on [`ScanDiff`](../../diff/struct.ScanDiff.html), so
+//!the open ports of hosts that appeared or went down count as exposed or
+//!closed. The [Markdown](../markdown/fn.write_drift.html) and
+//![HTML](../html/fn.write_drift.html) writers render it.
+use std::fmt;
+
+use crate::diff::ScanDiff;
+use crate::port::{Port, PortProtocol};
+use crate::report::host_label;
+use crate::series::ScanSeries;
+use crate::NmapResults;
+
+///Kind of a [`Drift`](struct.Drift.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DriftKind {
+    ///A port was opened.
+    Exposed,
+    ///A port was closed.
+    Closed,
+    ///The service of a port open in both scans changed.
+    Changed,
+}
+
+impl fmt::Display for DriftKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DriftKind::Exposed => "exposed",
+            DriftKind::Closed => "closed",
+            DriftKind::Changed => "changed",
+        })
+    }
+}
+
+///A change to the exposure of a port.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drift {
+    pub kind: DriftKind,
+    ///Host label, e.g. `scanme.nmap.org (45.33.32.156)`.
+    pub host: String,
+    pub protocol: PortProtocol,
+    pub port_number: u16,
+    ///Service in the baseline, e.g. `ssh OpenSSH 7.4`, if the port was open.
+    pub before: Option<String>,
+    ///Service in the current scan, if the port is open.
+    pub after: Option<String>,
+}
+
+///The changes between a baseline scan and a current scan.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DriftReport {
+    ///Start time of the baseline scan, as seconds since Unix epoch.
+    pub baseline_time: i64,
+    ///Start time of the current scan, as seconds since Unix epoch.
+    pub current_time: i64,
+    pub(crate) changes: Vec<Drift>,
+}
+
+impl DriftReport {
+    ///Compares `current` against `baseline`. Changes are listed per host,
+    ///in the order of the current scan, then removed hosts.
+    pub fn new(baseline: &NmapResults, current: &NmapResults) -> Self {
+        let diff = ScanDiff::between(baseline, current);
+        let mut changes = Vec::new();
+
+        for host in diff.new_hosts() {
+            for port in host.port_info.open_ports() {
+                changes.push(drift(
+                    DriftKind::Exposed,
+                    host_label(host),
+                    None,
+                    Some(port),
+                ));
+            }
+        }
+        for host_diff in diff.changed_hosts() {
+            let host = host_label(host_diff.new);
+            for port in host_diff.opened() {
+                changes.push(drift(DriftKind::Exposed, host.clone(), None, Some(port)));
+            }
+            for port in host_diff.closed() {
+                changes.push(drift(DriftKind::Closed, host.clone(), Some(port), None));
+            }
+            for change in host_diff.service_changes() {
+                changes.push(drift(
+                    DriftKind::Changed,
+                    host.clone(),
+                    Some(change.old),
+                    Some(change.new),
+                ));
+            }
+        }
+        for host in diff.removed_hosts() {
+            for port in host.port_info.open_ports() {
+                changes.push(drift(DriftKind::Closed, host_label(host), Some(port), None));
+            }
+        }
+
+        DriftReport {
+            baseline_time: baseline.scan_start_time,
+            current_time: current.scan_start_time,
+            changes,
+        }
+    }
+
+    ///Compares the latest scan of `series` against its oldest one. Returns
+    ///`None` if the series holds less than two scans.
+    pub fn from_series(series: &ScanSeries) -> Option<Self> {
+        let mut scans = series.scans();
+        match (scans.next(), scans.next_back()) {
+            (Some(baseline), Some(current)) => Some(DriftReport::new(baseline, current)),
+            _ => None,
+        }
+    }
+
+    ///Returns an iterator over the changes.
+    pub fn changes(&self) -> std::slice::Iter<'_, Drift> {
+        self.changes.iter()
+    }
+
+    ///Returns the number of changes of the given kind.
+    pub fn count(&self, kind: DriftKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+}
+
+fn drift(kind: DriftKind, host: String, before: Option<&Port>, after: Option<&Port>) -> Drift {
+    let port = after
+        .or(before)
+        .expect("a drift concerns at least one port");
+    Drift {
+        kind,
+        host,
+        protocol: port.protocol.clone(),
+        port_number: port.port_number,
+        before: before.map(describe),
+        after: after.map(describe),
+    }
+}
+
+//`ssh OpenSSH 7.4`, `ssh` or `unknown` without version detection.
+fn describe(port: &Port) -> String {
+    match &port.service_info {
+        Some(service) => match service.version_string() {
+            Some(version) => format!("{} {}", service.name, version),
+            None => service.name.clone(),
+        },
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BASELINE: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    const CURRENT: &str = r#"<nmaprun start="1588405212">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.0" method="probed" conf="10"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    fn report() -> DriftReport {
+        DriftReport::new(
+            &NmapResults::parse(BASELINE).unwrap(),
+            &NmapResults::parse(CURRENT).unwrap(),
+        )
+    }
+
+    #[test]
+    fn drift_report() {
+        let report = report();
+
+        assert_eq!(report.baseline_time, 1588318812);
+        assert_eq!(report.count(DriftKind::Exposed), 1);
+        assert_eq!(report.count(DriftKind::Closed), 1);
+        assert_eq!(report.count(DriftKind::Changed), 1);
+        assert_eq!(
+            report.changes().next().unwrap(),
+            &Drift {
+                kind: DriftKind::Exposed,
+                host: "10.0.0.2".to_string(),
+                protocol: PortProtocol::Tcp,
+                port_number: 443,
+                before: None,
+                after: Some("https".to_string()),
+            }
+        );
+        let changed = report
+            .changes()
+            .find(|c| c.kind == DriftKind::Changed)
+            .unwrap();
+        assert_eq!(changed.before.as_deref(), Some("ssh OpenSSH 7.4"));
+        assert_eq!(changed.after.as_deref(), Some("ssh OpenSSH 8.0"));
+
+        let series = ScanSeries::from(vec![
+            NmapResults::parse(CURRENT).unwrap(),
+            NmapResults::parse(BASELINE).unwrap(),
+        ]);
+        assert_eq!(DriftReport::from_series(&series), Some(report));
+        assert_eq!(DriftReport::from_series(&ScanSeries::new()), None);
+    }
+}
@@ -0,0 +1,295 @@
+//!Self-contained HTML report.
+//!
+//!The generated page needs no external stylesheet or script: it contains
+//!the scan metadata, a sortable host table and one expandable section per
+//!host with its ports, their services and scripts, and its host scripts.
+//!It is meant as a replacement for running the XML through Nmap's XSL
+//!stylesheet.
+use std::io::{self, Write};
+
+use crate::host::{Host, HostState, Script};
+use crate::port::PortState;
+use crate::report::drift::{DriftKind, DriftReport};
+use crate::report::host_label;
+use crate::util::{escape_xml, format_rfc3339};
+use crate::NmapResults;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}\
+th{background:#eee}table.sortable th{cursor:pointer}\
+.up{color:#080}.down{color:#a00}\
+pre{background:#f6f6f6;padding:.5em;white-space:pre-wrap}";
+
+const SCRIPT: &str = "document.querySelectorAll('table.sortable th').forEach(function(th){\
+th.addEventListener('click',function(){\
+var table=th.closest('table'),body=table.tBodies[0],i=th.cellIndex,\
+asc=th.dataset.order!=='asc';th.dataset.order=asc?'asc':'desc';\
+Array.from(body.rows).sort(function(a,b){\
+var x=a.cells[i].textContent,y=b.cells[i].textContent,\
+n=parseFloat(x)-parseFloat(y);\
+return (isNaN(n)?x.localeCompare(y):n)*(asc?1:-1);\
+}).forEach(function(r){body.appendChild(r);});});});";
+
+///Writes an HTML report of `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html>")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>Nmap scan report</title>")?;
+    writeln!(w, "<style>{}</style>", STYLE)?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<h1>Nmap scan report</h1>")?;
+
+    write_metadata(results, w)?;
+    write_host_table(results, w)?;
+
+    writeln!(w, "<h2>Host details</h2>")?;
+    for host in results.hosts() {
+        write_host_details(host, w)?;
+    }
+
+    writeln!(w, "<script>{}</script>", SCRIPT)?;
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
+
+///Renders an HTML report of `results`.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("HTML report is valid UTF-8")
+}
+
+///Writes an HTML page listing the changes of a drift `report` to `w`.
+pub fn write_drift<W: Write>(report: &DriftReport, w: &mut W) -> io::Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html>")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>Drift report</title>")?;
+    writeln!(w, "<style>{}</style>", STYLE)?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<h1>Drift report</h1>")?;
+
+    writeln!(w, "<table class=\"metadata\">")?;
+    write_row(w, "Baseline", &format_rfc3339(report.baseline_time))?;
+    write_row(w, "Current", &format_rfc3339(report.current_time))?;
+    write_row(
+        w,
+        "Changes",
+        &format!(
+            "{} exposed, {} closed, {} changed",
+            report.count(DriftKind::Exposed),
+            report.count(DriftKind::Closed),
+            report.count(DriftKind::Changed)
+        ),
+    )?;
+    writeln!(w, "</table>")?;
+
+    writeln!(w, "<table class=\"sortable\">")?;
+    writeln!(
+        w,
+        "<thead><tr><th>Change</th><th>Host</th><th>Port</th><th>Before</th><th>After</th></tr></thead>"
+    )?;
+    writeln!(w, "<tbody>")?;
+    for change in report.changes() {
+        writeln!(
+            w,
+            "<tr><td>{}</td><td>{}</td><td>{}/{}</td><td>{}</td><td>{}</td></tr>",
+            change.kind,
+            escape_xml(&change.host),
+            change.port_number,
+            change.protocol,
+            escape_xml(change.before.as_deref().unwrap_or("")),
+            escape_xml(change.after.as_deref().unwrap_or(""))
+        )?;
+    }
+    writeln!(w, "</tbody>")?;
+    writeln!(w, "</table>")?;
+
+    writeln!(w, "<script>{}</script>", SCRIPT)?;
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
+
+///Renders an HTML page listing the changes of a drift `report`.
+pub fn drift_to_string(report: &DriftReport) -> String {
+    let mut out = Vec::new();
+    write_drift(report, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("HTML report is valid UTF-8")
+}
+
+fn write_metadata<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    let total = results.hosts().count();
+    let up = results
+        .hosts()
+        .filter(|h| h.status.state == HostState::Up)
+        .count();
+
+    writeln!(w, "<table class=\"metadata\">")?;
+    if let Some(version) = &results.nmap_version {
+        write_row(w, "Nmap version", &escape_xml(version))?;
+    }
+    if let Some(args) = &results.args {
+        write_row(w, "Command", &format!("<code>{}</code>", escape_xml(args)))?;
+    }
+    write_row(w, "Started", &format_rfc3339(results.scan_start_time))?;
+    if let Some(end) = results.scan_end_time {
+        write_row(w, "Finished", &format_rfc3339(end))?;
+    }
+    write_row(w, "Hosts", &format!("{} scanned, {} up", total, up))?;
+    writeln!(w, "</table>")
+}
+
+fn write_row<W: Write>(w: &mut W, name: &str, value: &str) -> io::Result<()> {
+    writeln!(w, "<tr><th>{}</th><td>{}</td></tr>", name, value)
+}
+
+fn write_host_table<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    writeln!(w, "<h2>Hosts</h2>")?;
+    writeln!(w, "<table class=\"sortable\">")?;
+    writeln!(
+        w,
+        "<thead><tr><th>Address</th><th>Hostname</th><th>State</th><th>Open ports</th></tr></thead>"
+    )?;
+    writeln!(w, "<tbody>")?;
+
+    for host in results.hosts() {
+        let address = host
+            .primary_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_default();
+        let name = host
+            .host_names()
+            .next()
+            .map(|h| h.name.as_str())
+            .unwrap_or("");
+        let open = host
+            .port_info
+            .ports()
+            .filter(|p| p.status.state == PortState::Open)
+            .count();
+
+        writeln!(
+            w,
+            "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>",
+            escape_xml(&address),
+            escape_xml(name),
+            host.status.state,
+            host.status.state,
+            open
+        )?;
+    }
+
+    writeln!(w, "</tbody>")?;
+    writeln!(w, "</table>")
+}
+
+fn write_host_details<W: Write>(host: &Host, w: &mut W) -> io::Result<()> {
+    writeln!(w, "<details>")?;
+    writeln!(
+        w,
+        "<summary>{} <span class=\"{}\">{}</span></summary>",
+        escape_xml(&host_label(host)),
+        host.status.state,
+        host.status.state
+    )?;
+
+    if host.port_info.ports().next().is_some() {
+        writeln!(w, "<table class=\"sortable\">")?;
+        writeln!(
+            w,
+            "<thead><tr><th>Port</th><th>Protocol</th><th>State</th><th>Service</th><th>Reason</th><th>Scripts</th></tr></thead>"
+        )?;
+        writeln!(w, "<tbody>")?;
+        for port in host.port_info.ports() {
+            let service = match &port.service_info {
+                Some(service) => match service.version_string() {
+                    Some(version) => format!("{} {}", service.name, version),
+                    None => service.name.clone(),
+                },
+                None => String::new(),
+            };
+            write!(
+                w,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>",
+                port.port_number,
+                port.protocol,
+                escape_xml(&port.status.state.to_string()),
+                escape_xml(&service),
+                escape_xml(&port.status.reason)
+            )?;
+            //Port scripts fold away in their row, so that sorting keeps
+            //them with their port.
+            if !port.scripts.is_empty() {
+                writeln!(w, "<details>")?;
+                writeln!(
+                    w,
+                    "<summary>{}</summary>",
+                    port.scripts
+                        .iter()
+                        .map(|s| escape_xml(&s.id))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+                for script in &port.scripts {
+                    write_script(script, w)?;
+                }
+                write!(w, "</details>")?;
+            }
+            writeln!(w, "</td></tr>")?;
+        }
+        writeln!(w, "</tbody>")?;
+        writeln!(w, "</table>")?;
+    }
+
+    for script in host.scripts() {
+        write_script(script, w)?;
+    }
+
+    writeln!(w, "</details>")
+}
+
+fn write_script<W: Write>(script: &Script, w: &mut W) -> io::Result<()> {
+    writeln!(w, "<h4>{}</h4>", escape_xml(&script.id))?;
+    writeln!(
+        w,
+        "<pre>{}</pre>",
+        escape_xml(script.output.trim_start_matches('\n'))
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn html_report_escapes_and_lists_hosts() {
+        let xml = r#"<nmaprun start="1588318812" args="nmap &lt;target&gt;" version="7.60">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="Apache httpd" version="2.4.7" extrainfo="Ubuntu" method="probed" conf="10"/><script id="http-title" output="Go &lt;b&gt;ahead&lt;/b&gt; &amp; ScanMe!"/></port>
+</ports>
+<hostscript><script id="banner" output="&lt;script&gt;alert(1)&lt;/script&gt;"/></hostscript>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+        let html = to_string(&results);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<code>nmap &lt;target&gt;</code>"));
+        assert!(html
+            .contains("<tr><td>45.33.32.156</td><td></td><td class=\"up\">up</td><td>1</td></tr>"));
+        assert!(html.contains("<pre>&lt;script&gt;alert(1)&lt;/script&gt;</pre>"));
+        assert!(html.contains("<td>http Apache httpd 2.4.7 (Ubuntu)</td>"));
+        assert!(html.contains("<summary>http-title</summary>"));
+        assert!(html.contains("<pre>Go &lt;b&gt;ahead&lt;/b&gt; &amp; ScanMe!</pre>"));
+        assert!(!html.contains("<b>ahead"));
+        assert!(!html.contains("<script>alert"));
+    }
+}
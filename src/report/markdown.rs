@@ -0,0 +1,307 @@
+//!GitHub-flavored Markdown summary.
+//!
+//!Lists every host that is up with a table of its open ports and an
+//!excerpt of the output of each script run on the host or its open ports,
+//!ready to paste into a ticket, a wiki page or a pentest report draft.
+use std::io::{self, Write};
+
+use crate::host::{Host, HostState, Script};
+use crate::port::PortState;
+use crate::report::drift::{DriftKind, DriftReport};
+use crate::report::host_label;
+use crate::util::format_rfc3339;
+use crate::NmapResults;
+
+///Maximum number of lines of script output quoted per script.
+const SCRIPT_EXCERPT_LINES: usize = 10;
+
+///Writes a Markdown summary of `results` to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    let total = results.hosts().count();
+    let up = results
+        .hosts()
+        .filter(|h| h.status.state == HostState::Up)
+        .count();
+
+    writeln!(w, "# Nmap scan report")?;
+    writeln!(w)?;
+    if let Some(args) = &results.args {
+        writeln!(w, "- **Command:** {}", code_span(args))?;
+    }
+    writeln!(
+        w,
+        "- **Started:** {}",
+        format_rfc3339(results.scan_start_time)
+    )?;
+    if let Some(end) = results.scan_end_time {
+        writeln!(w, "- **Finished:** {}", format_rfc3339(end))?;
+    }
+    writeln!(w, "- **Hosts:** {} scanned, {} up", total, up)?;
+
+    for host in results.hosts().filter(|h| h.status.state == HostState::Up) {
+        writeln!(w)?;
+        write_host(host, w)?;
+    }
+
+    Ok(())
+}
+
+///Renders a Markdown summary of `results`.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("Markdown report is valid UTF-8")
+}
+
+///Writes a Markdown table of the changes of a drift `report` to `w`.
+pub fn write_drift<W: Write>(report: &DriftReport, w: &mut W) -> io::Result<()> {
+    writeln!(w, "# Drift report")?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "- **Baseline:** {}",
+        format_rfc3339(report.baseline_time)
+    )?;
+    writeln!(w, "- **Current:** {}", format_rfc3339(report.current_time))?;
+    writeln!(
+        w,
+        "- **Changes:** {} exposed, {} closed, {} changed",
+        report.count(DriftKind::Exposed),
+        report.count(DriftKind::Closed),
+        report.count(DriftKind::Changed)
+    )?;
+    writeln!(w)?;
+
+    if report.changes().next().is_none() {
+        return writeln!(w, "No drift.");
+    }
+
+    writeln!(w, "| Change | Host | Port | Before | After |")?;
+    writeln!(w, "|--------|------|-----:|--------|-------|")?;
+    for change in report.changes() {
+        writeln!(
+            w,
+            "| {} | {} | {}/{} | {} | {} |",
+            change.kind,
+            escape_cell(&change.host),
+            change.port_number,
+            change.protocol,
+            escape_cell(change.before.as_deref().unwrap_or("")),
+            escape_cell(change.after.as_deref().unwrap_or(""))
+        )?;
+    }
+
+    Ok(())
+}
+
+///Renders a Markdown table of the changes of a drift `report`.
+pub fn drift_to_string(report: &DriftReport) -> String {
+    let mut out = Vec::new();
+    write_drift(report, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("Markdown report is valid UTF-8")
+}
+
+fn write_host<W: Write>(host: &Host, w: &mut W) -> io::Result<()> {
+    writeln!(w, "## {}", host_label(host))?;
+    writeln!(w)?;
+
+    let open = host
+        .port_info
+        .ports()
+        .filter(|p| p.status.state == PortState::Open)
+        .collect::<Vec<_>>();
+
+    if open.is_empty() {
+        writeln!(w, "No open ports.")?;
+    } else {
+        writeln!(w, "| Port | Protocol | Service |")?;
+        writeln!(w, "|-----:|----------|---------|")?;
+        for port in &open {
+            let service = match &port.service_info {
+                Some(service) => match service.version_string() {
+                    Some(version) => format!("{} {}", service.name, version),
+                    None => service.name.clone(),
+                },
+                None => String::new(),
+            };
+            writeln!(
+                w,
+                "| {} | {} | {} |",
+                port.port_number,
+                port.protocol,
+                escape_cell(&service)
+            )?;
+        }
+    }
+
+    for port in open.iter().filter(|p| !p.scripts.is_empty()) {
+        writeln!(w)?;
+        writeln!(
+            w,
+            "**{}/{} script results**",
+            port.port_number, port.protocol
+        )?;
+        for script in &port.scripts {
+            writeln!(w)?;
+            write_script(script, w)?;
+        }
+    }
+
+    if host.scripts().next().is_some() {
+        writeln!(w)?;
+        writeln!(w, "**Host script results**")?;
+        for script in host.scripts() {
+            writeln!(w)?;
+            write_script(script, w)?;
+        }
+    }
+
+    Ok(())
+}
+
+//`text` as inline code, delimited like the script fences by more backticks
+//than it contains in a row. Text starting or ending with a backtick is
+//padded with a space, which Markdown strips.
+fn code_span(text: &str) -> String {
+    let delimiter = "`".repeat(longest_backtick_run(text) + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{} {} {}", delimiter, text, delimiter)
+    } else {
+        format!("{}{}{}", delimiter, text, delimiter)
+    }
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn write_script<W: Write>(script: &Script, w: &mut W) -> io::Result<()> {
+    let lines = script
+        .output
+        .trim_start_matches('\n')
+        .lines()
+        .collect::<Vec<_>>();
+    let excerpt = &lines[..lines.len().min(SCRIPT_EXCERPT_LINES)];
+    let more = lines.len() - excerpt.len();
+
+    //The fence must be longer than any run of backticks in the output, or
+    //the output could close it.
+    let longest_run = excerpt
+        .iter()
+        .map(|line| longest_backtick_run(line))
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    writeln!(w, "`{}`:", script.id)?;
+    writeln!(w)?;
+    writeln!(w, "{}", fence)?;
+    for line in excerpt {
+        writeln!(w, "{}", line)?;
+    }
+    if more > 0 {
+        writeln!(w, "[{} more lines]", more)?;
+    }
+    writeln!(w, "{}", fence)
+}
+
+//Cells are single lines, so line breaks become `<br>`.
+fn escape_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn markdown_lists_open_ports_of_up_hosts() {
+        let xml = r#"<nmaprun start="1588318812" args="nmap -sS --script-args 'x=`id`' 10.0.0.0/30">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH|patched" extrainfo="multi&#xa;line" method="probed" conf="10"/><script id="ssh-banner" output="```&#xa;SSH-2.0-OpenSSH"/></port>
+<port protocol="tcp" portid="23"><state state="closed" reason="reset" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+<hostscript><script id="smb2-time" output="&#xa;  date: 2021-06-12T03:17:58"/></hostscript>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "# Nmap scan report\n\
+             \n\
+             - **Command:** ``nmap -sS --script-args 'x=`id`' 10.0.0.0/30``\n\
+             - **Started:** 2020-05-01T07:40:12Z\n\
+             - **Hosts:** 2 scanned, 1 up\n\
+             \n\
+             ## 10.0.0.1\n\
+             \n\
+             | Port | Protocol | Service |\n\
+             |-----:|----------|---------|\n\
+             | 22 | tcp | ssh OpenSSH\\|patched (multi<br>line) |\n\
+             \n\
+             **22/tcp script results**\n\
+             \n\
+             `ssh-banner`:\n\
+             \n\
+             ````\n\
+             ```\n\
+             SSH-2.0-OpenSSH\n\
+             ````\n\
+             \n\
+             **Host script results**\n\
+             \n\
+             `smb2-time`:\n\
+             \n\
+             ```\n\
+             \x20 date: 2021-06-12T03:17:58\n\
+             ```\n"
+        );
+    }
+
+    #[test]
+    fn markdown_drift_table() {
+        use crate::port::PortProtocol;
+        use crate::report::drift::Drift;
+
+        let report = DriftReport {
+            baseline_time: 1588318812,
+            current_time: 1588405212,
+            changes: vec![Drift {
+                kind: DriftKind::Changed,
+                host: "10.0.0.1".to_string(),
+                protocol: PortProtocol::Tcp,
+                port_number: 22,
+                before: Some("ssh OpenSSH 7.4".to_string()),
+                after: Some("ssh OpenSSH 8.0".to_string()),
+            }],
+        };
+
+        assert_eq!(
+            drift_to_string(&report),
+            "# Drift report\n\
+             \n\
+             - **Baseline:** 2020-05-01T07:40:12Z\n\
+             - **Current:** 2020-05-02T07:40:12Z\n\
+             - **Changes:** 0 exposed, 0 closed, 1 changed\n\
+             \n\
+             | Change | Host | Port | Before | After |\n\
+             |--------|------|-----:|--------|-------|\n\
+             | changed | 10.0.0.1 | 22/tcp | ssh OpenSSH 7.4 | ssh OpenSSH 8.0 |\n"
+        );
+    }
+
+    #[test]
+    fn code_spans() {
+        assert_eq!(code_span("nmap -sS"), "`nmap -sS`");
+        assert_eq!(code_span("a ``b`` c"), "```a ``b`` c```");
+        assert_eq!(code_span("`id`"), "`` `id` ``");
+    }
+}
@@ -0,0 +1,27 @@
+//!Human-readable reports generated from parsed results.
+//!
+//!Like the [`export`](../export/index.html) module, every report exposes a
+//!`write()` function taking any
+//![`std::io::Write`](https://doc.rust-lang.org/std/io/trait.Write.html)
+//!implementation and a `to_string()` convenience wrapper.
+use crate::host::Host;
+
+pub mod drift;
+pub mod html;
+pub mod markdown;
+pub mod ndiff;
+pub mod normal;
+
+///Returns the label Nmap uses for a host in its reports, e.g.
+///`scanme.nmap.org (45.33.32.156)`.
+pub(crate) fn host_label(host: &Host) -> String {
+    let ip = host.primary_ip().map(|ip| ip.to_string());
+    let name = host.host_names().next().map(|h| h.name.as_str());
+
+    match (name, ip) {
+        (Some(name), Some(ip)) => format!("{} ({})", name, ip),
+        (Some(name), None) => name.to_string(),
+        (None, Some(ip)) => ip,
+        (None, None) => "unknown host".to_string(),
+    }
+}
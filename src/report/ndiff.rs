@@ -0,0 +1,231 @@
+//!Text diff in the format of Nmap's `ndiff` tool.
+//!
+//!Renders a [`ScanDiff`](../../diff/struct.ScanDiff.html) the way
+//!`ndiff` does without `--verbose`: lines starting with `-` come from the
+//!old scan, lines starting with `+` from the new one, and unchanged context
+//!lines start with a space. Only hosts with changes are listed, sorted by
+//!address, each with the rows of its changed ports.
+use std::io::{self, Write};
+
+use crate::diff::ScanDiff;
+use crate::host::{Host, HostState};
+use crate::port::Port;
+use crate::report::host_label;
+use crate::util::start_comment;
+use crate::NmapResults;
+
+///Writes `diff` in `ndiff` text format to `w`.
+pub fn write<W: Write>(diff: &ScanDiff, w: &mut W) -> io::Result<()> {
+    let old_header = scan_header(diff.old);
+    let new_header = scan_header(diff.new);
+    if old_header != new_header {
+        if let Some(header) = old_header {
+            writeln!(w, "-{}", header)?;
+        }
+        if let Some(header) = new_header {
+            writeln!(w, "+{}", header)?;
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for host_diff in diff.changed_hosts() {
+        let (old, new) = (host_diff.old, host_diff.new);
+        let mut rows = Vec::new();
+        for port in host_diff.opened() {
+            if let Some(old_port) = same_port(old, port) {
+                rows.push(('-', old_port));
+            }
+            rows.push(('+', *port));
+        }
+        for port in host_diff.closed() {
+            rows.push(('-', *port));
+            if let Some(new_port) = same_port(new, port) {
+                rows.push(('+', new_port));
+            }
+        }
+        for change in host_diff.service_changes() {
+            rows.push(('-', change.old));
+            rows.push(('+', change.new));
+        }
+        //Keeps the `-` row of a port before its `+` row.
+        rows.sort_by_key(|(_, p)| (p.port_number, p.protocol.to_string()));
+        blocks.push((new, ' ', Vec::new(), rows));
+    }
+    for host in diff.new_hosts() {
+        let rows = host.port_info.open_ports().map(|p| ('+', p)).collect();
+        match diff
+            .old
+            .host_by_addr(&host.primary_ip().expect("diffed hosts have an IP"))
+        {
+            Some(old) => blocks.push((host, ' ', status_change(old, host), rows)),
+            None => blocks.push((host, '+', vec![('+', HostState::Up)], rows)),
+        }
+    }
+    for host in diff.removed_hosts() {
+        let rows = host.port_info.open_ports().map(|p| ('-', p)).collect();
+        match diff
+            .new
+            .host_by_addr(&host.primary_ip().expect("diffed hosts have an IP"))
+        {
+            Some(new) => blocks.push((host, ' ', status_change(host, new), rows)),
+            None => blocks.push((host, '-', vec![('-', HostState::Up)], rows)),
+        }
+    }
+    blocks.sort_by_key(|(host, ..)| host.primary_ip());
+
+    for (host, marker, status, rows) in blocks {
+        writeln!(w)?;
+        writeln!(w, "{}{}:", marker, host_label(host))?;
+        for (marker, state) in status {
+            writeln!(w, "{}Host is {}.", marker, state)?;
+        }
+        write_port_rows(&rows, marker, w)?;
+    }
+
+    Ok(())
+}
+
+///Renders `diff` in `ndiff` text format.
+pub fn to_string(diff: &ScanDiff) -> String {
+    let mut out = Vec::new();
+    write(diff, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("ndiff output is valid UTF-8")
+}
+
+//`Nmap 7.80 scan initiated Tue May 12 15:08:55 2020 as: nmap ...`
+fn scan_header(results: &NmapResults) -> Option<String> {
+    start_comment(results).map(|line| line.trim_start_matches("# ").to_string())
+}
+
+fn same_port<'a>(host: &'a Host, port: &Port) -> Option<&'a Port> {
+    host.port_info
+        .get_port(port.protocol.clone(), port.port_number)
+}
+
+fn status_change(old: &Host, new: &Host) -> Vec<(char, HostState)> {
+    vec![
+        ('-', old.status.state.clone()),
+        ('+', new.status.state.clone()),
+    ]
+}
+
+//Prints the rows under a `PORT STATE SERVICE VERSION` header, which is a
+//context line unless the whole host was added or removed.
+fn write_port_rows<W: Write>(rows: &[(char, &Port)], marker: char, w: &mut W) -> io::Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let cells = rows
+        .iter()
+        .map(|(marker, p)| {
+            let service = p.service_info.as_ref();
+            (
+                *marker,
+                format!("{}/{}", p.port_number, p.protocol),
+                p.status.state.to_string(),
+                match service {
+                    Some(s) => match &s.tunnel {
+                        Some(tunnel) => format!("{}/{}", tunnel, s.name),
+                        None => s.name.clone(),
+                    },
+                    None => "unknown".to_string(),
+                },
+                service.and_then(|s| s.version_string()).unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let width = |column: fn(&(char, String, String, String, String)) -> usize, header: &str| {
+        cells.iter().map(column).fold(header.len(), usize::max)
+    };
+    let port_width = width(|c| c.1.len(), "PORT");
+    let state_width = width(|c| c.2.len(), "STATE");
+    let service_width = width(|c| c.3.len(), "SERVICE");
+
+    let line = |marker: char, port: &str, state: &str, service: &str, version: &str| {
+        format!(
+            "{}{:<pw$} {:<sw$} {:<vw$} {}",
+            marker,
+            port,
+            state,
+            service,
+            version,
+            pw = port_width,
+            sw = state_width,
+            vw = service_width
+        )
+        .trim_end()
+        .to_string()
+    };
+
+    writeln!(w, "{}", line(marker, "PORT", "STATE", "SERVICE", "VERSION"))?;
+    for (marker, port, state, service, version) in &cells {
+        writeln!(w, "{}", line(*marker, port, state, service, version))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OLD: &str = r#"<nmaprun start="1589296135" version="7.80" args="nmap -sV 10.0.0.0/30">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    const NEW: &str = r#"<nmaprun start="1589382535" version="7.80" args="nmap -sV 10.0.0.0/30">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="closed" reason="reset" reason_ttl="53"/><service name="telnet" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" tunnel="ssl" method="table" conf="3"/></port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn ndiff_text() {
+        let old = NmapResults::parse(OLD).unwrap();
+        let new = NmapResults::parse(NEW).unwrap();
+
+        assert_eq!(
+            to_string(&ScanDiff::between(&old, &new)),
+            "-Nmap 7.80 scan initiated Tue May 12 15:08:55 2020 as: nmap -sV 10.0.0.0/30\n\
+             +Nmap 7.80 scan initiated Wed May 13 15:08:55 2020 as: nmap -sV 10.0.0.0/30\n\
+             \n \
+             10.0.0.1:\n \
+             PORT   STATE  SERVICE VERSION\n\
+             -22/tcp open   ssh     OpenSSH 7.4\n\
+             +22/tcp open   ssh     OpenSSH 8.0\n\
+             -23/tcp open   telnet\n\
+             +23/tcp closed telnet\n\
+             \n \
+             10.0.0.2:\n\
+             -Host is up.\n\
+             +Host is down.\n\
+             \n\
+             +10.0.0.3:\n\
+             +Host is up.\n\
+             +PORT    STATE SERVICE  VERSION\n\
+             +443/tcp open  ssl/http\n"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//!Nmap normal (`-oN`) output.
+//!
+//!Produces the per-host port tables and script blocks printed by the Nmap
+//!console, which is handy to get a readable summary out of an archive that
+//!only kept XML. Times in the comment lines are printed in UTC.
+use std::io::{self, Write};
+
+use crate::host::{Host, HostState, Script};
+use crate::report::host_label;
+use crate::util::{done_comment, start_comment};
+use crate::NmapResults;
+
+///Writes `results` in Nmap normal output format to `w`.
+pub fn write<W: Write>(results: &NmapResults, w: &mut W) -> io::Result<()> {
+    if let Some(line) = start_comment(results) {
+        writeln!(w, "{}", line)?;
+    }
+
+    for host in results.hosts() {
+        write_host(host, w)?;
+    }
+
+    if let Some(line) = done_comment(results) {
+        writeln!(w, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+///Renders `results` in Nmap normal output format.
+pub fn to_string(results: &NmapResults) -> String {
+    let mut out = Vec::new();
+    write(results, &mut out).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("normal output is valid UTF-8")
+}
+
+fn write_host<W: Write>(host: &Host, w: &mut W) -> io::Result<()> {
+    if host.status.state != HostState::Up {
+        return writeln!(
+            w,
+            "Nmap scan report for {} [host {}]",
+            host_label(host),
+            host.status.state
+        );
+    }
+
+    writeln!(w, "Nmap scan report for {}", host_label(host))?;
+    writeln!(w, "Host is up.")?;
+
+    for extra in host.port_info.extra_ports() {
+        writeln!(w, "Not shown: {} {} ports", extra.count, extra.state)?;
+    }
+
+    write_port_table(host, w)?;
+
+    if let Some(mac) = host.mac() {
+        writeln!(
+            w,
+            "MAC Address: {} ({})",
+            mac,
+            host.mac_vendor().unwrap_or("Unknown")
+        )?;
+    }
+
+    if host.scripts().next().is_some() {
+        writeln!(w)?;
+        writeln!(w, "Host script results:")?;
+        for script in host.scripts() {
+            write_script(script, w)?;
+        }
+    }
+
+    writeln!(w)
+}
+
+fn write_port_table<W: Write>(host: &Host, w: &mut W) -> io::Result<()> {
+    let rows = host
+        .port_info
+        .ports()
+        .map(|p| {
+            let service = p.service_info.as_ref();
+            (
+                format!("{}/{}", p.port_number, p.protocol),
+                p.status.state.to_string(),
+                match service {
+                    Some(s) => match &s.tunnel {
+                        Some(tunnel) => format!("{}/{}", tunnel, s.name),
+                        None => s.name.clone(),
+                    },
+                    None => "unknown".to_string(),
+                },
+                service.and_then(|s| s.version_string()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let port_width = rows
+        .iter()
+        .map(|r| r.0.len())
+        .fold("PORT".len(), usize::max);
+    let state_width = rows
+        .iter()
+        .map(|r| r.1.len())
+        .fold("STATE".len(), usize::max);
+    let with_version = rows.iter().any(|r| r.3.is_some());
+
+    if with_version {
+        let service_width = rows
+            .iter()
+            .map(|r| r.2.len())
+            .fold("SERVICE".len(), usize::max);
+        writeln!(
+            w,
+            "{:<pw$} {:<sw$} {:<vw$} VERSION",
+            "PORT",
+            "STATE",
+            "SERVICE",
+            pw = port_width,
+            sw = state_width,
+            vw = service_width
+        )?;
+        for ((port, state, service, version), p) in rows.into_iter().zip(host.port_info.ports()) {
+            writeln!(
+                w,
+                "{:<pw$} {:<sw$} {:<vw$} {}",
+                port,
+                state,
+                service,
+                version.unwrap_or_default(),
+                pw = port_width,
+                sw = state_width,
+                vw = service_width
+            )?;
+            for script in &p.scripts {
+                write_script(script, w)?;
+            }
+        }
+    } else {
+        writeln!(
+            w,
+            "{:<pw$} {:<sw$} SERVICE",
+            "PORT",
+            "STATE",
+            pw = port_width,
+            sw = state_width
+        )?;
+        for ((port, state, service, _), p) in rows.into_iter().zip(host.port_info.ports()) {
+            writeln!(
+                w,
+                "{:<pw$} {:<sw$} {}",
+                port,
+                state,
+                service,
+                pw = port_width,
+                sw = state_width
+            )?;
+            for script in &p.scripts {
+                write_script(script, w)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+//Nmap prefixes script output with `|` and marks the last line with `|_`.
+fn write_script<W: Write>(script: &Script, w: &mut W) -> io::Result<()> {
+    let mut lines = script.output.lines().collect::<Vec<_>>();
+    if lines.is_empty() {
+        lines.push("");
+    }
+    let last = lines.len() - 1;
+
+    for (i, line) in lines.iter().enumerate() {
+        let marker = if i == last { "|_" } else { "| " };
+        if i == 0 {
+            writeln!(w, "{}{}: {}", marker, script.id, line)?;
+        } else {
+            writeln!(w, "{}{}", marker, line)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normal_version_column() {
+        let xml = r#"<nmaprun start="1589292535">
+<host><status state="up" reason="syn-ack" reason_ttl="0"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="ssh" product="OpenSSH" version="7.4" extrainfo="protocol 2.0" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="8080"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="http-proxy" method="table" conf="3"/><script id="http-title" output="Site doesn&apos;t have a title."/></port>
+</ports>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "Nmap scan report for 10.0.0.1\n\
+             Host is up.\n\
+             PORT     STATE SERVICE    VERSION\n\
+             22/tcp   open  ssh        OpenSSH 7.4 (protocol 2.0)\n\
+             8080/tcp open  http-proxy \n\
+             |_http-title: Site doesn't have a title.\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn normal_host_report() {
+        let xml = r#"<nmaprun start="1589292535">
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.59.138" addrtype="ipv4"/>
+<address addr="00:0C:29:71:23:2B" addrtype="mac" vendor="VMware"/>
+<ports><extraports state="closed" count="998"/>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="3389"><state state="filtered" reason="no-response" reason_ttl="0"/><service name="ms-wbt-server" method="table" conf="3"/></port>
+</ports>
+<hostscript><script id="smb2-time" output="&#xa;  date: 2021-06-12T03:17:58&#xa;  start_date: N/A"/></hostscript>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="192.168.59.139" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+        let results = NmapResults::parse(xml).unwrap();
+
+        assert_eq!(
+            to_string(&results),
+            "Nmap scan report for 192.168.59.138\n\
+             Host is up.\n\
+             Not shown: 998 closed ports\n\
+             PORT     STATE    SERVICE\n\
+             22/tcp   open     ssh\n\
+             3389/tcp filtered ms-wbt-server\n\
+             MAC Address: 00:0C:29:71:23:2B (VMware)\n\
+             \n\
+             Host script results:\n\
+             | smb2-time: \n\
+             |   date: 2021-06-12T03:17:58\n\
+             |_  start_date: N/A\n\
+             \n\
+             Nmap scan report for 192.168.59.139 [host down]\n"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//!Resuming interrupted scans.
+//!
+//![`remaining()`](fn.remaining.html) compares the targets a scan was
+//!started with to the hosts it reported and returns the targets never
+//!reported, as a list that can be passed back to Nmap with `-iL`. This
+//!approximates `--resume` for workflows that only keep the XML output,
+//!usually read with
+//![`NmapResults::parse_partial()`](../struct.NmapResults.html#method.parse_partial)
+//!since an interrupted scan leaves the document unfinished.
+//!
+//!Targets are IP addresses, networks in CIDR notation, Nmap octet ranges
+//!such as `10.0.0-3.1-254` or `192.168.1.*`, and host names. Addresses are
+//!listed as the fewest networks covering them. Host names count as reported
+//!if a host has that name.
+//!
+//!Nmap leaves hosts that are down out of its output unless run with `-v`,
+//!so such hosts are listed as remaining too.
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::host::Address;
+use crate::net::IpNetwork;
+use crate::{Error, NmapResults};
+
+///Returns the targets of `targets` that `results` has not reported, in
+///target order.
+pub fn remaining(results: &NmapResults, targets: &[&str]) -> Result<Vec<String>, Error> {
+    let mut reported_v4 = BTreeSet::new();
+    let mut reported_v6 = BTreeSet::new();
+    for host in results.hosts() {
+        for address in host.addresses() {
+            match address {
+                Address::IpAddr(IpAddr::V4(ip)) => {
+                    reported_v4.insert(u32::from(*ip) as u128);
+                }
+                Address::IpAddr(IpAddr::V6(ip)) => {
+                    reported_v6.insert(u128::from(*ip));
+                }
+                Address::MacAddr(_) => {}
+            }
+        }
+    }
+
+    let mut remaining = Vec::new();
+    for target in targets.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        match parse_target(target)? {
+            Target::Name(name) => {
+                let reported = results
+                    .hosts()
+                    .any(|h| h.host_names().any(|n| n.name.eq_ignore_ascii_case(name)));
+                if !reported {
+                    remaining.push(name.to_string());
+                }
+            }
+            Target::V4(ranges) => {
+                for (start, end) in ranges {
+                    for (start, end) in subtract(start, end, &reported_v4) {
+                        remaining.extend(networks(start, end, 32));
+                    }
+                }
+            }
+            Target::V6(start, end) => {
+                for (start, end) in subtract(start, end, &reported_v6) {
+                    remaining.extend(networks(start, end, 128));
+                }
+            }
+        }
+    }
+    Ok(remaining)
+}
+
+//Addresses are handled as inclusive ranges of integers.
+enum Target<'a> {
+    Name(&'a str),
+    V4(Vec<(u128, u128)>),
+    V6(u128, u128),
+}
+
+fn parse_target(target: &str) -> Result<Target<'_>, Error> {
+    let invalid = || Error::InvalidData(format!("invalid target `{}`", target));
+
+    if target.contains('/') || target.parse::<IpAddr>().is_ok() {
+        let network = target.parse::<IpNetwork>().map_err(|_| invalid())?;
+        return Ok(match network.network() {
+            IpAddr::V4(ip) => {
+                let start = u32::from(ip) as u128;
+                Target::V4(vec![(
+                    start,
+                    start | host_mask(32 - network.prefix_len() as u32),
+                )])
+            }
+            IpAddr::V6(ip) => {
+                let start = u128::from(ip);
+                Target::V6(start, start | host_mask(128 - network.prefix_len() as u32))
+            }
+        });
+    }
+
+    let octets = target.split('.').collect::<Vec<_>>();
+    let is_range = octets.len() == 4
+        && octets
+            .iter()
+            .all(|o| !o.is_empty() && o.chars().all(|c| c.is_ascii_digit() || "*,-".contains(c)));
+    if !is_range {
+        if target.contains(':') {
+            return Err(invalid());
+        }
+        return Ok(Target::Name(target));
+    }
+
+    let octets = octets
+        .iter()
+        .map(|o| parse_octet_range(o).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+    //One range of the last octet per combination of the first three.
+    let mut ranges = Vec::new();
+    for &(a_start, a_end) in &octets[0] {
+        for a in a_start..=a_end {
+            for &(b_start, b_end) in &octets[1] {
+                for b in b_start..=b_end {
+                    for &(c_start, c_end) in &octets[2] {
+                        for c in c_start..=c_end {
+                            for &(d_start, d_end) in &octets[3] {
+                                let prefix = u32::from(Ipv4Addr::new(a, b, c, 0)) as u128;
+                                ranges.push((prefix + d_start as u128, prefix + d_end as u128));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(Target::V4(ranges))
+}
+
+//`*`, `5`, `1-20` or a comma separated list of those.
+fn parse_octet_range(octet: &str) -> Option<Vec<(u8, u8)>> {
+    octet
+        .split(',')
+        .map(|part| match part {
+            "*" => Some((0, 255)),
+            _ => match part.split_once('-') {
+                Some((start, end)) => {
+                    let start = if start.is_empty() {
+                        0
+                    } else {
+                        start.parse().ok()?
+                    };
+                    let end = if end.is_empty() {
+                        255
+                    } else {
+                        end.parse().ok()?
+                    };
+                    if start <= end {
+                        Some((start, end))
+                    } else {
+                        None
+                    }
+                }
+                None => part.parse().ok().map(|o| (o, o)),
+            },
+        })
+        .collect()
+}
+
+//Removes the `reported` addresses from the range `start..=end`.
+fn subtract(start: u128, end: u128, reported: &BTreeSet<u128>) -> Vec<(u128, u128)> {
+    let mut ranges = Vec::new();
+    let mut next = Some(start);
+    for &addr in reported.range(start..=end) {
+        if let Some(from) = next {
+            if addr > from {
+                ranges.push((from, addr - 1));
+            }
+        }
+        next = addr.checked_add(1);
+    }
+    if let Some(from) = next.filter(|&from| from <= end) {
+        ranges.push((from, end));
+    }
+    ranges
+}
+
+//The fewest networks covering `start..=end`, single addresses without a
+//prefix length.
+fn networks(start: u128, end: u128, bits: u32) -> Vec<String> {
+    let mut networks = Vec::new();
+    let mut start = start;
+    loop {
+        //The largest block aligned on `start` that does not go past `end`.
+        let mut block_bits = start.trailing_zeros().min(bits);
+        while block_bits > 0 && start | host_mask(block_bits) > end {
+            block_bits -= 1;
+        }
+
+        let addr = if bits == 32 {
+            IpAddr::V4(Ipv4Addr::from(start as u32))
+        } else {
+            IpAddr::V6(Ipv6Addr::from(start))
+        };
+        if block_bits == 0 {
+            networks.push(addr.to_string());
+        } else {
+            networks.push(format!("{}/{}", addr, bits - block_bits));
+        }
+
+        let last = start | host_mask(block_bits);
+        if last >= end {
+            return networks;
+        }
+        start = last + 1;
+    }
+}
+
+fn host_mask(host_bits: u32) -> u128 {
+    1u128
+        .checked_shl(host_bits)
+        .map_or(u128::MAX, |size| size - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PARTIAL: &str = r#"<?xml version="1.0"?>
+<nmaprun scanner="nmap" args="nmap 10.0.0.0/28 db.example.com web.example.com" start="1588318812" version="7.80">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.0" addrtype="ipv4"/>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<hostnames><hostname name="web.example.com" type="user"/></hostnames>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.9" addrtype="ipv4"/>
+<ports><port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/>"#;
+
+    #[test]
+    fn remaining_targets() {
+        let results = NmapResults::parse_partial(PARTIAL).unwrap();
+        assert_eq!(results.hosts().count(), 2);
+
+        let targets = ["10.0.0.0/28", "db.example.com", "web.example.com"];
+        assert_eq!(
+            remaining(&results, &targets).unwrap(),
+            [
+                "10.0.0.1",
+                "10.0.0.2/31",
+                "10.0.0.4",
+                "10.0.0.6/31",
+                "10.0.0.8/29",
+                "db.example.com"
+            ]
+        );
+        assert_eq!(
+            remaining(&results, &["10.0.0-1.4-5", "2001:db8::/127"]).unwrap(),
+            ["10.0.0.4", "10.0.1.4/31", "2001:db8::/127"]
+        );
+        assert!(remaining(&results, &["10.0.0.300"]).is_err());
+    }
+}
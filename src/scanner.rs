@@ -0,0 +1,753 @@
+//!Running Nmap and parsing its output.
+//!
+//![`Scanner`](struct.Scanner.html) builds an Nmap command line, runs the
+//!`nmap` binary with XML written to standard output (`-oX -`) and parses
+//!the result:
+//!
+//!```no_run
+//!use nmap_xml_parser::scanner::Scanner;
+//!
+//!let results = Scanner::new()
+//!    .target("scanme.nmap.org")
+//!    .ports("22,80,443")
+//!    .service_detection(true)
+//!    .run()
+//!    .unwrap();
+//!```
+//!
+//!If Nmap exits with an error, its standard error output is returned in
+//![`Error::ScanFailed`](../enum.Error.html#variant.ScanFailed).
+//!
+//![`Scanner::spawn()`](struct.Scanner.html#method.spawn) runs the scan in
+//!the background instead. The [`RunningScan`](struct.RunningScan.html) it
+//!returns yields hosts as Nmap finishes them, and can be interrupted, by
+//!the caller or after a [`timeout`](struct.Scanner.html#method.timeout),
+//!keeping the hosts found so far. An
+//![`InterruptHandle`](struct.InterruptHandle.html) interrupts it from
+//!another thread while this one waits for hosts. The progress Nmap reports with
+//![`stats_every()`](struct.Scanner.html#method.stats_every) comes through a
+//!separate channel. Its methods block; async code can drive it from a
+//!blocking task.
+//!
+//!Requires the `scanner` feature.
+use std::ffi::OsString;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use roxmltree::{Document, Node};
+
+use crate::host::Host;
+use crate::util::{node_attr_as_string, parse_node_attr};
+use crate::{Error, NmapResults};
+
+///Builds and runs an Nmap scan.
+#[derive(Clone, Debug)]
+pub struct Scanner {
+    binary: OsString,
+    targets: Vec<String>,
+    ports: Option<String>,
+    top_ports: Option<u32>,
+    service_detection: bool,
+    os_detection: bool,
+    scripts: Vec<String>,
+    timing: Option<u8>,
+    extra_args: Vec<String>,
+    timeout: Option<Duration>,
+    stats_every: Option<Duration>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner {
+            binary: OsString::from("nmap"),
+            targets: Vec::new(),
+            ports: None,
+            top_ports: None,
+            service_detection: false,
+            os_detection: false,
+            scripts: Vec::new(),
+            timing: None,
+            extra_args: Vec::new(),
+            timeout: None,
+            stats_every: None,
+        }
+    }
+}
+
+impl Scanner {
+    ///Creates a scanner running `nmap` from the `PATH`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Sets the Nmap binary to run.
+    pub fn binary<S: Into<OsString>>(mut self, binary: S) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    ///Adds a target: a host name, an IP address or a network.
+    pub fn target(mut self, target: &str) -> Self {
+        self.targets.push(target.to_string());
+        self
+    }
+
+    ///Adds targets.
+    pub fn targets<I, S>(mut self, targets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.targets
+            .extend(targets.into_iter().map(|t| t.as_ref().to_string()));
+        self
+    }
+
+    ///Sets the ports to scan, in Nmap's `-p` syntax, e.g. `22,80,1000-2000`
+    ///or `U:53,T:443`.
+    pub fn ports(mut self, ports: &str) -> Self {
+        self.ports = Some(ports.to_string());
+        self
+    }
+
+    ///Scans the `n` most common ports (`--top-ports`).
+    pub fn top_ports(mut self, n: u32) -> Self {
+        self.top_ports = Some(n);
+        self
+    }
+
+    ///Enables service and version detection (`-sV`).
+    pub fn service_detection(mut self, enabled: bool) -> Self {
+        self.service_detection = enabled;
+        self
+    }
+
+    ///Enables OS detection (`-O`), which requires privileges.
+    pub fn os_detection(mut self, enabled: bool) -> Self {
+        self.os_detection = enabled;
+        self
+    }
+
+    ///Adds a script, category or script expression to run (`--script`).
+    pub fn script(mut self, script: &str) -> Self {
+        self.scripts.push(script.to_string());
+        self
+    }
+
+    ///Sets the timing template (`-T0` to `-T5`).
+    pub fn timing(mut self, template: u8) -> Self {
+        self.timing = Some(template);
+        self
+    }
+
+    ///Adds an argument passed to Nmap as is.
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.extra_args.push(arg.to_string());
+        self
+    }
+
+    ///Interrupts a scan started with [`spawn()`](#method.spawn) once
+    ///`timeout` has elapsed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    ///Makes Nmap report the progress of the scan every `interval`
+    ///(`--stats-every`), see
+    ///[`RunningScan::progress()`](struct.RunningScan.html#method.progress).
+    pub fn stats_every(mut self, interval: Duration) -> Self {
+        self.stats_every = Some(interval);
+        self
+    }
+
+    ///Returns the arguments Nmap is run with, targets last.
+    pub fn args(&self) -> Result<Vec<String>, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::InvalidData("no targets to scan".to_string()));
+        }
+        if let Some(timing) = self.timing.filter(|&t| t > 5) {
+            return Err(Error::InvalidData(format!(
+                "invalid timing template `{}`",
+                timing
+            )));
+        }
+
+        let mut args = vec!["-oX".to_string(), "-".to_string()];
+        if self.service_detection {
+            args.push("-sV".to_string());
+        }
+        if self.os_detection {
+            args.push("-O".to_string());
+        }
+        if let Some(ports) = &self.ports {
+            args.push("-p".to_string());
+            args.push(ports.clone());
+        }
+        if let Some(n) = self.top_ports {
+            args.push("--top-ports".to_string());
+            args.push(n.to_string());
+        }
+        if !self.scripts.is_empty() {
+            args.push("--script".to_string());
+            args.push(self.scripts.join(","));
+        }
+        if let Some(timing) = self.timing {
+            args.push(format!("-T{}", timing));
+        }
+        if let Some(interval) = self.stats_every {
+            args.push("--stats-every".to_string());
+            args.push(format!("{}ms", interval.as_millis().max(1)));
+        }
+        args.extend(self.extra_args.iter().cloned());
+        args.push("--".to_string());
+        args.extend(self.targets.iter().cloned());
+        Ok(args)
+    }
+
+    ///Returns the command running the scan, for callers that need to
+    ///manage the process themselves.
+    pub fn command(&self) -> Result<Command, Error> {
+        let mut command = Command::new(&self.binary);
+        command.args(self.args()?);
+        Ok(command)
+    }
+
+    ///Runs the scan and parses its results. Blocks until Nmap exits.
+    pub fn run(&self) -> Result<NmapResults, Error> {
+        let output = self
+            .command()?
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(Error::ScanFailed {
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        let xml = String::from_utf8(output.stdout)
+            .map_err(|_| Error::InvalidData("Nmap output is not valid UTF-8".to_string()))?;
+        NmapResults::parse(&xml)
+    }
+
+    ///Starts the scan in the background.
+    pub fn spawn(&self) -> Result<RunningScan, Error> {
+        let mut child = self
+            .command()?
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        //Nmap blocks if nobody reads its standard error.
+        let stderr = thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        });
+
+        let handle = InterruptHandle {
+            child: Arc::new(Mutex::new(child)),
+            interrupted: Arc::new(AtomicBool::new(false)),
+        };
+        if let Some(timeout) = self.timeout {
+            let InterruptHandle { child, interrupted } = handle.clone();
+            let deadline = Instant::now() + timeout;
+            thread::spawn(move || loop {
+                //Holding the child until it is reaped keeps its pid valid.
+                let mut child = match child.lock() {
+                    Ok(child) => child,
+                    Err(_) => return,
+                };
+                if !matches!(child.try_wait(), Ok(None)) {
+                    return;
+                }
+                if Instant::now() >= deadline {
+                    if interrupt(&mut child).is_ok() {
+                        interrupted.store(true, Ordering::SeqCst);
+                    }
+                    return;
+                }
+                drop(child);
+                thread::sleep(Duration::from_millis(50));
+            });
+        }
+
+        let (hosts_tx, hosts_rx) = mpsc::channel();
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let reader = thread::spawn(move || {
+            let mut output = OutputReader {
+                stdout: BufReader::new(stdout),
+                buffer: String::new(),
+                header: None,
+                end_time: None,
+            };
+            //Sending fails once the receiver is dropped, but the output
+            //still has to be read for Nmap to finish.
+            loop {
+                match output.next_event() {
+                    Ok(Some(Event::Host(element))) => {
+                        let host = Document::parse(&element)
+                            .map_err(Error::from)
+                            .and_then(|doc| Host::parse_in_context(doc.root_element()));
+                        let _ = hosts_tx.send(host);
+                    }
+                    Ok(Some(Event::Progress(progress))) => {
+                        let _ = progress_tx.send(progress);
+                    }
+                    Ok(None) => return Ok((output.header, output.end_time)),
+                    Err(e) => return Err(e),
+                }
+            }
+        });
+
+        Ok(RunningScan {
+            handle,
+            stderr: Some(stderr),
+            reader: Some(reader),
+            hosts_rx,
+            progress_rx: Some(progress_rx),
+            hosts: Vec::new(),
+        })
+    }
+}
+
+///A scan running in the background, started by
+///[`Scanner::spawn()`](struct.Scanner.html#method.spawn).
+///
+///Iterating yields the hosts as Nmap reports them, and
+///[`progress()`](#method.progress) the progress of its tasks.
+///[`finish()`](#method.finish) waits for the scan to end and returns all
+///its results.
+#[derive(Debug)]
+pub struct RunningScan {
+    handle: InterruptHandle,
+    stderr: Option<JoinHandle<String>>,
+    reader: Option<JoinHandle<Result<ReaderOutput, Error>>>,
+    hosts_rx: Receiver<Result<Host, Error>>,
+    progress_rx: Option<Receiver<TaskProgress>>,
+    hosts: Vec<Host>,
+}
+
+//The `nmaprun` start tag and the end time of the scan.
+type ReaderOutput = (Option<String>, Option<i64>);
+
+impl RunningScan {
+    ///Returns the progress reports of the scan, which Nmap only writes
+    ///with `--stats-every` (see
+    ///[`Scanner::stats_every()`](struct.Scanner.html#method.stats_every)).
+    ///The channel can be read from another thread while this one waits for
+    ///hosts, and is only returned by the first call.
+    pub fn progress(&mut self) -> Option<Receiver<TaskProgress>> {
+        self.progress_rx.take()
+    }
+
+    ///Returns a handle interrupting the scan from another thread, e.g.
+    ///while this one is blocked waiting for the next host.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.handle.clone()
+    }
+
+    ///Interrupts the scan as Ctrl-C would. Hosts already reported are
+    ///kept.
+    pub fn interrupt(&self) -> io::Result<()> {
+        self.handle.interrupt()
+    }
+
+    ///Returns `true` if the scan was interrupted, by
+    ///[`interrupt()`](#method.interrupt), an
+    ///[`InterruptHandle`](struct.InterruptHandle.html) or on timeout.
+    pub fn is_interrupted(&self) -> bool {
+        self.handle.is_interrupted()
+    }
+
+    ///Waits for the scan to end and returns its results, the hosts already
+    ///yielded included. An interrupted scan returns the hosts reported
+    ///before the interruption and no end time.
+    pub fn finish(mut self) -> Result<NmapResults, Error> {
+        for host in self.by_ref() {
+            host?;
+        }
+        let (header, end_time) = match self.reader.take().map(JoinHandle::join) {
+            Some(Ok(output)) => output?,
+            _ => return Err(thread_panicked()),
+        };
+        let status = match self.handle.child.lock() {
+            Ok(mut child) => child.wait()?,
+            Err(_) => return Err(thread_panicked()),
+        };
+        let stderr = self
+            .stderr
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default();
+        if !status.success() && !self.is_interrupted() {
+            return Err(Error::ScanFailed {
+                status,
+                stderr: stderr.trim().to_string(),
+            });
+        }
+
+        let header = header.ok_or(Error::MissingElement {
+            element: "nmaprun",
+            parent: "document",
+        })?;
+        let mut results = NmapResults::parse(&format!("{}</nmaprun>", header))?;
+        results.hosts = std::mem::take(&mut self.hosts);
+        results.scan_end_time = end_time;
+        Ok(results)
+    }
+}
+
+///Interrupts a [`RunningScan`](struct.RunningScan.html) from any thread,
+///returned by
+///[`RunningScan::interrupt_handle()`](struct.RunningScan.html#method.interrupt_handle).
+#[derive(Clone, Debug)]
+pub struct InterruptHandle {
+    child: Arc<Mutex<Child>>,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    ///Interrupts the scan as Ctrl-C would. Hosts already reported are
+    ///kept, and interrupting a scan that has ended does nothing.
+    pub fn interrupt(&self) -> io::Result<()> {
+        let mut child = self
+            .child
+            .lock()
+            .map_err(|_| io::Error::other(THREAD_PANICKED))?;
+        match child.try_wait()? {
+            //A failed scan must still be reported as such by `finish()`.
+            Some(_) => Ok(()),
+            None => {
+                interrupt(&mut child)?;
+                //Stored while the child is held, so before `finish()` can
+                //reap it and check the flag.
+                self.interrupted.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    }
+
+    ///Returns `true` if the scan was interrupted.
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+}
+
+impl Iterator for RunningScan {
+    type Item = Result<Host, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let host = self.hosts_rx.recv().ok()?;
+        if let Ok(host) = &host {
+            self.hosts.push(host.clone());
+        }
+        Some(host)
+    }
+}
+
+///The progress of a task of a running scan, e.g. `SYN Stealth Scan`, as
+///reported by Nmap's `<taskprogress>` elements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskProgress {
+    pub task: String,
+    ///Time of the report, in seconds since Unix epoch.
+    pub time: i64,
+    ///Completion of the task, in percent.
+    pub percent: f64,
+    ///Estimated number of seconds left.
+    pub remaining: Option<i64>,
+    ///Estimated completion time, in seconds since Unix epoch.
+    pub etc: Option<i64>,
+}
+
+impl TaskProgress {
+    fn parse(node: Node) -> Result<Self, Error> {
+        let task = node_attr_as_string!(node, "taskprogress", "task");
+
+        let time = parse_node_attr!(node, "taskprogress", "time", i64);
+
+        let percent = parse_node_attr!(node, "taskprogress", "percent", f64);
+
+        let remaining = node.attribute("remaining").and_then(|r| r.parse().ok());
+        let etc = node.attribute("etc").and_then(|e| e.parse().ok());
+
+        Ok(TaskProgress {
+            task,
+            time,
+            percent,
+            remaining,
+            etc,
+        })
+    }
+}
+
+enum Event {
+    Host(String),
+    Progress(TaskProgress),
+}
+
+//Splits the XML written by Nmap into events as it arrives.
+struct OutputReader {
+    stdout: BufReader<ChildStdout>,
+    //Output not consumed yet.
+    buffer: String,
+    header: Option<String>,
+    end_time: Option<i64>,
+}
+
+impl OutputReader {
+    fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        loop {
+            if self.header.is_none() {
+                if let Some(start) = self.buffer.find("<nmaprun") {
+                    if let Some(end) = self.buffer[start..].find('>') {
+                        self.header = Some(self.buffer[start..=start + end].to_string());
+                        self.buffer.drain(..=start + end);
+                    }
+                }
+            }
+            if self.header.is_some() {
+                if let Some(element) = take_element(&mut self.buffer, "taskprogress") {
+                    let doc = Document::parse(&element)?;
+                    return Ok(Some(Event::Progress(TaskProgress::parse(
+                        doc.root_element(),
+                    )?)));
+                }
+                if let Some(element) = take_element(&mut self.buffer, "host") {
+                    return Ok(Some(Event::Host(element)));
+                }
+                if let Some(element) = take_element(&mut self.buffer, "runstats") {
+                    let doc = Document::parse(&element)?;
+                    self.end_time = doc
+                        .descendants()
+                        .find(|n| n.has_tag_name("finished"))
+                        .and_then(|n| n.attribute("time"))
+                        .and_then(|t| t.parse().ok());
+                }
+            }
+
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.buffer.push_str(&line);
+        }
+    }
+}
+
+const THREAD_PANICKED: &str = "scanner thread panicked";
+
+fn thread_panicked() -> Error {
+    Error::Io(io::Error::other(THREAD_PANICKED))
+}
+
+//Removes the first complete `<name>` element from `buffer` and returns it,
+//discarding what precedes it.
+fn take_element(buffer: &mut String, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let start = buffer.match_indices(&open).map(|(i, _)| i).find(|&i| {
+        matches!(
+            buffer[i + open.len()..].chars().next(),
+            Some(' ') | Some('>') | Some('/') | Some('\n')
+        )
+    })?;
+    //Attribute values cannot contain `>` unescaped, so the first one ends
+    //the start tag.
+    let tag_end = start + buffer[start..].find('>')?;
+    let end = if buffer[..tag_end].ends_with('/') {
+        tag_end + 1
+    } else {
+        start + buffer[start..].find(&close)? + close.len()
+    };
+    let element = buffer[start..end].to_string();
+    buffer.drain(..end);
+    Some(element)
+}
+
+//Sends SIGINT so that Nmap stops cleanly. Other platforms have no
+//equivalent, so the process is killed there.
+#[cfg(unix)]
+fn interrupt(child: &mut Child) -> io::Result<()> {
+    use std::convert::TryFrom;
+
+    //`pid_t` is an `i32` on every Unix target Rust supports.
+    extern "C" {
+        fn kill(pid: i32, sig: i32) -> i32;
+    }
+    const SIGINT: i32 = 2;
+    let pid = i32::try_from(child.id()).map_err(io::Error::other)?;
+    //SAFETY: `kill` only takes integers and touches no memory of ours. The
+    //caller holds the child, which is not reaped yet, so `pid` still refers
+    //to it rather than to a process reusing its pid.
+    if unsafe { kill(pid, SIGINT) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn interrupt(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scanner_args() {
+        let scanner = Scanner::new()
+            .targets(["10.0.0.0/24", "scanme.nmap.org"])
+            .ports("22,80")
+            .service_detection(true)
+            .script("default")
+            .script("vuln")
+            .timing(4)
+            .stats_every(Duration::from_secs(5))
+            .arg("--open");
+        assert_eq!(
+            scanner.args().unwrap().join(" "),
+            "-oX - -sV -p 22,80 --script default,vuln -T4 --stats-every 5000ms --open -- 10.0.0.0/24 scanme.nmap.org"
+        );
+        assert!(Scanner::new().args().is_err());
+        assert!(Scanner::new().target("10.0.0.1").timing(6).args().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scanner_failures() {
+        let err = Scanner::new()
+            .binary("false")
+            .target("10.0.0.1")
+            .run()
+            .unwrap_err();
+        assert!(matches!(err, Error::ScanFailed { .. }));
+
+        //Interrupting a scan that has already failed changes nothing.
+        let mut scan = Scanner::new()
+            .binary("false")
+            .target("10.0.0.1")
+            .spawn()
+            .unwrap();
+        assert!(scan.next().is_none());
+        while scan
+            .handle
+            .child
+            .lock()
+            .unwrap()
+            .try_wait()
+            .unwrap()
+            .is_none()
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+        scan.interrupt_handle().interrupt().unwrap();
+        assert!(!scan.is_interrupted());
+        assert!(matches!(scan.finish(), Err(Error::ScanFailed { .. })));
+
+        let err = Scanner::new()
+            .binary("/nonexistent/nmap")
+            .target("10.0.0.1")
+            .run()
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+    }
+
+    //Stands in for Nmap, printing two hosts a second apart.
+    #[cfg(unix)]
+    const FAKE_NMAP: &str = r#"#!/bin/sh
+printf '<?xml version="1.0"?>\n<nmaprun scanner="nmap" args="nmap" start="1588318812" version="7.80">\n'
+printf '<taskprogress task="Ping Scan" time="1588318813" percent="50.00" remaining="1" etc="1588318814"/>\n'
+printf '<host><status state="up" reason="echo-reply" reason_ttl="53"/>\n<address addr="10.0.0.1" addrtype="ipv4"/>\n<hostnames><hostname name="a.example.com" type="PTR"/></hostnames>\n</host>\n'
+sleep 1
+printf '<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.2" addrtype="ipv4"/></host>\n'
+printf '<runstats><finished time="1588318820"/></runstats>\n</nmaprun>\n'"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn streamed_hosts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("nmap_xml_parser-{}-nmap", std::process::id()));
+        std::fs::write(&path, FAKE_NMAP).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let scanner = Scanner::new().binary(&path).target("10.0.0.1");
+        let mut scan = scanner.spawn().unwrap();
+        let progress = scan.progress().unwrap();
+        let host = scan.next().unwrap().unwrap();
+        assert_eq!(host.host_names().next().unwrap().name, "a.example.com");
+        assert_eq!(
+            progress.recv().unwrap(),
+            TaskProgress {
+                task: "Ping Scan".to_string(),
+                time: 1588318813,
+                percent: 50.0,
+                remaining: Some(1),
+                etc: Some(1588318814),
+            }
+        );
+
+        let results = scan.finish().unwrap();
+        assert_eq!(results.hosts().count(), 2);
+        assert_eq!(results.scan_start_time, 1588318812);
+        assert_eq!(results.scan_end_time, Some(1588318820));
+
+        let mut scan = scanner.timeout(Duration::from_millis(300)).spawn().unwrap();
+        assert!(scan.next().unwrap().is_ok());
+        let results = scan.finish().unwrap();
+        assert_eq!(results.hosts().count(), 1);
+        assert_eq!(results.scan_end_time, None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn interrupt_from_another_thread() {
+        use std::os::unix::fs::PermissionsExt;
+
+        //Reports a host, then hangs until interrupted.
+        let mut path = std::env::temp_dir();
+        path.push(format!("nmap_xml_parser-{}-nmap-hang", std::process::id()));
+        let script = FAKE_NMAP.split("sleep 1").next().unwrap();
+        std::fs::write(&path, format!("{}exec sleep 30\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let started = Instant::now();
+        let mut scan = Scanner::new()
+            .binary(&path)
+            .target("10.0.0.1")
+            .spawn()
+            .unwrap();
+        assert!(scan.next().unwrap().is_ok());
+        let handle = scan.interrupt_handle();
+        let interrupter = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            handle.interrupt()
+        });
+        //Blocks until the other thread interrupts the scan.
+        assert!(scan.next().is_none());
+        interrupter.join().unwrap().unwrap();
+        assert!(scan.is_interrupted());
+        let results = scan.finish().unwrap();
+        assert_eq!(results.hosts().count(), 1);
+        assert_eq!(results.scan_end_time, None);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
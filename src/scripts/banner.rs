@@ -0,0 +1,83 @@
+//!Service banners reported by the `banner` script.
+//!
+//!Banners are normalized to a single line so they can be compared and
+//!indexed: line breaks, whether raw or escaped by Nmap as `\x0D\x0A`,
+//!become spaces, runs of whitespace are collapsed and the result is cut to
+//![`MAX_LEN`](constant.MAX_LEN.html) characters.
+use crate::host::Script;
+
+///The number of characters banners are cut to.
+pub const MAX_LEN: usize = 256;
+
+///The normalized banner of a port, as the typed result of a `banner`
+///script.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Banner(pub String);
+
+///Parses the normalized banner of a `banner` script.
+pub fn parse(script: &Script) -> Option<String> {
+    if script.id != "banner" {
+        return None;
+    }
+    Some(normalize(&script.output, MAX_LEN)).filter(|banner| !banner.is_empty())
+}
+
+///Like [`parse()`](fn.parse.html), wrapping the banner in a `Banner`.
+pub fn parse_banner(script: &Script) -> Option<Banner> {
+    parse(script).map(Banner)
+}
+
+///Joins the lines of `banner` and cuts it to `max_len` characters.
+pub fn normalize(banner: &str, max_len: usize) -> String {
+    let unescaped = ["\\x0D", "\\x0d", "\\x0A", "\\x0a", "\\r", "\\n"]
+        .iter()
+        .fold(banner.to_string(), |banner, escape| {
+            banner.replace(escape, " ")
+        });
+    unescaped
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(max_len)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    #[test]
+    fn banner() {
+        let mut port = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="21"><state state="open" reason="syn-ack" reason_ttl="53"/>
+<script id="banner" output="220-Welcome\x0D\x0A220  ProFTPD Server&#xa;"/>
+</port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap()
+        .hosts()
+        .next()
+        .unwrap()
+        .port_info
+        .ports()
+        .next()
+        .unwrap()
+        .clone();
+        assert_eq!(
+            port.banner().as_deref(),
+            Some("220-Welcome 220 ProFTPD Server")
+        );
+
+        port.scripts = vec![Script::new("banner".to_string(), "x".repeat(300))];
+        assert_eq!(port.banner().unwrap().len(), MAX_LEN);
+        assert_eq!(normalize("a\r\n\tb", 2), "a ");
+    }
+}
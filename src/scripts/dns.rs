@@ -0,0 +1,131 @@
+//!DNS records found by the `dns-brute` and `dns-zone-transfer` scripts.
+use std::net::IpAddr;
+
+use crate::host::{Host, Script, ScriptElement};
+
+///A DNS resource record.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DnsRecord {
+    ///Owner name, without the trailing dot.
+    pub name: String,
+    ///Record type, e.g. `A` or `MX`.
+    pub record_type: String,
+    ///Record data as printed by Nmap, e.g. an address or `10 mail.example.com.`.
+    pub data: String,
+}
+
+impl DnsRecord {
+    ///Returns the address of an `A` or `AAAA` record.
+    pub fn address(&self) -> Option<IpAddr> {
+        match self.record_type.as_str() {
+            "A" | "AAAA" => self.data.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+///Parses the records of a `dns-brute` or `dns-zone-transfer` script.
+///`dns-brute` only finds address records.
+pub fn parse(script: &Script) -> Option<Vec<DnsRecord>> {
+    match script.id.as_str() {
+        "dns-brute" if script.elements().next().is_some() => Some(
+            script
+                .elements()
+                .flat_map(ScriptElement::elements)
+                .filter_map(|e| address_record(e.elem("hostname")?, e.elem("address")?))
+                .collect(),
+        ),
+        //`    www.example.com - 93.184.216.34`
+        "dns-brute" => Some(
+            script
+                .output
+                .lines()
+                .filter_map(|line| line.trim().split_once(" - "))
+                .filter_map(|(name, address)| address_record(name, address))
+                .collect(),
+        ),
+        //`www.zonetransfer.me.            A    5.196.105.14`
+        "dns-zone-transfer" => Some(
+            script
+                .output
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.split_whitespace();
+                    let name = fields.next()?;
+                    let record_type = fields.next()?;
+                    let data = fields.collect::<Vec<_>>().join(" ");
+                    if !record_type
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                    {
+                        return None;
+                    }
+                    Some(DnsRecord {
+                        name: name.trim_end_matches('.').to_string(),
+                        record_type: record_type.to_string(),
+                        data,
+                    })
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+///Returns the records found by the DNS scripts run on `host`.
+pub fn host_records(host: &Host) -> Vec<DnsRecord> {
+    host.scripts().filter_map(parse).flatten().collect()
+}
+
+fn address_record(name: &str, address: &str) -> Option<DnsRecord> {
+    let address = address.trim().parse::<IpAddr>().ok()?;
+    Some(DnsRecord {
+        name: name.trim().trim_end_matches('.').to_string(),
+        record_type: if address.is_ipv4() { "A" } else { "AAAA" }.to_string(),
+        data: address.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    #[test]
+    fn dns_brute() {
+        let xml = r#"<script id="dns-brute" output="&#xa;  DNS Brute-force hostnames: &#xa;    www.example.com - 93.184.216.34&#xa;    www.example.com - 2606:2800:220:1:248:1893:25c8:1946">
+<table key="DNS Brute-force hostnames">
+<table><elem key="hostname">www.example.com</elem><elem key="address">93.184.216.34</elem></table>
+<table><elem key="hostname">www.example.com</elem><elem key="address">2606:2800:220:1:248:1893:25c8:1946</elem></table>
+</table>
+</script>"#;
+        let script = Script::parse(Document::parse(xml).unwrap().root_element()).unwrap();
+        let records = parse(&script).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].record_type, "A");
+        assert_eq!(records[1].record_type, "AAAA");
+        assert_eq!(records[0].address(), Some("93.184.216.34".parse().unwrap()));
+        let text = parse(&Script::new(script.id.clone(), script.output.clone())).unwrap();
+        assert_eq!(text, records);
+    }
+
+    #[test]
+    fn dns_zone_transfer() {
+        let script = Script::new(
+            "dns-zone-transfer".to_string(),
+            "\nzonetransfer.me.       SOA   nsztm1.digi.ninja. robin.digi.ninja.\n\
+zonetransfer.me.       MX    0 ASPMX.L.GOOGLE.COM.\n\
+www.zonetransfer.me.   A     5.196.105.14\n"
+                .to_string(),
+        );
+        let records = parse(&script).unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].data, "nsztm1.digi.ninja. robin.digi.ninja.");
+        assert_eq!(records[1].record_type, "MX");
+        assert_eq!(records[2].name, "www.zonetransfer.me");
+        assert_eq!(records[2].address(), Some("5.196.105.14".parse().unwrap()));
+        assert_eq!(records[1].address(), None);
+    }
+}
@@ -0,0 +1,125 @@
+//!Anonymous FTP access reported by the `ftp-anon` script.
+use crate::host::Script;
+use crate::port::Port;
+
+///The result of an anonymous FTP login.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FtpAnon {
+    pub allowed: bool,
+    pub(crate) entries: Vec<FtpEntry>,
+}
+
+impl FtpAnon {
+    ///Returns an iterator over the entries of the root directory listing.
+    pub fn entries(&self) -> std::slice::Iter<'_, FtpEntry> {
+        self.entries.iter()
+    }
+}
+
+///An entry of a directory listing, in Unix `ls -l` or IIS format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FtpEntry {
+    pub name: String,
+    pub directory: bool,
+    pub size: Option<u64>,
+    ///`true` if Nmap flagged the entry as writeable by anonymous users.
+    pub writeable: bool,
+    ///The listing line, as the server sent it.
+    pub line: String,
+}
+
+///Parses the output of an `ftp-anon` script.
+pub fn parse(script: &Script) -> Option<FtpAnon> {
+    if script.id != "ftp-anon" {
+        return None;
+    }
+
+    let mut lines = script
+        .output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    let allowed = lines
+        .next()
+        .is_some_and(|l| l.starts_with("Anonymous FTP login allowed"));
+    let entries = if allowed {
+        lines.filter_map(parse_entry).collect()
+    } else {
+        Vec::new()
+    };
+    Some(FtpAnon { allowed, entries })
+}
+
+///Returns `true` if the `ftp-anon` script run on `port` logged in.
+pub fn port_allows_anonymous(port: &Port) -> bool {
+    port.scripts
+        .iter()
+        .filter_map(parse)
+        .any(|anon| anon.allowed)
+}
+
+//`drwxr-xr-x    2 0        0            4096 Jun 08  2020 pub`
+//`06-08-20  10:00AM       <DIR>          pub`
+fn parse_entry(line: &str) -> Option<FtpEntry> {
+    let (line, writeable) = match line.strip_suffix("[NSE: writeable]") {
+        Some(line) => (line.trim_end(), true),
+        None => (line, false),
+    };
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+
+    let (name, directory, size) = match fields.first()?.chars().next()? {
+        'd' | '-' | 'l' if fields.len() >= 9 => (
+            fields[8..].join(" "),
+            fields[0].starts_with('d'),
+            fields[4].parse().ok(),
+        ),
+        c if c.is_ascii_digit() && fields.len() >= 4 => (
+            fields[3..].join(" "),
+            fields[2] == "<DIR>",
+            fields[2].parse().ok(),
+        ),
+        _ => return None,
+    };
+    Some(FtpEntry {
+        name,
+        directory,
+        size,
+        writeable,
+        line: line.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ftp_anon() {
+        let script = Script::new(
+            "ftp-anon".to_string(),
+            "Anonymous FTP login allowed (FTP code 230)\n\
+drwxr-xr-x    2 0        0            4096 Jun 08  2020 pub\n\
+-rw-r--r--    1 0        0              41 Jun 08  2020 welcome message.txt [NSE: writeable]\n\
+06-08-20  10:00AM       <DIR>          aspnet_client\n"
+                .to_string(),
+        );
+        let anon = parse(&script).unwrap();
+        assert!(anon.allowed);
+
+        let entries = anon.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].directory);
+        assert_eq!(entries[0].name, "pub");
+        assert_eq!(entries[1].name, "welcome message.txt");
+        assert_eq!(entries[1].size, Some(41));
+        assert!(entries[1].writeable);
+        assert!(entries[2].directory);
+        assert_eq!(entries[2].name, "aspnet_client");
+
+        let denied = Script::new(
+            "ftp-anon".to_string(),
+            "ERROR: Script execution failed".to_string(),
+        );
+        assert!(!parse(&denied).unwrap().allowed);
+    }
+}
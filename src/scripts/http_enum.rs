@@ -0,0 +1,73 @@
+//!Paths found by the `http-enum` script.
+use crate::host::Script;
+use crate::port::Port;
+
+///A path of a web server matching one of the `http-enum` fingerprints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpEnumEntry {
+    pub path: String,
+    ///What the fingerprint says the path is, e.g. `Possible admin folder`.
+    pub description: String,
+    ///The status code of the response, when it was not `200 OK`.
+    pub status: Option<u16>,
+}
+
+///Parses the output of an `http-enum` script.
+pub fn parse(script: &Script) -> Option<Vec<HttpEnumEntry>> {
+    if script.id != "http-enum" {
+        return None;
+    }
+    Some(script.output.lines().filter_map(parse_line).collect())
+}
+
+///Returns the paths found by the `http-enum` script run on `port`.
+pub fn port_entries(port: &Port) -> Vec<HttpEnumEntry> {
+    port.scripts.iter().filter_map(parse).flatten().collect()
+}
+
+//`/login.php: Possible admin folder (401 Unauthorized)`
+fn parse_line(line: &str) -> Option<HttpEnumEntry> {
+    let (path, description) = line.trim().split_once(": ")?;
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    let (description, status) = match description.rsplit_once(" (") {
+        Some((text, code)) => match code.split(' ').next().and_then(|c| c.parse().ok()) {
+            Some(status) => (text, Some(status)),
+            None => (description, None),
+        },
+        None => (description, None),
+    };
+    Some(HttpEnumEntry {
+        path: path.to_string(),
+        description: description.trim().to_string(),
+        status,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn http_enum() {
+        let script = Script::new(
+            "http-enum".to_string(),
+            "\n  /admin/: Possible admin folder\n  /icons/: Potentially interesting folder w/ directory listing\n  /manager/html: Apache Tomcat (401 Unauthorized)\n  /test/: Test page (Apache 2.4)\n"
+                .to_string(),
+        );
+        let entries = parse(&script).unwrap();
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].path, "/admin/");
+        assert_eq!(entries[0].status, None);
+        assert_eq!(
+            entries[1].description,
+            "Potentially interesting folder w/ directory listing"
+        );
+        assert_eq!(entries[2].description, "Apache Tomcat");
+        assert_eq!(entries[2].status, Some(401));
+        assert_eq!(entries[3].description, "Test page (Apache 2.4)");
+    }
+}
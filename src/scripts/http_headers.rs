@@ -0,0 +1,142 @@
+//!HTTP response headers reported by the `http-headers` and
+//!`http-server-header` scripts.
+use crate::host::Script;
+use crate::port::Port;
+
+///The headers of an HTTP response, in the order the server sent them.
+///Header names are compared case-insensitively.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpHeaders {
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+impl HttpHeaders {
+    ///Returns an iterator over the header names and values.
+    pub fn iter(&self) -> std::slice::Iter<'_, (String, String)> {
+        self.headers.iter()
+    }
+
+    ///Returns the value of the first header named `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    ///Returns `true` if the response has a header named `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    ///Returns the `Server` header.
+    pub fn server(&self) -> Option<&str> {
+        self.get("Server")
+    }
+
+    ///Returns those of `names` the response does not have, e.g. to flag
+    ///missing security headers.
+    pub fn missing<'a>(&self, names: &[&'a str]) -> Vec<&'a str> {
+        names
+            .iter()
+            .copied()
+            .filter(|name| !self.contains(name))
+            .collect()
+    }
+}
+
+///Parses the output of an `http-headers` or `http-server-header` script.
+///The latter only gives the `Server` header.
+pub fn parse(script: &Script) -> Option<HttpHeaders> {
+    match script.id.as_str() {
+        "http-headers" => Some(parse_headers(&script.output)),
+        "http-server-header" => {
+            //Older versions only have the text output.
+            let servers = match script.elements().next() {
+                Some(_) => script.elements().filter_map(|e| e.value()).collect(),
+                None => vec![script.output.trim()],
+            };
+            Some(HttpHeaders {
+                headers: servers
+                    .into_iter()
+                    .filter(|s| !s.is_empty())
+                    .map(|s| ("Server".to_string(), s.to_string()))
+                    .collect(),
+            })
+        }
+        _ => None,
+    }
+}
+
+///Returns the headers found by the `http-headers` and `http-server-header`
+///scripts run on `port`, those of `http-headers` first.
+pub fn port_headers(port: &Port) -> Option<HttpHeaders> {
+    let mut scripts = port.scripts.iter().collect::<Vec<_>>();
+    scripts.sort_by_key(|s| s.id != "http-headers");
+    scripts
+        .into_iter()
+        .filter_map(parse)
+        .reduce(|mut headers, more| {
+            for (name, value) in more.headers {
+                if !headers.contains(&name) {
+                    headers.headers.push((name, value));
+                }
+            }
+            headers
+        })
+}
+
+//`  Server: nginx` lines, followed by `  (Request type: HEAD)`.
+fn parse_headers(output: &str) -> HttpHeaders {
+    let headers = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('('))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty() && !name.contains(' '))
+        .collect();
+    HttpHeaders { headers }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    #[test]
+    fn http_headers() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/>
+<script id="http-server-header" output="nginx/1.18.0"><elem>nginx/1.18.0</elem></script>
+<script id="http-headers" output="&#xa;  Date: Fri, 01 May 2020 15:40:12 GMT&#xa;  Content-Type: text/html&#xa;  X-Frame-Options: DENY&#xa;  &#xa;  (Request type: HEAD)&#xa;"/>
+</port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let port = results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .next()
+            .unwrap();
+        let headers = port_headers(port).unwrap();
+
+        assert_eq!(headers.iter().count(), 4);
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.server(), Some("nginx/1.18.0"));
+        assert_eq!(
+            headers.missing(&["X-Frame-Options", "Strict-Transport-Security"]),
+            ["Strict-Transport-Security"]
+        );
+        assert_eq!(headers.get("Date"), Some("Fri, 01 May 2020 15:40:12 GMT"));
+    }
+}
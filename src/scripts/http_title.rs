@@ -0,0 +1,97 @@
+//!Page titles reported by the `http-title` script.
+use crate::host::Script;
+use crate::port::Port;
+
+///The title of the page served at the root of a web server.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpTitle {
+    ///The title, `None` if the page has none.
+    pub title: Option<String>,
+    ///Where the server redirected to, whether Nmap followed the redirect
+    ///or not.
+    pub redirect_url: Option<String>,
+}
+
+///Parses the output of an `http-title` script.
+pub fn parse(script: &Script) -> Option<HttpTitle> {
+    if script.id != "http-title" {
+        return None;
+    }
+
+    if script.elements().next().is_some() {
+        return Some(HttpTitle {
+            title: script.elem("title").map(String::from),
+            redirect_url: script.elem("redirect_url").map(String::from),
+        });
+    }
+
+    let mut title = HttpTitle::default();
+    for line in script
+        .output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+    {
+        if let Some(url) = line
+            .strip_prefix("Did not follow redirect to ")
+            .or_else(|| line.strip_prefix("Requested resource was "))
+        {
+            title.redirect_url = Some(url.to_string());
+        } else if !line.starts_with("Site doesn't have a title") && title.title.is_none() {
+            title.title = Some(line.to_string());
+        }
+    }
+    Some(title)
+}
+
+///Returns the title found by the `http-title` script run on `port`.
+pub fn port_title(port: &Port) -> Option<HttpTitle> {
+    port.scripts.iter().find_map(parse)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    fn script(xml: &str) -> Script {
+        Script::parse(Document::parse(xml).unwrap().root_element()).unwrap()
+    }
+
+    #[test]
+    fn http_title() {
+        let titled = script(
+            r#"<script id="http-title" output="Go ahead and ScanMe!"><elem key="title">Go ahead and ScanMe!</elem></script>"#,
+        );
+        assert_eq!(
+            parse(&titled).unwrap().title.as_deref(),
+            Some("Go ahead and ScanMe!")
+        );
+
+        let redirect = script(
+            r#"<script id="http-title" output="Did not follow redirect to https://example.com/"><elem key="redirect_url">https://example.com/</elem></script>"#,
+        );
+        let redirect = parse(&redirect).unwrap();
+        assert_eq!(redirect.title, None);
+        assert_eq!(
+            redirect.redirect_url.as_deref(),
+            Some("https://example.com/")
+        );
+    }
+
+    #[test]
+    fn http_title_text() {
+        let text = Script::new(
+            "http-title".to_string(),
+            "Site doesn't have a title (text/html).\nRequested resource was http://10.0.0.1/login"
+                .to_string(),
+        );
+        assert_eq!(
+            parse(&text).unwrap(),
+            HttpTitle {
+                title: None,
+                redirect_url: Some("http://10.0.0.1/login".to_string()),
+            }
+        );
+    }
+}
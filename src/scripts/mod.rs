@@ -0,0 +1,341 @@
+//!Typed results of NSE scripts.
+//!
+//!Each module parses the output of one script, or a family of related
+//!scripts, with a `parse()` function taking a
+//![`Script`](../host/struct.Script.html). It returns `None` when the script
+//!is not one the module handles or its output cannot be read. The structured
+//!`<elem>` and `<table>` output is used when Nmap emitted it, the text output
+//!otherwise.
+//!
+//!The parsers are also available through the
+//![`ScriptParser`](trait.ScriptParser.html) trait, implemented by the types
+//!they return. [`Host::typed_scripts()`](../host/struct.Host.html#method.typed_scripts)
+//!runs the parsers of a [`ScriptRegistry`](struct.ScriptRegistry.html) on
+//!every script of a host, and parsers of other scripts can be added to it
+//!with [`register()`](fn.register.html).
+pub mod banner;
+pub mod dns;
+pub mod ftp_anon;
+pub mod http_enum;
+pub mod http_headers;
+pub mod http_title;
+pub mod nbstat;
+pub mod rdp_ntlm_info;
+pub mod smb_os_discovery;
+pub mod smb_security_mode;
+pub mod snmp;
+pub mod ssh_hostkey;
+pub mod ssl_cert;
+pub mod ssl_enum_ciphers;
+pub mod vuln;
+pub mod vulners;
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+
+use crate::host::{Host, Script};
+use crate::port::Port;
+
+///A typed result of a script.
+///
+///Implementations are registered in a [`ScriptRegistry`](struct.ScriptRegistry.html)
+///so that [`Host::typed_scripts()`](../host/struct.Host.html#method.typed_scripts)
+///finds them, and can be used directly with
+///[`Script::parse_as()`](../host/struct.Script.html#method.parse_as).
+pub trait ScriptParser: Sized + 'static {
+    ///The ids of the scripts parsed. An empty list means any script, for
+    ///parsers that recognize the scripts they handle by their output.
+    const SCRIPT_IDS: &'static [&'static str];
+
+    ///Parses `script`, returning `None` if its output cannot be read.
+    fn parse(script: &Script) -> Option<Self>;
+}
+
+macro_rules! builtin_parser {
+    ($type:ty, $parse:path, [$($id:expr),+]) => {
+        impl ScriptParser for $type {
+            const SCRIPT_IDS: &'static [&'static str] = &[$($id),+];
+
+            fn parse(script: &Script) -> Option<Self> {
+                $parse(script)
+            }
+        }
+    };
+}
+
+builtin_parser!(banner::Banner, banner::parse_banner, ["banner"]);
+builtin_parser!(
+    Vec<dns::DnsRecord>,
+    dns::parse,
+    ["dns-brute", "dns-zone-transfer"]
+);
+builtin_parser!(ftp_anon::FtpAnon, ftp_anon::parse, ["ftp-anon"]);
+builtin_parser!(
+    Vec<http_enum::HttpEnumEntry>,
+    http_enum::parse,
+    ["http-enum"]
+);
+builtin_parser!(
+    http_headers::HttpHeaders,
+    http_headers::parse,
+    ["http-headers", "http-server-header"]
+);
+builtin_parser!(http_title::HttpTitle, http_title::parse, ["http-title"]);
+builtin_parser!(nbstat::Nbstat, nbstat::parse, ["nbstat"]);
+builtin_parser!(
+    rdp_ntlm_info::RdpNtlmInfo,
+    rdp_ntlm_info::parse,
+    ["rdp-ntlm-info"]
+);
+builtin_parser!(
+    smb_os_discovery::SmbOsDiscovery,
+    smb_os_discovery::parse,
+    ["smb-os-discovery"]
+);
+builtin_parser!(
+    smb_security_mode::SmbSecurityMode,
+    smb_security_mode::parse,
+    ["smb-security-mode", "smb2-security-mode"]
+);
+builtin_parser!(snmp::SnmpInfo, snmp::parse, ["snmp-sysdescr", "snmp-info"]);
+builtin_parser!(
+    Vec<ssh_hostkey::SshHostKey>,
+    ssh_hostkey::parse,
+    ["ssh-hostkey"]
+);
+builtin_parser!(ssl_cert::SslCert, ssl_cert::parse, ["ssl-cert"]);
+builtin_parser!(
+    ssl_enum_ciphers::SslEnumCiphers,
+    ssl_enum_ciphers::parse,
+    ["ssl-enum-ciphers"]
+);
+builtin_parser!(
+    Vec<vulners::VulnFinding>,
+    vulners::parse,
+    ["vulners", "vulscan"]
+);
+
+impl ScriptParser for Vec<vuln::VulnReport> {
+    const SCRIPT_IDS: &'static [&'static str] = &[];
+
+    fn parse(script: &Script) -> Option<Self> {
+        vuln::parse(script)
+    }
+}
+
+type ParseFn = Box<dyn Fn(&Script) -> Option<Box<dyn Any>> + Send + Sync>;
+
+///The parsers tried on scripts, by script id.
+pub struct ScriptRegistry {
+    by_id: HashMap<String, Vec<ParseFn>>,
+    //Parsers of any script, tried after those registered for the id.
+    any: Vec<ParseFn>,
+}
+
+impl ScriptRegistry {
+    ///Returns a registry without any parser.
+    pub fn empty() -> Self {
+        ScriptRegistry {
+            by_id: HashMap::new(),
+            any: Vec::new(),
+        }
+    }
+
+    ///Registers `T` for the scripts it parses. Parsers of the same script
+    ///are tried in registration order.
+    pub fn register<T: ScriptParser>(&mut self) {
+        let parse = || -> ParseFn { Box::new(|script| T::parse(script).map(|v| Box::new(v) as _)) };
+        if T::SCRIPT_IDS.is_empty() {
+            self.any.push(parse());
+        }
+        for id in T::SCRIPT_IDS {
+            self.by_id.entry(id.to_string()).or_default().push(parse());
+        }
+    }
+
+    ///Parses `script` with the first registered parser that reads it.
+    pub fn parse<'a>(&self, script: &'a Script, port: Option<&'a Port>) -> Option<TypedScript<'a>> {
+        self.by_id
+            .get(&script.id)
+            .into_iter()
+            .flatten()
+            .chain(self.any.iter())
+            .find_map(|parse| parse(script))
+            .map(|value| TypedScript {
+                script,
+                port,
+                value,
+            })
+    }
+
+    ///Parses the scripts run on `host` and its ports, leaving out those no
+    ///parser reads.
+    pub fn host_scripts<'a>(&self, host: &'a Host) -> Vec<TypedScript<'a>> {
+        let host_scripts = host.scripts().map(|script| (script, None));
+        let port_scripts = host
+            .port_info
+            .ports()
+            .flat_map(|port| port.scripts.iter().map(move |script| (script, Some(port))));
+        host_scripts
+            .chain(port_scripts)
+            .filter_map(|(script, port)| self.parse(script, port))
+            .collect()
+    }
+}
+
+///Returns a registry with the parsers of this module.
+impl Default for ScriptRegistry {
+    fn default() -> Self {
+        let mut registry = ScriptRegistry::empty();
+        registry.register::<banner::Banner>();
+        registry.register::<Vec<dns::DnsRecord>>();
+        registry.register::<ftp_anon::FtpAnon>();
+        registry.register::<Vec<http_enum::HttpEnumEntry>>();
+        registry.register::<http_headers::HttpHeaders>();
+        registry.register::<http_title::HttpTitle>();
+        registry.register::<nbstat::Nbstat>();
+        registry.register::<rdp_ntlm_info::RdpNtlmInfo>();
+        registry.register::<smb_os_discovery::SmbOsDiscovery>();
+        registry.register::<smb_security_mode::SmbSecurityMode>();
+        registry.register::<snmp::SnmpInfo>();
+        registry.register::<Vec<ssh_hostkey::SshHostKey>>();
+        registry.register::<ssl_cert::SslCert>();
+        registry.register::<ssl_enum_ciphers::SslEnumCiphers>();
+        registry.register::<Vec<vulners::VulnFinding>>();
+        registry.register::<Vec<vuln::VulnReport>>();
+        registry
+    }
+}
+
+impl fmt::Debug for ScriptRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids = self.by_id.keys().collect::<Vec<_>>();
+        ids.sort();
+        f.debug_struct("ScriptRegistry")
+            .field("script_ids", &ids)
+            .field("any", &self.any.len())
+            .finish()
+    }
+}
+
+static REGISTRY: OnceLock<RwLock<ScriptRegistry>> = OnceLock::new();
+
+pub(crate) fn registry() -> &'static RwLock<ScriptRegistry> {
+    REGISTRY.get_or_init(|| RwLock::new(ScriptRegistry::default()))
+}
+
+///Registers `T` in the registry used by
+///[`Host::typed_scripts()`](../host/struct.Host.html#method.typed_scripts),
+///after the built-in parsers.
+pub fn register<T: ScriptParser>() {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .register::<T>();
+}
+
+///A script and its typed result.
+#[derive(Debug)]
+pub struct TypedScript<'a> {
+    pub script: &'a Script,
+    ///The port the script ran on, `None` for host scripts.
+    pub port: Option<&'a Port>,
+    value: Box<dyn Any>,
+}
+
+impl TypedScript<'_> {
+    ///Returns the result if it is a `T`.
+    pub fn get<T: ScriptParser>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+
+    ///Returns `true` if the result is a `T`.
+    pub fn is<T: ScriptParser>(&self) -> bool {
+        self.value.is::<T>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    //A parser of a script without a built-in one.
+    #[derive(Debug, PartialEq)]
+    struct MysqlInfo {
+        version: String,
+    }
+
+    impl ScriptParser for MysqlInfo {
+        const SCRIPT_IDS: &'static [&'static str] = &["mysql-info"];
+
+        fn parse(script: &Script) -> Option<Self> {
+            Some(MysqlInfo {
+                version: script.elem("Version")?.to_string(),
+            })
+        }
+    }
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/>
+<script id="http-title" output="Home"><elem key="title">Home</elem></script>
+</port>
+<port protocol="tcp" portid="3306"><state state="open" reason="syn-ack" reason_ttl="53"/>
+<script id="mysql-info" output="&#xa;  Version: 5.7.30"><elem key="Version">5.7.30</elem></script>
+</port>
+</ports>
+<hostscript><script id="smb-os-discovery" output="&#xa;  OS: Windows 10"><elem key="os">Windows 10</elem></script></hostscript>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn registry_dispatch() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let host = results.hosts().next().unwrap();
+
+        let mut registry = ScriptRegistry::default();
+        let typed = registry.host_scripts(host);
+        assert_eq!(typed.len(), 2);
+        assert!(typed[0].port.is_none());
+        assert_eq!(
+            typed[0]
+                .get::<smb_os_discovery::SmbOsDiscovery>()
+                .unwrap()
+                .os
+                .as_deref(),
+            Some("Windows 10")
+        );
+        assert_eq!(typed[1].port.unwrap().port_number, 80);
+        assert!(typed[1].is::<http_title::HttpTitle>());
+        assert!(typed[1].get::<ssl_cert::SslCert>().is_none());
+
+        registry.register::<MysqlInfo>();
+        let typed = registry.host_scripts(host);
+        assert_eq!(typed.len(), 3);
+        assert_eq!(typed[2].get::<MysqlInfo>().unwrap().version, "5.7.30");
+        assert!(ScriptRegistry::empty().host_scripts(host).is_empty());
+    }
+
+    #[test]
+    fn parse_as() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let host = results.hosts().next().unwrap();
+        let script = host.scripts().next().unwrap();
+
+        assert!(script
+            .parse_as::<smb_os_discovery::SmbOsDiscovery>()
+            .is_some());
+        assert!(script.parse_as::<http_title::HttpTitle>().is_none());
+
+        register::<MysqlInfo>();
+        assert!(host
+            .typed_scripts()
+            .iter()
+            .any(|typed| typed.is::<MysqlInfo>()));
+    }
+}
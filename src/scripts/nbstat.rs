@@ -0,0 +1,155 @@
+//!NetBIOS information reported by the `nbstat` script.
+use crate::host::{Host, Script, ScriptElement};
+
+///What a host told about itself over NetBIOS.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Nbstat {
+    ///NetBIOS computer name.
+    pub name: Option<String>,
+    ///Logged in user, `None` when Nmap reported it as `<unknown>`.
+    pub user: Option<String>,
+    pub mac: Option<String>,
+    ///Vendor of the MAC address, from the text output only.
+    pub mac_vendor: Option<String>,
+    pub(crate) names: Vec<NetbiosName>,
+}
+
+impl Nbstat {
+    ///Returns an iterator over the names the host registered.
+    pub fn names(&self) -> std::slice::Iter<'_, NetbiosName> {
+        self.names.iter()
+    }
+}
+
+///A name registered by a host, e.g. `WORKGROUP<00>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetbiosName {
+    pub name: String,
+    ///The service suffix, e.g. `0x20` for the file server service.
+    pub suffix: u8,
+    ///`true` for a group name, such as a workgroup, `false` for a unique one.
+    pub group: bool,
+}
+
+///Parses the output of an `nbstat` script.
+pub fn parse(script: &Script) -> Option<Nbstat> {
+    if script.id != "nbstat" {
+        return None;
+    }
+
+    let mut nbstat = Nbstat::default();
+    let mut lines = script
+        .output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    //`NetBIOS name: WIN01, NetBIOS user: <unknown>, NetBIOS MAC: 00:0c:29:71:23:2b (VMware)`
+    if let Some(summary) = lines.next() {
+        for field in summary.split(", ") {
+            if let Some(name) = field.strip_prefix("NetBIOS name: ") {
+                nbstat.name = Some(name.to_string());
+            } else if let Some(user) = field.strip_prefix("NetBIOS user: ") {
+                nbstat.user = Some(user.to_string());
+            } else if let Some(mac) = field.strip_prefix("NetBIOS MAC: ") {
+                let (mac, vendor) = match mac.split_once(" (") {
+                    Some((mac, vendor)) => (mac, Some(vendor.trim_end_matches(')'))),
+                    None => (mac, None),
+                };
+                nbstat.mac = Some(mac.to_string());
+                nbstat.mac_vendor = vendor.filter(|v| *v != "unknown").map(String::from);
+            }
+        }
+    }
+    //`WIN01<00>            Flags: <unique><active>`
+    for line in lines {
+        if let Some((name, flags)) = line.split_once("Flags: ") {
+            if let Some((name, suffix)) = name.trim().split_once('<') {
+                if let Ok(suffix) = u8::from_str_radix(suffix.trim_end_matches('>'), 16) {
+                    nbstat.names.push(NetbiosName {
+                        name: name.to_string(),
+                        suffix,
+                        group: flags.contains("<group>"),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(name) = script.elem("server_name") {
+        nbstat.name = Some(name.to_string());
+    }
+    if let Some(user) = script.elem("user") {
+        nbstat.user = Some(user.to_string());
+    }
+    if let Some(mac) = script.elem("mac") {
+        nbstat.mac = Some(mac.to_string());
+    }
+    if let Some(names) = script.get("names") {
+        nbstat.names = names.elements().filter_map(structured_name).collect();
+    }
+    if nbstat.user.as_deref() == Some("<unknown>") {
+        nbstat.user = None;
+    }
+
+    Some(nbstat)
+}
+
+///Returns the information of the `nbstat` script run on `host`.
+pub fn host_nbstat(host: &Host) -> Option<Nbstat> {
+    host.scripts().find_map(parse)
+}
+
+//`<elem key="name">WIN01</elem><elem key="suffix">0</elem><elem key="flags">1024</elem>`
+fn structured_name(name: &ScriptElement) -> Option<NetbiosName> {
+    let flags = name.elem("flags").and_then(|f| f.parse::<u16>().ok())?;
+    Some(NetbiosName {
+        name: name.elem("name")?.to_string(),
+        suffix: name.elem("suffix")?.parse().ok()?,
+        group: flags & 0x8000 != 0,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    const OUTPUT: &str = "NetBIOS name: WIN01, NetBIOS user: <unknown>, NetBIOS MAC: 00:0c:29:71:23:2b (VMware)\n\
+Names:\n  WIN01<00>            Flags: <unique><active>\n  WORKGROUP<00>        Flags: <group><active>\n  WIN01<20>            Flags: <unique><active>\n";
+
+    #[test]
+    fn nbstat_text() {
+        let nbstat = parse(&Script::new("nbstat".to_string(), OUTPUT.to_string())).unwrap();
+
+        assert_eq!(nbstat.name.as_deref(), Some("WIN01"));
+        assert_eq!(nbstat.user, None);
+        assert_eq!(nbstat.mac.as_deref(), Some("00:0c:29:71:23:2b"));
+        assert_eq!(nbstat.mac_vendor.as_deref(), Some("VMware"));
+        let names = nbstat.names().collect::<Vec<_>>();
+        assert_eq!(names.len(), 3);
+        assert!(names[1].group);
+        assert_eq!(names[2].suffix, 0x20);
+    }
+
+    #[test]
+    fn nbstat_structured() {
+        let xml = r#"<script id="nbstat" output="NetBIOS name: WIN01, NetBIOS user: ADMIN, NetBIOS MAC: 00:0c:29:71:23:2b (VMware)">
+<table key="names">
+<table><elem key="name">WIN01</elem><elem key="suffix">0</elem><elem key="flags">1024</elem></table>
+<table><elem key="name">WORKGROUP</elem><elem key="suffix">0</elem><elem key="flags">33792</elem></table>
+</table>
+<elem key="user">ADMIN</elem>
+<elem key="server_name">WIN01</elem>
+<elem key="mac">00:0c:29:71:23:2b</elem>
+</script>"#;
+        let script = Script::parse(Document::parse(xml).unwrap().root_element()).unwrap();
+        let nbstat = parse(&script).unwrap();
+
+        assert_eq!(nbstat.user.as_deref(), Some("ADMIN"));
+        assert_eq!(nbstat.mac_vendor.as_deref(), Some("VMware"));
+        let names = nbstat.names().collect::<Vec<_>>();
+        assert_eq!(names.len(), 2);
+        assert!(!names[0].group);
+        assert!(names[1].group);
+    }
+}
@@ -0,0 +1,71 @@
+//!Windows host information reported by the `rdp-ntlm-info` script.
+use crate::host::Script;
+use crate::port::Port;
+use crate::util::parse_rfc3339;
+
+///What a host told about itself in the NTLM challenge of an RDP login.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RdpNtlmInfo {
+    pub target_name: Option<String>,
+    pub netbios_domain: Option<String>,
+    pub netbios_computer: Option<String>,
+    pub dns_domain: Option<String>,
+    pub dns_computer: Option<String>,
+    pub dns_tree: Option<String>,
+    ///Windows build, e.g. `10.0.17763`.
+    pub product_version: Option<String>,
+    ///Time of the host, in seconds since Unix epoch.
+    pub system_time: Option<i64>,
+}
+
+///Parses the structured output of an `rdp-ntlm-info` script.
+pub fn parse(script: &Script) -> Option<RdpNtlmInfo> {
+    if script.id != "rdp-ntlm-info" || script.elements().next().is_none() {
+        return None;
+    }
+
+    let elem = |key| script.elem(key).map(String::from);
+    Some(RdpNtlmInfo {
+        target_name: elem("Target_Name"),
+        netbios_domain: elem("NetBIOS_Domain_Name"),
+        netbios_computer: elem("NetBIOS_Computer_Name"),
+        dns_domain: elem("DNS_Domain_Name"),
+        dns_computer: elem("DNS_Computer_Name"),
+        dns_tree: elem("DNS_Tree_Name"),
+        product_version: elem("Product_Version"),
+        system_time: script.elem("System_Time").and_then(parse_rfc3339),
+    })
+}
+
+///Returns the information of the `rdp-ntlm-info` script run on `port`.
+pub fn port_info(port: &Port) -> Option<RdpNtlmInfo> {
+    port.scripts.iter().find_map(parse)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    #[test]
+    fn rdp_ntlm_info() {
+        let xml = r#"<script id="rdp-ntlm-info" output="&#xa;  Target_Name: CORP">
+<elem key="Target_Name">CORP</elem>
+<elem key="NetBIOS_Domain_Name">CORP</elem>
+<elem key="NetBIOS_Computer_Name">WS01</elem>
+<elem key="DNS_Domain_Name">corp.local</elem>
+<elem key="DNS_Computer_Name">ws01.corp.local</elem>
+<elem key="DNS_Tree_Name">corp.local</elem>
+<elem key="Product_Version">10.0.17763</elem>
+<elem key="System_Time">2020-05-01T07:40:12+00:00</elem>
+</script>"#;
+        let script = Script::parse(Document::parse(xml).unwrap().root_element()).unwrap();
+        let info = parse(&script).unwrap();
+
+        assert_eq!(info.target_name.as_deref(), Some("CORP"));
+        assert_eq!(info.netbios_computer.as_deref(), Some("WS01"));
+        assert_eq!(info.dns_computer.as_deref(), Some("ws01.corp.local"));
+        assert_eq!(info.product_version.as_deref(), Some("10.0.17763"));
+        assert_eq!(info.system_time, Some(1588318812));
+    }
+}
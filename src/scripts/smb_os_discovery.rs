@@ -0,0 +1,94 @@
+//!Windows host information reported by the `smb-os-discovery` script.
+use crate::host::{Host, Script};
+use crate::util::parse_rfc3339;
+
+///What a host told about itself over SMB.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmbOsDiscovery {
+    ///Operating system, e.g. `Windows Server 2016 Standard 14393`.
+    pub os: Option<String>,
+    pub lan_manager: Option<String>,
+    ///NetBIOS computer name.
+    pub computer_name: Option<String>,
+    ///NetBIOS domain name.
+    pub domain: Option<String>,
+    pub workgroup: Option<String>,
+    pub domain_dns: Option<String>,
+    pub forest_dns: Option<String>,
+    pub fqdn: Option<String>,
+    pub cpe: Option<String>,
+    ///Time of the host, in seconds since Unix epoch.
+    pub system_time: Option<i64>,
+}
+
+///Parses the structured output of an `smb-os-discovery` script.
+pub fn parse(script: &Script) -> Option<SmbOsDiscovery> {
+    if script.id != "smb-os-discovery" || script.elements().next().is_none() {
+        return None;
+    }
+
+    //Nmap leaves the NUL terminators of NetBIOS names in, escaped.
+    let elem = |key| {
+        script
+            .elem(key)
+            .map(|value| value.trim_end_matches("\\x00").to_string())
+            .filter(|value| !value.is_empty())
+    };
+    Some(SmbOsDiscovery {
+        os: elem("os"),
+        lan_manager: elem("lanmanager"),
+        computer_name: elem("NetBIOS computer name").or_else(|| elem("server")),
+        domain: elem("NetBIOS domain name"),
+        workgroup: elem("workgroup"),
+        domain_dns: elem("domain_dns"),
+        forest_dns: elem("forest_dns"),
+        fqdn: elem("fqdn"),
+        cpe: elem("cpe"),
+        system_time: script.elem("date").and_then(parse_rfc3339),
+    })
+}
+
+///Returns the information of the `smb-os-discovery` script run on `host`.
+pub fn host_discovery(host: &Host) -> Option<SmbOsDiscovery> {
+    host.scripts().find_map(parse)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    #[test]
+    fn smb_os_discovery() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="127"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<hostscript><script id="smb-os-discovery" output="&#xa;  OS: Windows Server 2016 Standard 14393">
+<elem key="os">Windows Server 2016 Standard 14393</elem>
+<elem key="lanmanager">Windows Server 2016 Standard 6.3</elem>
+<elem key="server">DC01\x00</elem>
+<elem key="date">2020-05-01T15:40:12+02:00</elem>
+<elem key="fqdn">dc01.corp.local</elem>
+<elem key="domain_dns">corp.local</elem>
+<elem key="forest_dns">corp.local</elem>
+<elem key="workgroup">CORP\x00</elem>
+<elem key="cpe">cpe:/o:microsoft:windows_server_2016::-</elem>
+</script></hostscript>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let smb = host_discovery(results.hosts().next().unwrap()).unwrap();
+
+        assert_eq!(
+            smb.os.as_deref(),
+            Some("Windows Server 2016 Standard 14393")
+        );
+        assert_eq!(smb.computer_name.as_deref(), Some("DC01"));
+        assert_eq!(smb.workgroup.as_deref(), Some("CORP"));
+        assert_eq!(smb.domain, None);
+        assert_eq!(smb.fqdn.as_deref(), Some("dc01.corp.local"));
+        assert_eq!(smb.system_time, Some(1588340412));
+    }
+}
@@ -0,0 +1,128 @@
+//!SMB signing and authentication settings reported by the
+//!`smb-security-mode` (SMBv1) and `smb2-security-mode` scripts.
+use crate::host::{Host, Script};
+
+///The security settings of an SMB server.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmbSecurityMode {
+    ///The SMBv2 dialect the settings were read with, e.g. `3:1:1`. `None`
+    ///for SMBv1.
+    pub dialect: Option<String>,
+    pub signing_enabled: bool,
+    pub signing_required: bool,
+    ///`user` or `share`, SMBv1 only.
+    pub authentication_level: Option<String>,
+    ///Account Nmap logged in with, e.g. `guest`, SMBv1 only.
+    pub account_used: Option<String>,
+    ///`supported` or `plaintext`, SMBv1 only.
+    pub challenge_response: Option<String>,
+}
+
+///Parses the output of an `smb-security-mode` or `smb2-security-mode`
+///script.
+pub fn parse(script: &Script) -> Option<SmbSecurityMode> {
+    match script.id.as_str() {
+        "smb-security-mode" => {
+            //`  message_signing: disabled (dangerous, but default)` lines
+            //when there is no structured output.
+            let field = |key: &str| {
+                script.elem(key).map(String::from).or_else(|| {
+                    script
+                        .output
+                        .lines()
+                        .find_map(|l| l.trim().strip_prefix(key)?.strip_prefix(": "))
+                        .map(|value| value.split(" (").next().unwrap_or(value).to_string())
+                })
+            };
+            let signing = field("message_signing")?;
+            Some(SmbSecurityMode {
+                dialect: None,
+                signing_enabled: signing != "disabled",
+                signing_required: signing == "required",
+                authentication_level: field("authentication_level"),
+                account_used: field("account_used"),
+                challenge_response: field("challenge_response"),
+            })
+        }
+        //`  3:1:1: ` then `    Message signing enabled but not required`
+        "smb2-security-mode" => {
+            let (dialect, signing) = match script.elements().next() {
+                Some(table) => (
+                    table.key().map(String::from),
+                    table.elements().find_map(|e| e.value())?.to_string(),
+                ),
+                None => {
+                    let mut lines = script
+                        .output
+                        .lines()
+                        .map(str::trim)
+                        .filter(|l| !l.is_empty());
+                    let dialect = lines.next()?.trim_end_matches(':').trim().to_string();
+                    (Some(dialect), lines.next()?.to_string())
+                }
+            };
+            Some(SmbSecurityMode {
+                dialect,
+                signing_enabled: signing.starts_with("Message signing enabled"),
+                signing_required: signing.ends_with("and required"),
+                ..Default::default()
+            })
+        }
+        _ => None,
+    }
+}
+
+///Returns the settings found by the SMB security mode scripts run on
+///`host`, SMBv2 first.
+pub fn host_security_modes(host: &Host) -> Vec<SmbSecurityMode> {
+    let mut modes = host.scripts().filter_map(parse).collect::<Vec<_>>();
+    modes.sort_by_key(|mode| mode.dialect.is_none());
+    modes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    #[test]
+    fn smb_security_modes() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="127"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<hostscript>
+<script id="smb-security-mode" output="&#xa;  account_used: guest&#xa;  authentication_level: user&#xa;  challenge_response: supported&#xa;  message_signing: disabled (dangerous, but default)">
+<elem key="account_used">guest</elem>
+<elem key="authentication_level">user</elem>
+<elem key="challenge_response">supported</elem>
+<elem key="message_signing">disabled</elem>
+</script>
+<script id="smb2-security-mode" output="&#xa;  3:1:1: &#xa;    Message signing enabled and required">
+<table key="3:1:1">
+<elem>Message signing enabled and required</elem>
+</table>
+</script>
+</hostscript>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let host = results.hosts().next().unwrap();
+        let modes = host_security_modes(host);
+
+        assert_eq!(modes.len(), 2);
+        assert_eq!(modes[0].dialect.as_deref(), Some("3:1:1"));
+        assert!(modes[0].signing_enabled && modes[0].signing_required);
+        assert!(!modes[1].signing_enabled);
+        assert_eq!(modes[1].account_used.as_deref(), Some("guest"));
+
+        //The text outputs give the same settings.
+        let text = host
+            .scripts()
+            .map(|s| parse(&Script::new(s.id.clone(), s.output.clone())).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(text[0], modes[1]);
+        assert_eq!(text[1], modes[0]);
+    }
+}
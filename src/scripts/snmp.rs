@@ -0,0 +1,175 @@
+//!Device information reported by the `snmp-sysdescr` and `snmp-info`
+//!scripts.
+//!
+//!`snmp-sysdescr` reads the system description and uptime, `snmp-info` the
+//!SNMPv3 engine of the agent. Scripts that read the other objects of the
+//!`system` group, such as custom ones, are understood when they emit them
+//!as elements named after the objects, e.g. `sysContact`.
+use crate::host::Script;
+use crate::port::Port;
+
+///What an SNMP agent told about its device.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnmpInfo {
+    pub sys_descr: Option<String>,
+    pub sys_object_id: Option<String>,
+    ///Time since the agent started, in hundredths of a second.
+    pub uptime_ticks: Option<u64>,
+    pub contact: Option<String>,
+    pub location: Option<String>,
+    ///Vendor of the SNMPv3 engine, e.g. `ciscoSystems`.
+    pub enterprise: Option<String>,
+    ///Hex identifier of the SNMPv3 engine.
+    pub engine_id: Option<String>,
+    pub engine_boots: Option<u32>,
+    ///Seconds since the SNMPv3 engine last booted.
+    pub engine_time: Option<u64>,
+}
+
+impl SnmpInfo {
+    //Fills the fields `other` knows and this does not.
+    fn fill(&mut self, other: SnmpInfo) {
+        fn fill<T>(field: &mut Option<T>, other: Option<T>) {
+            if field.is_none() {
+                *field = other;
+            }
+        }
+        fill(&mut self.sys_descr, other.sys_descr);
+        fill(&mut self.sys_object_id, other.sys_object_id);
+        fill(&mut self.uptime_ticks, other.uptime_ticks);
+        fill(&mut self.contact, other.contact);
+        fill(&mut self.location, other.location);
+        fill(&mut self.enterprise, other.enterprise);
+        fill(&mut self.engine_id, other.engine_id);
+        fill(&mut self.engine_boots, other.engine_boots);
+        fill(&mut self.engine_time, other.engine_time);
+    }
+}
+
+///Parses the output of an `snmp-sysdescr` or `snmp-info` script.
+pub fn parse(script: &Script) -> Option<SnmpInfo> {
+    match script.id.as_str() {
+        "snmp-sysdescr" | "snmp-info" => {}
+        _ => return None,
+    }
+
+    let elem = |key| script.elem(key).map(String::from);
+    let mut info = SnmpInfo {
+        sys_descr: elem("sysDescr"),
+        sys_object_id: elem("sysObjectID"),
+        uptime_ticks: script.elem("sysUpTime").and_then(|t| t.parse().ok()),
+        contact: elem("sysContact"),
+        location: elem("sysLocation"),
+        enterprise: elem("enterprise"),
+        engine_id: elem("engineIDData"),
+        engine_boots: script.elem("snmpEngineBoots").and_then(|b| b.parse().ok()),
+        engine_time: script.elem("snmpEngineTime").and_then(parse_duration),
+    };
+
+    //`Cisco IOS Software, ...` then `  System uptime: 2 days, 3:04:05.67 (18384567 timeticks)`
+    if script.id == "snmp-sysdescr" {
+        let mut lines = script
+            .output
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty());
+        if info.sys_descr.is_none() {
+            info.sys_descr = lines.next().map(String::from);
+        }
+        if info.uptime_ticks.is_none() {
+            info.uptime_ticks = lines
+                .find_map(|line| line.strip_prefix("System uptime: "))
+                .and_then(|uptime| uptime.rsplit_once('(')?.1.split_once(' '))
+                .and_then(|(ticks, _)| ticks.parse().ok());
+        }
+    }
+
+    Some(info)
+}
+
+///Returns what the SNMP scripts run on `port` found.
+pub fn port_info(port: &Port) -> Option<SnmpInfo> {
+    port.scripts
+        .iter()
+        .filter_map(parse)
+        .reduce(|mut info, other| {
+            info.fill(other);
+            info
+        })
+}
+
+//`snmp-info` prints the engine time as `1d02h03m04s`.
+fn parse_duration(duration: &str) -> Option<u64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in duration.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        seconds += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+    if number.is_empty() {
+        Some(seconds)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    #[test]
+    fn snmp_scripts() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="254"/>
+<address addr="10.0.0.254" addrtype="ipv4"/>
+<ports>
+<port protocol="udp" portid="161"><state state="open" reason="udp-response" reason_ttl="254"/>
+<script id="snmp-info" output="&#xa;  enterprise: ciscoSystems">
+<elem key="enterprise">ciscoSystems</elem>
+<elem key="engineIDFormat">mac</elem>
+<elem key="engineIDData">00:1b:54:2a:8e:c0</elem>
+<elem key="snmpEngineBoots">12</elem>
+<elem key="snmpEngineTime">2d03h04m05s</elem>
+</script>
+<script id="snmp-sysdescr" output="Cisco IOS Software, C2960 Software (C2960-LANBASEK9-M), Version 12.2(55)SE&#xa;  System uptime: 2 days, 3:04:05.67 (18384567 timeticks)"/>
+</port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let port = results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .next()
+            .unwrap();
+        let info = port_info(port).unwrap();
+
+        assert_eq!(
+            info.sys_descr.as_deref(),
+            Some("Cisco IOS Software, C2960 Software (C2960-LANBASEK9-M), Version 12.2(55)SE")
+        );
+        assert_eq!(info.uptime_ticks, Some(18384567));
+        assert_eq!(info.enterprise.as_deref(), Some("ciscoSystems"));
+        assert_eq!(info.engine_id.as_deref(), Some("00:1b:54:2a:8e:c0"));
+        assert_eq!(info.engine_boots, Some(12));
+        assert_eq!(info.engine_time, Some(2 * 86400 + 3 * 3600 + 4 * 60 + 5));
+        assert_eq!(info.contact, None);
+    }
+}
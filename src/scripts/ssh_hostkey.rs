@@ -0,0 +1,108 @@
+//!Host keys reported by the `ssh-hostkey` script.
+use crate::host::Script;
+use crate::port::Port;
+
+///A public host key of an SSH server.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SshHostKey {
+    ///Key algorithm, e.g. `ssh-rsa`. Only the short name, e.g. `RSA`, is
+    ///known when the key was read from the text output.
+    pub algorithm: String,
+    pub bits: Option<u32>,
+    ///MD5 fingerprint of the key in lower case hex, without colons.
+    pub fingerprint: String,
+    ///The key, base64 encoded, if Nmap reported it.
+    pub key: Option<String>,
+}
+
+///Parses the keys of an `ssh-hostkey` script.
+pub fn parse(script: &Script) -> Option<Vec<SshHostKey>> {
+    if script.id != "ssh-hostkey" {
+        return None;
+    }
+
+    if script.elements().next().is_none() {
+        return Some(script.output.lines().filter_map(parse_line).collect());
+    }
+    Some(
+        script
+            .elements()
+            .filter_map(|key| {
+                Some(SshHostKey {
+                    algorithm: key.elem("type")?.to_string(),
+                    bits: key.elem("bits").and_then(|b| b.parse().ok()),
+                    fingerprint: normalize(key.elem("fingerprint")?),
+                    key: key.elem("key").map(String::from),
+                })
+            })
+            .collect(),
+    )
+}
+
+///Returns the keys found by the `ssh-hostkey` scripts run on `port`.
+pub fn port_keys(port: &Port) -> Vec<SshHostKey> {
+    port.scripts.iter().filter_map(parse).flatten().collect()
+}
+
+//`2048 ac:00:a0:1a:82:ff:cc:55:99:dc:67:2b:34:97:6b:75 (RSA)`
+fn parse_line(line: &str) -> Option<SshHostKey> {
+    let mut fields = line.split_whitespace();
+    let bits = fields.next()?.parse().ok()?;
+    let fingerprint = normalize(fields.next()?);
+    let algorithm = fields
+        .next()?
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .to_string();
+    Some(SshHostKey {
+        algorithm,
+        bits: Some(bits),
+        fingerprint,
+        key: None,
+    })
+}
+
+fn normalize(fingerprint: &str) -> String {
+    fingerprint.replace(':', "").to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    #[test]
+    fn ssh_hostkey() {
+        let xml = r#"<script id="ssh-hostkey" output="&#xa;  2048 ac:00:a0:1a:82:ff:cc:55:99:dc:67:2b:34:97:6b:75 (RSA)&#xa;  256 1d:c7:ae:5c:b3:cb:cd:4d:0c:3a:45:19:27:1e:e5:87 (ED25519)">
+<table>
+<elem key="fingerprint">ac00a01a82ffcc5599dc672b34976b75</elem>
+<elem key="type">ssh-rsa</elem>
+<elem key="bits">2048</elem>
+<elem key="key">AAAAB3NzaC1yc2EAAAADAQABAAABAQ</elem>
+</table>
+<table>
+<elem key="fingerprint">1dc7ae5cb3cbcd4d0c3a4519271ee587</elem>
+<elem key="type">ssh-ed25519</elem>
+<elem key="bits">256</elem>
+<elem key="key">AAAAC3NzaC1lZDI1NTE5AAAAI</elem>
+</table>
+</script>"#;
+        let doc = Document::parse(xml).unwrap();
+        let script = Script::parse(doc.root_element()).unwrap();
+        let keys = parse(&script).unwrap();
+
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].algorithm, "ssh-rsa");
+        assert_eq!(keys[0].bits, Some(2048));
+        assert_eq!(keys[0].fingerprint, "ac00a01a82ffcc5599dc672b34976b75");
+        assert_eq!(keys[1].key.as_deref(), Some("AAAAC3NzaC1lZDI1NTE5AAAAI"));
+
+        //The text output gives the same fingerprints.
+        let text = parse(&Script::new(script.id.clone(), script.output.clone())).unwrap();
+        assert_eq!(text[1].algorithm, "ED25519");
+        assert_eq!(
+            text.iter().map(|k| &k.fingerprint).collect::<Vec<_>>(),
+            keys.iter().map(|k| &k.fingerprint).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//!Certificates reported by the `ssl-cert` script.
+use crate::host::{Script, ScriptElement};
+use crate::util::parse_rfc3339;
+
+///A TLS certificate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SslCert {
+    pub subject: DistinguishedName,
+    pub issuer: DistinguishedName,
+    ///Start of the validity window, in seconds since Unix epoch.
+    pub not_before: Option<i64>,
+    ///End of the validity window, in seconds since Unix epoch.
+    pub not_after: Option<i64>,
+    ///The subject alternative names, such as `DNS:scanme.nmap.org`.
+    pub(crate) alt_names: Vec<String>,
+    ///Public key type, e.g. `rsa` or `ec`.
+    pub key_type: Option<String>,
+    pub key_bits: Option<u32>,
+    pub signature_algorithm: Option<String>,
+    ///Hex fingerprints, as Nmap prints them.
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub pem: Option<String>,
+}
+
+impl SslCert {
+    ///Returns an iterator over the subject alternative names, with their
+    ///type prefix.
+    pub fn alt_names(&self) -> std::slice::Iter<'_, String> {
+        self.alt_names.iter()
+    }
+
+    ///Returns an iterator over the DNS names among the subject alternative
+    ///names.
+    pub fn dns_names(&self) -> impl Iterator<Item = &str> {
+        self.alt_names
+            .iter()
+            .filter_map(|name| name.strip_prefix("DNS:"))
+    }
+
+    ///Returns `true` if the certificate is not valid yet or no longer
+    ///valid at `time`, in seconds since Unix epoch.
+    pub fn is_expired_at(&self, time: i64) -> bool {
+        self.not_before.is_some_and(|t| time < t) || self.not_after.is_some_and(|t| time > t)
+    }
+}
+
+///The subject or issuer of a certificate.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DistinguishedName {
+    pub(crate) attributes: Vec<(String, String)>,
+}
+
+impl DistinguishedName {
+    ///Returns an iterator over the attributes, named as Nmap names them,
+    ///e.g. `commonName` or `organizationName`.
+    pub fn attributes(&self) -> std::slice::Iter<'_, (String, String)> {
+        self.attributes.iter()
+    }
+
+    ///Returns the value of the attribute `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn common_name(&self) -> Option<&str> {
+        self.get("commonName")
+    }
+
+    pub fn organization(&self) -> Option<&str> {
+        self.get("organizationName")
+    }
+}
+
+///Parses the structured output of an `ssl-cert` script.
+pub fn parse(script: &Script) -> Option<SslCert> {
+    if script.id != "ssl-cert" {
+        return None;
+    }
+
+    let subject = distinguished_name(script.get("subject")?);
+    let issuer = script
+        .get("issuer")
+        .map(distinguished_name)
+        .unwrap_or_default();
+    let validity = script.get("validity");
+    let time = |key| validity.and_then(|v| v.elem(key)).and_then(parse_time);
+    let pubkey = script.get("pubkey");
+
+    let alt_names = script
+        .get("extensions")
+        .into_iter()
+        .flat_map(ScriptElement::elements)
+        .filter(|e| e.elem("name") == Some("X509v3 Subject Alternative Name"))
+        .filter_map(|e| e.elem("value"))
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    Some(SslCert {
+        subject,
+        issuer,
+        not_before: time("notBefore"),
+        not_after: time("notAfter"),
+        alt_names,
+        key_type: pubkey.and_then(|k| k.elem("type")).map(String::from),
+        key_bits: pubkey
+            .and_then(|k| k.elem("bits"))
+            .and_then(|b| b.parse().ok()),
+        signature_algorithm: script.elem("sig_algo").map(String::from),
+        md5: script.elem("md5").map(String::from),
+        sha1: script.elem("sha1").map(String::from),
+        sha256: script.elem("sha256").map(String::from),
+        pem: script.elem("pem").map(String::from),
+    })
+}
+
+fn distinguished_name(table: &ScriptElement) -> DistinguishedName {
+    DistinguishedName {
+        attributes: table
+            .elements()
+            .filter_map(|e| Some((e.key()?.to_string(), e.value()?.to_string())))
+            .collect(),
+    }
+}
+
+//Nmap prints validity times as `2020-04-01T00:00:00`, in UTC.
+fn parse_time(time: &str) -> Option<i64> {
+    parse_rfc3339(time).or_else(|| parse_rfc3339(&format!("{}Z", time)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    #[test]
+    fn ssl_cert() {
+        let xml = r#"<script id="ssl-cert" output="Subject: commonName=scanme.nmap.org">
+<table key="subject">
+<elem key="commonName">scanme.nmap.org</elem>
+<elem key="organizationName">Insecure.Com LLC</elem>
+</table>
+<table key="issuer">
+<elem key="commonName">R3</elem>
+<elem key="organizationName">Let&apos;s Encrypt</elem>
+</table>
+<table key="pubkey">
+<elem key="type">rsa</elem>
+<elem key="bits">2048</elem>
+</table>
+<table key="extensions">
+<table>
+<elem key="name">X509v3 Subject Alternative Name</elem>
+<elem key="value">DNS:scanme.nmap.org, DNS:www.scanme.nmap.org, IP Address:45.33.32.156</elem>
+</table>
+</table>
+<elem key="sig_algo">sha256WithRSAEncryption</elem>
+<table key="validity">
+<elem key="notBefore">2020-04-01T00:00:00</elem>
+<elem key="notAfter">2020-06-30T00:00:00</elem>
+</table>
+<elem key="md5">1f8e6b0b5c3c1e0b2a7f0a7d4f3f1f2e</elem>
+<elem key="sha1">8c2f1d0c9a1b2c3d4e5f60718293a4b5c6d7e8f9</elem>
+</script>"#;
+        let doc = Document::parse(xml).unwrap();
+        let script = Script::parse(doc.root_element()).unwrap();
+        let cert = parse(&script).unwrap();
+
+        assert_eq!(cert.subject.common_name(), Some("scanme.nmap.org"));
+        assert_eq!(cert.issuer.organization(), Some("Let's Encrypt"));
+        assert_eq!(cert.not_before, Some(1585699200));
+        assert_eq!(cert.not_after, Some(1593475200));
+        assert!(!cert.is_expired_at(1588318812));
+        assert!(cert.is_expired_at(1600000000));
+        assert_eq!(
+            cert.dns_names().collect::<Vec<_>>(),
+            ["scanme.nmap.org", "www.scanme.nmap.org"]
+        );
+        assert_eq!(cert.alt_names().count(), 3);
+        assert_eq!(cert.key_type.as_deref(), Some("rsa"));
+        assert_eq!(cert.key_bits, Some(2048));
+        assert_eq!(
+            cert.signature_algorithm.as_deref(),
+            Some("sha256WithRSAEncryption")
+        );
+        assert!(cert.sha256.is_none());
+
+        let other = Script::new("ssh-hostkey".to_string(), String::new());
+        assert!(parse(&other).is_none());
+    }
+}
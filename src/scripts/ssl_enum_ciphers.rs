@@ -0,0 +1,170 @@
+//!TLS configuration reported by the `ssl-enum-ciphers` script.
+use crate::host::{Script, ScriptElement};
+use crate::port::Port;
+
+///The protocol versions and cipher suites a TLS server accepts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SslEnumCiphers {
+    pub(crate) protocols: Vec<TlsProtocol>,
+    ///Grade of the weakest cipher suite offered, from `A` to `F`.
+    pub least_strength: Option<String>,
+}
+
+impl SslEnumCiphers {
+    ///Returns an iterator over the protocol versions the server accepts.
+    pub fn protocols(&self) -> std::slice::Iter<'_, TlsProtocol> {
+        self.protocols.iter()
+    }
+
+    ///Returns the protocol version `version`, e.g. `TLSv1.2`, if the
+    ///server accepts it.
+    pub fn protocol(&self, version: &str) -> Option<&TlsProtocol> {
+        self.protocols.iter().find(|p| p.version == version)
+    }
+}
+
+///A protocol version and the cipher suites accepted with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsProtocol {
+    ///Version, e.g. `TLSv1.2` or `SSLv3`.
+    pub version: String,
+    pub(crate) ciphers: Vec<TlsCipher>,
+    pub compressors: Vec<String>,
+    ///`server` if the server picks the cipher suite, `client` if it lets
+    ///the client do it, `indeterminate` otherwise.
+    pub cipher_preference: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+impl TlsProtocol {
+    ///Returns an iterator over the cipher suites, in the order of
+    ///preference of the server when it has one.
+    pub fn ciphers(&self) -> std::slice::Iter<'_, TlsCipher> {
+        self.ciphers.iter()
+    }
+}
+
+///A cipher suite.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsCipher {
+    ///Name, e.g. `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`.
+    pub name: String,
+    ///Key exchange parameters, e.g. `secp256r1` or `dh 2048`.
+    pub kex_info: Option<String>,
+    ///Grade from `A` to `F`.
+    pub strength: Option<String>,
+}
+
+///Parses the structured output of an `ssl-enum-ciphers` script.
+pub fn parse(script: &Script) -> Option<SslEnumCiphers> {
+    if script.id != "ssl-enum-ciphers" || script.elements().next().is_none() {
+        return None;
+    }
+
+    let protocols = script
+        .elements()
+        .filter(|e| matches!(e, ScriptElement::Table { .. }))
+        .filter_map(|table| {
+            Some(TlsProtocol {
+                version: table.key()?.to_string(),
+                ciphers: table
+                    .get("ciphers")
+                    .into_iter()
+                    .flat_map(ScriptElement::elements)
+                    .filter_map(|cipher| {
+                        Some(TlsCipher {
+                            name: cipher.elem("name")?.to_string(),
+                            kex_info: cipher.elem("kex_info").map(String::from),
+                            strength: cipher.elem("strength").map(String::from),
+                        })
+                    })
+                    .collect(),
+                compressors: values(table.get("compressors")),
+                cipher_preference: table.elem("cipher preference").map(String::from),
+                warnings: values(table.get("warnings")),
+            })
+        })
+        .collect();
+
+    Some(SslEnumCiphers {
+        protocols,
+        least_strength: script.elem("least strength").map(String::from),
+    })
+}
+
+///Returns the TLS configuration found by the `ssl-enum-ciphers` script run
+///on `port`.
+pub fn port_ciphers(port: &Port) -> Option<SslEnumCiphers> {
+    port.scripts.iter().find_map(parse)
+}
+
+fn values(table: Option<&ScriptElement>) -> Vec<String> {
+    table
+        .into_iter()
+        .flat_map(ScriptElement::elements)
+        .filter_map(ScriptElement::value)
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    #[test]
+    fn ssl_enum_ciphers() {
+        let xml = r#"<script id="ssl-enum-ciphers" output="&#xa;  TLSv1.2: ...">
+<table key="TLSv1.0">
+<table key="ciphers">
+<table>
+<elem key="kex_info">dh 1024</elem>
+<elem key="name">TLS_DHE_RSA_WITH_AES_128_CBC_SHA</elem>
+<elem key="strength">C</elem>
+</table>
+</table>
+<table key="compressors"><elem>NULL</elem></table>
+<elem key="cipher preference">client</elem>
+<table key="warnings"><elem>Key exchange (dh 1024) of lower strength than certificate key</elem></table>
+</table>
+<table key="TLSv1.2">
+<table key="ciphers">
+<table>
+<elem key="kex_info">secp256r1</elem>
+<elem key="name">TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256</elem>
+<elem key="strength">A</elem>
+</table>
+<table>
+<elem key="kex_info">secp256r1</elem>
+<elem key="name">TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384</elem>
+<elem key="strength">A</elem>
+</table>
+</table>
+<table key="compressors"><elem>NULL</elem></table>
+<elem key="cipher preference">server</elem>
+</table>
+<elem key="least strength">C</elem>
+</script>"#;
+        let script = Script::parse(Document::parse(xml).unwrap().root_element()).unwrap();
+        let tls = parse(&script).unwrap();
+
+        assert_eq!(tls.least_strength.as_deref(), Some("C"));
+        assert_eq!(
+            tls.protocols()
+                .map(|p| p.version.as_str())
+                .collect::<Vec<_>>(),
+            ["TLSv1.0", "TLSv1.2"]
+        );
+        let old = tls.protocol("TLSv1.0").unwrap();
+        assert_eq!(old.cipher_preference.as_deref(), Some("client"));
+        assert_eq!(old.warnings.len(), 1);
+        let cipher = old.ciphers().next().unwrap();
+        assert_eq!(cipher.kex_info.as_deref(), Some("dh 1024"));
+        assert_eq!(cipher.strength.as_deref(), Some("C"));
+
+        let current = tls.protocol("TLSv1.2").unwrap();
+        assert_eq!(current.ciphers().count(), 2);
+        assert_eq!(current.compressors, ["NULL"]);
+        assert!(current.warnings.is_empty());
+    }
+}
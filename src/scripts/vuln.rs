@@ -0,0 +1,170 @@
+//!Results of the scripts of the `vuln` category.
+//!
+//!Those scripts report through Nmap's `vulns` library, which gives their
+//!structured output the same shape whatever the script: one table per
+//!vulnerability checked, with its state, title, identifiers, risk factor
+//!and references. [`parse()`](fn.parse.html) reads any script with that
+//!shape.
+use std::str::FromStr;
+
+use crate::host::{Host, Script, ScriptElement};
+use crate::util::string_enum;
+
+///A vulnerability checked by a script.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VulnReport {
+    ///The script that checked it.
+    pub script_id: String,
+    ///The key of the table, usually the main identifier of the vulnerability.
+    pub key: Option<String>,
+    pub title: String,
+    pub state: VulnState,
+    ///Identifiers such as `CVE:CVE-2017-0143`.
+    pub ids: Vec<String>,
+    ///`High`, `Medium` or `Low`, as the script wrote it.
+    pub risk_factor: Option<String>,
+    ///Scores such as (`CVSSv2`, `9.3`).
+    pub scores: Vec<(String, String)>,
+    pub description: Option<String>,
+    ///Disclosure date, e.g. `2017-03-14`.
+    pub disclosure: Option<String>,
+    pub references: Vec<String>,
+}
+
+///Outcome of a vulnerability check.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VulnState {
+    NotVulnerable,
+    LikelyVulnerable,
+    Vulnerable,
+    VulnerableDos,
+    VulnerableExploitable,
+    Unknown,
+}
+
+string_enum!(VulnState, "vulnerability state", {
+    NotVulnerable => "NOT VULNERABLE",
+    LikelyVulnerable => "LIKELY VULNERABLE",
+    Vulnerable => "VULNERABLE",
+    VulnerableDos => "VULNERABLE (DoS)",
+    VulnerableExploitable => "VULNERABLE (Exploitable)",
+    Unknown => "UNKNOWN (unable to test)",
+});
+
+impl VulnState {
+    ///Returns `true` for the states confirming the vulnerability.
+    pub fn is_vulnerable(&self) -> bool {
+        matches!(
+            self,
+            VulnState::Vulnerable | VulnState::VulnerableDos | VulnState::VulnerableExploitable
+        )
+    }
+}
+
+///Parses the vulnerabilities reported in the structured output of `script`,
+///whatever its id. Returns `None` if the output does not have the shape of
+///the `vulns` library.
+pub fn parse(script: &Script) -> Option<Vec<VulnReport>> {
+    let reports = script
+        .elements()
+        .filter_map(|table| parse_report(&script.id, table))
+        .collect::<Vec<_>>();
+    if reports.is_empty() {
+        None
+    } else {
+        Some(reports)
+    }
+}
+
+///Returns the vulnerabilities reported by the scripts run on `host` and
+///its ports.
+pub fn host_reports(host: &Host) -> Vec<VulnReport> {
+    host.scripts()
+        .chain(host.port_info.ports().flat_map(|p| p.scripts.iter()))
+        .filter_map(parse)
+        .flatten()
+        .collect()
+}
+
+fn parse_report(script_id: &str, table: &ScriptElement) -> Option<VulnReport> {
+    let state = VulnState::from_str(table.elem("state")?).ok()?;
+    let values = |key| {
+        table
+            .get(key)
+            .into_iter()
+            .flat_map(ScriptElement::elements)
+            .filter_map(ScriptElement::value)
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    let description = values("description");
+
+    Some(VulnReport {
+        script_id: script_id.to_string(),
+        key: table.key().map(String::from),
+        title: table.elem("title")?.to_string(),
+        state,
+        ids: values("ids"),
+        risk_factor: table.elem("risk_factor").map(String::from),
+        scores: table
+            .get("scores")
+            .into_iter()
+            .flat_map(ScriptElement::elements)
+            .filter_map(|e| Some((e.key()?.to_string(), e.value()?.to_string())))
+            .collect(),
+        description: if description.is_empty() {
+            None
+        } else {
+            Some(description.join("\n"))
+        },
+        disclosure: table.elem("disclosure").map(String::from),
+        references: values("refs"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roxmltree::Document;
+
+    #[test]
+    fn vuln_report() {
+        let xml = r#"<script id="smb-vuln-ms17-010" output="&#xa;  VULNERABLE:">
+<table key="CVE-2017-0143">
+<elem key="title">Remote Code Execution vulnerability in Microsoft SMBv1 servers (ms17-010)</elem>
+<elem key="state">VULNERABLE</elem>
+<table key="ids">
+<elem>CVE:CVE-2017-0143</elem>
+</table>
+<elem key="risk_factor">HIGH</elem>
+<table key="scores"><elem key="CVSSv2">9.3</elem></table>
+<table key="description">
+<elem>A critical remote code execution vulnerability exists in Microsoft SMBv1&#xa;servers (ms17-010).</elem>
+</table>
+<table key="dates"><table key="disclosure"><elem key="year">2017</elem><elem key="month">03</elem><elem key="day">14</elem></table></table>
+<elem key="disclosure">2017-03-14</elem>
+<table key="refs">
+<elem>https://cve.mitre.org/cgi-bin/cvename.cgi?name=CVE-2017-0143</elem>
+<elem>https://technet.microsoft.com/en-us/library/security/ms17-010.aspx</elem>
+</table>
+</table>
+</script>"#;
+        let script = Script::parse(Document::parse(xml).unwrap().root_element()).unwrap();
+        let reports = parse(&script).unwrap();
+
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(report.script_id, "smb-vuln-ms17-010");
+        assert_eq!(report.key.as_deref(), Some("CVE-2017-0143"));
+        assert_eq!(report.state, VulnState::Vulnerable);
+        assert!(report.state.is_vulnerable());
+        assert_eq!(report.ids, ["CVE:CVE-2017-0143"]);
+        assert_eq!(report.risk_factor.as_deref(), Some("HIGH"));
+        assert_eq!(report.scores, [("CVSSv2".to_string(), "9.3".to_string())]);
+        assert_eq!(report.disclosure.as_deref(), Some("2017-03-14"));
+        assert_eq!(report.references.len(), 2);
+
+        let other = Script::new("http-title".to_string(), "Title".to_string());
+        assert!(parse(&other).is_none());
+    }
+}
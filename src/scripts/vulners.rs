@@ -0,0 +1,214 @@
+//!Vulnerabilities reported by the `vulners` and `vulscan` scripts.
+//!
+//!`vulners` lists the vulnerabilities known for the CPEs of a service along
+//!with their CVSS score and whether an exploit is public. `vulscan` lists
+//!entries matching the service version in a set of offline databases and
+//!carries no score.
+use crate::host::Script;
+use crate::port::Port;
+
+///A vulnerability found by a script.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VulnFinding {
+    ///The identifier in the database it was found in, such as `CVE-2020-15778`.
+    pub id: String,
+    pub cvss: Option<f64>,
+    ///`true` if `vulners` flagged a public exploit.
+    pub exploit: bool,
+    ///The type of the entry for `vulners` (`cve`, `packetstorm`, ...), the
+    ///database name for `vulscan`.
+    pub source: Option<String>,
+    ///The CPE the entry was matched on, `vulners` only.
+    pub cpe: Option<String>,
+    pub url: Option<String>,
+    ///The title of the entry, `vulscan` only.
+    pub description: Option<String>,
+}
+
+impl VulnFinding {
+    fn new(id: &str) -> Self {
+        VulnFinding {
+            id: id.to_string(),
+            cvss: None,
+            exploit: false,
+            source: None,
+            cpe: None,
+            url: None,
+            description: None,
+        }
+    }
+}
+
+///Parses the findings of a `vulners` or `vulscan` script.
+pub fn parse(script: &Script) -> Option<Vec<VulnFinding>> {
+    match script.id.as_str() {
+        "vulners" if script.elements().next().is_some() => Some(parse_vulners_elements(script)),
+        "vulners" => Some(parse_vulners_output(&script.output)),
+        "vulscan" => Some(parse_vulscan_output(&script.output)),
+        _ => None,
+    }
+}
+
+///Returns the findings of all the `vulners` and `vulscan` scripts run on
+///`port`.
+pub fn port_findings(port: &Port) -> Vec<VulnFinding> {
+    port.scripts.iter().filter_map(parse).flatten().collect()
+}
+
+//<table key="cpe:/a:openbsd:openssh:7.4"><table><elem key="id">...
+fn parse_vulners_elements(script: &Script) -> Vec<VulnFinding> {
+    let mut findings = Vec::new();
+    for cpe in script.elements() {
+        for entry in cpe.elements() {
+            let id = match entry.elem("id") {
+                Some(id) => id,
+                None => continue,
+            };
+            let mut finding = VulnFinding::new(id);
+            finding.cvss = entry.elem("cvss").and_then(|c| c.parse().ok());
+            finding.exploit = entry.elem("is_exploit") == Some("true");
+            finding.source = entry.elem("type").map(String::from);
+            finding.cpe = cpe.key().map(String::from);
+            finding.url = finding
+                .source
+                .as_ref()
+                .map(|source| format!("https://vulners.com/{}/{}", source, id));
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+//  cpe:/a:openbsd:openssh:7.4:
+//      CVE-2020-15778  6.8  https://vulners.com/cve/CVE-2020-15778  *EXPLOIT*
+fn parse_vulners_output(output: &str) -> Vec<VulnFinding> {
+    let mut findings = Vec::new();
+    let mut cpe = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("cpe:") {
+            cpe = Some(line.trim_end_matches(':').to_string());
+            continue;
+        }
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() < 2 {
+            continue;
+        }
+        let cvss = match fields[1].parse::<f64>() {
+            Ok(cvss) => cvss,
+            Err(_) => continue,
+        };
+        let mut finding = VulnFinding::new(fields[0]);
+        finding.cvss = Some(cvss);
+        finding.exploit = fields.contains(&"*EXPLOIT*");
+        finding.cpe = cpe.clone();
+        finding.url = fields
+            .get(2)
+            .filter(|url| url.starts_with("http"))
+            .map(|url| url.to_string());
+        finding.source = finding
+            .url
+            .as_deref()
+            .and_then(|url| url.strip_prefix("https://vulners.com/"))
+            .and_then(|path| path.split('/').next())
+            .map(String::from);
+        findings.push(finding);
+    }
+    findings
+}
+
+//VulDB - https://vuldb.com:
+//[12345] OpenSSH up to 7.4 ...
+fn parse_vulscan_output(output: &str) -> Vec<VulnFinding> {
+    let mut findings = Vec::new();
+    let mut database = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(entry) = line.strip_prefix('[') {
+            if let Some((id, title)) = entry.split_once(']') {
+                let mut finding = VulnFinding::new(id);
+                finding.source = database.clone();
+                let title = title.trim();
+                if !title.is_empty() {
+                    finding.description = Some(title.to_string());
+                }
+                findings.push(finding);
+            }
+        } else if let Some((name, _)) = line.strip_suffix(':').and_then(|l| l.split_once(" - ")) {
+            database = Some(name.to_string());
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NmapResults;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/>
+<script id="vulners" output="&#xa;  cpe:/a:openbsd:openssh:7.4: &#xa;    &#x9;CVE-2020-15778&#x9;6.8&#x9;https://vulners.com/cve/CVE-2020-15778&#xa;    &#x9;EDB-ID:46516&#x9;5.8&#x9;https://vulners.com/exploitdb/EDB-ID:46516&#x9;*EXPLOIT*&#xa;">
+<table key="cpe:/a:openbsd:openssh:7.4">
+<table>
+<elem key="is_exploit">false</elem>
+<elem key="cvss">6.8</elem>
+<elem key="id">CVE-2020-15778</elem>
+<elem key="type">cve</elem>
+</table>
+<table>
+<elem key="is_exploit">true</elem>
+<elem key="cvss">5.8</elem>
+<elem key="id">EDB-ID:46516</elem>
+<elem key="type">exploitdb</elem>
+</table>
+</table>
+</script>
+<script id="vulscan" output="VulDB - https://vuldb.com:&#xa;[99346] OpenSSH up to 7.4 scp Client privilege escalation&#xa;&#xa;MITRE CVE - https://cve.mitre.org:&#xa;No findings&#xa;"/>
+</port>
+</ports>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn vulners_and_vulscan() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let port = results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .next()
+            .unwrap();
+
+        let findings = port_findings(port);
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0].id, "CVE-2020-15778");
+        assert_eq!(findings[0].cvss, Some(6.8));
+        assert!(!findings[0].exploit);
+        assert_eq!(
+            findings[0].cpe.as_deref(),
+            Some("cpe:/a:openbsd:openssh:7.4")
+        );
+        assert!(findings[1].exploit);
+        assert_eq!(
+            findings[1].url.as_deref(),
+            Some("https://vulners.com/exploitdb/EDB-ID:46516")
+        );
+        assert_eq!(findings[2].id, "99346");
+        assert_eq!(findings[2].source.as_deref(), Some("VulDB"));
+        assert_eq!(
+            findings[2].description.as_deref(),
+            Some("OpenSSH up to 7.4 scp Client privilege escalation")
+        );
+
+        //The text output gives the same findings as the tables.
+        let text = Script::new("vulners".to_string(), port.scripts[0].output.clone());
+        assert_eq!(parse(&text).unwrap(), findings[..2]);
+    }
+}
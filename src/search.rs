@@ -0,0 +1,785 @@
+//!Searching the text found by a scan.
+//!
+//![`search_scripts()`](fn.search_scripts.html) looks for plain text in
+//!script outputs. [`search_fields()`](fn.search_fields.html) applies a
+//![`Matcher`](trait.Matcher.html), such as a [`Pattern`](struct.Pattern.html),
+//!to hostnames, service information and script outputs:
+//!
+//!```
+//!# use std::path::PathBuf;
+//!# use std::fs;
+//!use nmap_xml_parser::NmapResults;
+//!# let mut nmap_xml_file = PathBuf::new();
+//!# nmap_xml_file.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+//!# nmap_xml_file.push("tests/test.xml");
+//!let content = fs::read_to_string(nmap_xml_file).unwrap();
+//!let results = NmapResults::parse(&content).unwrap();
+//!let matches = results.search_pattern("(?i)^(ssh|telnet)$").unwrap();
+//!assert_eq!(matches.len(), 1);
+//!```
+//!
+//!`Pattern` supports a subset of the usual regular expression syntax:
+//!literals, `.`, character classes such as `[a-z0-9_]` and `[^/]`, the
+//!escapes `\d`, `\w` and `\s`, groups, alternation with `|`, the
+//!repetitions `*`, `+`, `?` and `{m,n}`, the anchors `^` and `$`, and a
+//!leading `(?i)` for case-insensitive matching. Matching takes time linear
+//!in the length of the text, so long script outputs and patterns such as
+//!`(a*)*b` are safe to search. Other engines, such as the `regex` crate,
+//!can be used by implementing `Matcher` for them.
+use std::fmt;
+
+use crate::host::{Host, Script};
+use crate::port::Port;
+use crate::{Error, NmapResults};
+
+///A line of script output matching a search.
+#[derive(Clone, Debug)]
+pub struct ScriptMatch<'a> {
+    pub host: &'a Host,
+
+    ///The port the script ran against, `None` for host scripts.
+    pub port: Option<&'a Port>,
+
+    pub script_id: &'a str,
+
+    ///The whole matching line, trimmed.
+    pub context: &'a str,
+}
+
+///Returns the lines of host and port script outputs containing `text`,
+///compared case-insensitively, in scan order.
+pub fn search_scripts<'a>(results: &'a NmapResults, text: &str) -> Vec<ScriptMatch<'a>> {
+    let needle = text.to_lowercase();
+    let mut matches = Vec::new();
+
+    for host in results.hosts() {
+        let scripts = host.scripts().map(|s| (None, s)).chain(
+            host.port_info
+                .ports()
+                .flat_map(|p| p.scripts.iter().map(move |s| (Some(p), s))),
+        );
+        for (port, script) in scripts {
+            push_matches(&mut matches, host, port, script, |line| {
+                line.to_lowercase().contains(&needle)
+            });
+        }
+    }
+
+    matches
+}
+
+fn push_matches<'a, F: Fn(&str) -> bool>(
+    matches: &mut Vec<ScriptMatch<'a>>,
+    host: &'a Host,
+    port: Option<&'a Port>,
+    script: &'a Script,
+    is_match: F,
+) {
+    for line in script.output.lines().map(str::trim) {
+        if is_match(line) {
+            matches.push(ScriptMatch {
+                host,
+                port,
+                script_id: &script.id,
+                context: line,
+            });
+        }
+    }
+}
+
+///Something that finds matches in text.
+pub trait Matcher {
+    ///Returns the byte range of the first match in `text`, if any.
+    fn find(&self, text: &str) -> Option<(usize, usize)>;
+}
+
+///Plain, case-sensitive substring matching.
+impl Matcher for str {
+    fn find(&self, text: &str) -> Option<(usize, usize)> {
+        text.find(self).map(|start| (start, start + self.len()))
+    }
+}
+
+///The field of the scan a [`FieldMatch`](struct.FieldMatch.html) was found
+///in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchField<'a> {
+    HostName,
+    ServiceName,
+    ServiceProduct,
+    ServiceVersion,
+    ServiceExtraInfo,
+    ///A line of the output of the script with this id.
+    ScriptOutput(&'a str),
+}
+
+///A match of a [`Matcher`](trait.Matcher.html) in a field of the scan.
+#[derive(Clone, Debug)]
+pub struct FieldMatch<'a> {
+    pub host: &'a Host,
+
+    ///The port the field belongs to, `None` for host fields and scripts.
+    pub port: Option<&'a Port>,
+
+    pub field: SearchField<'a>,
+
+    ///The field value, or the line for script outputs.
+    pub text: &'a str,
+
+    ///Byte range of the match in `text`.
+    pub range: (usize, usize),
+}
+
+///Applies `matcher` to the hostnames, service names, products, versions and
+///extra information, and to each line of the script outputs of the scan.
+///Matches are returned in scan order, at most one per field or line.
+pub fn search_fields<'a, M: Matcher + ?Sized>(
+    results: &'a NmapResults,
+    matcher: &M,
+) -> Vec<FieldMatch<'a>> {
+    let mut matches = Vec::new();
+
+    for host in results.hosts() {
+        let mut check = |port: Option<&'a Port>, field: SearchField<'a>, text: &'a str| {
+            if let Some(range) = matcher.find(text) {
+                matches.push(FieldMatch {
+                    host,
+                    port,
+                    field,
+                    text,
+                    range,
+                });
+            }
+        };
+
+        for name in host.host_names() {
+            check(None, SearchField::HostName, &name.name);
+        }
+        for script in host.scripts() {
+            for line in script.output.lines().map(str::trim) {
+                check(None, SearchField::ScriptOutput(&script.id), line);
+            }
+        }
+        for port in host.port_info.ports() {
+            if let Some(service) = &port.service_info {
+                check(Some(port), SearchField::ServiceName, &service.name);
+                let optional = [
+                    (SearchField::ServiceProduct, &service.product),
+                    (SearchField::ServiceVersion, &service.version),
+                    (SearchField::ServiceExtraInfo, &service.extra_info),
+                ];
+                for (field, value) in optional.iter() {
+                    if let Some(value) = value {
+                        check(Some(port), field.clone(), value);
+                    }
+                }
+            }
+            for script in &port.scripts {
+                for line in script.output.lines().map(str::trim) {
+                    check(Some(port), SearchField::ScriptOutput(&script.id), line);
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+///A compiled pattern, in the subset of regular expression syntax described
+///in the [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    source: String,
+    program: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+type Alternatives = Vec<Vec<Node>>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<(char, char)>, bool),
+    Start,
+    End,
+    Group(Alternatives),
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+impl Pattern {
+    ///Compiles `pattern`.
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        let (body, case_insensitive) = match pattern.strip_prefix("(?i)") {
+            Some(body) => (body, true),
+            None => (pattern, false),
+        };
+        let mut parser = PatternParser {
+            chars: body.chars().collect(),
+            pos: 0,
+            depth: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err(invalid_pattern("unmatched `)`"));
+        }
+
+        Ok(Pattern {
+            source: pattern.to_string(),
+            program: Compiler::compile(&alternatives)?,
+            case_insensitive,
+        })
+    }
+
+    ///Returns whether the pattern matches somewhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    fn match_char(&self, node: &Node, c: char) -> bool {
+        let fold = |c: char| {
+            if self.case_insensitive {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                c
+            }
+        };
+        match node {
+            Node::Any => c != '\n',
+            Node::Char(expected) => fold(*expected) == fold(c),
+            Node::Class(ranges, negated) => {
+                let c = fold(c);
+                let found = ranges.iter().any(|&(low, high)| {
+                    (low..=high).contains(&c)
+                        || (self.case_insensitive
+                            && ((fold(low)..=fold(high)).contains(&c)
+                                || c.to_uppercase().any(|u| (low..=high).contains(&u))))
+                });
+                found != *negated
+            }
+            _ => false,
+        }
+    }
+
+    //Adds the threads reachable from `pc` without consuming a character
+    //to `threads`, in priority order.
+    fn add_thread(&self, threads: &mut Threads, pc: usize, start: usize, pos: usize, len: usize) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if !threads.visit(pc) {
+                continue;
+            }
+            match &self.program[pc] {
+                Inst::Jump(to) => stack.push(*to),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == len => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                Inst::Step(_) | Inst::Match => threads.list.push((pc, start)),
+            }
+        }
+    }
+}
+
+///Runs the compiled program over the text as a Pike VM: all threads move
+///forward one character at a time, so matching takes time linear in the
+///length of the text, whatever the pattern. Threads are kept in priority
+///order, which gives the leftmost match, greedy as a backtracking engine
+///would find it.
+impl Matcher for Pattern {
+    fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut found = None;
+
+        let mut pos = 0;
+        loop {
+            //A thread starting here ranks below those started earlier.
+            if found.is_none() {
+                self.add_thread(&mut current, 0, pos, pos, text.len());
+            }
+            if current.list.is_empty() && found.is_some() {
+                break;
+            }
+
+            let c = text[pos..].chars().next();
+            let next_pos = pos + c.map_or(0, char::len_utf8);
+            for &(pc, start) in &current.list {
+                match &self.program[pc] {
+                    Inst::Match => {
+                        found = Some((start, pos));
+                        //Lower priority threads cannot win anymore.
+                        break;
+                    }
+                    Inst::Step(node) => {
+                        if c.is_some_and(|c| self.match_char(node, c)) {
+                            self.add_thread(&mut next, pc + 1, start, next_pos, text.len());
+                        }
+                    }
+                    _ => unreachable!("only steps and matches are queued"),
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+            if c.is_none() {
+                break;
+            }
+            pos = next_pos;
+        }
+        found
+    }
+}
+
+//An instruction of a compiled pattern.
+#[derive(Clone, Debug, PartialEq)]
+enum Inst {
+    //Consumes a character matching `Char`, `Any` or `Class`.
+    Step(Node),
+    Start,
+    End,
+    //Continues at both, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+//Patterns compiling to more instructions are refused, e.g. nested counted
+//repetitions such as `(a{1000}){1000}`.
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn compile(alternatives: &[Vec<Node>]) -> Result<Vec<Inst>, Error> {
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.alternatives(alternatives)?;
+        compiler.push(Inst::Match)?;
+        Ok(compiler.program)
+    }
+
+    fn push(&mut self, inst: Inst) -> Result<usize, Error> {
+        if self.program.len() >= MAX_INSTRUCTIONS {
+            return Err(invalid_pattern("pattern too large"));
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    //`a|b|c` as `split(a, split(b, c))`, each branch jumping to the end.
+    fn alternatives(&mut self, alternatives: &[Vec<Node>]) -> Result<(), Error> {
+        let mut jumps = Vec::new();
+        for (i, sequence) in alternatives.iter().enumerate() {
+            if i + 1 == alternatives.len() {
+                self.sequence(sequence)?;
+                break;
+            }
+            let split = self.push(Inst::Split(0, 0))?;
+            self.sequence(sequence)?;
+            jumps.push(self.push(Inst::Jump(0))?);
+            self.program[split] = Inst::Split(split + 1, self.program.len());
+        }
+        let end = self.program.len();
+        for jump in jumps {
+            self.program[jump] = Inst::Jump(end);
+        }
+        Ok(())
+    }
+
+    fn sequence(&mut self, sequence: &[Node]) -> Result<(), Error> {
+        sequence.iter().try_for_each(|node| self.node(node))
+    }
+
+    fn node(&mut self, node: &Node) -> Result<(), Error> {
+        match node {
+            Node::Group(alternatives) => self.alternatives(alternatives),
+            Node::Repeat(node, min, max) => {
+                for _ in 0..*min {
+                    self.node(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.node(node)?;
+                        self.push(Inst::Jump(split))?;
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.node(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Node::Start => self.push(Inst::Start).map(drop),
+            Node::End => self.push(Inst::End).map(drop),
+            node => self.push(Inst::Step(node.clone())).map(drop),
+        }
+    }
+}
+
+//The threads of a Pike VM at one position of the text, each a program
+//counter and the position its match started at.
+struct Threads {
+    list: Vec<(usize, usize)>,
+    //`generation` for the instructions visited at this position
+    visited: Vec<usize>,
+    generation: usize,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads {
+            list: Vec::new(),
+            visited: vec![0; len],
+            generation: 1,
+        }
+    }
+
+    //Marks `pc` visited, returning `false` if it already was.
+    fn visit(&mut self, pc: usize) -> bool {
+        let first = self.visited[pc] != self.generation;
+        self.visited[pc] = self.generation;
+        first
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.generation += 1;
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+//Groups nested deeper are refused, so that parsing and compiling them
+//cannot overflow the stack.
+const MAX_GROUP_DEPTH: usize = 64;
+
+struct PatternParser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl PatternParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn alternatives(&mut self) -> Result<Alternatives, Error> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, Error> {
+        let mut sequence = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            sequence.push(self.repetition(atom)?);
+        }
+        Ok(sequence)
+    }
+
+    fn repetition(&mut self, atom: Node) -> Result<Node, Error> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let bounds = self.until('}')?;
+                let number = |s: &str| {
+                    s.trim().parse::<usize>().map_err(|_| {
+                        invalid_pattern(format!("invalid repetition `{{{}}}`", bounds))
+                    })
+                };
+                let (min, max) = match bounds.split_once(',') {
+                    Some((min, "")) => (number(min)?, None),
+                    Some((min, max)) => (number(min)?, Some(number(max)?)),
+                    None => (number(&bounds)?, Some(number(&bounds)?)),
+                };
+                if max.is_some_and(|max| max < min) {
+                    return Err(invalid_pattern(format!(
+                        "invalid repetition `{{{}}}`",
+                        bounds
+                    )));
+                }
+                return Ok(Node::Repeat(Box::new(atom), min, max));
+            }
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            return Err(invalid_pattern("repetition of an anchor"));
+        }
+        self.pos += 1;
+        Ok(Node::Repeat(Box::new(atom), min, max))
+    }
+
+    fn until(&mut self, end: char) -> Result<String, Error> {
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == end => return Ok(text),
+                Some(c) => text.push(c),
+                None => return Err(invalid_pattern(format!("missing `{}`", end))),
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, Error> {
+        match self.next() {
+            Some('(') => {
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                self.depth += 1;
+                if self.depth > MAX_GROUP_DEPTH {
+                    return Err(invalid_pattern(format!(
+                        "groups nested deeper than {} levels",
+                        MAX_GROUP_DEPTH
+                    )));
+                }
+                let alternatives = self.alternatives()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(')') => Ok(Node::Group(alternatives)),
+                    _ => Err(invalid_pattern("missing `)`")),
+                }
+            }
+            Some('[') => self.class(),
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => self.escape(),
+            Some(c @ ('*' | '+' | '?' | '{')) => {
+                Err(invalid_pattern(format!("nothing to repeat before `{}`", c)))
+            }
+            Some(c) => Ok(Node::Char(c)),
+            None => Err(invalid_pattern("unexpected end of pattern")),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, Error> {
+        let c = self
+            .next()
+            .ok_or_else(|| invalid_pattern("trailing `\\`"))?;
+        Ok(match escape_class(c) {
+            Some(class) => class,
+            None => Node::Char(match c {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                c => c,
+            }),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, Error> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = match self.next() {
+                Some(']') if !first => break,
+                Some('\\') => {
+                    let c = self.next().ok_or_else(|| invalid_pattern("missing `]`"))?;
+                    if let Some(Node::Class(class, false)) = escape_class(c) {
+                        ranges.extend(class);
+                        first = false;
+                        continue;
+                    }
+                    c
+                }
+                Some(c) => c,
+                None => return Err(invalid_pattern("missing `]`")),
+            };
+            first = false;
+
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&n| n != ']') {
+                self.pos += 1;
+                let high = self.next().expect("checked above");
+                if high < c {
+                    return Err(invalid_pattern(format!("invalid range `{}-{}`", c, high)));
+                }
+                ranges.push((c, high));
+            } else {
+                ranges.push((c, c));
+            }
+        }
+
+        Ok(Node::Class(ranges, negated))
+    }
+}
+
+fn escape_class(c: char) -> Option<Node> {
+    let (ranges, negated): (&[(char, char)], bool) = match c {
+        'd' => (&[('0', '9')], false),
+        'D' => (&[('0', '9')], true),
+        'w' => (&[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], false),
+        'W' => (&[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], true),
+        's' => (
+            &[(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')],
+            false,
+        ),
+        'S' => (
+            &[(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')],
+            true,
+        ),
+        _ => return None,
+    };
+    Some(Node::Class(ranges.to_vec(), negated))
+}
+
+fn invalid_pattern(message: impl Into<String>) -> Error {
+    Error::InvalidPattern(message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn script_search() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><script id="http-title" output="Apache Tomcat/8.5.5"/><script id="http-headers" output="&#xa;  Server: Apache-Coyote/1.1&#xa;  Content-Type: text/html&#xa;"/></port>
+</ports>
+<hostscript><script id="smb-os-discovery" output="&#xa;  OS: Windows Server 2016&#xa;  Computer name: TOMCAT01&#xa;"/></hostscript>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        let matches = results.search_scripts("tomcat");
+        assert_eq!(matches.len(), 2);
+        assert!(matches[0].port.is_none());
+        assert_eq!(matches[0].script_id, "smb-os-discovery");
+        assert_eq!(matches[0].context, "Computer name: TOMCAT01");
+        assert_eq!(matches[1].port.unwrap().port_number, 80);
+        assert_eq!(matches[1].context, "Apache Tomcat/8.5.5");
+
+        assert!(search_scripts(&results, "jboss").is_empty());
+    }
+
+    #[test]
+    fn patterns() {
+        let find = |pattern: &str, text: &str| Pattern::new(pattern).unwrap().find(text);
+
+        assert_eq!(find("jboss|tomcat", "Apache Tomcat"), None);
+        assert_eq!(find("(?i)jboss|tomcat", "Apache Tomcat"), Some((7, 13)));
+        assert_eq!(find("^Apache", "Apache Tomcat"), Some((0, 6)));
+        assert_eq!(find("^Tomcat", "Apache Tomcat"), None);
+        assert_eq!(find("\\d+\\.\\d+", "OpenSSH 8.2p1"), Some((8, 11)));
+        assert_eq!(find("a.*c", "xabcbcx"), Some((1, 6)));
+        assert_eq!(find("colou?r", "color"), Some((0, 5)));
+        assert_eq!(find("[^ ]+$", "nginx 1.18.0"), Some((6, 12)));
+        assert_eq!(find("(ab){2,}", "abababx"), Some((0, 6)));
+        assert_eq!(find("x{2}", "xxx"), Some((0, 2)));
+        assert_eq!(find("(?i)[a-c]+", "xxBCa"), Some((2, 5)));
+        assert_eq!(find("(a*)*b", "aaab"), Some((0, 4)));
+        assert_eq!(find("é", "café"), Some((3, 5)));
+
+        for invalid in &[
+            "(ab",
+            "ab)",
+            "[ab",
+            "*a",
+            "a{2,1}",
+            "a{x}",
+            "\\",
+            "(a{1000}){1000}",
+        ] {
+            assert!(Pattern::new(invalid).is_err(), "{}", invalid);
+        }
+        let nested = |depth| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Pattern::new(&nested(64)).is_ok());
+        assert!(Pattern::new(&nested(65)).is_err());
+        assert!(Pattern::new(&nested(200_000)).is_err());
+    }
+
+    #[test]
+    fn patterns_on_long_input() {
+        let line = "x".to_string() + &"a".repeat(50_000);
+        assert_eq!(Pattern::new("x.*y").unwrap().find(&line), None);
+        assert_eq!(Pattern::new("a.*z").unwrap().find(&line[1..20_001]), None);
+        assert_eq!(
+            Pattern::new("x.*a$").unwrap().find(&line),
+            Some((0, line.len()))
+        );
+
+        //Exponential for a backtracking engine.
+        let a = "a".repeat(30);
+        assert_eq!(Pattern::new("(a*)*b").unwrap().find(&a), None);
+        assert_eq!(
+            Pattern::new("(a*)*b").unwrap().find(&(a.clone() + "b")),
+            Some((0, 31))
+        );
+        assert_eq!(Pattern::new("(a|aa)+$").unwrap().find(&a), Some((0, 30)));
+    }
+
+    #[test]
+    fn field_search() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="tomcat01.example.com" type="PTR"/></hostnames>
+<ports>
+<port protocol="tcp" portid="8080"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" product="Apache Tomcat" version="8.5.5" method="probed" conf="10"/><script id="http-title" output="JBoss Welcome"/></port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        let matches = results.search_pattern("(?i)jboss|tomcat").unwrap();
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[0].field, SearchField::HostName);
+        assert_eq!(matches[0].range, (0, 6));
+        assert_eq!(matches[1].field, SearchField::ServiceProduct);
+        assert_eq!(matches[1].port.unwrap().port_number, 8080);
+        assert_eq!(matches[2].field, SearchField::ScriptOutput("http-title"));
+        assert_eq!(matches[2].text, "JBoss Welcome");
+
+        assert_eq!(search_fields(&results, "8.5").len(), 1);
+        assert!(results.search_pattern("(jboss").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//!Recurring scans of the same targets.
+//!
+//!A [`ScanSeries`](struct.ScanSeries.html) keeps the results of several
+//!scans ordered by start time, and tells when hosts and ports were first
+//!and last seen. A host is seen by a scan when it is up, a port when it is
+//!open. Times are seconds since Unix epoch, taken from the host when Nmap
+//!recorded them and from the scan otherwise.
+use std::net::IpAddr;
+
+use crate::host::{Host, HostState};
+use crate::port::PortProtocol;
+use crate::NmapResults;
+
+///Results of several scans ordered by start time.
+#[derive(Clone, Debug, Default)]
+pub struct ScanSeries {
+    pub(crate) scans: Vec<NmapResults>,
+}
+
+impl ScanSeries {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Adds the results of a scan to the series. Scans starting at the same
+    ///time are kept in the order they were added.
+    pub fn push(&mut self, results: NmapResults) {
+        let position = self
+            .scans
+            .partition_point(|s| s.scan_start_time <= results.scan_start_time);
+        self.scans.insert(position, results);
+    }
+
+    ///Returns an iterator over the scans, oldest first.
+    pub fn scans(&self) -> std::slice::Iter<'_, NmapResults> {
+        self.scans.iter()
+    }
+
+    ///Returns the most recent scan, if any.
+    pub fn latest(&self) -> Option<&NmapResults> {
+        self.scans.last()
+    }
+
+    ///Returns when the host with the IP address `addr` was first seen up.
+    pub fn first_seen(&self, addr: &IpAddr) -> Option<i64> {
+        self.sightings(addr).next()
+    }
+
+    ///Returns when the host with the IP address `addr` was last seen up.
+    pub fn last_seen(&self, addr: &IpAddr) -> Option<i64> {
+        self.sightings(addr).last()
+    }
+
+    ///Returns when `port` over `protocol` was first seen open on the host
+    ///with the IP address `addr`.
+    pub fn port_first_seen(&self, addr: &IpAddr, protocol: PortProtocol, port: u16) -> Option<i64> {
+        self.port_sightings(addr, protocol, port).next()
+    }
+
+    ///Returns when `port` over `protocol` was last seen open on the host
+    ///with the IP address `addr`.
+    pub fn port_last_seen(&self, addr: &IpAddr, protocol: PortProtocol, port: u16) -> Option<i64> {
+        self.port_sightings(addr, protocol, port).last()
+    }
+
+    //The times of the scans that found the host up, oldest first.
+    fn sightings<'a>(&'a self, addr: &'a IpAddr) -> impl Iterator<Item = i64> + 'a {
+        self.scans.iter().filter_map(move |scan| {
+            scan.host_by_addr(addr)
+                .filter(|h| h.status.state == HostState::Up)
+                .map(|h| seen_at(scan, h))
+        })
+    }
+
+    fn port_sightings<'a>(
+        &'a self,
+        addr: &'a IpAddr,
+        protocol: PortProtocol,
+        port: u16,
+    ) -> impl Iterator<Item = i64> + 'a {
+        self.scans.iter().filter_map(move |scan| {
+            scan.host_by_addr(addr)
+                .filter(|h| h.port_info.is_open(protocol.clone(), port))
+                .map(|h| seen_at(scan, h))
+        })
+    }
+}
+
+impl From<Vec<NmapResults>> for ScanSeries {
+    fn from(mut scans: Vec<NmapResults>) -> Self {
+        scans.sort_by_key(|s| s.scan_start_time);
+        ScanSeries { scans }
+    }
+}
+
+pub(crate) fn seen_at(scan: &NmapResults, host: &Host) -> i64 {
+    host.scan_start_time.unwrap_or(scan.scan_start_time)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scan(start: i64, ports: &[u16]) -> NmapResults {
+        let ports = ports
+            .iter()
+            .map(|p| {
+                format!(
+                    r#"<port protocol="tcp" portid="{}"><state state="open" reason="syn-ack" reason_ttl="53"/></port>"#,
+                    p
+                )
+            })
+            .collect::<String>();
+        let xml = format!(
+            r#"<nmaprun start="{}">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>{}</ports>
+</host>
+</nmaprun>"#,
+            start, ports
+        );
+        NmapResults::parse(&xml).unwrap()
+    }
+
+    #[test]
+    fn first_and_last_seen() {
+        let mut series = ScanSeries::from(vec![scan(300, &[22]), scan(100, &[22, 23])]);
+        series.push(scan(200, &[22, 80]));
+        series.push(scan(400, &[]));
+        let ip = "10.0.0.1".parse().unwrap();
+
+        assert_eq!(
+            series
+                .scans()
+                .map(|s| s.scan_start_time)
+                .collect::<Vec<_>>(),
+            [100, 200, 300, 400]
+        );
+        assert_eq!(series.first_seen(&ip), Some(100));
+        assert_eq!(series.last_seen(&ip), Some(400));
+        assert_eq!(
+            series.port_first_seen(&ip, PortProtocol::Tcp, 22),
+            Some(100)
+        );
+        assert_eq!(series.port_last_seen(&ip, PortProtocol::Tcp, 22), Some(300));
+        assert_eq!(
+            series.port_first_seen(&ip, PortProtocol::Tcp, 80),
+            Some(200)
+        );
+        assert_eq!(series.port_last_seen(&ip, PortProtocol::Tcp, 80), Some(200));
+        assert_eq!(series.port_first_seen(&ip, PortProtocol::Udp, 22), None);
+        assert_eq!(series.first_seen(&"10.0.0.2".parse().unwrap()), None);
+    }
+}
@@ -0,0 +1,418 @@
+//!Service detection probes from Nmap's `nmap-service-probes` file.
+//!
+//!The file lists the probes Nmap sends during version detection and, for
+//!each, the rules matching the responses to a service. Parsing it allows
+//!re-matching captured banners or finding the probe that produced the
+//!responses recorded in a service fingerprint.
+//!
+//!Match patterns are kept in Nmap's PCRE syntax.
+//![`MatchRule::compile()`](struct.MatchRule.html#method.compile) turns them
+//!into a [`Pattern`](../search/struct.Pattern.html) when they only use the
+//!syntax it supports; `.` does not match newlines even with the `s` flag.
+use std::str::FromStr;
+
+use crate::port::PortProtocol;
+use crate::search::{Matcher, Pattern};
+use crate::Error;
+
+const FORMAT: &str = "nmap-service-probes";
+
+///The probes of an `nmap-service-probes` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceProbes {
+    ///The ports version detection skips, in Nmap's port list syntax.
+    pub exclude: Option<String>,
+    pub(crate) probes: Vec<Probe>,
+}
+
+///A probe and the rules matching its responses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Probe {
+    pub protocol: PortProtocol,
+    pub name: String,
+    ///The bytes sent, with the escapes of the file decoded.
+    pub payload: Vec<u8>,
+    ///`true` if the probe is not sent when the scan is limited to a payload.
+    pub no_payload: bool,
+    pub rarity: Option<u8>,
+    ///The ports the probe is tried first on, in Nmap's port list syntax.
+    pub ports: Option<String>,
+    ///Same as `ports`, for services in SSL tunnels.
+    pub ssl_ports: Option<String>,
+    pub total_wait_ms: Option<u32>,
+    pub tcp_wrapped_ms: Option<u32>,
+    ///The probes whose rules are also tried on the responses of this one.
+    pub fallback: Vec<String>,
+    pub(crate) matches: Vec<MatchRule>,
+}
+
+impl Probe {
+    ///Returns an iterator over the `match` and `softmatch` rules, in file
+    ///order.
+    pub fn matches(&self) -> std::slice::Iter<'_, MatchRule> {
+        self.matches.iter()
+    }
+}
+
+///A `match` or `softmatch` rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchRule {
+    pub service: String,
+    ///The pattern, in Perl regular expression syntax.
+    pub pattern: String,
+    ///The pattern flags: `i` for case-insensitive, `s` for `.` matching
+    ///newlines.
+    pub flags: String,
+    ///`true` for a `softmatch`, which only names the service and lets
+    ///later probes find the version.
+    pub soft: bool,
+    pub version_info: VersionInfo,
+}
+
+impl MatchRule {
+    ///Compiles the pattern of the rule, failing when it uses syntax
+    ///[`Pattern`](../search/struct.Pattern.html) does not support.
+    pub fn compile(&self) -> Result<Pattern, Error> {
+        if self.flags.contains('i') {
+            Pattern::new(&format!("(?i){}", self.pattern))
+        } else {
+            Pattern::new(&self.pattern)
+        }
+    }
+}
+
+///The version templates of a rule. They may refer to the groups of the
+///pattern as `$1`, `$2`...
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VersionInfo {
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub info: Option<String>,
+    pub hostname: Option<String>,
+    pub os_type: Option<String>,
+    pub device_type: Option<String>,
+    pub cpes: Vec<String>,
+}
+
+impl ServiceProbes {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Parses the content of an `nmap-service-probes` file. Directives
+    ///before the first `Probe` line, other than `Exclude`, are errors.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut probes = ServiceProbes::new();
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |what: &str| Error::InvalidImport {
+                format: FORMAT,
+                message: format!("invalid {} on line {}", what, number + 1),
+            };
+
+            let (directive, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+            if directive == "Exclude" {
+                probes.exclude = Some(rest.to_string());
+                continue;
+            }
+            if directive == "Probe" {
+                probes
+                    .probes
+                    .push(parse_probe(rest).ok_or_else(|| invalid("probe"))?);
+                continue;
+            }
+
+            let probe = probes
+                .probes
+                .last_mut()
+                .ok_or_else(|| invalid("directive outside of a probe"))?;
+            match directive {
+                "match" | "softmatch" => probe.matches.push(
+                    parse_match(rest, directive == "softmatch")
+                        .ok_or_else(|| invalid("match rule"))?,
+                ),
+                "ports" => probe.ports = Some(rest.to_string()),
+                "sslports" => probe.ssl_ports = Some(rest.to_string()),
+                "rarity" => probe.rarity = Some(rest.parse().map_err(|_| invalid("rarity"))?),
+                "totalwaitms" => {
+                    probe.total_wait_ms = Some(rest.parse().map_err(|_| invalid("totalwaitms"))?)
+                }
+                "tcpwrappedms" => {
+                    probe.tcp_wrapped_ms = Some(rest.parse().map_err(|_| invalid("tcpwrappedms"))?)
+                }
+                "fallback" => {
+                    probe.fallback = rest.split(',').map(|f| f.trim().to_string()).collect()
+                }
+                _ => return Err(invalid("directive")),
+            }
+        }
+
+        Ok(probes)
+    }
+
+    ///Returns an iterator over the probes, in file order.
+    pub fn probes(&self) -> std::slice::Iter<'_, Probe> {
+        self.probes.iter()
+    }
+
+    ///Returns the probe named `name` for `protocol`.
+    pub fn probe(&self, protocol: PortProtocol, name: &str) -> Option<&Probe> {
+        self.probes
+            .iter()
+            .find(|p| p.protocol == protocol && p.name == name)
+    }
+
+    ///Returns the rule matching `response` to the probe `name`, trying the
+    ///rules of its fallback probes next as Nmap does. A `match` wins over a
+    ///`softmatch` found first. Rules whose pattern cannot be compiled are
+    ///skipped.
+    pub fn identify(
+        &self,
+        protocol: PortProtocol,
+        name: &str,
+        response: &str,
+    ) -> Option<&MatchRule> {
+        let probe = self.probe(protocol.clone(), name)?;
+        let mut soft = None;
+        let fallbacks = probe
+            .fallback
+            .iter()
+            .filter_map(|f| self.probe(protocol.clone(), f));
+        for rule in std::iter::once(probe)
+            .chain(fallbacks)
+            .flat_map(Probe::matches)
+        {
+            if soft.is_some() && rule.soft {
+                continue;
+            }
+            if rule.compile().is_ok_and(|p| p.find(response).is_some()) {
+                if !rule.soft {
+                    return Some(rule);
+                }
+                soft = Some(rule);
+            }
+        }
+        soft
+    }
+
+    ///Returns the probes whose responses are recorded in the service
+    ///fingerprint `fingerprint`, as found in the `servicefp` attribute, in
+    ///the order they were sent.
+    pub fn fingerprint_probes(&self, fingerprint: &str) -> Vec<&Probe> {
+        let protocol = if fingerprint.contains("-UDP:") {
+            PortProtocol::Udp
+        } else {
+            PortProtocol::Tcp
+        };
+        //Responses are recorded as `%r(GetRequest,...)` or `SF:r(...)`.
+        fingerprint
+            .split("r(")
+            .skip(1)
+            .filter_map(|response| response.split(',').next())
+            .filter_map(|name| self.probe(protocol.clone(), name))
+            .collect()
+    }
+}
+
+//`TCP GetRequest q|GET / HTTP/1.0\r\n\r\n|` with an optional `no-payload`.
+fn parse_probe(line: &str) -> Option<Probe> {
+    let (protocol, rest) = line.split_once(' ')?;
+    let protocol = PortProtocol::from_str(&protocol.to_lowercase()).ok()?;
+    let (name, rest) = rest.trim_start().split_once(' ')?;
+    let rest = rest.trim_start().strip_prefix('q')?;
+    let (payload, rest) = delimited(rest)?;
+
+    Some(Probe {
+        protocol,
+        name: name.to_string(),
+        payload: unescape(payload),
+        no_payload: rest.trim() == "no-payload",
+        rarity: None,
+        ports: None,
+        ssl_ports: None,
+        total_wait_ms: None,
+        tcp_wrapped_ms: None,
+        fallback: Vec::new(),
+        matches: Vec::new(),
+    })
+}
+
+//`ftp m/^220 ProFTPD (\S+) Server/i p/ProFTPD/ v/$1/ cpe:/a:proftpd:proftpd:$1/`
+fn parse_match(line: &str, soft: bool) -> Option<MatchRule> {
+    let (service, rest) = line.split_once(' ')?;
+    let rest = rest.trim_start().strip_prefix('m')?;
+    let (pattern, rest) = delimited(rest)?;
+    let flags = rest
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect::<String>();
+    let mut rest = &rest[flags.len()..];
+
+    let mut version_info = VersionInfo::default();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (tag, after) = match rest.strip_prefix("cpe:") {
+            Some(after) => ("cpe", after),
+            None => rest.split_at(rest.chars().next()?.len_utf8()),
+        };
+        let (value, after) = delimited(after)?;
+        //The `a` flag marks a CPE for an application, as the `/a` part does.
+        rest = after.strip_prefix('a').unwrap_or(after);
+        let value = Some(value.to_string());
+        match tag {
+            "p" => version_info.product = value,
+            "v" => version_info.version = value,
+            "i" => version_info.info = value,
+            "h" => version_info.hostname = value,
+            "o" => version_info.os_type = value,
+            "d" => version_info.device_type = value,
+            "cpe" => version_info.cpes.push(format!("cpe:/{}", value?)),
+            _ => return None,
+        }
+    }
+
+    Some(MatchRule {
+        service: service.to_string(),
+        pattern: pattern.to_string(),
+        flags,
+        soft,
+        version_info,
+    })
+}
+
+//Splits `|text|rest` into `text` and `rest`, the first character being the
+//delimiter.
+fn delimited(s: &str) -> Option<(&str, &str)> {
+    let delimiter = s.chars().next()?;
+    let s = &s[delimiter.len_utf8()..];
+    let end = s.find(delimiter)?;
+    Some((&s[..end], &s[end + delimiter.len_utf8()..]))
+}
+
+//Decodes the C-style escapes of probe payloads.
+fn unescape(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('0') => bytes.push(0),
+            Some('a') => bytes.push(7),
+            Some('b') => bytes.push(8),
+            Some('f') => bytes.push(12),
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(11),
+            Some('x') => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) => bytes.push(byte),
+                    Err(_) => bytes.extend_from_slice(format!("\\x{}", hex).as_bytes()),
+                }
+            }
+            Some(c) => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROBES: &str = r#"# Nmap service detection probe list
+Exclude T:9100-9107
+
+Probe TCP NULL q||
+totalwaitms 6000
+tcpwrappedms 3000
+match ftp m/^220 ProFTPD (\d+\.\d+\.\d+) Server/ p/ProFTPD/ v/$1/ cpe:/a:proftpd:proftpd:$1/
+softmatch ssh m/^SSH-([\d.]+)-/i
+
+Probe TCP GetRequest q|GET / HTTP/1.0\r\n\r\n|
+rarity 1
+ports 80,8080
+sslports 443
+fallback NULL
+match http m|^HTTP/1\.[01] \d\d\d .*Server: nginx|s p/nginx/ cpe:/a:igor_sysoev:nginx/
+"#;
+
+    #[test]
+    fn probe_file() {
+        let probes = ServiceProbes::parse(PROBES).unwrap();
+        assert_eq!(probes.exclude.as_deref(), Some("T:9100-9107"));
+        assert_eq!(probes.probes().count(), 2);
+
+        let null = probes.probe(PortProtocol::Tcp, "NULL").unwrap();
+        assert!(null.payload.is_empty());
+        assert_eq!(null.total_wait_ms, Some(6000));
+        let ftp = null.matches().next().unwrap();
+        assert_eq!(ftp.service, "ftp");
+        assert_eq!(ftp.version_info.product.as_deref(), Some("ProFTPD"));
+        assert_eq!(ftp.version_info.version.as_deref(), Some("$1"));
+        assert_eq!(ftp.version_info.cpes, ["cpe:/a:proftpd:proftpd:$1"]);
+        let ssh = null.matches().nth(1).unwrap();
+        assert!(ssh.soft);
+        assert_eq!(ssh.flags, "i");
+
+        let get = probes.probe(PortProtocol::Tcp, "GetRequest").unwrap();
+        assert_eq!(get.payload, b"GET / HTTP/1.0\r\n\r\n");
+        assert_eq!(get.rarity, Some(1));
+        assert_eq!(get.ssl_ports.as_deref(), Some("443"));
+        assert_eq!(get.fallback, ["NULL"]);
+        assert_eq!(get.matches().next().unwrap().flags, "s");
+
+        let err = ServiceProbes::parse("rarity 1\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error parsing nmap-service-probes input: invalid directive outside of a probe on line 1"
+        );
+    }
+
+    #[test]
+    fn identify_banners() {
+        let probes = ServiceProbes::parse(PROBES).unwrap();
+        let rule = probes
+            .identify(
+                PortProtocol::Tcp,
+                "GetRequest",
+                "220 ProFTPD 1.3.5 Server ready",
+            )
+            .unwrap();
+        assert_eq!(rule.service, "ftp");
+        let rule = probes
+            .identify(PortProtocol::Tcp, "NULL", "ssh-2.0-OpenSSH_7.4")
+            .unwrap();
+        assert!(rule.soft);
+        assert!(probes
+            .identify(PortProtocol::Tcp, "NULL", "hello")
+            .is_none());
+
+        let fingerprint = "SF-Port80-TCP:V=7.60%I=7%D=5/1%Time=5EAC3D4A%P=x86_64-pc-linux-gnu%r(GetRequest,10,\"HTTP/1\\.0\\x20200\")%r(NULL,0,\"\");";
+        assert_eq!(
+            probes
+                .fingerprint_probes(fingerprint)
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>(),
+            ["GetRequest", "NULL"]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//!Well-known services from Nmap's `nmap-services` file.
+//!
+//!The file lists the service name registered for each port along with how
+//!often the port was found open in Nmap's own scans, the frequency Nmap
+//!picks its "top ports" by. It is loaded at runtime, from the copy shipped
+//!with Nmap or a user-supplied one, so it matches the Nmap version in use.
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::port::{Port, PortProtocol, ServiceInfo, ServiceMethod};
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "nmap-services";
+
+///A port listed in `nmap-services`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceEntry {
+    pub name: String,
+    pub protocol: PortProtocol,
+    pub port_number: u16,
+    ///The fraction of scanned hosts the port was found open on, between 0
+    ///and 1. Lists without frequencies, such as `/etc/services`, give 0.
+    pub frequency: f64,
+    pub comment: Option<String>,
+}
+
+///Service names and frequencies by port.
+#[derive(Clone, Debug, Default)]
+pub struct ServicesDatabase {
+    entries: Vec<ServiceEntry>,
+    index: HashMap<(PortProtocol, u16), usize>,
+}
+
+impl ServicesDatabase {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Parses `nmap-services` lines such as
+    ///`http 80/tcp 0.484143 # World Wide Web HTTP`. The frequency and the
+    ///comment may be left out. Comments and blank lines are skipped.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut database = ServicesDatabase::new();
+
+        for (number, line) in input.lines().enumerate() {
+            let (line, comment) = match line.split_once('#') {
+                Some((line, comment)) => (line.trim(), Some(comment.trim())),
+                None => (line.trim(), None),
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || Error::InvalidImport {
+                format: FORMAT,
+                message: format!("invalid service line {}", number + 1),
+            };
+
+            let mut fields = line.split_whitespace();
+            let name = fields.next().ok_or_else(invalid)?;
+            let (port_number, protocol) = fields
+                .next()
+                .and_then(|port| port.split_once('/'))
+                .ok_or_else(invalid)?;
+            let port_number = port_number.parse::<u16>().map_err(|_| invalid())?;
+            let protocol = PortProtocol::from_str(protocol).map_err(|_| invalid())?;
+            let frequency = match fields.next() {
+                Some(frequency) => frequency.parse::<f64>().map_err(|_| invalid())?,
+                None => 0.0,
+            };
+
+            database.insert(ServiceEntry {
+                name: name.to_string(),
+                protocol,
+                port_number,
+                frequency,
+                comment: comment.filter(|c| !c.is_empty()).map(String::from),
+            });
+        }
+
+        Ok(database)
+    }
+
+    ///Adds `entry`, replacing the entry of the same port if there is one.
+    pub fn insert(&mut self, entry: ServiceEntry) {
+        let key = (entry.protocol.clone(), entry.port_number);
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i] = entry,
+            None => {
+                self.index.insert(key, self.entries.len());
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    ///Returns the entry of port `port_number` of `protocol`.
+    pub fn get(&self, protocol: PortProtocol, port_number: u16) -> Option<&ServiceEntry> {
+        self.index
+            .get(&(protocol, port_number))
+            .map(|&i| &self.entries[i])
+    }
+
+    ///Returns the entry of the protocol and number of `port`.
+    pub fn entry_for(&self, port: &Port) -> Option<&ServiceEntry> {
+        self.get(port.protocol.clone(), port.port_number)
+    }
+
+    ///Returns an iterator over the entries, in file order.
+    pub fn entries(&self) -> std::slice::Iter<'_, ServiceEntry> {
+        self.entries.iter()
+    }
+
+    ///Returns the `n` most frequently open ports of `protocol`, most
+    ///frequent first, as `nmap --top-ports` would pick them.
+    pub fn top_ports(&self, protocol: PortProtocol, n: usize) -> Vec<&ServiceEntry> {
+        let mut entries = self
+            .entries
+            .iter()
+            .filter(|e| e.protocol == protocol)
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.frequency.total_cmp(&a.frequency));
+        entries.truncate(n);
+        entries
+    }
+
+    ///Names the services of the ports of `results` that have no service or
+    ///one Nmap only looked up in its table, which is what older or
+    ///imported results may hold. Ports detected by probing are left alone.
+    ///Returns the number of ports changed.
+    pub fn annotate(&self, results: &mut NmapResults) -> usize {
+        let mut changed = 0;
+        for port in results
+            .hosts
+            .iter_mut()
+            .flat_map(|h| h.port_info.ports.iter_mut())
+        {
+            let entry = match self.entry_for(port) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            match &mut port.service_info {
+                Some(service) if service.method == ServiceMethod::Table => {
+                    if service.name != entry.name {
+                        service.name = entry.name.clone();
+                        changed += 1;
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    port.service_info = Some(ServiceInfo {
+                        name: entry.name.clone(),
+                        confidence_level: 3,
+                        method: ServiceMethod::Table,
+                        product: None,
+                        version: None,
+                        extra_info: None,
+                        tunnel: None,
+                        cpes: Vec::new(),
+                    });
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    ///Returns the number of entries in the database.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    ///Returns `true` if the database has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SERVICES: &str = "# Fields in this file are: Service name, portnum/protocol, open-frequency, optional comments\n\
+#\n\
+ssh\t22/tcp\t0.182286\t# Secure Shell Login\n\
+telnet\t23/tcp\t0.221265\n\
+http\t80/tcp\t0.484143\t# World Wide Web HTTP\n\
+domain\t53/udp\t0.213496\t# Domain Name Server\n";
+
+    #[test]
+    fn services_file() {
+        let database = ServicesDatabase::parse(SERVICES).unwrap();
+        assert_eq!(database.len(), 4);
+
+        let ssh = database.get(PortProtocol::Tcp, 22).unwrap();
+        assert_eq!(ssh.name, "ssh");
+        assert_eq!(ssh.frequency, 0.182286);
+        assert_eq!(ssh.comment.as_deref(), Some("Secure Shell Login"));
+        assert!(database.get(PortProtocol::Udp, 22).is_none());
+
+        let top = database.top_ports(PortProtocol::Tcp, 2);
+        assert_eq!(
+            top.iter().map(|e| e.port_number).collect::<Vec<_>>(),
+            [80, 23]
+        );
+
+        let err = ServicesDatabase::parse("ssh 22\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error parsing nmap-services input: invalid service line 1"
+        );
+    }
+
+    #[test]
+    fn annotate_ports() {
+        let mut results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/></port>
+<port protocol="tcp" portid="23"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="unknown" method="table" conf="3"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https" product="nginx" method="probed" conf="10"/></port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let database = ServicesDatabase::parse(SERVICES).unwrap();
+
+        assert_eq!(database.annotate(&mut results), 2);
+        let names = results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .ports()
+            .map(|p| p.service_info.as_ref().unwrap().name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["ssh", "telnet", "https"]);
+    }
+}
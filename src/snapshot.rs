@@ -0,0 +1,372 @@
+//!Snapshots of the exposure of a network, for checking later scans.
+//!
+//!A snapshot lists the hosts that are up and their open ports with the
+//!service detected on each, sorted so that it only changes when the
+//!exposure does. Service versions are left out on purpose: an upgrade
+//!does not change what is reachable. The text form keeps one host per
+//!line and one indented port per line:
+//!
+//!```text
+//!#Exposure of the DMZ
+//!10.0.0.1
+//!  22/tcp ssh
+//!  443/tcp https
+//!10.0.0.2
+//!  53/udp
+//!```
+//!
+//!Lines starting with `#` and blank lines are ignored. A snapshot is
+//!typically written once with `results.snapshot().to_string()`, committed,
+//!and checked in CI with
+//![`NmapResults::matches_snapshot()`](../struct.NmapResults.html#method.matches_snapshot).
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::port::{PortProtocol, PortState};
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "snapshot";
+
+///The exposure of a network at the time of a scan.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub(crate) hosts: Vec<SnapshotHost>,
+}
+
+impl Snapshot {
+    ///Takes a snapshot of the hosts up in `results`. Hosts found more than
+    ///once are merged.
+    pub fn from_results(results: &NmapResults) -> Self {
+        let mut snapshot = Snapshot::default();
+        for host in results.hosts_up() {
+            let ip = match host.primary_ip() {
+                Some(ip) => ip,
+                None => continue,
+            };
+            let position = match snapshot.hosts.iter().position(|h| h.ip == ip) {
+                Some(position) => position,
+                None => {
+                    snapshot.hosts.push(SnapshotHost {
+                        ip,
+                        ports: Vec::new(),
+                    });
+                    snapshot.hosts.len() - 1
+                }
+            };
+            let ports = &mut snapshot.hosts[position].ports;
+            for port in host.port_info.ports_in_state(PortState::Open) {
+                if !ports
+                    .iter()
+                    .any(|p| p.protocol == port.protocol && p.port_number == port.port_number)
+                {
+                    ports.push(SnapshotPort {
+                        protocol: port.protocol.clone(),
+                        port_number: port.port_number,
+                        service: port.service_info.as_ref().map(|s| s.name.clone()),
+                    });
+                }
+            }
+        }
+        snapshot.sort();
+        snapshot
+    }
+
+    ///Parses the text form of a snapshot.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut snapshot = Snapshot::default();
+        for (number, line) in input.lines().enumerate() {
+            let error = |message: String| Error::InvalidImport {
+                format: FORMAT,
+                message: format!("line {}: {}", number + 1, message),
+            };
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                let ip = line
+                    .trim()
+                    .parse::<IpAddr>()
+                    .map_err(|_| error(format!("invalid host address `{}`", line.trim())))?;
+                if snapshot.hosts.iter().any(|h| h.ip == ip) {
+                    return Err(error(format!("duplicate host `{}`", ip)));
+                }
+                snapshot.hosts.push(SnapshotHost {
+                    ip,
+                    ports: Vec::new(),
+                });
+                continue;
+            }
+
+            let host = snapshot
+                .hosts
+                .last_mut()
+                .ok_or_else(|| error("port listed before any host".to_string()))?;
+            let mut fields = line.split_whitespace();
+            let endpoint = fields.next().unwrap_or_default();
+            let (port_number, protocol) = endpoint
+                .split_once('/')
+                .ok_or_else(|| error(format!("invalid port `{}`", endpoint)))?;
+            let port_number = port_number
+                .parse::<u16>()
+                .map_err(|_| error(format!("invalid port number `{}`", port_number)))?;
+            let protocol = PortProtocol::from_str(protocol)
+                .map_err(|_| error(format!("invalid port protocol `{}`", protocol)))?;
+            let service = fields.next().map(String::from);
+            if let Some(extra) = fields.next() {
+                return Err(error(format!("unexpected `{}`", extra)));
+            }
+            host.ports.push(SnapshotPort {
+                protocol,
+                port_number,
+                service,
+            });
+        }
+        snapshot.sort();
+        Ok(snapshot)
+    }
+
+    ///Returns an iterator over the hosts, sorted by address.
+    pub fn hosts(&self) -> std::slice::Iter<'_, SnapshotHost> {
+        self.hosts.iter()
+    }
+
+    ///Compares `actual`, usually a snapshot of a new scan, with this one.
+    pub fn compare(&self, actual: &Snapshot) -> SnapshotReport {
+        let mut mismatches = Vec::new();
+        for expected in self.hosts() {
+            let host = match actual.hosts().find(|h| h.ip == expected.ip) {
+                Some(host) => host,
+                None => {
+                    mismatches.push(Mismatch::MissingHost(expected.ip));
+                    continue;
+                }
+            };
+            for port in expected.ports() {
+                match host.port(&port.protocol, port.port_number) {
+                    None => mismatches.push(Mismatch::MissingPort {
+                        ip: expected.ip,
+                        port: port.clone(),
+                    }),
+                    Some(found) if found.service != port.service => {
+                        mismatches.push(Mismatch::ServiceChanged {
+                            ip: expected.ip,
+                            expected: port.clone(),
+                            actual: found.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+            for port in host.ports() {
+                if expected.port(&port.protocol, port.port_number).is_none() {
+                    mismatches.push(Mismatch::UnexpectedPort {
+                        ip: host.ip,
+                        port: port.clone(),
+                    });
+                }
+            }
+        }
+        for host in actual.hosts() {
+            if !self.hosts().any(|h| h.ip == host.ip) {
+                mismatches.push(Mismatch::UnexpectedHost(host.clone()));
+            }
+        }
+        SnapshotReport { mismatches }
+    }
+
+    fn sort(&mut self) {
+        self.hosts.sort_by_key(|h| h.ip);
+        for host in self.hosts.iter_mut() {
+            host.ports
+                .sort_by_key(|p| (p.port_number, p.protocol.to_string()));
+        }
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for host in self.hosts() {
+            writeln!(f, "{}", host.ip)?;
+            for port in host.ports() {
+                writeln!(f, "  {}", port)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+///A host up in a snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotHost {
+    pub ip: IpAddr,
+    pub(crate) ports: Vec<SnapshotPort>,
+}
+
+impl SnapshotHost {
+    ///Returns an iterator over the open ports, sorted by number.
+    pub fn ports(&self) -> std::slice::Iter<'_, SnapshotPort> {
+        self.ports.iter()
+    }
+
+    fn port(&self, protocol: &PortProtocol, port_number: u16) -> Option<&SnapshotPort> {
+        self.ports
+            .iter()
+            .find(|p| &p.protocol == protocol && p.port_number == port_number)
+    }
+}
+
+///An open port in a snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotPort {
+    pub protocol: PortProtocol,
+    pub port_number: u16,
+    ///Name of the service detected on the port.
+    pub service: Option<String>,
+}
+
+///Formats the port as in the text form, e.g. `22/tcp ssh`.
+impl fmt::Display for SnapshotPort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.port_number, self.protocol)?;
+        if let Some(service) = &self.service {
+            write!(f, " {}", service)?;
+        }
+        Ok(())
+    }
+}
+
+///The differences between an expected and an actual snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotReport {
+    pub(crate) mismatches: Vec<Mismatch>,
+}
+
+impl SnapshotReport {
+    ///Returns `true` if the snapshots are the same.
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    ///Returns an iterator over the differences, host by host in the order
+    ///of the expected snapshot, then the unexpected hosts.
+    pub fn mismatches(&self) -> std::slice::Iter<'_, Mismatch> {
+        self.mismatches.iter()
+    }
+}
+
+///Lists the differences one per line, as a diff of the text forms would:
+///`-` for what is expected but missing, `+` for what is unexpected.
+impl fmt::Display for SnapshotReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for mismatch in self.mismatches() {
+            writeln!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+///A difference between an expected and an actual snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    ///An expected host is not up.
+    MissingHost(IpAddr),
+    ///A host is up but not expected.
+    UnexpectedHost(SnapshotHost),
+    ///An expected port is not open.
+    MissingPort { ip: IpAddr, port: SnapshotPort },
+    ///A port is open but not expected.
+    UnexpectedPort { ip: IpAddr, port: SnapshotPort },
+    ///A port is open as expected but runs another service.
+    ServiceChanged {
+        ip: IpAddr,
+        expected: SnapshotPort,
+        actual: SnapshotPort,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::MissingHost(ip) => write!(f, "- {}", ip),
+            Mismatch::UnexpectedHost(host) => {
+                write!(f, "+ {}", host.ip)?;
+                for port in host.ports() {
+                    write!(f, "\n+ {} {}", host.ip, port)?;
+                }
+                Ok(())
+            }
+            Mismatch::MissingPort { ip, port } => write!(f, "- {} {}", ip, port),
+            Mismatch::UnexpectedPort { ip, port } => write!(f, "+ {} {}", ip, port),
+            Mismatch::ServiceChanged {
+                ip,
+                expected,
+                actual,
+            } => write!(f, "- {} {}\n+ {} {}", ip, expected, ip, actual),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="https" method="table" conf="3"/></port>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"/></port>
+<port protocol="tcp" portid="23"><state state="closed" reason="reset" reason_ttl="53"/></port>
+<port protocol="udp" portid="53"><state state="open" reason="udp-response" reason_ttl="53"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn snapshot_text() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let snapshot = Snapshot::from_results(&results);
+        let text = snapshot.to_string();
+        assert_eq!(
+            text,
+            "10.0.0.1\n10.0.0.2\n  22/tcp ssh\n  53/udp\n  443/tcp https\n"
+        );
+        assert_eq!(Snapshot::parse(&text).unwrap(), snapshot);
+
+        let err = Snapshot::parse("#ports\n  22/tcp ssh\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error parsing snapshot input: line 2: port listed before any host"
+        );
+    }
+
+    #[test]
+    fn snapshot_mismatches() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let expected =
+            Snapshot::parse("10.0.0.2\n  22/tcp ssh\n  53/udp domain\n  80/tcp http\n10.0.0.3\n")
+                .unwrap();
+        let report = expected.compare(&Snapshot::from_results(&results));
+
+        assert!(!report.is_match());
+        assert_eq!(report.mismatches().count(), 5);
+        assert_eq!(
+            report.to_string(),
+            "- 10.0.0.2 53/udp domain\n\
+             + 10.0.0.2 53/udp\n\
+             - 10.0.0.2 80/tcp http\n\
+             + 10.0.0.2 443/tcp https\n\
+             - 10.0.0.3\n\
+             + 10.0.0.1\n"
+        );
+        assert!(expected.compare(&expected).is_match());
+    }
+}
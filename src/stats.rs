@@ -0,0 +1,219 @@
+//!Aggregate statistics over a scan.
+//!
+//!```
+//!# use std::path::PathBuf;
+//!# use std::fs;
+//!use nmap_xml_parser::{stats::ScanStats, NmapResults};
+//!# let mut nmap_xml_file = PathBuf::new();
+//!# nmap_xml_file.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+//!# nmap_xml_file.push("tests/test.xml");
+//!let content = fs::read_to_string(nmap_xml_file).unwrap();
+//!let results = NmapResults::parse(&content).unwrap();
+//!let stats = ScanStats::from(&results);
+//!assert_eq!(stats.hosts.up, 1);
+//!assert_eq!(stats.ports.open, 4);
+//!```
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::host::HostState;
+use crate::port::{PortProtocol, PortState};
+use crate::NmapResults;
+
+///Totals computed from a scan.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanStats {
+    ///Host counts by state.
+    pub hosts: HostStats,
+
+    ///Port counts by state and protocol, over all hosts.
+    pub ports: PortStats,
+
+    ///Distinct service names detected on open ports.
+    pub services: BTreeSet<String>,
+}
+
+///Host counts by state.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostStats {
+    pub total: usize,
+    pub up: usize,
+    pub down: usize,
+    pub unknown: usize,
+    pub skipped: usize,
+}
+
+///Port counts of the ports Nmap listed individually, by state and by
+///protocol. Ports summarized in `extraports` only count in `unlisted`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PortStats {
+    pub total: usize,
+    pub open: usize,
+    pub closed: usize,
+    pub filtered: usize,
+    pub unfiltered: usize,
+    pub open_filtered: usize,
+    pub closed_filtered: usize,
+    pub ip: usize,
+    pub tcp: usize,
+    pub udp: usize,
+    pub sctp: usize,
+    pub unlisted: u64,
+}
+
+impl From<&NmapResults> for ScanStats {
+    fn from(results: &NmapResults) -> Self {
+        let mut stats = ScanStats::default();
+
+        for host in results.hosts() {
+            stats.hosts.total += 1;
+            match host.status.state {
+                HostState::Up => stats.hosts.up += 1,
+                HostState::Down => stats.hosts.down += 1,
+                HostState::Unknown => stats.hosts.unknown += 1,
+                HostState::Skipped => stats.hosts.skipped += 1,
+            }
+
+            for extra in host.port_info.extra_ports() {
+                stats.ports.unlisted += u64::from(extra.count);
+            }
+
+            for port in host.port_info.ports() {
+                let ports = &mut stats.ports;
+                ports.total += 1;
+                match port.status.state {
+                    PortState::Open => ports.open += 1,
+                    PortState::Closed => ports.closed += 1,
+                    PortState::Filtered => ports.filtered += 1,
+                    PortState::Unfiltered => ports.unfiltered += 1,
+                    PortState::OpenFiltered => ports.open_filtered += 1,
+                    PortState::CloseFiltered => ports.closed_filtered += 1,
+                }
+                match port.protocol {
+                    PortProtocol::Ip => ports.ip += 1,
+                    PortProtocol::Tcp => ports.tcp += 1,
+                    PortProtocol::Udp => ports.udp += 1,
+                    PortProtocol::Sctp => ports.sctp += 1,
+                }
+
+                if port.status.state == PortState::Open {
+                    if let Some(service) = &port.service_info {
+                        stats.services.insert(service.name.clone());
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+///Returns the `n` service names most often found on open ports, with the
+///number of open ports running them, most frequent first. Ties are ordered
+///by name.
+pub fn top_services(results: &NmapResults, n: usize) -> Vec<(String, usize)> {
+    let mut counts = BTreeMap::new();
+    for (_, port) in results.open_ports() {
+        if let Some(service) = &port.service_info {
+            *counts.entry(service.name.clone()).or_insert(0) += 1;
+        }
+    }
+    top(counts, n)
+}
+
+///Returns the `n` port numbers most often found open, with the number of
+///hosts they are open on, most frequent first. Ties are ordered by port
+///number. Protocols are not told apart.
+pub fn top_ports(results: &NmapResults, n: usize) -> Vec<(u16, usize)> {
+    let mut counts = BTreeMap::new();
+    for host in results.hosts() {
+        let open = host
+            .port_info
+            .open_ports()
+            .map(|p| p.port_number)
+            .collect::<BTreeSet<_>>();
+        for port_number in open {
+            *counts.entry(port_number).or_insert(0) += 1;
+        }
+    }
+    top(counts, n)
+}
+
+fn top<K: Ord>(counts: BTreeMap<K, usize>, n: usize) -> Vec<(K, usize)> {
+    let mut ranked = counts.into_iter().collect::<Vec<_>>();
+    //Stable sort, so equal counts keep the key order of the map.
+    ranked.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    ranked.truncate(n);
+    ranked
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SCAN: &str = r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<extraports state="closed" count="995"/>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="table" conf="3"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+<port protocol="tcp" portid="8080"><state state="filtered" reason="no-response" reason_ttl="0"/><service name="http-proxy" method="table" conf="3"/></port>
+<port protocol="udp" portid="53"><state state="open|filtered" reason="no-response" reason_ttl="0"/></port>
+</ports>
+</host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="http" method="table" conf="3"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+</nmaprun>"#;
+
+    #[test]
+    fn scan_stats() {
+        let results = NmapResults::parse(SCAN).unwrap();
+        let stats = ScanStats::from(&results);
+
+        assert_eq!(
+            stats.hosts,
+            HostStats {
+                total: 3,
+                up: 2,
+                down: 1,
+                ..HostStats::default()
+            }
+        );
+        assert_eq!(
+            stats.ports,
+            PortStats {
+                total: 5,
+                open: 3,
+                filtered: 1,
+                open_filtered: 1,
+                tcp: 4,
+                udp: 1,
+                unlisted: 995,
+                ..PortStats::default()
+            }
+        );
+        assert_eq!(
+            stats.services.into_iter().collect::<Vec<_>>(),
+            vec!["http", "ssh"]
+        );
+    }
+
+    #[test]
+    fn top_services_and_ports() {
+        let results = NmapResults::parse(SCAN).unwrap();
+
+        assert_eq!(
+            top_services(&results, 5),
+            vec![("http".to_string(), 2), ("ssh".to_string(), 1)]
+        );
+        assert_eq!(top_ports(&results, 1), vec![(80, 2)]);
+        assert_eq!(top_ports(&results, 5), vec![(80, 2), (22, 1)]);
+    }
+}
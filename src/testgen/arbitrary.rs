@@ -0,0 +1,461 @@
+//!Random values of the public types, for property tests and fuzzing.
+//!
+//![`Arbitrary`](trait.Arbitrary.html) builds a value from an
+//![`Unstructured`](struct.Unstructured.html) source of randomness, either
+//!the bytes handed over by a fuzzer or a seeded generator. The API follows
+//!the `arbitrary` crate, so wrapping these implementations for it is
+//!straightforward. Once the bytes run out every choice falls back to its
+//!smallest option, so short inputs give small scans.
+//!
+//!Values stay within what Nmap XML can express: strings have no control
+//!characters other than newlines, ports are unique per protocol and
+//!addresses are unique per scan. Such results survive
+//![`to_xml()`](../fn.to_xml.html) and parsing unchanged.
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::Rng;
+use crate::cpe::Cpe;
+use crate::host::{
+    Address, Host, HostState, HostStatus, Hostname, HostnameType, Script, ScriptElement, Times,
+};
+use crate::net::MacAddr;
+use crate::port::{
+    ExtraPorts, Port, PortInfo, PortProtocol, PortState, PortStatus, ServiceInfo, ServiceMethod,
+};
+use crate::NmapResults;
+
+//Plenty of characters that need escaping in XML.
+const ALPHABET: &[char] = &[
+    'a', 'b', 'c', 'x', 'y', 'z', 'A', 'Z', '0', '1', '9', ' ', '.', '-', '_', '/', ':', '|', '<',
+    '>', '&', '"', '\'', '\n', 'é', '✓',
+];
+
+const SCRIPT_IDS: &[&str] = &[
+    "banner",
+    "http-title",
+    "ssh-hostkey",
+    "ssl-cert",
+    "smb-os-discovery",
+    "vulners",
+];
+
+const CPES: &[&str] = &[
+    "cpe:/a:openbsd:openssh:7.4",
+    "cpe:/a:apache:http_server:2.4.41",
+    "cpe:/o:linux:linux_kernel",
+    "cpe:/h:cisco:ios",
+];
+
+///A source of randomness for [`Arbitrary`](trait.Arbitrary.html).
+pub struct Unstructured<'a> {
+    data: &'a [u8],
+    rng: Option<Rng>,
+}
+
+impl<'a> Unstructured<'a> {
+    ///Draws from `data`, as given by a fuzzer.
+    pub fn new(data: &'a [u8]) -> Self {
+        Unstructured { data, rng: None }
+    }
+
+    ///Returns `true` once all the data was used. Never `true` when seeded.
+    pub fn is_empty(&self) -> bool {
+        self.rng.is_none() && self.data.is_empty()
+    }
+
+    pub fn u8(&mut self) -> u8 {
+        if let Some(rng) = &mut self.rng {
+            return rng.next() as u8;
+        }
+        match self.data.split_first() {
+            Some((&b, rest)) => {
+                self.data = rest;
+                b
+            }
+            None => 0,
+        }
+    }
+
+    pub fn u16(&mut self) -> u16 {
+        u16::from_le_bytes([self.u8(), self.u8()])
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        u32::from_le_bytes([self.u8(), self.u8(), self.u8(), self.u8()])
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.u8() & 1 == 1
+    }
+
+    ///Returns a number below `n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        match n {
+            0 => 0,
+            1..=0x100 => self.u8() as usize % n,
+            _ => self.u32() as usize % n,
+        }
+    }
+
+    ///Returns one of `items`, which must not be empty.
+    pub fn choose<'b, T>(&mut self, items: &'b [T]) -> &'b T {
+        &items[self.below(items.len())]
+    }
+
+    ///Returns a string of up to `max_len` characters.
+    pub fn text(&mut self, max_len: usize) -> String {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| *self.choose(ALPHABET)).collect()
+    }
+
+    //Text that is not empty and keeps its meaning when parsed as a name,
+    //for attributes Nmap always fills in.
+    fn word(&mut self, max_len: usize) -> String {
+        let len = 1 + self.below(max_len);
+        (0..len)
+            .map(|_| *self.choose(&['a', 'b', 'c', 'x', 'y', 'z', '0', '9', '-']))
+            .collect()
+    }
+}
+
+impl Unstructured<'static> {
+    ///Draws from a generator seeded with `seed`, for property tests.
+    pub fn from_seed(seed: u64) -> Self {
+        Unstructured {
+            data: &[],
+            rng: Some(Rng(seed)),
+        }
+    }
+}
+
+///A type that can be built from random data.
+pub trait Arbitrary: Sized {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self;
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        if u.bool() {
+            Some(T::arbitrary(u))
+        } else {
+            None
+        }
+    }
+}
+
+impl Arbitrary for NmapResults {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let mut results = NmapResults::empty();
+        results.scan_start_time = u.u32() as i64;
+        results.scan_end_time = if u.bool() {
+            Some(results.scan_start_time + u.u16() as i64)
+        } else {
+            None
+        };
+        results.args = if u.bool() { Some(u.text(24)) } else { None };
+        results.nmap_version = if u.bool() {
+            Some(u.choose(&["7.80", "7.94", "7.95"]).to_string())
+        } else {
+            None
+        };
+
+        let mut seen: Vec<Address> = Vec::new();
+        for _ in 0..u.below(6) {
+            let mut host = Host::arbitrary(u);
+            host.addresses.retain(|a| !seen.contains(a));
+            seen.extend(host.addresses.iter().cloned());
+            results.hosts.push(host);
+        }
+        results
+    }
+}
+
+impl Arbitrary for Host {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let mut addresses = Vec::new();
+        for _ in 0..u.below(3) {
+            addresses.push(Address::arbitrary(u));
+        }
+        let mut host = Host::new(addresses, HostStatus::arbitrary(u));
+        for _ in 0..u.below(3) {
+            host.host_names.push(Hostname::arbitrary(u));
+        }
+        for _ in 0..u.below(3) {
+            host.scripts.push(Script::arbitrary(u));
+        }
+        host.port_info = PortInfo::arbitrary(u);
+        if u.bool() {
+            let start = u.u32() as i64;
+            host.scan_start_time = Some(start);
+            host.scan_end_time = Some(start + u.u16() as i64);
+        }
+        host.times = Option::arbitrary(u);
+        host
+    }
+}
+
+impl Arbitrary for Address {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        match u.below(3) {
+            0 => Address::IpAddr(IpAddr::V4(Ipv4Addr::from(u.u32()))),
+            1 => {
+                let mut segments = [0u16; 8];
+                for segment in segments.iter_mut() {
+                    *segment = u.u16();
+                }
+                Address::IpAddr(IpAddr::V6(Ipv6Addr::from(segments)))
+            }
+            _ => {
+                let mut octets = [0u8; 6];
+                for octet in octets.iter_mut() {
+                    *octet = u.u8();
+                }
+                Address::MacAddr(MacAddr::new(octets))
+            }
+        }
+    }
+}
+
+impl Arbitrary for HostStatus {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        HostStatus {
+            state: HostState::arbitrary(u),
+            reason: u.word(12),
+            reason_ttl: u.u8(),
+        }
+    }
+}
+
+impl Arbitrary for HostState {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        u.choose(&[
+            HostState::Up,
+            HostState::Down,
+            HostState::Unknown,
+            HostState::Skipped,
+        ])
+        .clone()
+    }
+}
+
+impl Arbitrary for Hostname {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        Hostname {
+            name: format!("{}.{}", u.word(8), u.choose(&["com", "org", "local"])),
+            source: HostnameType::arbitrary(u),
+        }
+    }
+}
+
+impl Arbitrary for HostnameType {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        u.choose(&[
+            HostnameType::User,
+            HostnameType::Dns,
+            HostnameType::Resolved,
+        ])
+        .clone()
+    }
+}
+
+impl Arbitrary for Times {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        Times {
+            srtt: u.u32() as i64,
+            rttvar: u.u16() as i64,
+            timeout: u.u32() as i64,
+        }
+    }
+}
+
+impl Arbitrary for PortInfo {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let mut port_info = PortInfo::default();
+        let mut seen = HashSet::new();
+        for _ in 0..u.below(8) {
+            let port = Port::arbitrary(u);
+            if seen.insert((port.protocol.clone(), port.port_number)) {
+                port_info.ports.push(port);
+            }
+        }
+        for _ in 0..u.below(2) {
+            port_info.extra_ports.push(ExtraPorts::arbitrary(u));
+        }
+        port_info
+    }
+}
+
+impl Arbitrary for ExtraPorts {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        ExtraPorts {
+            state: PortState::arbitrary(u),
+            count: u.u16() as u32,
+        }
+    }
+}
+
+impl Arbitrary for Port {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let protocol = PortProtocol::arbitrary(u);
+        //Mostly well known ports, so that ports repeat across hosts.
+        let port_number = if u.bool() {
+            *u.choose(&[22, 53, 80, 161, 443, 445, 3389, 8080])
+        } else {
+            u.u16()
+        };
+        let status = PortStatus::arbitrary(u);
+        let service_info = Option::arbitrary(u);
+        let mut scripts = Vec::new();
+        for _ in 0..u.below(3) {
+            scripts.push(Script::arbitrary(u));
+        }
+        Port {
+            protocol,
+            port_number,
+            status,
+            service_info,
+            scripts,
+        }
+    }
+}
+
+impl Arbitrary for PortProtocol {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        u.choose(&[
+            PortProtocol::Tcp,
+            PortProtocol::Udp,
+            PortProtocol::Sctp,
+            PortProtocol::Ip,
+        ])
+        .clone()
+    }
+}
+
+impl Arbitrary for PortStatus {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        PortStatus {
+            state: PortState::arbitrary(u),
+            reason: u.word(12),
+            reason_ttl: u.u8(),
+        }
+    }
+}
+
+impl Arbitrary for PortState {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        u.choose(&[
+            PortState::Open,
+            PortState::Closed,
+            PortState::Filtered,
+            PortState::Unfiltered,
+            PortState::OpenFiltered,
+            PortState::CloseFiltered,
+        ])
+        .clone()
+    }
+}
+
+impl Arbitrary for ServiceInfo {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let text = |u: &mut Unstructured<'_>| if u.bool() { Some(u.text(16)) } else { None };
+        let mut cpes = Vec::new();
+        for _ in 0..u.below(3) {
+            cpes.push(u.choose(CPES).parse::<Cpe>().expect("valid CPE"));
+        }
+        ServiceInfo {
+            name: u.word(10),
+            confidence_level: u.below(11) as u8,
+            method: ServiceMethod::arbitrary(u),
+            product: text(u),
+            version: text(u),
+            extra_info: text(u),
+            tunnel: if u.bool() {
+                Some("ssl".to_string())
+            } else {
+                None
+            },
+            cpes,
+        }
+    }
+}
+
+impl Arbitrary for ServiceMethod {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        u.choose(&[ServiceMethod::Table, ServiceMethod::Probe])
+            .clone()
+    }
+}
+
+impl Arbitrary for Script {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        let mut script = Script::new(u.choose(SCRIPT_IDS).to_string(), u.text(40));
+        for _ in 0..u.below(4) {
+            script.elements.push(element(u, 2));
+        }
+        script
+    }
+}
+
+impl Arbitrary for ScriptElement {
+    fn arbitrary(u: &mut Unstructured<'_>) -> Self {
+        element(u, 2)
+    }
+}
+
+//Tables nest at most `depth` levels deep.
+fn element(u: &mut Unstructured<'_>, depth: usize) -> ScriptElement {
+    let key = if u.bool() { Some(u.word(8)) } else { None };
+    if depth > 0 && u.below(4) == 0 {
+        let mut elements = Vec::new();
+        for _ in 0..u.below(4) {
+            elements.push(element(u, depth - 1));
+        }
+        ScriptElement::Table { key, elements }
+    } else {
+        ScriptElement::Elem {
+            key,
+            value: u.text(16),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testgen::to_xml;
+
+    #[test]
+    fn xml_round_trip() {
+        for seed in 0..200 {
+            let results = NmapResults::arbitrary(&mut Unstructured::from_seed(seed));
+            let parsed = NmapResults::parse(&to_xml(&results)).unwrap();
+
+            assert_eq!(parsed.scan_start_time, results.scan_start_time);
+            assert_eq!(parsed.scan_end_time, results.scan_end_time);
+            assert_eq!(parsed.args, results.args);
+            assert_eq!(parsed.hosts().count(), results.hosts().count());
+            for (a, b) in parsed.hosts().zip(results.hosts()) {
+                assert_eq!(a.addresses, b.addresses, "seed {}", seed);
+                assert_eq!(a.status.state, b.status.state);
+                assert_eq!(a.host_names, b.host_names);
+                assert_eq!(a.scripts, b.scripts, "seed {}", seed);
+                assert_eq!(a.port_info.ports, b.port_info.ports, "seed {}", seed);
+                assert_eq!(a.port_info.extra_ports, b.port_info.extra_ports);
+                assert_eq!(a.times, b.times);
+            }
+        }
+    }
+
+    #[test]
+    fn fuzzer_input() {
+        //Running out of data gives the smallest scan.
+        let empty = NmapResults::arbitrary(&mut Unstructured::new(&[]));
+        assert_eq!(empty.hosts().count(), 0);
+
+        let data = (0..=255).collect::<Vec<u8>>();
+        let mut u = Unstructured::new(&data);
+        let results = NmapResults::arbitrary(&mut u);
+        assert!(NmapResults::parse(&to_xml(&results)).is_ok());
+    }
+}
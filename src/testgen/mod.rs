@@ -0,0 +1,560 @@
+//!Synthetic scans for benchmarks and tests.
+//!
+//![`generate_xml()`](fn.generate_xml.html) writes a random but plausible
+//!Nmap XML document: sequential IPv4 targets, some of them down, with
+//!common services, version details, hostnames and script output.
+//![`generate()`](fn.generate.html) parses it, so the results go through
+//!the same code as real scans. The output only depends on the
+//![`ScanProfile`](struct.ScanProfile.html), seed included, so tests can
+//!rely on it.
+//!
+//!To check invariants over random data instead, the types implement
+//![`arbitrary::Arbitrary`](arbitrary/trait.Arbitrary.html), and
+//![`to_xml()`](fn.to_xml.html) writes any results back as Nmap XML.
+//!
+//!Requires the `testgen` feature.
+use std::fmt::Write;
+
+use crate::host::{Address, HostnameType, Script, ScriptElement};
+use crate::port::Port;
+use crate::util::{escape_xml, format_ctime};
+use crate::NmapResults;
+
+pub mod arbitrary;
+
+///The shape of a generated scan.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanProfile {
+    pub hosts: usize,
+    ///Maximum number of ports listed per host that is up.
+    pub ports_per_host: usize,
+    ///Maximum number of scripts per port.
+    pub scripts_per_port: usize,
+    ///Share of the hosts that are down, between 0 and 1.
+    pub down_ratio: f64,
+    pub seed: u64,
+}
+
+impl Default for ScanProfile {
+    fn default() -> Self {
+        ScanProfile {
+            hosts: 16,
+            ports_per_host: 8,
+            scripts_per_port: 1,
+            down_ratio: 0.25,
+            seed: 0,
+        }
+    }
+}
+
+impl ScanProfile {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn hosts(mut self, hosts: usize) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    pub fn ports_per_host(mut self, ports: usize) -> Self {
+        self.ports_per_host = ports;
+        self
+    }
+
+    pub fn scripts_per_port(mut self, scripts: usize) -> Self {
+        self.scripts_per_port = scripts;
+        self
+    }
+
+    pub fn down_ratio(mut self, ratio: f64) -> Self {
+        self.down_ratio = ratio;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+//Port, service, product, version, CPE.
+const SERVICES: &[(u16, &str, &str, &str, &str)] = &[
+    (21, "ftp", "vsftpd", "3.0.3", "cpe:/a:vsftpd:vsftpd:3.0.3"),
+    (22, "ssh", "OpenSSH", "7.4", "cpe:/a:openbsd:openssh:7.4"),
+    (25, "smtp", "Postfix smtpd", "", "cpe:/a:postfix:postfix"),
+    (53, "domain", "ISC BIND", "9.11.4", "cpe:/a:isc:bind:9.11.4"),
+    (
+        80,
+        "http",
+        "nginx",
+        "1.18.0",
+        "cpe:/a:igor_sysoev:nginx:1.18.0",
+    ),
+    (110, "pop3", "Dovecot pop3d", "", "cpe:/a:dovecot:dovecot"),
+    (
+        139,
+        "netbios-ssn",
+        "Samba smbd",
+        "3.X - 4.X",
+        "cpe:/a:samba:samba",
+    ),
+    (
+        443,
+        "https",
+        "Apache httpd",
+        "2.4.41",
+        "cpe:/a:apache:http_server:2.4.41",
+    ),
+    (
+        445,
+        "microsoft-ds",
+        "Samba smbd",
+        "4.7.6",
+        "cpe:/a:samba:samba:4.7.6",
+    ),
+    (
+        3306,
+        "mysql",
+        "MySQL",
+        "5.7.30",
+        "cpe:/a:mysql:mysql:5.7.30",
+    ),
+    (
+        3389,
+        "ms-wbt-server",
+        "Microsoft Terminal Services",
+        "",
+        "cpe:/o:microsoft:windows",
+    ),
+    (
+        5432,
+        "postgresql",
+        "PostgreSQL DB",
+        "9.6.0 - 9.6.5",
+        "cpe:/a:postgresql:postgresql:9.6",
+    ),
+    (6379, "redis", "Redis key-value store", "5.0.7", ""),
+    (
+        8080,
+        "http-proxy",
+        "Squid http proxy",
+        "4.10",
+        "cpe:/a:squid-cache:squid:4.10",
+    ),
+    (8443, "https-alt", "", "", ""),
+    (9200, "wap-wsp", "Elasticsearch REST API", "7.6.2", ""),
+];
+
+const NAMES: &[&str] = &["web", "mail", "db", "files", "gw", "ns", "app", "backup"];
+
+//splitmix64, good enough for test data and stable across platforms.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+///Writes a random Nmap XML document shaped by `profile`.
+pub fn generate_xml(profile: &ScanProfile) -> String {
+    let mut rng = Rng(profile.seed);
+    let start = 1_588_318_812 + (profile.seed % 86400) as i64;
+    let end = start + 2 + profile.hosts as i64 / 4;
+    let mut xml = String::new();
+    let mut up = 0;
+
+    let _ = writeln!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<nmaprun scanner=\"nmap\" args=\"nmap -sV -sC 10.0.0.0/16\" start=\"{}\" startstr=\"{}\" version=\"7.80\" xmloutputversion=\"1.04\">",
+        start,
+        format_ctime(start)
+    );
+    for i in 0..profile.hosts {
+        let ip = format!("10.0.{}.{}", i / 254, i % 254 + 1);
+        if rng.chance(profile.down_ratio) {
+            let _ = writeln!(
+                xml,
+                "<host><status state=\"down\" reason=\"no-response\" reason_ttl=\"0\"/>\n<address addr=\"{}\" addrtype=\"ipv4\"/>\n</host>",
+                ip
+            );
+            continue;
+        }
+        up += 1;
+
+        let ttl = [64, 128, 255][rng.below(3)];
+        let _ = writeln!(
+            xml,
+            "<host starttime=\"{}\" endtime=\"{}\"><status state=\"up\" reason=\"echo-reply\" reason_ttl=\"{}\"/>\n<address addr=\"{}\" addrtype=\"ipv4\"/>",
+            start,
+            end,
+            ttl - 1,
+            ip
+        );
+        if rng.chance(0.5) {
+            let _ = writeln!(
+                xml,
+                "<hostnames><hostname name=\"{}{}.example.com\" type=\"PTR\"/></hostnames>",
+                NAMES[rng.below(NAMES.len())],
+                i
+            );
+        }
+
+        let count = rng.below(profile.ports_per_host + 1);
+        let first = rng.below(SERVICES.len());
+        xml.push_str("<ports>");
+        if count < 1000 {
+            let _ = write!(
+                xml,
+                "<extraports state=\"closed\" count=\"{}\"/>",
+                1000 - count
+            );
+        }
+        xml.push('\n');
+        //Consecutive entries of the table, wrapping, so ports are unique;
+        //ports beyond the table get unknown services.
+        for j in 0..count {
+            let (port, name, product, version, cpe) = if j < SERVICES.len() {
+                SERVICES[(first + j) % SERVICES.len()]
+            } else {
+                (10000 + j as u16, "unknown", "", "", "")
+            };
+            write_port(
+                &mut xml, &mut rng, profile, port, name, product, version, cpe, ttl,
+            );
+        }
+        xml.push_str("</ports>\n");
+        if rng.chance(0.3) {
+            let _ = writeln!(
+                xml,
+                "<os><osmatch name=\"Linux 3.2 - 4.9\" accuracy=\"{}\" line=\"61812\"><osclass type=\"general purpose\" vendor=\"Linux\" osfamily=\"Linux\" osgen=\"3.X\" accuracy=\"{}\"><cpe>cpe:/o:linux:linux_kernel:3</cpe></osclass></osmatch></os>",
+                90 + rng.below(11),
+                90
+            );
+        }
+        let _ = writeln!(
+            xml,
+            "<times srtt=\"{}\" rttvar=\"{}\" to=\"100000\"/>\n</host>",
+            1000 + rng.below(200_000),
+            rng.below(5000)
+        );
+    }
+    let _ = writeln!(
+        xml,
+        "<runstats><finished time=\"{}\" timestr=\"{}\" exit=\"success\"/><hosts up=\"{}\" down=\"{}\" total=\"{}\"/>\n</runstats>\n</nmaprun>",
+        end,
+        format_ctime(end),
+        up,
+        profile.hosts - up,
+        profile.hosts
+    );
+    xml
+}
+
+///Generates random results shaped by `profile`.
+pub fn generate(profile: &ScanProfile) -> NmapResults {
+    NmapResults::parse(&generate_xml(profile)).expect("generated scans are valid")
+}
+
+///Writes `results` as Nmap XML.
+///
+///Addresses, host names, status, ports, scripts and round trip times are
+///written; traceroutes and OS detection results are left out.
+pub fn to_xml(results: &NmapResults) -> String {
+    let mut xml =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<nmaprun scanner=\"nmap\"");
+    if let Some(args) = &results.args {
+        let _ = write!(xml, " args=\"{}\"", attr(args));
+    }
+    let _ = write!(xml, " start=\"{}\"", results.scan_start_time);
+    if let Some(version) = &results.nmap_version {
+        let _ = write!(xml, " version=\"{}\"", attr(version));
+    }
+    if let Some(version) = &results.output_version {
+        let _ = write!(xml, " xmloutputversion=\"{}\"", version);
+    }
+    xml.push_str(">\n");
+
+    for host in results.hosts() {
+        xml.push_str("<host");
+        if let (Some(start), Some(end)) = (host.scan_start_time, host.scan_end_time) {
+            let _ = write!(xml, " starttime=\"{}\" endtime=\"{}\"", start, end);
+        }
+        let _ = writeln!(
+            xml,
+            "><status state=\"{}\" reason=\"{}\" reason_ttl=\"{}\"/>",
+            host.status.state,
+            attr(&host.status.reason),
+            host.status.reason_ttl
+        );
+        for address in host.addresses() {
+            let (addr, addrtype) = match address {
+                Address::IpAddr(ip) if ip.is_ipv4() => (ip.to_string(), "ipv4"),
+                Address::IpAddr(ip) => (ip.to_string(), "ipv6"),
+                Address::MacAddr(mac) => (mac.to_string(), "mac"),
+            };
+            let _ = writeln!(
+                xml,
+                "<address addr=\"{}\" addrtype=\"{}\"/>",
+                attr(&addr),
+                addrtype
+            );
+        }
+        xml.push_str("<hostnames>");
+        for name in host.host_names() {
+            let source = match name.source {
+                HostnameType::User => "user",
+                HostnameType::Dns => "PTR",
+                HostnameType::Resolved => "resolved",
+            };
+            let _ = write!(
+                xml,
+                "<hostname name=\"{}\" type=\"{}\"/>",
+                attr(&name.name),
+                source
+            );
+        }
+        xml.push_str("</hostnames>\n<ports>");
+        for extra in host.port_info.extra_ports() {
+            let _ = write!(
+                xml,
+                "<extraports state=\"{}\" count=\"{}\"/>",
+                extra.state, extra.count
+            );
+        }
+        xml.push('\n');
+        for port in host.port_info.ports() {
+            write_model_port(&mut xml, port);
+        }
+        xml.push_str("</ports>\n");
+        if host.scripts().next().is_some() {
+            xml.push_str("<hostscript>");
+            for script in host.scripts() {
+                write_script(&mut xml, script);
+            }
+            xml.push_str("</hostscript>\n");
+        }
+        if let Some(times) = host.times() {
+            let _ = writeln!(
+                xml,
+                "<times srtt=\"{}\" rttvar=\"{}\" to=\"{}\"/>",
+                times.srtt, times.rttvar, times.timeout
+            );
+        }
+        xml.push_str("</host>\n");
+    }
+
+    if let Some(end) = results.scan_end_time {
+        let _ = writeln!(
+            xml,
+            "<runstats><finished time=\"{}\" timestr=\"{}\" exit=\"success\"/></runstats>",
+            end,
+            format_ctime(end)
+        );
+    }
+    xml.push_str("</nmaprun>\n");
+    xml
+}
+
+fn write_model_port(xml: &mut String, port: &Port) {
+    let _ = write!(
+        xml,
+        "<port protocol=\"{}\" portid=\"{}\"><state state=\"{}\" reason=\"{}\" reason_ttl=\"{}\"/>",
+        port.protocol,
+        port.port_number,
+        port.status.state,
+        attr(&port.status.reason),
+        port.status.reason_ttl
+    );
+    if let Some(service) = &port.service_info {
+        let _ = write!(xml, "<service name=\"{}\"", attr(&service.name));
+        for (name, value) in [
+            ("product", &service.product),
+            ("version", &service.version),
+            ("extrainfo", &service.extra_info),
+            ("tunnel", &service.tunnel),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(xml, " {}=\"{}\"", name, attr(value));
+            }
+        }
+        let _ = write!(
+            xml,
+            " method=\"{}\" conf=\"{}\">",
+            service.method, service.confidence_level
+        );
+        for cpe in service.cpes.iter() {
+            let _ = write!(xml, "<cpe>{}</cpe>", escape_xml(&cpe.to_string()));
+        }
+        xml.push_str("</service>");
+    }
+    for script in port.scripts.iter() {
+        write_script(xml, script);
+    }
+    xml.push_str("</port>\n");
+}
+
+fn write_script(xml: &mut String, script: &Script) {
+    let _ = write!(
+        xml,
+        "<script id=\"{}\" output=\"{}\">",
+        attr(&script.id),
+        attr(&script.output)
+    );
+    for element in script.elements() {
+        write_element(xml, element);
+    }
+    xml.push_str("</script>");
+}
+
+fn write_element(xml: &mut String, element: &ScriptElement) {
+    let key = match element.key() {
+        Some(key) => format!(" key=\"{}\"", attr(key)),
+        None => String::new(),
+    };
+    match element {
+        ScriptElement::Elem { value, .. } => {
+            let _ = write!(xml, "<elem{}>{}</elem>", key, escape_xml(value));
+        }
+        ScriptElement::Table { elements, .. } => {
+            let _ = write!(xml, "<table{}>", key);
+            for element in elements {
+                write_element(xml, element);
+            }
+            xml.push_str("</table>");
+        }
+    }
+}
+
+//Escapes an attribute value. Whitespace other than spaces is written as
+//character references, as parsers normalize it to spaces otherwise.
+fn attr(value: &str) -> String {
+    escape_xml(value)
+        .replace('\n', "&#xa;")
+        .replace('\r', "&#xd;")
+        .replace('\t', "&#x9;")
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_port(
+    xml: &mut String,
+    rng: &mut Rng,
+    profile: &ScanProfile,
+    port: u16,
+    name: &str,
+    product: &str,
+    version: &str,
+    cpe: &str,
+    ttl: u8,
+) {
+    let _ = write!(
+        xml,
+        "<port protocol=\"tcp\" portid=\"{}\"><state state=\"open\" reason=\"syn-ack\" reason_ttl=\"{}\"/>",
+        port,
+        ttl - 1
+    );
+    if product.is_empty() {
+        let _ = write!(
+            xml,
+            "<service name=\"{}\" method=\"table\" conf=\"3\"/>",
+            name
+        );
+    } else {
+        let _ = write!(
+            xml,
+            "<service name=\"{}\" product=\"{}\"{} method=\"probed\" conf=\"10\">",
+            name,
+            escape_xml(product),
+            if version.is_empty() {
+                String::new()
+            } else {
+                format!(" version=\"{}\"", escape_xml(version))
+            }
+        );
+        if !cpe.is_empty() {
+            let _ = write!(xml, "<cpe>{}</cpe>", cpe);
+        }
+        xml.push_str("</service>");
+    }
+
+    for _ in 0..rng.below(profile.scripts_per_port + 1) {
+        let script = match rng.below(3) {
+            0 => format!(
+                "<script id=\"http-title\" output=\"Welcome to {0}\"><elem key=\"title\">Welcome to {0}</elem></script>",
+                escape_xml(if product.is_empty() { name } else { product })
+            ),
+            1 => format!(
+                "<script id=\"banner\" output=\"{} {}\"/>",
+                escape_xml(name),
+                escape_xml(version)
+            ),
+            _ => {
+                let fingerprint = format!("{:016x}{:016x}", rng.next(), rng.next());
+                format!(
+                    "<script id=\"ssh-hostkey\" output=\"&#xa;  2048 {} (RSA)\"><table><elem key=\"fingerprint\">{}</elem><elem key=\"type\">ssh-rsa</elem><elem key=\"bits\">2048</elem></table></script>",
+                    fingerprint
+                        .as_bytes()
+                        .chunks(2)
+                        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+                        .collect::<Vec<_>>()
+                        .join(":"),
+                    fingerprint
+                )
+            }
+        };
+        xml.push_str(&script);
+    }
+    xml.push_str("</port>\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::host::HostState;
+
+    #[test]
+    fn generated_scans() {
+        let profile = ScanProfile::new()
+            .hosts(300)
+            .ports_per_host(20)
+            .scripts_per_port(2)
+            .seed(42);
+        let results = generate(&profile);
+
+        assert_eq!(results.hosts().count(), 300);
+        let down = results
+            .hosts()
+            .filter(|h| h.status.state == HostState::Down)
+            .count();
+        assert!(down > 30 && down < 120, "{} hosts down", down);
+        assert!(results.hosts().all(|h| h.port_info.ports().count() <= 20));
+        assert!(results.iter_ports().any(|(_, p)| !p.scripts.is_empty()));
+        assert!(results
+            .host_by_addr(&"10.0.1.46".parse().unwrap())
+            .is_some());
+
+        //The same profile gives the same scan.
+        assert_eq!(generate_xml(&profile), generate_xml(&profile));
+        assert_ne!(
+            generate_xml(&profile),
+            generate_xml(&profile.clone().seed(43))
+        );
+    }
+}
@@ -1,18 +1,22 @@
+use roxmltree::Node;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::host::HostState;
+use crate::version::{self, OutputVersion};
+use crate::{Error, NmapResults};
+
 macro_rules! parse_node_attr {
     ($node:expr, $nodename:expr, $attribute:expr, $type:ty) => {{
-        $node
-            .attribute($attribute)
-            .ok_or_else(|| {
-                Error::from(formatcp!(
-                    "expected `{}` attribute in `{}`",
-                    $attribute,
-                    $nodename
-                ))
-            })
-            .and_then(|s| {
-                s.parse::<$type>()
-                    .map_err(|_| Error::from(formatcp!("failed to parse `{}`", $attribute)))
-            })?
+        let value = $node.attribute($attribute).ok_or(Error::MissingAttribute {
+            element: $nodename,
+            attribute: $attribute,
+        })?;
+        value.parse::<$type>().map_err(|_| Error::InvalidValue {
+            element: $nodename,
+            attribute: $attribute,
+            value: value.to_string(),
+        })?
     }};
 }
 
@@ -20,12 +24,9 @@ macro_rules! node_attr_as_string {
     ($node:expr, $nodename:expr, $attribute:expr) => {{
         $node
             .attribute($attribute)
-            .ok_or_else(|| {
-                Error::from(formatcp!(
-                    "expected `{}` attribute in `{}` node",
-                    $attribute,
-                    $nodename
-                ))
+            .ok_or(Error::MissingAttribute {
+                element: $nodename,
+                attribute: $attribute,
             })?
             .to_string()
     }};
@@ -33,18 +34,495 @@ macro_rules! node_attr_as_string {
 
 macro_rules! from_node_attr {
     ($node:expr, $nodename:expr, $attribute:expr, $type:ty) => {{
-        let s = $node.attribute($attribute).ok_or_else(|| {
-            Error::from(formatcp!(
-                "expected `{}` attribute in `{}` node",
-                $attribute,
-                $nodename
-            ))
+        let value = $node.attribute($attribute).ok_or(Error::MissingAttribute {
+            element: $nodename,
+            attribute: $attribute,
         })?;
-        <$type>::from_str(s)
-            .map_err(|_| Error::from(formatcp!("failed to parse `{}`", stringify!($type))))?
+        <$type>::from_str(value).map_err(|_| Error::InvalidValue {
+            element: $nodename,
+            attribute: $attribute,
+            value: value.to_string(),
+        })?
     }};
 }
 
+///Implements `Display` and `FromStr` for a fieldless enum. Variants are
+///written as their first name and parsed from any of their names; `$kind`
+///names the type in parse errors.
+macro_rules! string_enum {
+    ($type:ident, $kind:expr, { $($variant:ident => $name:literal $(| $alias:literal)*),+ $(,)? }) => {
+        impl std::fmt::Display for $type {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(match self {
+                    $($type::$variant => $name,)+
+                })
+            }
+        }
+
+        impl std::str::FromStr for $type {
+            type Err = crate::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name $(| $alias)* => Ok($type::$variant),)+
+                    _ => Err(crate::Error::UnknownValue {
+                        kind: $kind,
+                        value: s.to_string(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
 pub(crate) use from_node_attr;
 pub(crate) use node_attr_as_string;
 pub(crate) use parse_node_attr;
+pub(crate) use string_enum;
+
+///Parses the attribute `attribute` of `node`, an `element` node, if it is
+///present.
+pub(crate) fn parse_optional_attr<T: FromStr>(
+    node: Node,
+    element: &'static str,
+    attribute: &'static str,
+) -> Result<Option<T>, Error> {
+    node.attribute(attribute)
+        .map(|value| {
+            value.parse::<T>().map_err(|_| Error::InvalidValue {
+                element,
+                attribute,
+                value: value.to_string(),
+            })
+        })
+        .transpose()
+}
+
+///Parses the `reason` and `reason_ttl` attributes of `node`, an `element`
+///node. Documents before output version 1.04 may lack them.
+pub(crate) fn parse_reason(node: Node, element: &'static str) -> Result<(String, u8), Error> {
+    let legacy = || version::is_before(node, OutputVersion::new(1, 4));
+    let reason = match node.attribute("reason") {
+        Some(reason) => reason.to_string(),
+        None if legacy() => String::new(),
+        None => {
+            return Err(Error::MissingAttribute {
+                element,
+                attribute: "reason",
+            })
+        }
+    };
+    let reason_ttl = match parse_optional_attr(node, element, "reason_ttl")? {
+        Some(reason_ttl) => reason_ttl,
+        None if legacy() => 0,
+        None => {
+            return Err(Error::MissingAttribute {
+                element,
+                attribute: "reason_ttl",
+            })
+        }
+    };
+    Ok((reason, reason_ttl))
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+///Splits seconds since Unix epoch into a UTC (year, month, day, hour,
+///minute, second) tuple. Months and days are 1-based.
+pub(crate) fn civil_from_epoch(epoch: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = epoch.div_euclid(86400);
+    let secs = epoch.rem_euclid(86400);
+
+    //http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        year,
+        month,
+        day,
+        (secs / 3600) as u32,
+        (secs % 3600 / 60) as u32,
+        (secs % 60) as u32,
+    )
+}
+
+///Converts a UTC date and time to seconds since Unix epoch. Months and days
+///are 1-based.
+pub(crate) fn epoch_from_civil(
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+) -> i64 {
+    //http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    days * 86400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64
+}
+
+///Parses a time printed the way Nmap does in its text outputs, e.g.
+///`Fri May  1 15:40:12 2020`, as a UTC time.
+pub(crate) fn parse_ctime(s: &str) -> Option<i64> {
+    let mut fields = s.split_whitespace();
+    let _weekday = fields.next()?;
+    let month_name = fields.next()?;
+    let month = MONTHS.iter().position(|m| *m == month_name)? as u32 + 1;
+    let day = fields.next()?.parse().ok()?;
+    let mut time = fields.next()?.split(':').map(|f| f.parse::<u32>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    let year = fields.next()?.parse().ok()?;
+
+    Some(epoch_from_civil(year, month, day, hour, minute, second))
+}
+
+///Formats seconds since Unix epoch the way Nmap prints times in its
+///text outputs, e.g. `Fri May  1 07:40:12 2020`. Always UTC.
+pub(crate) fn format_ctime(epoch: i64) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_epoch(epoch);
+    let weekday = WEEKDAYS[epoch.div_euclid(86400).rem_euclid(7) as usize];
+
+    format!(
+        "{} {} {:>2} {:02}:{:02}:{:02} {}",
+        weekday,
+        MONTHS[month as usize - 1],
+        day,
+        hour,
+        minute,
+        second,
+        year
+    )
+}
+
+///Converts seconds since Unix epoch to a `SystemTime`, or `None` if the
+///platform cannot represent it.
+pub(crate) fn system_time(epoch: i64) -> Option<SystemTime> {
+    let offset = Duration::from_secs(epoch.unsigned_abs());
+    if epoch < 0 {
+        UNIX_EPOCH.checked_sub(offset)
+    } else {
+        UNIX_EPOCH.checked_add(offset)
+    }
+}
+
+///Returns the time elapsed from `start` to `end`, both in seconds since Unix
+///epoch, or `None` if `end` is before `start`.
+pub(crate) fn duration_between(start: i64, end: i64) -> Option<Duration> {
+    let seconds = end.checked_sub(start)?;
+    if seconds < 0 {
+        return None;
+    }
+    Some(Duration::from_secs(seconds as u64))
+}
+
+///Formats seconds since Unix epoch as an RFC 3339 UTC timestamp, e.g.
+///`2020-05-01T07:40:12Z`.
+pub fn format_rfc3339(epoch: i64) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_epoch(epoch);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+///Parses an RFC 3339 timestamp such as `2023-06-01T12:00:00.123Z` or
+///`2023-06-01T14:00:00+02:00` to seconds since Unix epoch. Fractional
+///seconds are truncated.
+pub(crate) fn parse_rfc3339(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let number = |range: std::ops::Range<usize>| s.get(range)?.parse::<u32>().ok();
+    if s.len() < 19 || s.as_bytes()[4] != b'-' || s.as_bytes()[7] != b'-' {
+        return None;
+    }
+    if !matches!(s.as_bytes()[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    let epoch = epoch_from_civil(
+        number(0..4)? as i64,
+        number(5..7)?,
+        number(8..10)?,
+        number(11..13)?,
+        number(14..16)?,
+        number(17..19)?,
+    );
+
+    let zone = s[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let mut hm = zone[1..].split(':').map(|f| f.parse::<i64>().ok());
+            sign * (hm.next()?? * 3600 + hm.next()?? * 60)
+        }
+    };
+
+    Some(epoch - offset)
+}
+
+///Escapes the characters that are significant in XML and HTML text and
+///attribute values.
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+///Computes the SHA-1 digest of `data`.
+pub(crate) fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e].iter()) {
+            *state = state.wrapping_add(*value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+///Computes the HMAC-SHA-1 (RFC 2104) of `data` with `key`.
+pub(crate) fn hmac_sha1(key: &[u8], data: &[u8]) -> [u8; 20] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..20].copy_from_slice(&sha1(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = block.iter().map(|b| b ^ 0x36).collect::<Vec<_>>();
+    inner.extend_from_slice(data);
+    let mut outer = block.iter().map(|b| b ^ 0x5c).collect::<Vec<_>>();
+    outer.extend_from_slice(&sha1(&inner));
+    sha1(&outer)
+}
+
+///Builds a name based (version 5) UUID from `namespace` and `name`.
+pub(crate) fn uuid_v5(namespace: &[u8; 16], name: &str) -> String {
+    let mut data = namespace.to_vec();
+    data.extend_from_slice(name.as_bytes());
+    let mut bytes = sha1(&data);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+///Returns the `# Nmap ... scan initiated` comment line shared by Nmap's
+///text output formats.
+pub(crate) fn start_comment(results: &NmapResults) -> Option<String> {
+    let args = results.args.as_ref()?;
+    Some(format!(
+        "# Nmap {} scan initiated {} as: {}",
+        results.nmap_version.as_deref().unwrap_or(""),
+        format_ctime(results.scan_start_time),
+        args
+    ))
+}
+
+///Returns the `# Nmap done at ...` comment line shared by Nmap's text
+///output formats.
+pub(crate) fn done_comment(results: &NmapResults) -> Option<String> {
+    let end = results.scan_end_time?;
+    let total = results.hosts().count();
+    let up = results
+        .hosts()
+        .filter(|h| h.status.state == HostState::Up)
+        .count();
+
+    Some(format!(
+        "# Nmap done at {} -- {} IP {} ({} {} up) scanned in {}.00 seconds",
+        format_ctime(end),
+        total,
+        if total == 1 { "address" } else { "addresses" },
+        up,
+        if up == 1 { "host" } else { "hosts" },
+        end.saturating_sub(results.scan_start_time)
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ctime_formatting() {
+        assert_eq!(format_ctime(0), "Thu Jan  1 00:00:00 1970");
+        assert_eq!(format_ctime(1588318812), "Fri May  1 07:40:12 2020");
+        assert_eq!(format_rfc3339(1588318812), "2020-05-01T07:40:12Z");
+        assert_eq!(parse_ctime("Fri May  1 07:40:12 2020"), Some(1588318812));
+        assert_eq!(parse_ctime("Thu Jan  1 00:00:00 1970"), Some(0));
+        assert_eq!(parse_ctime("Fri Mai  1 07:40:12 2020"), None);
+    }
+
+    #[test]
+    fn out_of_range_times() {
+        assert_eq!(
+            system_time(-1),
+            UNIX_EPOCH.checked_sub(Duration::from_secs(1))
+        );
+        //Whether these fit depends on the platform, but they must not panic.
+        let _ = system_time(i64::MAX);
+        let _ = system_time(i64::MIN);
+
+        let mut results = NmapResults::parse(r#"<nmaprun start="1588318812"/>"#).unwrap();
+        results.scan_start_time = i64::MIN;
+        results.scan_end_time = Some(i64::MAX);
+        assert!(done_comment(&results)
+            .unwrap()
+            .ends_with(&format!("scanned in {}.00 seconds", i64::MAX)));
+    }
+
+    #[test]
+    fn rfc3339_parsing() {
+        assert_eq!(parse_rfc3339("2020-05-01T07:40:12Z"), Some(1588318812));
+        assert_eq!(
+            parse_rfc3339("2020-05-01T09:40:12.123456789+02:00"),
+            Some(1588318812)
+        );
+        assert_eq!(parse_rfc3339("2020-05-01"), None);
+    }
+
+    #[test]
+    fn sha1_digest() {
+        let hex = |d: [u8; 20]| d.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(b"The quick brown fox jumps over the lazy dog")),
+            "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12"
+        );
+        assert_eq!(
+            hex(hmac_sha1(
+                b"key",
+                b"The quick brown fox jumps over the lazy dog"
+            )),
+            "de7c9b85b8b78aa6bc8a7a36f70a90701c9db4d9"
+        );
+    }
+
+    #[test]
+    fn name_based_uuid() {
+        //DNS namespace example from RFC 4122 errata / Python's uuid module.
+        let dns = [
+            0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4,
+            0x30, 0xc8,
+        ];
+        assert_eq!(
+            uuid_v5(&dns, "python.org"),
+            "886313e1-3b8a-5372-9b90-0c9aee199e5d"
+        );
+    }
+
+    #[test]
+    fn xml_escaping() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn enum_strings() {
+        use crate::host::HostnameType;
+        use crate::port::PortState;
+
+        assert_eq!(PortState::CloseFiltered.to_string(), "closed|filtered");
+        assert_eq!(
+            PortState::from_str("close|filtered").unwrap(),
+            PortState::CloseFiltered
+        );
+        assert_eq!(HostnameType::from_str("PTR").unwrap(), HostnameType::Dns);
+        assert_eq!(HostnameType::Dns.to_string(), "Dns");
+        assert_eq!(
+            PortState::from_str("Open").unwrap_err().to_string(),
+            "unknown port state `Open`"
+        );
+    }
+}
@@ -0,0 +1,830 @@
+//!Checks documents against the structure of Nmap's DTD.
+//!
+//![`validate()`](fn.validate.html) reports where a document strays from
+//!the element and attribute declarations of `nmap.dtd`, which are carried
+//!here as a ruleset rather than read from the DTD file. It is meant for
+//!triaging files from tools claiming to write Nmap XML: a document that is
+//!not well-formed XML is an error, while a well-formed one gives the list of
+//![`Violation`](struct.Violation.html)s found, empty if it is valid.
+//!
+//!The ruleset checks which elements may appear in which, how many times
+//!they must or may appear, the required and allowed attributes and the
+//!values of enumerated and numeric attributes. The order of children is
+//!not checked.
+//!
+//!```
+//!use nmap_xml_parser::validate::{validate, ViolationKind};
+//!
+//!let violations = validate(
+//!    r#"<nmaprun scanner="masscan" start="1588318812" version="1.0" xmloutputversion="1.03">
+//!<host><address addr="10.0.0.1" addrtype="ipv4"/></host>
+//!</nmaprun>"#,
+//!)
+//!.unwrap();
+//!assert_eq!(violations[0].path, "nmaprun");
+//!assert_eq!(violations[0].to_string(), "1:1: nmaprun: invalid value `masscan` for `scanner` attribute in `nmaprun` node");
+//!assert!(violations
+//!    .iter()
+//!    .any(|v| v.kind == ViolationKind::MissingElement { element: "status", parent: "host" }));
+//!```
+use std::fmt;
+
+use roxmltree::{Document, Node};
+
+use crate::Error;
+
+use self::Occurs::{Any as Many, One, OneOrMore, Optional};
+use self::Values::{Any, Number, OneOf};
+
+///A place where a document breaks the ruleset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    ///Path to the offending element, in the form of
+    ///[`Error::path()`](../enum.Error.html#method.path), e.g.
+    ///`host[10.0.0.1] > ports > port[tcp/22]`, or `nmaprun` for the root.
+    pub path: String,
+    ///Line of the offending element, starting at 1.
+    pub line: u32,
+    ///Column of the offending element, starting at 1.
+    pub column: u32,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.path, self.kind
+        )
+    }
+}
+
+///How a document breaks the ruleset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    ///The root element is not `nmaprun`.
+    WrongRoot(String),
+    ///The element is not declared at all.
+    UnknownElement(String),
+    ///The element is declared but may not appear in `parent`.
+    UnexpectedElement {
+        element: String,
+        parent: &'static str,
+    },
+    ///`parent` lacks a child it must have.
+    MissingElement {
+        element: &'static str,
+        parent: &'static str,
+    },
+    ///`parent` has several of a child it may have at most once.
+    RepeatedElement {
+        element: &'static str,
+        parent: &'static str,
+    },
+    ///The element has text but is declared to hold elements only.
+    UnexpectedText { element: &'static str },
+    ///The element lacks a required attribute.
+    MissingAttribute {
+        element: &'static str,
+        attribute: &'static str,
+    },
+    ///The attribute is not declared for the element.
+    UnknownAttribute {
+        element: &'static str,
+        attribute: String,
+    },
+    ///The attribute value is not one of those declared, or not a number.
+    InvalidValue {
+        element: &'static str,
+        attribute: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViolationKind::WrongRoot(element) => {
+                write!(f, "root node is `{}` instead of `nmaprun`", element)
+            }
+            ViolationKind::UnknownElement(element) => write!(f, "unknown `{}` node", element),
+            ViolationKind::UnexpectedElement { element, parent } => {
+                write!(f, "unexpected `{}` node in `{}`", element, parent)
+            }
+            ViolationKind::MissingElement { element, parent } => {
+                write!(f, "expected `{}` node in `{}`", element, parent)
+            }
+            ViolationKind::RepeatedElement { element, parent } => {
+                write!(f, "more than one `{}` node in `{}`", element, parent)
+            }
+            ViolationKind::UnexpectedText { element } => {
+                write!(f, "unexpected text in `{}` node", element)
+            }
+            ViolationKind::MissingAttribute { element, attribute } => write!(
+                f,
+                "expected `{}` attribute in `{}` node",
+                attribute, element
+            ),
+            ViolationKind::UnknownAttribute { element, attribute } => {
+                write!(f, "unknown `{}` attribute in `{}` node", attribute, element)
+            }
+            ViolationKind::InvalidValue {
+                element,
+                attribute,
+                value,
+            } => write!(
+                f,
+                "invalid value `{}` for `{}` attribute in `{}` node",
+                value, attribute, element
+            ),
+        }
+    }
+}
+
+///Checks `xml` against the ruleset. Violations are listed in document
+///order, those of an element before those of its children.
+pub fn validate(xml: &str) -> Result<Vec<Violation>, Error> {
+    let doc = Document::parse(xml)?;
+    let mut validator = Validator {
+        doc: &doc,
+        violations: Vec::new(),
+    };
+
+    let root = doc.root_element();
+    match root.tag_name().name() {
+        "nmaprun" => validator.element(root, &RULES[0], "nmaprun"),
+        name => validator.report(root, name, ViolationKind::WrongRoot(name.to_string())),
+    }
+    Ok(validator.violations)
+}
+
+struct Validator<'d, 'input> {
+    doc: &'d Document<'input>,
+    violations: Vec<Violation>,
+}
+
+impl<'d, 'input> Validator<'d, 'input> {
+    fn report(&mut self, node: Node, path: &str, kind: ViolationKind) {
+        let pos = self.doc.text_pos_at(node.range().start);
+        self.violations.push(Violation {
+            path: path.to_string(),
+            line: pos.row,
+            column: pos.col,
+            kind,
+        });
+    }
+
+    fn element(&mut self, node: Node, rule: &'static Rule, path: &str) {
+        self.attributes(node, rule, path);
+
+        let mut counts = vec![0usize; rule.children.len()];
+        let mut children = Vec::new();
+        for child in node.children() {
+            if child.is_text() {
+                let text = child.text().unwrap_or_default();
+                if !rule.text && !text.trim().is_empty() {
+                    self.report(
+                        node,
+                        path,
+                        ViolationKind::UnexpectedText { element: rule.name },
+                    );
+                }
+                continue;
+            }
+            if !child.is_element() {
+                continue;
+            }
+
+            let name = child.tag_name().name();
+            //Paths start below the root, as those of parse errors do
+            let child_path = match node.parent_element() {
+                Some(_) => format!("{} > {}", path, segment(child)),
+                None => segment(child),
+            };
+            let declared = rule.children.iter().position(|(n, _)| *n == name);
+            match (declared, lookup(name)) {
+                (Some(i), Some(child_rule)) => {
+                    counts[i] += 1;
+                    children.push((child, child_rule, child_path));
+                }
+                (_, Some(_)) => {
+                    let kind = ViolationKind::UnexpectedElement {
+                        element: name.to_string(),
+                        parent: rule.name,
+                    };
+                    self.report(child, &child_path, kind);
+                }
+                (_, None) => {
+                    let kind = ViolationKind::UnknownElement(name.to_string());
+                    self.report(child, &child_path, kind);
+                }
+            }
+        }
+
+        for (&(element, occurs), &count) in rule.children.iter().zip(&counts) {
+            let parent = rule.name;
+            if count == 0 && matches!(occurs, Occurs::One | Occurs::OneOrMore) {
+                self.report(
+                    node,
+                    path,
+                    ViolationKind::MissingElement { element, parent },
+                );
+            }
+            if count > 1 && matches!(occurs, Occurs::One | Occurs::Optional) {
+                self.report(
+                    node,
+                    path,
+                    ViolationKind::RepeatedElement { element, parent },
+                );
+            }
+        }
+
+        for (child, rule, child_path) in children {
+            self.element(child, rule, &child_path);
+        }
+    }
+
+    fn attributes(&mut self, node: Node, rule: &'static Rule, path: &str) {
+        let element = rule.name;
+        for attribute in rule.attributes {
+            let value = match node.attribute(attribute.name) {
+                Some(value) => value,
+                None => {
+                    if attribute.required {
+                        let attribute = attribute.name;
+                        self.report(
+                            node,
+                            path,
+                            ViolationKind::MissingAttribute { element, attribute },
+                        );
+                    }
+                    continue;
+                }
+            };
+            let valid = match attribute.values {
+                Values::Any => true,
+                Values::Number => value.trim().parse::<f64>().is_ok(),
+                Values::OneOf(values) => values.contains(&value),
+            };
+            if !valid {
+                let kind = ViolationKind::InvalidValue {
+                    element,
+                    attribute: attribute.name,
+                    value: value.to_string(),
+                };
+                self.report(node, path, kind);
+            }
+        }
+
+        for attribute in node.attributes() {
+            if !rule.attributes.iter().any(|a| a.name == attribute.name()) {
+                let kind = ViolationKind::UnknownAttribute {
+                    element,
+                    attribute: attribute.name().to_string(),
+                };
+                self.report(node, path, kind);
+            }
+        }
+    }
+}
+
+//The path segment of `node`, with the key telling it apart from its
+//siblings for hosts, ports and scripts.
+fn segment(node: Node) -> String {
+    let name = node.tag_name().name();
+    let key = match name {
+        "host" => node
+            .children()
+            .find(|c| c.has_tag_name("address"))
+            .and_then(|a| a.attribute("addr"))
+            .map(String::from),
+        "port" => match (node.attribute("protocol"), node.attribute("portid")) {
+            (Some(protocol), Some(portid)) => Some(format!("{}/{}", protocol, portid)),
+            _ => None,
+        },
+        "script" => node.attribute("id").map(String::from),
+        _ => None,
+    };
+    match key {
+        Some(key) => format!("{}[{}]", name, key),
+        None => name.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Occurs {
+    One,
+    Optional,
+    Any,
+    OneOrMore,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Values {
+    Any,
+    Number,
+    OneOf(&'static [&'static str]),
+}
+
+#[derive(Debug)]
+struct Attribute {
+    name: &'static str,
+    required: bool,
+    values: Values,
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: &'static str,
+    children: &'static [(&'static str, Occurs)],
+    text: bool,
+    attributes: &'static [Attribute],
+}
+
+fn lookup(name: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.name == name)
+}
+
+const fn required(name: &'static str, values: Values) -> Attribute {
+    Attribute {
+        name,
+        required: true,
+        values,
+    }
+}
+
+const fn implied(name: &'static str, values: Values) -> Attribute {
+    Attribute {
+        name,
+        required: false,
+        values,
+    }
+}
+
+const fn element(
+    name: &'static str,
+    children: &'static [(&'static str, Occurs)],
+    attributes: &'static [Attribute],
+) -> Rule {
+    Rule {
+        name,
+        children,
+        text: false,
+        attributes,
+    }
+}
+
+const fn text(name: &'static str, attributes: &'static [Attribute]) -> Rule {
+    Rule {
+        name,
+        children: &[],
+        text: true,
+        attributes,
+    }
+}
+
+const PROTOCOLS: Values = OneOf(&["ip", "tcp", "udp", "sctp"]);
+const PORT_STATES: Values = OneOf(&[
+    "open",
+    "closed",
+    "filtered",
+    "unfiltered",
+    "open|filtered",
+    "closed|filtered",
+    "unknown",
+]);
+const SCRIPTS: &[(&str, Occurs)] = &[("script", OneOrMore)];
+
+//The element and attribute declarations of nmap.dtd, for
+//xmloutputversion 1.05. `nmaprun` comes first.
+static RULES: &[Rule] = &[
+    element(
+        "nmaprun",
+        &[
+            ("scaninfo", Many),
+            ("verbose", One),
+            ("debugging", One),
+            ("target", Many),
+            ("taskbegin", Many),
+            ("taskprogress", Many),
+            ("taskend", Many),
+            ("hosthint", Many),
+            ("prescript", Many),
+            ("postscript", Many),
+            ("host", Many),
+            ("output", Many),
+            ("runstats", One),
+        ],
+        &[
+            required("scanner", OneOf(&["nmap"])),
+            implied("args", Any),
+            implied("start", Number),
+            implied("startstr", Any),
+            required("version", Any),
+            implied("profile_name", Any),
+            required("xmloutputversion", Any),
+        ],
+    ),
+    element(
+        "scaninfo",
+        &[],
+        &[
+            required(
+                "type",
+                OneOf(&[
+                    "syn",
+                    "ack",
+                    "bounce",
+                    "connect",
+                    "null",
+                    "xmas",
+                    "window",
+                    "maimon",
+                    "fin",
+                    "udp",
+                    "sctpinit",
+                    "sctpcookieecho",
+                    "ipproto",
+                ]),
+            ),
+            implied("scanflags", Any),
+            required("protocol", PROTOCOLS),
+            required("numservices", Number),
+            required("services", Any),
+        ],
+    ),
+    element("verbose", &[], &[implied("level", Number)]),
+    element("debugging", &[], &[implied("level", Number)]),
+    element(
+        "target",
+        &[],
+        &[
+            required("specification", Any),
+            implied("status", OneOf(&["skipped"])),
+            implied("reason", OneOf(&["invalid"])),
+        ],
+    ),
+    element(
+        "taskbegin",
+        &[],
+        &[
+            required("task", Any),
+            required("time", Number),
+            implied("extrainfo", Any),
+        ],
+    ),
+    element(
+        "taskprogress",
+        &[],
+        &[
+            required("task", Any),
+            required("time", Number),
+            required("percent", Number),
+            required("remaining", Number),
+            required("etc", Number),
+        ],
+    ),
+    element(
+        "taskend",
+        &[],
+        &[
+            required("task", Any),
+            required("time", Number),
+            implied("extrainfo", Any),
+        ],
+    ),
+    element(
+        "hosthint",
+        &[
+            ("status", One),
+            ("address", OneOrMore),
+            ("hostnames", Optional),
+        ],
+        &[],
+    ),
+    element(
+        "host",
+        &[
+            ("status", One),
+            ("address", OneOrMore),
+            ("hostnames", Many),
+            ("smurf", Many),
+            ("ports", Many),
+            ("os", Many),
+            ("distance", Many),
+            ("uptime", Many),
+            ("tcpsequence", Many),
+            ("ipidsequence", Many),
+            ("tcptssequence", Many),
+            ("hostscript", Many),
+            ("trace", Many),
+            ("times", Optional),
+        ],
+        &[
+            implied("starttime", Number),
+            implied("endtime", Number),
+            implied("timedout", OneOf(&["true", "false"])),
+            implied("comment", Any),
+        ],
+    ),
+    element(
+        "status",
+        &[],
+        &[
+            required("state", OneOf(&["up", "down", "unknown", "skipped"])),
+            required("reason", Any),
+            required("reason_ttl", Any),
+        ],
+    ),
+    element(
+        "address",
+        &[],
+        &[
+            required("addr", Any),
+            implied("addrtype", OneOf(&["ipv4", "ipv6", "mac"])),
+            implied("vendor", Any),
+        ],
+    ),
+    element("hostnames", &[("hostname", Many)], &[]),
+    element(
+        "hostname",
+        &[],
+        &[
+            implied("name", Any),
+            implied("type", OneOf(&["user", "PTR"])),
+        ],
+    ),
+    element("smurf", &[], &[required("responses", Number)]),
+    element("ports", &[("extraports", Many), ("port", Many)], &[]),
+    element(
+        "extraports",
+        &[("extrareasons", Many)],
+        &[required("state", PORT_STATES), required("count", Number)],
+    ),
+    element(
+        "extrareasons",
+        &[],
+        &[
+            required("reason", Any),
+            required("count", Number),
+            implied("proto", PROTOCOLS),
+            implied("ports", Any),
+        ],
+    ),
+    element(
+        "port",
+        &[
+            ("state", One),
+            ("owner", Optional),
+            ("service", Optional),
+            ("script", Many),
+        ],
+        &[required("protocol", PROTOCOLS), required("portid", Number)],
+    ),
+    element(
+        "state",
+        &[],
+        &[
+            required("state", PORT_STATES),
+            required("reason", Any),
+            required("reason_ttl", Any),
+            implied("reason_ip", Any),
+        ],
+    ),
+    element("owner", &[], &[required("name", Any)]),
+    element(
+        "service",
+        &[("cpe", Many)],
+        &[
+            required("name", Any),
+            required(
+                "conf",
+                OneOf(&["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10"]),
+            ),
+            required("method", OneOf(&["table", "probed"])),
+            implied("version", Any),
+            implied("product", Any),
+            implied("extrainfo", Any),
+            implied("tunnel", OneOf(&["ssl"])),
+            implied("proto", OneOf(&["rpc"])),
+            implied("rpcnum", Any),
+            implied("lowver", Any),
+            implied("highver", Any),
+            implied("hostname", Any),
+            implied("ostype", Any),
+            implied("devicetype", Any),
+            implied("servicefp", Any),
+        ],
+    ),
+    text("cpe", &[]),
+    Rule {
+        name: "script",
+        children: &[("table", Many), ("elem", Many)],
+        text: true,
+        attributes: &[required("id", Any), required("output", Any)],
+    },
+    element(
+        "table",
+        &[("table", Many), ("elem", Many)],
+        &[implied("key", Any)],
+    ),
+    text("elem", &[implied("key", Any)]),
+    element(
+        "os",
+        &[
+            ("portused", Many),
+            ("osmatch", Many),
+            ("osfingerprint", Many),
+        ],
+        &[],
+    ),
+    element(
+        "portused",
+        &[],
+        &[
+            required("state", PORT_STATES),
+            required("proto", PROTOCOLS),
+            required("portid", Number),
+        ],
+    ),
+    element(
+        "osmatch",
+        &[("osclass", Many)],
+        &[
+            required("name", Any),
+            required("accuracy", Number),
+            required("line", Number),
+        ],
+    ),
+    element(
+        "osclass",
+        &[("cpe", Many)],
+        &[
+            required("vendor", Any),
+            implied("osgen", Any),
+            implied("type", Any),
+            required("accuracy", Number),
+            required("osfamily", Any),
+        ],
+    ),
+    element("osfingerprint", &[], &[required("fingerprint", Any)]),
+    element("distance", &[], &[required("value", Number)]),
+    element(
+        "uptime",
+        &[],
+        &[required("seconds", Number), implied("lastboot", Any)],
+    ),
+    element(
+        "tcpsequence",
+        &[],
+        &[
+            required("index", Number),
+            required("difficulty", Any),
+            required("values", Any),
+        ],
+    ),
+    element(
+        "ipidsequence",
+        &[],
+        &[required("class", Any), required("values", Any)],
+    ),
+    element(
+        "tcptssequence",
+        &[],
+        &[required("class", Any), implied("values", Any)],
+    ),
+    element(
+        "trace",
+        &[("hop", Many)],
+        &[implied("proto", Any), implied("port", Any)],
+    ),
+    element(
+        "hop",
+        &[],
+        &[
+            required("ttl", Number),
+            implied("rtt", Any),
+            implied("ipaddr", Any),
+            implied("host", Any),
+        ],
+    ),
+    element(
+        "times",
+        &[],
+        &[
+            required("srtt", Any),
+            required("rttvar", Any),
+            required("to", Any),
+        ],
+    ),
+    element("hostscript", SCRIPTS, &[]),
+    element("prescript", SCRIPTS, &[]),
+    element("postscript", SCRIPTS, &[]),
+    element("runstats", &[("finished", One), ("hosts", One)], &[]),
+    element(
+        "finished",
+        &[],
+        &[
+            required("time", Number),
+            implied("timestr", Any),
+            required("elapsed", Number),
+            implied("summary", Any),
+            implied("exit", OneOf(&["error", "success"])),
+            implied("errormsg", Any),
+        ],
+    ),
+    element(
+        "hosts",
+        &[],
+        &[
+            implied("up", Number),
+            implied("down", Number),
+            required("total", Number),
+        ],
+    ),
+    text("output", &[implied("type", OneOf(&["interactive"]))]),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn violations() {
+        let violations = validate(
+            r#"<nmaprun scanner="nmap" start="1588318812" version="7.80" xmloutputversion="1.04">
+<verbose level="0"/>
+<debugging level="0"/>
+<host starttime="soon"><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22" banner="SSH-2.0"><state state="open" reason="syn-ack" reason_ttl="53"/><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" method="guessed" conf="3"/></port>
+<port protocol="tcp" portid="80"><service name="http" method="table" conf="3"/><hostname name="www"/><banner/></port>
+</ports>
+</host>
+<runstats><finished time="1588318884" elapsed="72.00" exit="success"/><hosts total="1"/></runstats>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        let port = "host[10.0.0.1] > ports > port";
+        assert_eq!(
+            violations
+                .iter()
+                .map(|v| (v.line, v.path.as_str(), v.kind.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    4,
+                    "host[10.0.0.1]",
+                    "invalid value `soon` for `starttime` attribute in `host` node".to_string()
+                ),
+                (
+                    7,
+                    &format!("{}[tcp/22]", port)[..],
+                    "unknown `banner` attribute in `port` node".to_string()
+                ),
+                (
+                    7,
+                    &format!("{}[tcp/22]", port),
+                    "more than one `state` node in `port`".to_string()
+                ),
+                (
+                    7,
+                    &format!("{}[tcp/22] > service", port),
+                    "invalid value `guessed` for `method` attribute in `service` node".to_string()
+                ),
+                (
+                    8,
+                    &format!("{}[tcp/80] > hostname", port),
+                    "unexpected `hostname` node in `port`".to_string()
+                ),
+                (
+                    8,
+                    &format!("{}[tcp/80] > banner", port),
+                    "unknown `banner` node".to_string()
+                ),
+                (
+                    8,
+                    &format!("{}[tcp/80]", port),
+                    "expected `state` node in `port`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_root() {
+        let violations = validate("<?xml version=\"1.0\"?>\n<scan/>").unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].to_string(),
+            "2:1: scan: root node is `scan` instead of `nmaprun`"
+        );
+        assert!(matches!(
+            validate("<nmaprun><host></nmaprun>"),
+            Err(Error::XmlSyntax(_))
+        ));
+    }
+}
@@ -0,0 +1,105 @@
+//!Versions of the Nmap XML output format.
+//!
+//!Nmap declares the revision of its output format in the
+//!`xmloutputversion` attribute of `nmaprun`, kept by the parser as
+//![`NmapResults::output_version`](../struct.NmapResults.html#structfield.output_version).
+//!Documents up to
+//![`SUPPORTED_OUTPUT_VERSION`](constant.SUPPORTED_OUTPUT_VERSION.html) are
+//!understood, and the parser adapts to the older ones as follows:
+//!
+//!* Before 1.04, `osclass` nodes are siblings of `osmatch` nodes instead
+//!  of their children. Nmap did not tie classes to matches then, so they
+//!  are given to the first match, the most accurate one.
+//!* Before 1.04, `status` and `state` nodes may lack the `reason` and
+//!  `reason_ttl` attributes, which then read as empty and 0.
+//!* The `close|filtered` spelling of the `closed|filtered` port state is
+//!  read as [`CloseFiltered`](../port/enum.PortState.html#variant.CloseFiltered)
+//!  in any version.
+//!
+//!Version 1.05 adds `hosthint` nodes, written before a host is scanned,
+//!which are skipped like the other progress nodes.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+
+///The latest output format version this crate is written against.
+pub const SUPPORTED_OUTPUT_VERSION: OutputVersion = OutputVersion::new(1, 5);
+
+///An `xmloutputversion`, e.g. `1.05`. Versions order by their major, then
+///minor number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OutputVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl OutputVersion {
+    pub const fn new(major: u16, minor: u16) -> Self {
+        OutputVersion { major, minor }
+    }
+
+    ///Returns `true` if the version is not newer than
+    ///[`SUPPORTED_OUTPUT_VERSION`](constant.SUPPORTED_OUTPUT_VERSION.html).
+    pub fn is_supported(&self) -> bool {
+        *self <= SUPPORTED_OUTPUT_VERSION
+    }
+}
+
+///Formats the version as Nmap writes it, with two digit minor numbers.
+impl fmt::Display for OutputVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)
+    }
+}
+
+impl FromStr for OutputVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || Error::UnknownValue {
+            kind: "output version",
+            value: s.to_string(),
+        };
+        let (major, minor) = s.trim().split_once('.').ok_or_else(unknown)?;
+        Ok(OutputVersion {
+            major: major.parse().map_err(|_| unknown())?,
+            minor: minor.parse().map_err(|_| unknown())?,
+        })
+    }
+}
+
+//The version declared by the document `node` belongs to, for the parsers
+//that accept older forms of a node.
+pub(crate) fn of_document(node: roxmltree::Node) -> Option<OutputVersion> {
+    node.document()
+        .root_element()
+        .attribute("xmloutputversion")?
+        .parse()
+        .ok()
+}
+
+//Whether the document `node` belongs to declares a version before `version`.
+pub(crate) fn is_before(node: roxmltree::Node, version: OutputVersion) -> bool {
+    of_document(node).is_some_and(|v| v < version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn output_versions() {
+        let version = "1.05".parse::<OutputVersion>().unwrap();
+        assert_eq!(version, SUPPORTED_OUTPUT_VERSION);
+        assert_eq!(version.to_string(), "1.05");
+        assert!("1.04".parse::<OutputVersion>().unwrap() < version);
+        assert!(OutputVersion::new(1, 10) > version);
+        assert!(!OutputVersion::new(2, 0).is_supported());
+        assert!(OutputVersion::new(1, 3).is_supported());
+        assert_eq!(
+            "1.x".parse::<OutputVersion>().unwrap_err().to_string(),
+            "unknown output version `1.x`"
+        );
+    }
+}
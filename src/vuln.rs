@@ -0,0 +1,469 @@
+//!Candidate vulnerabilities of the detected services and operating
+//!systems, from an offline NVD feed.
+//!
+//!This module requires the `nvd` feature. A
+//![`CveDatabase`](struct.CveDatabase.html) is loaded from an NVD JSON feed,
+//!either the 1.1 data feeds (`nvdcve-1.1-2021.json`) or the pages of the
+//!2.0 API, and [`candidates()`](fn.candidates.html) matches the CPEs
+//!reported by version and OS detection against it.
+//!
+//!Matching only relies on the vendor, product and version of the CPEs, so
+//!the results are candidates: configurations requiring a combination of
+//!platforms are not evaluated, and backported fixes are not known.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::cpe::Cpe;
+use crate::host::Host;
+use crate::json::{self, Value};
+use crate::port::Port;
+use crate::{Error, NmapResults};
+
+const FORMAT: &str = "NVD";
+
+///A vulnerability of an NVD feed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cve {
+    ///Identifier, e.g. `CVE-2020-15778`.
+    pub id: String,
+    ///CVSS base score, version 3 when the feed has it and version 2
+    ///otherwise.
+    pub cvss: Option<f64>,
+    ///English description.
+    pub description: Option<String>,
+    pub(crate) matches: Vec<CpeMatch>,
+}
+
+//A vulnerable CPE, possibly with a range of versions.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CpeMatch {
+    cpe: Cpe,
+    start_including: Option<String>,
+    start_excluding: Option<String>,
+    end_including: Option<String>,
+    end_excluding: Option<String>,
+}
+
+impl CpeMatch {
+    fn matches(&self, cpe: &Cpe) -> bool {
+        if self.cpe.part != cpe.part
+            || !self.cpe.vendor.eq_ignore_ascii_case(&cpe.vendor)
+            || !self.cpe.product.eq_ignore_ascii_case(&cpe.product)
+        {
+            return false;
+        }
+
+        let version = match &cpe.version {
+            Some(version) => version,
+            None => return false,
+        };
+        if let Some(vulnerable) = &self.cpe.version {
+            return compare_versions(vulnerable, version) == Ordering::Equal;
+        }
+
+        let within = |bound: &Option<String>, ordering: &[Ordering]| {
+            bound
+                .as_ref()
+                .is_none_or(|b| ordering.contains(&compare_versions(version, b)))
+        };
+        within(&self.start_including, &[Ordering::Greater, Ordering::Equal])
+            && within(&self.start_excluding, &[Ordering::Greater])
+            && within(&self.end_including, &[Ordering::Less, Ordering::Equal])
+            && within(&self.end_excluding, &[Ordering::Less])
+    }
+}
+
+///The vulnerabilities of an NVD feed, indexed by vendor and product.
+#[derive(Clone, Debug, Default)]
+pub struct CveDatabase {
+    cves: Vec<Cve>,
+    index: HashMap<(String, String), Vec<usize>>,
+}
+
+impl CveDatabase {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    ///Parses an NVD JSON feed.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut database = CveDatabase::new();
+        database.extend_from_feed(input)?;
+        Ok(database)
+    }
+
+    ///Adds the vulnerabilities of another NVD JSON feed, such as the feed
+    ///of another year.
+    pub fn extend_from_feed(&mut self, input: &str) -> Result<(), Error> {
+        let document = json::parse(input).map_err(error)?;
+
+        if let Some(items) = document.get("CVE_Items").and_then(Value::as_array) {
+            for item in items {
+                self.insert(parse_legacy_item(item)?);
+            }
+        } else if let Some(items) = document.get("vulnerabilities").and_then(Value::as_array) {
+            for item in items {
+                let cve = item
+                    .get("cve")
+                    .ok_or_else(|| error("expected `cve` in vulnerability"))?;
+                self.insert(parse_api_item(cve)?);
+            }
+        } else {
+            return Err(error("expected `CVE_Items` or `vulnerabilities`"));
+        }
+
+        Ok(())
+    }
+
+    fn insert(&mut self, cve: Cve) {
+        let position = self.cves.len();
+        let mut keys = cve
+            .matches
+            .iter()
+            .map(|m| (m.cpe.vendor.to_lowercase(), m.cpe.product.to_lowercase()))
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            self.index.entry(key).or_default().push(position);
+        }
+        self.cves.push(cve);
+    }
+
+    ///Returns the vulnerabilities affecting `cpe`, in feed order. CPEs
+    ///without a version only match vulnerabilities of all versions.
+    pub fn lookup(&self, cpe: &Cpe) -> Vec<&Cve> {
+        let key = (cpe.vendor.to_lowercase(), cpe.product.to_lowercase());
+        self.index
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.cves[i])
+            .filter(|cve| cve.matches.iter().any(|m| m.matches(cpe)))
+            .collect()
+    }
+
+    ///Returns the number of vulnerabilities in the database.
+    pub fn len(&self) -> usize {
+        self.cves.len()
+    }
+
+    ///Returns `true` if the database has no vulnerability.
+    pub fn is_empty(&self) -> bool {
+        self.cves.is_empty()
+    }
+}
+
+///A candidate vulnerability of a host.
+#[derive(Clone, Debug)]
+pub struct Finding<'a> {
+    pub host: &'a Host,
+    ///Open port of the vulnerable service, or `None` for the operating
+    ///system.
+    pub port: Option<&'a Port>,
+    pub cpe: &'a Cpe,
+    pub cve: &'a Cve,
+}
+
+///Matches the CPEs of the services on open ports and of the most likely
+///operating system of every host that is up against `database`. Findings
+///are listed in scan order.
+pub fn candidates<'a>(results: &'a NmapResults, database: &'a CveDatabase) -> Vec<Finding<'a>> {
+    let mut findings = Vec::new();
+
+    for host in results.hosts_up() {
+        for port in host.port_info.open_ports() {
+            let cpes = port.service_info.iter().flat_map(|s| s.cpes.iter());
+            for cpe in cpes {
+                for cve in database.lookup(cpe) {
+                    findings.push(Finding {
+                        host,
+                        port: Some(port),
+                        cpe,
+                        cve,
+                    });
+                }
+            }
+        }
+
+        let os_cpes = host
+            .os_matches()
+            .next()
+            .into_iter()
+            .flat_map(|m| m.classes())
+            .flat_map(|c| c.cpes.iter());
+        for cpe in os_cpes {
+            for cve in database.lookup(cpe) {
+                findings.push(Finding {
+                    host,
+                    port: None,
+                    cpe,
+                    cve,
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+fn error(message: impl Into<String>) -> Error {
+    Error::InvalidImport {
+        format: FORMAT,
+        message: message.into(),
+    }
+}
+
+//An item of the 1.1 data feeds.
+fn parse_legacy_item(item: &Value) -> Result<Cve, Error> {
+    let cve = item
+        .get("cve")
+        .ok_or_else(|| error("expected `cve` in CVE item"))?;
+    let id = cve
+        .get("CVE_data_meta")
+        .and_then(|m| m.get("ID"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| error("expected `CVE_data_meta.ID` in CVE item"))?;
+    let description = cve
+        .get("description")
+        .and_then(|d| d.get("description_data"))
+        .and_then(english_value);
+    let impact = item.get("impact");
+    let cvss = impact
+        .and_then(|i| i.get("baseMetricV3"))
+        .and_then(|m| m.get("cvssV3"))
+        .or_else(|| {
+            impact
+                .and_then(|i| i.get("baseMetricV2"))
+                .and_then(|m| m.get("cvssV2"))
+        })
+        .and_then(|c| c.get("baseScore"))
+        .and_then(Value::as_f64);
+
+    let mut matches = Vec::new();
+    let nodes = item
+        .get("configurations")
+        .and_then(|c| c.get("nodes"))
+        .and_then(Value::as_array)
+        .unwrap_or_default();
+    collect_matches(nodes, "cpe_match", "cpe23Uri", &mut matches)?;
+
+    Ok(Cve {
+        id: id.to_string(),
+        cvss,
+        description,
+        matches,
+    })
+}
+
+//A vulnerability of the 2.0 API.
+fn parse_api_item(cve: &Value) -> Result<Cve, Error> {
+    let id = cve
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| error("expected `id` in vulnerability"))?;
+    let description = cve.get("descriptions").and_then(english_value);
+    let metrics = cve.get("metrics");
+    let cvss = ["cvssMetricV31", "cvssMetricV30", "cvssMetricV2"]
+        .iter()
+        .find_map(|key| {
+            metrics
+                .and_then(|m| m.get(key))
+                .and_then(Value::as_array)
+                .and_then(|m| m.first())
+                .and_then(|m| m.get("cvssData"))
+                .and_then(|d| d.get("baseScore"))
+                .and_then(Value::as_f64)
+        });
+
+    let mut matches = Vec::new();
+    let configurations = cve
+        .get("configurations")
+        .and_then(Value::as_array)
+        .unwrap_or_default();
+    for configuration in configurations {
+        let nodes = configuration
+            .get("nodes")
+            .and_then(Value::as_array)
+            .unwrap_or_default();
+        collect_matches(nodes, "cpeMatch", "criteria", &mut matches)?;
+    }
+
+    Ok(Cve {
+        id: id.to_string(),
+        cvss,
+        description,
+        matches,
+    })
+}
+
+fn english_value(descriptions: &Value) -> Option<String> {
+    descriptions
+        .as_array()?
+        .iter()
+        .find(|d| d.get("lang").and_then(Value::as_str) == Some("en"))
+        .and_then(|d| d.get("value"))
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+//Collects the vulnerable CPEs of configuration nodes and their children.
+fn collect_matches(
+    nodes: &[Value],
+    matches_key: &str,
+    cpe_key: &str,
+    matches: &mut Vec<CpeMatch>,
+) -> Result<(), Error> {
+    for node in nodes {
+        let cpe_matches = node
+            .get(matches_key)
+            .and_then(Value::as_array)
+            .unwrap_or_default();
+        for cpe_match in cpe_matches {
+            if cpe_match.get("vulnerable") != Some(&Value::Bool(true)) {
+                continue;
+            }
+            let cpe = cpe_match
+                .get(cpe_key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| error(format!("expected `{}` in CPE match", cpe_key)))?;
+            let bound = |key: &str| cpe_match.get(key).and_then(Value::as_str).map(String::from);
+            matches.push(CpeMatch {
+                cpe: cpe.parse()?,
+                start_including: bound("versionStartIncluding"),
+                start_excluding: bound("versionStartExcluding"),
+                end_including: bound("versionEndIncluding"),
+                end_excluding: bound("versionEndExcluding"),
+            });
+        }
+
+        if let Some(children) = node.get("children").and_then(Value::as_array) {
+            collect_matches(children, matches_key, cpe_key, matches)?;
+        }
+    }
+    Ok(())
+}
+
+//Compares versions such as `7.4`, `8.2p1` or `2.4.41` component by
+//component, numbers numerically and other runs of characters
+//alphabetically. A version is lower than its extensions: `8.2 < 8.2p1`.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn runs(version: &str) -> Vec<&str> {
+        let mut runs = Vec::new();
+        for component in version.split(['.', '-', '_', ':']) {
+            let mut start = 0;
+            let bytes = component.as_bytes();
+            for i in 1..=bytes.len() {
+                if i == bytes.len() || bytes[i].is_ascii_digit() != bytes[i - 1].is_ascii_digit() {
+                    runs.push(&component[start..i]);
+                    start = i;
+                }
+            }
+        }
+        runs
+    }
+
+    let (a, b) = (runs(a), runs(b));
+    for (x, y) in a.iter().zip(b.iter()) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.to_lowercase().cmp(&y.to_lowercase()),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LEGACY_FEED: &str = r#"{"CVE_data_type":"CVE","CVE_Items":[
+{"cve":{"CVE_data_meta":{"ID":"CVE-2020-15778"},
+  "description":{"description_data":[{"lang":"en","value":"scp in OpenSSH through 8.3p1 allows command injection."}]}},
+ "configurations":{"nodes":[{"operator":"OR","cpe_match":[
+  {"vulnerable":true,"cpe23Uri":"cpe:2.3:a:openbsd:openssh:*:*:*:*:*:*:*:*","versionEndIncluding":"8.3p1"}]}]},
+ "impact":{"baseMetricV3":{"cvssV3":{"baseScore":7.8}},"baseMetricV2":{"cvssV2":{"baseScore":6.8}}}},
+{"cve":{"CVE_data_meta":{"ID":"CVE-2016-0777"}},
+ "configurations":{"nodes":[{"operator":"AND","children":[{"operator":"OR","cpe_match":[
+  {"vulnerable":true,"cpe23Uri":"cpe:2.3:a:openbsd:openssh:*:*:*:*:*:*:*:*","versionStartIncluding":"5.4","versionEndExcluding":"7.1"},
+  {"vulnerable":false,"cpe23Uri":"cpe:2.3:o:linux:linux_kernel:-:*:*:*:*:*:*:*"}]}]}]},
+ "impact":{"baseMetricV2":{"cvssV2":{"baseScore":4.0}}}}
+]}"#;
+
+    const API_FEED: &str = r#"{"resultsPerPage":1,"vulnerabilities":[{"cve":{
+"id":"CVE-2021-41617",
+"descriptions":[{"lang":"es","value":"..."},{"lang":"en","value":"sshd in OpenSSH 6.2 through 8.x before 8.8 ..."}],
+"metrics":{"cvssMetricV31":[{"cvssData":{"baseScore":7.0}}]},
+"configurations":[{"nodes":[{"operator":"OR","negate":false,"cpeMatch":[
+ {"vulnerable":true,"criteria":"cpe:2.3:a:openbsd:openssh:*:*:*:*:*:*:*:*","versionStartIncluding":"6.2","versionEndExcluding":"8.8"}]}]}]
+}}]}"#;
+
+    #[test]
+    fn version_ordering() {
+        assert_eq!(compare_versions("8.2p1", "8.3p1"), Ordering::Less);
+        assert_eq!(compare_versions("8.2", "8.2p1"), Ordering::Less);
+        assert_eq!(compare_versions("2.4.41", "2.4.7"), Ordering::Greater);
+        assert_eq!(compare_versions("7.4", "7.4"), Ordering::Equal);
+    }
+
+    #[test]
+    fn feed_lookup() {
+        let mut database = CveDatabase::parse(LEGACY_FEED).unwrap();
+        database.extend_from_feed(API_FEED).unwrap();
+        assert_eq!(database.len(), 3);
+
+        let ids = |cpe: &str| {
+            database
+                .lookup(&cpe.parse().unwrap())
+                .iter()
+                .map(|c| c.id.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids("cpe:/a:openbsd:openssh:7.4"),
+            ["CVE-2020-15778", "CVE-2021-41617"]
+        );
+        assert_eq!(
+            ids("cpe:/a:openbsd:openssh:6.6.1p1"),
+            ["CVE-2020-15778", "CVE-2016-0777", "CVE-2021-41617"]
+        );
+        assert!(ids("cpe:/a:openbsd:openssh:9.0").is_empty());
+        assert!(ids("cpe:/a:openbsd:openssh").is_empty());
+        assert!(ids("cpe:/o:linux:linux_kernel:4.4").is_empty());
+
+        let cve = database.lookup(&"cpe:/a:openbsd:openssh:8.0".parse().unwrap())[0];
+        assert_eq!(cve.cvss, Some(7.8));
+        assert!(cve
+            .description
+            .as_ref()
+            .unwrap()
+            .starts_with("scp in OpenSSH"));
+
+        assert!(CveDatabase::parse("{}").is_err());
+    }
+
+    #[test]
+    fn scan_candidates() {
+        let results = NmapResults::parse(
+            r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="45.33.32.156" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="8.9p1" method="probed" conf="10"><cpe>cpe:/a:openbsd:openssh:8.9p1</cpe></service></port>
+<port protocol="tcp" portid="2222"><state state="open" reason="syn-ack" reason_ttl="53"/><service name="ssh" product="OpenSSH" version="7.4" method="probed" conf="10"><cpe>cpe:/a:openbsd:openssh:7.4</cpe></service></port>
+</ports>
+</host>
+</nmaprun>"#,
+        )
+        .unwrap();
+        let database = CveDatabase::parse(API_FEED).unwrap();
+
+        let findings = candidates(&results, &database);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].port.unwrap().port_number, 2222);
+        assert_eq!(findings[0].cve.id, "CVE-2021-41617");
+    }
+}
@@ -8,35 +8,35 @@ use std::path::PathBuf;
 lazy_static! {
     static ref NMAP_TEST_XML: NmapResults = {
         let mut path = PathBuf::new();
-        path.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        path.push(std::env::var("CARGO_MANIFEST_DIR").unwrap());
         path.push("tests/test.xml");
         let content = fs::read_to_string(path).unwrap();
         NmapResults::parse(&content).unwrap()
     };
     static ref NMAP_ISSUE_ONE: NmapResults = {
         let mut path = PathBuf::new();
-        path.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        path.push(std::env::var("CARGO_MANIFEST_DIR").unwrap());
         path.push("tests/issue_1.xml");
         let content = fs::read_to_string(path).unwrap();
         NmapResults::parse(&content).unwrap()
     };
     static ref NMAP_HOST_DOWN: NmapResults = {
         let mut path = PathBuf::new();
-        path.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        path.push(std::env::var("CARGO_MANIFEST_DIR").unwrap());
         path.push("tests/host-down.xml");
         let content = fs::read_to_string(path).unwrap();
         NmapResults::parse(&content).unwrap()
     };
     static ref NMAP_INCOMPLETE_SCAN: NmapResults = {
         let mut path = PathBuf::new();
-        path.push(&std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        path.push(std::env::var("CARGO_MANIFEST_DIR").unwrap());
         path.push("tests/incomplete_scan.xml");
         let content = fs::read_to_string(path).unwrap();
         NmapResults::parse(&content).unwrap()
     };
 }
 
-fn vectors_eq<T: PartialEq>(a: &Vec<T>, b: &Vec<T>) -> bool {
+fn vectors_eq<T: PartialEq>(a: &[T], b: &[T]) -> bool {
     let matching = a.iter().zip(b.iter()).filter(|&(a, b)| a == b).count();
     matching == a.len() || matching == b.len()
 }
@@ -56,6 +56,244 @@ fn no_end_time() {
     assert_eq!(NMAP_INCOMPLETE_SCAN.scan_end_time, None);
 }
 
+#[test]
+fn scan_duration() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    assert_eq!(
+        NMAP_TEST_XML.scan_start(),
+        Some(UNIX_EPOCH + Duration::from_secs(1588318812))
+    );
+    assert_eq!(NMAP_TEST_XML.scan_duration(), Some(Duration::from_secs(2)));
+    assert_eq!(NMAP_INCOMPLETE_SCAN.scan_end(), None);
+    assert_eq!(NMAP_INCOMPLETE_SCAN.scan_duration(), None);
+
+    let host = NMAP_TEST_XML.hosts().next().unwrap();
+    assert_eq!(host.scan_duration(), Some(Duration::from_secs(2)));
+}
+
+#[test]
+fn hosts_up_and_down() {
+    assert_eq!(NMAP_TEST_XML.hosts_up().count(), 1);
+    assert_eq!(NMAP_TEST_XML.hosts_down().count(), 0);
+    assert_eq!(NMAP_HOST_DOWN.hosts_up().count(), 0);
+    assert_eq!(NMAP_HOST_DOWN.hosts_down().count(), 4);
+}
+
+#[test]
+fn open_ports() {
+    let open = NMAP_TEST_XML
+        .open_ports()
+        .map(|(_, p)| p.port_number)
+        .collect::<Vec<_>>();
+    assert_eq!(open, vec![22, 80, 9929, 31337]);
+    assert_eq!(NMAP_HOST_DOWN.open_ports().count(), 0);
+}
+
+#[test]
+fn host_by_addr() {
+    let ip: std::net::IpAddr = "45.33.32.156".parse().unwrap();
+    let host = NMAP_TEST_XML.host_by_addr(&ip).unwrap();
+    assert_eq!(host.addresses().next(), Some(&host::Address::IpAddr(ip)));
+
+    let missing: std::net::IpAddr = "10.0.0.1".parse().unwrap();
+    assert!(NMAP_TEST_XML.host_by_addr(&missing).is_none());
+    assert!(NMAP_TEST_XML.host_by_mac("00:11:22:33:44:55").is_none());
+
+    let results = NmapResults::parse(
+        r#"<nmaprun start="1588318812">
+<host><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="192.168.1.1" addrtype="ipv4"/>
+<address addr="00:11:22:AA:BB:CC" addrtype="mac"/>
+</host>
+</nmaprun>"#,
+    )
+    .unwrap();
+    let host = results.host_by_mac("00:11:22:aa:bb:cc").unwrap();
+    assert_eq!(host.addresses().count(), 2);
+}
+
+#[test]
+fn edit_results() {
+    let mut results = NmapResults::parse(
+        r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.1" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.2" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.3" addrtype="ipv4"/>
+<ports><port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/></port></ports>
+</host>
+</nmaprun>"#,
+    )
+    .unwrap();
+    let ip = |s: &str| s.parse::<std::net::IpAddr>().unwrap();
+    let first_ip = |h: &host::Host| h.addresses().next().cloned();
+
+    //Lookups after a removal see the shifted positions.
+    assert!(results.host_by_addr(&ip("10.0.0.3")).is_some());
+    assert!(results.remove_host(&ip("10.0.0.1")).is_some());
+    assert!(results.remove_host(&ip("10.0.0.1")).is_none());
+    let host = results.host_by_addr(&ip("10.0.0.3")).unwrap();
+    assert_eq!(first_ip(host), Some(host::Address::IpAddr(ip("10.0.0.3"))));
+
+    results.retain_hosts(|h| first_ip(h) != Some(host::Address::IpAddr(ip("10.0.0.2"))));
+    assert_eq!(results.hosts().count(), 1);
+
+    let host = results.host_by_addr_mut(&ip("10.0.0.3")).unwrap();
+    host.add_hostname("db.example.com", host::HostnameType::User);
+    assert!(host
+        .port_info
+        .set_state(port::PortProtocol::Tcp, 22, port::PortState::Filtered));
+    assert!(host
+        .port_info
+        .remove_port(port::PortProtocol::Udp, 22)
+        .is_none());
+    assert_eq!(results.hosts_by_name("db.example.com").count(), 1);
+    assert_eq!(
+        results
+            .hosts()
+            .next()
+            .unwrap()
+            .port_info
+            .filtered_ports()
+            .count(),
+        1
+    );
+
+    let host = results.hosts_mut().next().unwrap();
+    host.status.state = host::HostState::Down;
+    assert!(host.remove_hostname("db.example.com"));
+    assert_eq!(results.hosts_by_name("db.example.com").count(), 0);
+    assert_eq!(results.hosts_down().count(), 1);
+}
+
+#[test]
+fn hosts_by_name() {
+    assert_eq!(NMAP_TEST_XML.hosts_by_name("scanme.nmap.org").count(), 1);
+    assert_eq!(NMAP_TEST_XML.hosts_by_name("ScanMe.Nmap.org.").count(), 1);
+    assert_eq!(NMAP_TEST_XML.hosts_by_name("nmap.org").count(), 0);
+}
+
+#[test]
+fn hosts_in_network() {
+    let network = "45.33.32.0/24".parse().unwrap();
+    assert_eq!(NMAP_TEST_XML.hosts_in(network).count(), 1);
+    assert_eq!(
+        NMAP_TEST_XML.hosts_in_cidr("10.0.0.0/8").unwrap().count(),
+        0
+    );
+    assert!(NMAP_TEST_XML.hosts_in_cidr("10.0.0.0/33").is_err());
+}
+
+#[test]
+fn port_lookup() {
+    let port_info = &NMAP_TEST_XML.hosts().next().unwrap().port_info;
+
+    let port = port_info.get_port(port::PortProtocol::Tcp, 80).unwrap();
+    assert_eq!(port.service_info.as_ref().unwrap().name, "http");
+    assert!(port_info.get_port(port::PortProtocol::Udp, 80).is_none());
+
+    assert!(port_info.has_open(22));
+    assert!(!port_info.has_open(443));
+    assert!(port_info.is_open(port::PortProtocol::Tcp, 31337));
+    assert!(!port_info.is_open(port::PortProtocol::Udp, 31337));
+}
+
+#[test]
+fn port_state_iterators() {
+    let results = NmapResults::parse(
+        r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="53"/></port>
+<port protocol="tcp" portid="23"><state state="closed" reason="reset" reason_ttl="53"/></port>
+<port protocol="tcp" portid="25"><state state="filtered" reason="no-response" reason_ttl="0"/></port>
+<port protocol="udp" portid="53"><state state="open|filtered" reason="no-response" reason_ttl="0"/></port>
+</ports>
+</host>
+</nmaprun>"#,
+    )
+    .unwrap();
+    let port_info = &results.hosts().next().unwrap().port_info;
+    let numbers = |ports: Vec<&port::Port>| ports.iter().map(|p| p.port_number).collect::<Vec<_>>();
+
+    assert_eq!(numbers(port_info.open_ports().collect()), vec![22]);
+    assert_eq!(numbers(port_info.closed_ports().collect()), vec![23]);
+    assert_eq!(numbers(port_info.filtered_ports().collect()), vec![25]);
+    assert_eq!(
+        numbers(
+            port_info
+                .ports_in_state(port::PortState::OpenFiltered)
+                .collect()
+        ),
+        vec![53]
+    );
+}
+
+#[test]
+fn sort_hosts() {
+    let mut results = NmapResults::parse(
+        r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="2001:db8::1" addrtype="ipv6"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.10" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.9" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="9.9.9.9" addrtype="ipv4"/></host>
+</nmaprun>"#,
+    )
+    .unwrap();
+    let addresses = |hosts: Vec<&host::Host>| {
+        hosts
+            .iter()
+            .map(|h| match h.addresses().next().unwrap() {
+                host::Address::IpAddr(ip) => ip.to_string(),
+                host::Address::MacAddr(mac) => mac.to_string(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = vec!["9.9.9.9", "10.0.0.9", "10.0.0.10", "2001:db8::1"];
+
+    assert_eq!(addresses(results.sorted_hosts()), expected);
+    assert_eq!(addresses(results.hosts().collect())[0], "2001:db8::1");
+
+    let ip: std::net::IpAddr = "10.0.0.9".parse().unwrap();
+    assert!(results.host_by_addr(&ip).is_some());
+    results.sort_hosts();
+    assert_eq!(addresses(results.hosts().collect()), expected);
+    assert_eq!(
+        results.host_by_addr(&ip).unwrap().addresses().next(),
+        Some(&host::Address::IpAddr(ip))
+    );
+}
+
+#[test]
+fn group_by_subnet() {
+    let results = NmapResults::parse(
+        r#"<nmaprun start="1588318812">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.1.7" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.9" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="10.0.0.10" addrtype="ipv4"/></host>
+<host><status state="up" reason="echo-reply" reason_ttl="53"/><address addr="2001:db8::1" addrtype="ipv6"/></host>
+</nmaprun>"#,
+    )
+    .unwrap();
+
+    let groups = results.group_by_subnet(24);
+    let sizes = groups
+        .iter()
+        .map(|(network, hosts)| (network.to_string(), hosts.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sizes,
+        vec![
+            ("10.0.0.0/24".to_string(), 2),
+            ("10.0.1.0/24".to_string(), 1),
+            ("2001:d00::/24".to_string(), 1)
+        ]
+    );
+    assert_eq!(results.group_by_subnet(16).len(), 2);
+    assert_eq!(results.group_by_subnet(64).len(), 4);
+}
+
 #[test]
 fn host_start_time() {
     let host = NMAP_TEST_XML.hosts().next().unwrap();
@@ -78,7 +316,7 @@ fn host_ip_address() {
     let ip_addr = host.addresses().next().unwrap();
     match ip_addr {
         host::Address::IpAddr(s) => assert_eq!(s, &ip),
-        host::Address::MacAddr(_) => assert!(false),
+        host::Address::MacAddr(_) => unreachable!(),
     }
 }
 
@@ -108,8 +346,11 @@ fn host_hostnames() {
     expected.push(&h1);
     expected.push(&h2);
 
-    assert!(!(host.host_names().count() == 0));
-    assert!(vectors_eq(&host.host_names().collect(), &expected));
+    assert!(host.host_names().count() != 0);
+    assert!(vectors_eq(
+        &host.host_names().collect::<Vec<_>>(),
+        &expected
+    ));
 }
 
 #[test]
@@ -130,7 +371,13 @@ fn host_portinfo_ports() {
             name: "ssh".to_string(),
             method: port::ServiceMethod::Table,
             confidence_level: 3,
+            product: None,
+            version: None,
+            extra_info: None,
+            tunnel: None,
+            cpes: Vec::new(),
         }),
+        scripts: Vec::new(),
     };
 
     let p2 = port::Port {
@@ -145,7 +392,13 @@ fn host_portinfo_ports() {
             name: "http".to_string(),
             method: port::ServiceMethod::Table,
             confidence_level: 3,
+            product: None,
+            version: None,
+            extra_info: None,
+            tunnel: None,
+            cpes: Vec::new(),
         }),
+        scripts: Vec::new(),
     };
 
     let p3 = port::Port {
@@ -160,7 +413,13 @@ fn host_portinfo_ports() {
             name: "nping-echo".to_string(),
             method: port::ServiceMethod::Table,
             confidence_level: 3,
+            product: None,
+            version: None,
+            extra_info: None,
+            tunnel: None,
+            cpes: Vec::new(),
         }),
+        scripts: Vec::new(),
     };
 
     let p4 = port::Port {
@@ -175,7 +434,13 @@ fn host_portinfo_ports() {
             name: "Elite".to_string(),
             method: port::ServiceMethod::Table,
             confidence_level: 3,
+            product: None,
+            version: None,
+            extra_info: None,
+            tunnel: None,
+            cpes: Vec::new(),
         }),
+        scripts: Vec::new(),
     };
 
     expected.push(&p1);
@@ -183,14 +448,17 @@ fn host_portinfo_ports() {
     expected.push(&p3);
     expected.push(&p4);
 
-    assert!(!(host.port_info.ports().count() == 0));
-    assert!(vectors_eq(&host.port_info.ports().collect(), &expected));
+    assert!(host.port_info.ports().count() != 0);
+    assert!(vectors_eq(
+        &host.port_info.ports().collect::<Vec<_>>(),
+        &expected
+    ));
 }
 
 #[test]
 fn test_issue_one() {
     let ip: std::net::IpAddr = "192.168.59.138".parse().unwrap();
-    let mac = "00:0C:29:71:23:2B".to_string();
+    let mac: nmap_xml_parser::net::MacAddr = "00:0C:29:71:23:2B".parse().unwrap();
 
     let host = NMAP_ISSUE_ONE.hosts().next().unwrap();
     assert!(host.addresses().count() == 2);
@@ -201,13 +469,13 @@ fn test_issue_one() {
     println!("{:?}", ip_addr);
     match ip_addr {
         host::Address::IpAddr(s) => assert_eq!(s, &ip),
-        host::Address::MacAddr(_) => assert!(false),
+        host::Address::MacAddr(_) => unreachable!(),
     }
 
     let mac_addr = addresses.next().unwrap();
     println!("{:?}", mac_addr);
     match mac_addr {
-        host::Address::IpAddr(_) => assert!(false),
+        host::Address::IpAddr(_) => unreachable!(),
         host::Address::MacAddr(s) => assert_eq!(s, &mac),
     }
 }
@@ -224,6 +492,73 @@ fn test_iter_ports() {
     assert!(vectors_eq(&v, &expected));
 }
 
+#[test]
+fn test_into_hosts() {
+    let results = NMAP_TEST_XML.clone();
+    let borrowed = (&results).into_iter().count();
+
+    let mut ports = Vec::new();
+    for host in results {
+        ports.extend(
+            host.port_info
+                .into_ports()
+                .into_iter()
+                .map(|p| p.port_number),
+        );
+    }
+
+    assert_eq!(borrowed, NMAP_TEST_XML.hosts().count());
+    assert!(vectors_eq(&ports, &[22, 80, 9929, 31337]));
+}
+
+#[test]
+fn test_collection_sizes() {
+    assert_eq!(NMAP_TEST_XML.host_count(), NMAP_TEST_XML.hosts().count());
+    assert!(!NMAP_TEST_XML.is_empty());
+    assert!(NmapResults::parse(r#"<nmaprun start="1588318812"/>"#)
+        .unwrap()
+        .is_empty());
+
+    let host = &NMAP_TEST_XML.hosts_slice()[0];
+    assert_eq!(host.addresses_slice().len(), 1);
+    assert_eq!(host.script_count(), host.scripts().count());
+    assert_eq!(host.port_info.port_count(), 4);
+    assert_eq!(host.port_info.ports_slice()[0].port_number, 22);
+    assert!(!host.port_info.is_empty());
+}
+
+#[test]
+fn test_error_path() {
+    let err = NmapResults::parse(
+        r#"<nmaprun start="1623467939">
+<host><status state="up" reason="syn-ack" reason_ttl="0"/>
+<address addr="192.168.1.70" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="445"><state state="open" reason="syn-ack" reason_ttl="128"/><service method="probed" conf="10"/></port>
+</ports>
+</host>
+</nmaprun>"#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "host[192.168.1.70] > ports > port[tcp/445] > service: \
+         expected `name` attribute in `service` node"
+    );
+    assert_eq!(
+        err.path(),
+        Some("host[192.168.1.70] > ports > port[tcp/445] > service")
+    );
+    assert!(matches!(
+        err.root_cause(),
+        nmap_xml_parser::Error::MissingAttribute {
+            element: "service",
+            attribute: "name",
+        }
+    ));
+}
+
 #[test]
 fn test_host_down() {
     use host::HostState;
@@ -233,3 +568,86 @@ fn test_host_down() {
         assert_eq!(host.status.state, HostState::Down);
     }
 }
+
+#[test]
+fn test_snapshot_file() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("nmap_xml_parser-{}.snapshot", std::process::id()));
+    fs::write(&path, NMAP_TEST_XML.snapshot().to_string()).unwrap();
+
+    assert!(NMAP_TEST_XML.matches_snapshot(&path).unwrap().is_match());
+    let report = NMAP_HOST_DOWN.matches_snapshot(&path).unwrap();
+    assert!(!report.is_match());
+    fs::remove_file(&path).unwrap();
+
+    assert!(NMAP_TEST_XML
+        .matches_snapshot("/nonexistent.snapshot")
+        .is_err());
+}
+
+#[test]
+fn test_validate() {
+    use nmap_xml_parser::validate::{validate, ViolationKind};
+
+    let read = |name: &str| {
+        let mut path = PathBuf::new();
+        path.push(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        path.push("tests");
+        path.push(name);
+        fs::read_to_string(path).unwrap()
+    };
+    for name in &["test.xml", "issue_1.xml", "host-down.xml"] {
+        assert_eq!(validate(&read(name)).unwrap(), [], "{}", name);
+    }
+
+    let violations = validate(&read("incomplete_scan.xml")).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].path, "nmaprun");
+    assert_eq!(
+        violations[0].kind,
+        ViolationKind::MissingElement {
+            element: "runstats",
+            parent: "nmaprun",
+        }
+    );
+}
+
+#[test]
+fn test_output_version() {
+    use nmap_xml_parser::version::OutputVersion;
+    use nmap_xml_parser::Warning;
+
+    assert_eq!(NMAP_TEST_XML.output_version, Some(OutputVersion::new(1, 4)));
+
+    let scan = |version: &str| {
+        format!(
+            r#"<nmaprun scanner="nmap" start="1588318812" version="7.92" xmloutputversion="{}">
+<host><status state="up" reason="echo-reply" reason_ttl="53"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+</host>
+</nmaprun>"#,
+            version
+        )
+    };
+    let (results, warnings) = NmapResults::parse_with_warnings(&scan("1.05")).unwrap();
+    assert_eq!(results.hosts().count(), 1);
+    assert!(warnings.is_empty());
+
+    let (results, warnings) = NmapResults::parse_with_warnings(&scan("1.06")).unwrap();
+    assert_eq!(results.output_version, Some(OutputVersion::new(1, 6)));
+    assert_eq!(
+        warnings,
+        [Warning::NewerOutputVersion(OutputVersion::new(1, 6))]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "output version 1.06 is newer than the supported 1.05"
+    );
+
+    let (results, warnings) = NmapResults::parse_with_warnings(&scan("draft")).unwrap();
+    assert_eq!(results.output_version, None);
+    assert_eq!(
+        warnings,
+        [Warning::UnknownOutputVersion("draft".to_string())]
+    );
+}